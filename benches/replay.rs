@@ -0,0 +1,146 @@
+use std::hint::black_box;
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use slc_oxide::bench::workloads;
+use slc_oxide::blob::BlobPlanner;
+use slc_oxide::v3::action::minimum_sizes;
+use slc_oxide::v3::{ActionColumns, WriteOptions};
+use slc_oxide::Replay;
+
+fn read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+    for workload in workloads() {
+        group.throughput(Throughput::Elements(workload.len() as u64));
+        group.bench_function(format!("v2/{}", workload.name), |b| {
+            b.iter(|| Replay::<()>::read(&mut Cursor::new(black_box(&workload.v2_bytes))).unwrap())
+        });
+        group.bench_function(format!("v3/{}", workload.name), |b| {
+            b.iter(|| {
+                slc_oxide::v3::Replay::read(&mut Cursor::new(black_box(&workload.v3_bytes)))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write");
+    for workload in workloads() {
+        group.throughput(Throughput::Elements(workload.len() as u64));
+        group.bench_function(format!("v2/{}", workload.name), |b| {
+            let mut buffer = Vec::with_capacity(workload.v2_bytes.len());
+            b.iter(|| {
+                buffer.clear();
+                black_box(&workload.replay).write(&mut buffer).unwrap();
+            })
+        });
+        group.bench_function(format!("v3/{}", workload.name), |b| {
+            let mut buffer = Vec::with_capacity(workload.v3_bytes.len());
+            b.iter(|| {
+                buffer.clear();
+                black_box(&workload.v3).write(&mut buffer).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+fn encode_rle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_rle");
+    for workload in workloads() {
+        let atom = workload.action_atom();
+        group.throughput(Throughput::Elements(atom.actions.len() as u64));
+        for (name, swift) in [("swift", true), ("plain", false)] {
+            let options = WriteOptions {
+                swift,
+                ..WriteOptions::default()
+            };
+            group.bench_function(format!("{}/{}", name, workload.name), |b| {
+                let mut buffer = Vec::new();
+                b.iter(|| {
+                    buffer.clear();
+                    black_box(atom)
+                        .write_with_options(&mut buffer, &options)
+                        .unwrap();
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn convert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert");
+    for workload in workloads() {
+        group.throughput(Throughput::Elements(workload.len() as u64));
+        group.bench_function(format!("v2_to_v3/{}", workload.name), |b| {
+            b.iter(|| black_box(&workload.replay).to_v3())
+        });
+        group.bench_function(format!("v3_to_v2/{}", workload.name), |b| {
+            b.iter(|| Replay::<()>::read(&mut Cursor::new(black_box(&workload.v3_bytes))).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    for workload in workloads() {
+        let actions = &workload.action_atom().actions;
+        let columns = ActionColumns::from(&actions[..]);
+        group.throughput(Throughput::Elements(actions.len() as u64));
+        group.bench_function(format!("actions/{}", workload.name), |b| {
+            b.iter(|| {
+                let actions = black_box(actions);
+                let deltas: u64 = actions.iter().map(|a| a.delta()).sum();
+                let holds = actions.iter().filter(|a| a.holding).count();
+                (deltas, holds)
+            })
+        });
+        group.bench_function(format!("columns/{}", workload.name), |b| {
+            b.iter(|| {
+                let columns = black_box(&columns);
+                let deltas: u64 = columns.deltas.iter().sum();
+                let holds = columns
+                    .flags
+                    .iter()
+                    .filter(|&&f| f & ActionColumns::HOLDING != 0)
+                    .count();
+                (deltas, holds)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn classify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("classify");
+    for workload in workloads() {
+        let actions = &workload.action_atom().actions;
+        group.throughput(Throughput::Elements(actions.len() as u64));
+        group.bench_function(format!("actions/{}", workload.name), |b| {
+            b.iter(|| {
+                black_box(actions)
+                    .iter()
+                    .map(|a| a.minimum_size())
+                    .collect::<Vec<_>>()
+            })
+        });
+        group.bench_function(format!("actions_bulk/{}", workload.name), |b| {
+            b.iter(|| minimum_sizes(black_box(actions)))
+        });
+        group.bench_function(format!("blobs/{}", workload.name), |b| {
+            b.iter(|| {
+                let mut planner = BlobPlanner::new();
+                planner.extend(black_box(&workload.replay.inputs));
+                planner.finish()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read, write, encode_rle, convert, scan, classify);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slc_oxide::{ReadOptions, Replay};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    // Raw metas accept any size, which exercises the meta length field too.
+    let _ = Replay::<Vec<u8>>::read_with_options(&mut Cursor::new(data), &ReadOptions::untrusted());
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slc_oxide::v3::section::Section;
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = Cursor::new(data);
+    let mut actions = Vec::new();
+    while Section::read_with_limit(&mut reader, &mut actions, 1 << 16).is_ok() {}
+});
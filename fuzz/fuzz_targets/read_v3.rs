@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slc_oxide::v3::{ReadOptions, Replay};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = Replay::read_with_options(&mut Cursor::new(data), &ReadOptions::untrusted())
+    {
+        // Anything that decodes must encode again.
+        replay.write(&mut Vec::new()).unwrap();
+    }
+});
@@ -0,0 +1,171 @@
+//! Alignment of the inputs of two replays.
+//!
+//! Comparing replays frame by frame, like [`crate::diff`] does, reports
+//! every input after an early change as different once the timing drifts.
+//! [`align`] instead finds the fewest edits turning one input list into the
+//! other, so a re-recorded section shows up as the few inputs that really
+//! changed.
+//!
+//! Inputs are compared as tokens of their delta and data. Two inputs with
+//! the same data but a different delta are [`AlignOp::Shifted`], costing
+//! one edit like an insertion or a deletion does. Inputs with different data
+//! are never paired. The search is limited to a band around the diagonal,
+//! so it takes `O(n * band)` time and memory.
+
+use crate::{input::Input, meta::Meta, replay::Replay};
+
+/// A step of an alignment, holding indices into the compared input lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignOp {
+    /// The inputs have the same delta and data.
+    Match { left: usize, right: usize },
+    /// The inputs have the same data, but a different delta.
+    Shifted { left: usize, right: usize },
+    /// An input only exists on the left.
+    Deleted { left: usize },
+    /// An input only exists on the right.
+    Inserted { right: usize },
+}
+
+/// Options for [`align`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignOptions {
+    /// How far the alignment may stray from the diagonal, in inputs.
+    ///
+    /// The band is widened to the difference in length of the two lists if
+    /// it is narrower, so an alignment always exists.
+    pub band: usize,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self { band: 32 }
+    }
+}
+
+/// The edits turning one input list into another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Alignment {
+    /// Steps in order of both lists.
+    pub ops: Vec<AlignOp>,
+    /// Amount of edits, counting shifted, deleted and inserted inputs.
+    pub distance: usize,
+    pub left_len: usize,
+    pub right_len: usize,
+}
+
+impl Alignment {
+    /// Pairs of inputs found in both lists, shifted or not.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.ops.iter().filter_map(|op| match *op {
+            AlignOp::Match { left, right } | AlignOp::Shifted { left, right } => {
+                Some((left, right))
+            }
+            _ => None,
+        })
+    }
+
+    /// Similarity of the lists, from 0 for nothing in common to 1 for equal lists.
+    ///
+    /// Two empty lists are equal.
+    pub fn similarity(&self) -> f64 {
+        let total = self.left_len + self.right_len;
+        if total == 0 {
+            return 1.0;
+        }
+        1.0 - self.distance as f64 / total as f64
+    }
+}
+
+const DIAGONAL: u8 = 0;
+const UP: u8 = 1;
+const LEFT: u8 = 2;
+
+/// Find the fewest edits turning the `left` inputs into the `right` ones.
+pub fn align(left: &[Input], right: &[Input], options: &AlignOptions) -> Alignment {
+    let (n, m) = (left.len(), right.len());
+    let band = options.band.max(n.abs_diff(m));
+    let width = 2 * band + 1;
+
+    // Cell (i, j) lives at column j + band - i of row i.
+    let mut directions = vec![DIAGONAL; (n + 1) * width];
+    let mut previous = vec![usize::MAX; width];
+    let mut current = vec![usize::MAX; width];
+
+    for i in 0..=n {
+        current.fill(usize::MAX);
+        let first = i.saturating_sub(band);
+        let last = m.min(i + band);
+        for j in first..=last {
+            let k = j + band - i;
+            if i == 0 && j == 0 {
+                current[k] = 0;
+                continue;
+            }
+
+            let mut best = (usize::MAX, DIAGONAL);
+            if i > 0 && j > 0 && left[i - 1].data == right[j - 1].data {
+                let cost = (left[i - 1].delta != right[j - 1].delta) as usize;
+                best = (previous[k].saturating_add(cost), DIAGONAL);
+            }
+            if i > 0 && k + 1 < width {
+                let cost = previous[k + 1].saturating_add(1);
+                if cost < best.0 {
+                    best = (cost, UP);
+                }
+            }
+            if j > 0 && k > 0 {
+                let cost = current[k - 1].saturating_add(1);
+                if cost < best.0 {
+                    best = (cost, LEFT);
+                }
+            }
+
+            current[k] = best.0;
+            directions[i * width + k] = best.1;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[m + band - n];
+    let mut ops = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match directions[i * width + j + band - i] {
+            DIAGONAL => {
+                i -= 1;
+                j -= 1;
+                ops.push(if left[i].delta == right[j].delta {
+                    AlignOp::Match { left: i, right: j }
+                } else {
+                    AlignOp::Shifted { left: i, right: j }
+                });
+            }
+            UP => {
+                i -= 1;
+                ops.push(AlignOp::Deleted { left: i });
+            }
+            _ => {
+                j -= 1;
+                ops.push(AlignOp::Inserted { right: j });
+            }
+        }
+    }
+    ops.reverse();
+
+    Alignment {
+        ops,
+        distance,
+        left_len: n,
+        right_len: m,
+    }
+}
+
+/// Align the inputs of two replays, see [`align`].
+pub fn align_replays<M: Meta, N: Meta>(
+    left: &Replay<M>,
+    right: &Replay<N>,
+    options: &AlignOptions,
+) -> Alignment {
+    align(&left.inputs, &right.inputs, options)
+}
@@ -0,0 +1,39 @@
+//! Where deaths happen across a session.
+
+use crate::{input::InputData, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// Count deaths per bucket of time since the start of their attempt.
+///
+/// Attempts start at the beginning of the replay and at every restart.
+/// Times follow the tps changes of the replay, so buckets keep the same
+/// width in seconds when the tps changes mid-session. Bucket `i` counts
+/// deaths `i * bucket..(i + 1) * bucket` seconds into an attempt, and the
+/// result ends at the last bucket holding a death.
+///
+/// Returns no buckets if `bucket` isn't a positive number of seconds.
+pub fn death_heatmap<M: Meta>(replay: &Replay<M>, bucket: f64) -> Vec<u64> {
+    let mut buckets = Vec::new();
+    if bucket.is_nan() || bucket <= 0.0 {
+        return buckets;
+    }
+
+    let clock = TpsTimeline::from(replay);
+    let mut attempt_start = 0.0;
+    for input in replay.iter() {
+        match input.data {
+            InputData::Restart | InputData::RestartFull => {
+                attempt_start = clock.seconds(input.frame);
+            }
+            InputData::Death => {
+                let elapsed = (clock.seconds(input.frame) - attempt_start).max(0.0);
+                let index = (elapsed / bucket) as usize;
+                if index >= buckets.len() {
+                    buckets.resize(index + 1, 0);
+                }
+                buckets[index] += 1;
+            }
+            _ => {}
+        }
+    }
+    buckets
+}
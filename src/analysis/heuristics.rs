@@ -0,0 +1,395 @@
+//! Bot-likeness heuristics.
+//!
+//! These heuristics look for input patterns that are hard for a human to
+//! produce: perfectly regular press intervals, click rates above what is
+//! physically possible, long streaks of single-frame holds and both players
+//! pressing on the same frames far too often. They are meant
+//! as a shared baseline for moderation tooling, not as a verdict.
+
+use crate::{
+    input::{InputData, PlayerInput},
+    meta::Meta,
+    replay::{span_seconds, Replay},
+};
+
+/// Thresholds used by [`analyze`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicsOptions {
+    /// Minimum number of consecutive identical press intervals reported as regular.
+    pub min_regular_run: usize,
+    /// Maximum amount of presses per second considered humanly possible.
+    pub max_cps: f64,
+    /// Minimum number of consecutive single-frame holds reported as a streak.
+    pub min_frame_perfect_streak: usize,
+    /// Largest distance between presses of both players compared by [`coordination`], in frames.
+    pub coordination_window: u64,
+    /// Minimum number of player 1 presses with a close player 2 press to judge coordination.
+    pub min_coordinated_presses: usize,
+    /// Share of close presses on the exact same frame reported as synchronized.
+    pub max_same_frame_ratio: f64,
+}
+
+impl Default for HeuristicsOptions {
+    fn default() -> Self {
+        Self {
+            min_regular_run: 8,
+            max_cps: 25.0,
+            min_frame_perfect_streak: 6,
+            coordination_window: 4,
+            min_coordinated_presses: 10,
+            max_same_frame_ratio: 0.9,
+        }
+    }
+}
+
+/// The kind of pattern a [`Finding`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    /// Consecutive presses are spaced by the exact same amount of frames.
+    RegularDeltas,
+    /// More presses happened within one second than `max_cps` allows.
+    CpsBurst,
+    /// Consecutive presses were all held for exactly one frame.
+    FramePerfectStreak,
+    /// Both players press on the exact same frame more often than
+    /// `max_same_frame_ratio` allows. Reported for player 1.
+    SynchronizedPlayers,
+}
+
+/// A single suspicious pattern found in a replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub player_2: bool,
+    pub start_frame: u64,
+    pub end_frame: u64,
+    /// The measured value: run length, peak clicks per second, streak length
+    /// or same-frame ratio.
+    pub value: f64,
+    /// The threshold `value` was compared against.
+    pub threshold: f64,
+}
+
+impl Finding {
+    /// Severity of the finding, in the `0.5..=1.0` range.
+    ///
+    /// A finding exactly at its threshold has a severity of 0.5,
+    /// one at twice its threshold or more has a severity of 1.0.
+    pub fn severity(&self) -> f64 {
+        (self.value / self.threshold).min(2.0) / 2.0
+    }
+}
+
+/// Result of running the heuristics over a replay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Overall score of the replay, the highest severity of all findings.
+    ///
+    /// A replay without findings scores 0.
+    pub fn score(&self) -> f64 {
+        self.findings
+            .iter()
+            .map(Finding::severity)
+            .fold(0.0, f64::max)
+    }
+
+    /// Findings of a single kind.
+    pub fn of_kind(&self, kind: FindingKind) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(move |f| f.kind == kind)
+    }
+}
+
+struct Press {
+    frame: u64,
+    /// Time of the press, `None` once the replay ran at an invalid tps.
+    seconds: Option<f64>,
+    button: u8,
+    hold_length: Option<u64>,
+}
+
+/// Collects presses of one player, with TPS-aware timestamps and hold lengths.
+fn collect_presses<M: Meta>(replay: &Replay<M>, player_2: bool) -> Vec<Press> {
+    let mut presses: Vec<Press> = Vec::new();
+    let mut open: [Option<usize>; 4] = [None; 4];
+
+    let mut tps = replay.tps;
+    let mut last_frame = 0;
+    let mut seconds = Some(0.0);
+
+    for input in &replay.inputs {
+        seconds = seconds
+            .zip(span_seconds(input.frame - last_frame, tps))
+            .map(|(s, span)| s + span);
+        last_frame = input.frame;
+
+        match &input.data {
+            InputData::TPS(new_tps) => tps = *new_tps,
+            InputData::Player(PlayerInput {
+                hold,
+                player_2: p2,
+                button,
+            }) if *p2 == player_2 => {
+                let slot = (*button as usize).min(3);
+                if *hold {
+                    open[slot] = Some(presses.len());
+                    presses.push(Press {
+                        frame: input.frame,
+                        seconds,
+                        button: *button,
+                        hold_length: None,
+                    });
+                } else if let Some(i) = open[slot].take() {
+                    presses[i].hold_length = Some(input.frame - presses[i].frame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    presses
+}
+
+fn regular_deltas(
+    presses: &[Press],
+    player_2: bool,
+    options: &HeuristicsOptions,
+    out: &mut Vec<Finding>,
+) {
+    let mut run_start = 0;
+
+    for i in 1..=presses.len() {
+        let continues = i < presses.len()
+            && i >= 2
+            && presses[i].frame - presses[i - 1].frame
+                == presses[i - 1].frame - presses[i - 2].frame;
+
+        if continues {
+            continue;
+        }
+
+        // Intervals run_start..i-1 are all equal.
+        let run = i.saturating_sub(run_start + 1);
+        if run >= options.min_regular_run && run > 0 {
+            out.push(Finding {
+                kind: FindingKind::RegularDeltas,
+                player_2,
+                start_frame: presses[run_start].frame,
+                end_frame: presses[i - 1].frame,
+                value: run as f64,
+                threshold: options.min_regular_run as f64,
+            });
+        }
+
+        run_start = i.saturating_sub(1);
+    }
+}
+
+fn cps_bursts(
+    presses: &[Press],
+    player_2: bool,
+    options: &HeuristicsOptions,
+    out: &mut Vec<Finding>,
+) {
+    let mut window_start = 0;
+    let mut current: Option<Finding> = None;
+
+    // Presses after a span at an invalid tps have no time to compare.
+    let timed = presses.partition_point(|p| p.seconds.is_some());
+    let seconds = |i: usize| presses[i].seconds.unwrap_or_default();
+
+    for i in 0..timed {
+        while seconds(i) - seconds(window_start) >= 1.0 {
+            window_start += 1;
+        }
+
+        let cps = (i - window_start + 1) as f64;
+        if cps > options.max_cps {
+            let finding = current.get_or_insert(Finding {
+                kind: FindingKind::CpsBurst,
+                player_2,
+                start_frame: presses[window_start].frame,
+                end_frame: presses[i].frame,
+                value: cps,
+                threshold: options.max_cps,
+            });
+            finding.end_frame = presses[i].frame;
+            finding.value = finding.value.max(cps);
+        } else if let Some(finding) = current.take() {
+            out.push(finding);
+        }
+    }
+
+    out.extend(current);
+}
+
+fn frame_perfect_streaks(
+    presses: &[Press],
+    player_2: bool,
+    options: &HeuristicsOptions,
+    out: &mut Vec<Finding>,
+) {
+    let mut streak: Vec<&Press> = Vec::new();
+
+    let mut flush = |streak: &mut Vec<&Press>| {
+        if streak.len() >= options.min_frame_perfect_streak && !streak.is_empty() {
+            out.push(Finding {
+                kind: FindingKind::FramePerfectStreak,
+                player_2,
+                start_frame: streak[0].frame,
+                end_frame: streak[streak.len() - 1].frame,
+                value: streak.len() as f64,
+                threshold: options.min_frame_perfect_streak as f64,
+            });
+        }
+        streak.clear();
+    };
+
+    for press in presses {
+        let same_button = streak.last().is_none_or(|p| p.button == press.button);
+        if press.hold_length == Some(1) && same_button {
+            streak.push(press);
+        } else {
+            flush(&mut streak);
+            if press.hold_length == Some(1) {
+                streak.push(press);
+            }
+        }
+    }
+
+    flush(&mut streak);
+}
+
+/// How the presses of both players line up.
+///
+/// Every player 1 press is matched with the closest player 2 press, if one
+/// is within the window. Humans playing dual parts rarely press both on the
+/// exact same frame, while a macro built from a single timeline often does.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coordination {
+    pub player_1_presses: usize,
+    pub player_2_presses: usize,
+    /// Largest offset considered, in frames.
+    pub window: u64,
+    /// Matched presses per offset of player 2 from player 1, in frames.
+    /// Index `i` holds the presses with an offset of `i - window`.
+    pub offsets: Vec<usize>,
+    /// Frames of the first and last matched player 1 presses.
+    pub span: Option<(u64, u64)>,
+}
+
+impl Coordination {
+    /// Player 1 presses with a player 2 press within the window.
+    pub fn matched(&self) -> usize {
+        self.offsets.iter().sum()
+    }
+
+    /// Player 1 presses with a player 2 press on the same frame.
+    pub fn same_frame(&self) -> usize {
+        self.offsets.get(self.window as usize).copied().unwrap_or(0)
+    }
+
+    /// Share of matched presses on the same frame, 0 if none matched.
+    pub fn same_frame_ratio(&self) -> f64 {
+        match self.matched() {
+            0 => 0.0,
+            matched => self.same_frame() as f64 / matched as f64,
+        }
+    }
+}
+
+fn coordinate(p1: &[Press], p2: &[Press], window: u64) -> Coordination {
+    let mut result = Coordination {
+        player_1_presses: p1.len(),
+        player_2_presses: p2.len(),
+        window,
+        offsets: vec![0; window as usize * 2 + 1],
+        span: None,
+    };
+
+    let mut j = 0;
+    for press in p1 {
+        while j + 1 < p2.len() && p2[j + 1].frame <= press.frame {
+            j += 1;
+        }
+        // The last player 2 press at or before this one, and the one after it.
+        let closest = p2
+            .get(j..p2.len().min(j + 2))
+            .unwrap_or_default()
+            .iter()
+            .min_by_key(|other| other.frame.abs_diff(press.frame));
+        let Some(other) = closest.filter(|o| o.frame.abs_diff(press.frame) <= window) else {
+            continue;
+        };
+
+        let offset = other.frame as i64 - press.frame as i64 + window as i64;
+        result.offsets[offset as usize] += 1;
+        result.span = Some(
+            result
+                .span
+                .map_or((press.frame, press.frame), |(start, _)| {
+                    (start, press.frame)
+                }),
+        );
+    }
+    result
+}
+
+/// Measure how the presses of both players line up, see [`Coordination`].
+pub fn coordination<M: Meta>(replay: &Replay<M>, window: u64) -> Coordination {
+    coordinate(
+        &collect_presses(replay, false),
+        &collect_presses(replay, true),
+        window,
+    )
+}
+
+fn synchronized_players(
+    coordination: &Coordination,
+    options: &HeuristicsOptions,
+    out: &mut Vec<Finding>,
+) {
+    let ratio = coordination.same_frame_ratio();
+    if coordination.matched() < options.min_coordinated_presses
+        || ratio < options.max_same_frame_ratio
+    {
+        return;
+    }
+    if let Some((start_frame, end_frame)) = coordination.span {
+        out.push(Finding {
+            kind: FindingKind::SynchronizedPlayers,
+            player_2: false,
+            start_frame,
+            end_frame,
+            value: ratio,
+            threshold: options.max_same_frame_ratio,
+        });
+    }
+}
+
+/// Run all heuristics over a replay.
+///
+/// Both players are analyzed independently, then against each other.
+/// Findings are ordered by player, then by heuristic, then by frame, with
+/// the findings comparing both players last.
+pub fn analyze<M: Meta>(replay: &Replay<M>, options: &HeuristicsOptions) -> Report {
+    let mut findings = Vec::new();
+    let mut presses = [Vec::new(), Vec::new()];
+
+    for player_2 in [false, true] {
+        let player = collect_presses(replay, player_2);
+
+        regular_deltas(&player, player_2, options, &mut findings);
+        cps_bursts(&player, player_2, options, &mut findings);
+        frame_perfect_streaks(&player, player_2, options, &mut findings);
+        presses[player_2 as usize] = player;
+    }
+
+    let coordination = coordinate(&presses[0], &presses[1], options.coordination_window);
+    synchronized_players(&coordination, options, &mut findings);
+
+    Report { findings }
+}
@@ -0,0 +1,84 @@
+//! Hold lengths and click bursts of a player.
+//!
+//! Presses are paired with their releases by [`pair_inputs`], so these
+//! numbers agree with everything else built on holds.
+
+use crate::{holds::pair_inputs, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// Click metrics of one player.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HoldStats {
+    /// Amount of presses.
+    pub presses: usize,
+    /// Width of the histogram buckets, in frames.
+    pub bucket: u64,
+    /// Holds per bucket of length. Bucket `i` counts holds lasting
+    /// `i * bucket..(i + 1) * bucket` frames.
+    ///
+    /// Only holds ended by a release are counted, as the others were cut short.
+    pub histogram: Vec<u64>,
+    /// Most presses within any one second.
+    pub max_cps: usize,
+    /// Frame of the first press of the busiest second.
+    pub max_cps_frame: u64,
+    /// Most consecutive holds of the same button lasting exactly one frame.
+    pub longest_frame_perfect_streak: usize,
+}
+
+/// Measure the holds and clicks of a player.
+///
+/// Seconds follow the tps changes of the replay. A bucket width of 0 is
+/// treated as 1.
+pub fn hold_stats<M: Meta>(replay: &Replay<M>, player_2: bool, bucket: u64) -> HoldStats {
+    let bucket = bucket.max(1);
+    let holds: Vec<_> = pair_inputs(&replay.inputs)
+        .into_iter()
+        .filter(|h| h.player_2 == player_2)
+        .collect();
+
+    let mut stats = HoldStats {
+        presses: holds.len(),
+        bucket,
+        ..Default::default()
+    };
+
+    for hold in holds.iter().filter(|h| h.released) {
+        let index = (hold.length() / bucket) as usize;
+        if index >= stats.histogram.len() {
+            stats.histogram.resize(index + 1, 0);
+        }
+        stats.histogram[index] += 1;
+    }
+
+    let clock = TpsTimeline::from(replay);
+    let times: Vec<f64> = holds.iter().map(|h| clock.seconds(h.start)).collect();
+    let mut window_start = 0;
+    for (i, &time) in times.iter().enumerate() {
+        while time - times[window_start] >= 1.0 {
+            window_start += 1;
+        }
+        if i + 1 - window_start > stats.max_cps {
+            stats.max_cps = i + 1 - window_start;
+            stats.max_cps_frame = holds[window_start].start;
+        }
+    }
+
+    let mut streak = 0;
+    let mut streak_button = None;
+    for hold in &holds {
+        if hold.released && hold.length() == 1 {
+            streak = if streak_button == Some(hold.button) {
+                streak + 1
+            } else {
+                1
+            };
+            streak_button = Some(hold.button);
+            stats.longest_frame_perfect_streak = stats.longest_frame_perfect_streak.max(streak);
+        } else {
+            streak = 0;
+            streak_button = None;
+        }
+    }
+
+    stats
+}
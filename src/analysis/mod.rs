@@ -0,0 +1,12 @@
+//! Opt-in analyses over decoded replays.
+//!
+//! Nothing in this module runs as part of reading or writing a replay;
+//! call the analyses explicitly when you need them.
+
+pub mod heatmap;
+pub mod heuristics;
+pub mod holds;
+pub mod session;
+
+pub use heatmap::death_heatmap;
+pub use holds::{hold_stats, HoldStats};
@@ -0,0 +1,122 @@
+//! Statistics over the attempts of a recorded session.
+//!
+//! Attempts are split the way [`SessionAtom::from_actions`] splits them,
+//! but everything is gathered in a single pass over the actions, without
+//! building the attempts.
+//!
+//! [`SessionAtom::from_actions`]: crate::v3::session::SessionAtom::from_actions
+
+use crate::v3::{builtin::ActionAtom, ActionType, AttemptKind};
+
+/// Aggregated statistics of a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStats {
+    pub attempts: usize,
+    /// Frames played across every attempt.
+    pub total_frames: u64,
+    /// Average attempt length, in frames.
+    pub average_length: f64,
+    pub deaths: usize,
+    /// Width of the death buckets, in frames.
+    pub bucket_size: u64,
+    /// Deaths per bucket of frames since the start of their attempt.
+    ///
+    /// Bucket `i` counts deaths `i * bucket_size..(i + 1) * bucket_size`
+    /// frames into an attempt.
+    pub death_buckets: Vec<u64>,
+    /// The furthest an attempt got so far, in frames, after each attempt.
+    ///
+    /// Attempts get as far as the frame they died on, or their end if they
+    /// didn't die. Practice attempts don't count, and only repeat the best.
+    pub improvement: Vec<u64>,
+}
+
+struct Open {
+    start: u64,
+    death: Option<u64>,
+    kind: AttemptKind,
+}
+
+impl SessionStats {
+    /// Gather the statistics of the actions of a session.
+    ///
+    /// A bucket size of 0 is treated as 1.
+    pub fn new(atom: &ActionAtom, bucket_size: u64) -> Self {
+        let mut stats = Self {
+            bucket_size: bucket_size.max(1),
+            ..Default::default()
+        };
+        let mut open = Open {
+            start: 0,
+            death: None,
+            kind: AttemptKind::Normal,
+        };
+
+        for action in &atom.actions {
+            match action.action_type {
+                ActionType::Death => {
+                    stats.add_death(action.frame.saturating_sub(open.start));
+                    open.death = Some(action.frame);
+                }
+                ActionType::Restart | ActionType::RestartFull => {
+                    stats.close(&open, action.frame);
+                    open = Open {
+                        start: action.frame,
+                        death: None,
+                        kind: action.attempt_kind(),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        let end = atom.actions.last().map_or(0, |a| a.frame + 1);
+        if end > open.start {
+            stats.close(&open, end);
+        }
+
+        if stats.attempts > 0 {
+            stats.average_length = stats.total_frames as f64 / stats.attempts as f64;
+        }
+        stats
+    }
+
+    fn add_death(&mut self, frames: u64) {
+        let bucket = (frames / self.bucket_size) as usize;
+        if bucket >= self.death_buckets.len() {
+            self.death_buckets.resize(bucket + 1, 0);
+        }
+        self.death_buckets[bucket] += 1;
+        self.deaths += 1;
+    }
+
+    fn close(&mut self, attempt: &Open, end: u64) {
+        let length = end.saturating_sub(attempt.start);
+        let reached = attempt
+            .death
+            .unwrap_or(end)
+            .clamp(attempt.start, end.max(attempt.start))
+            - attempt.start;
+
+        let best = self.improvement.last().copied().unwrap_or(0);
+        self.improvement
+            .push(if attempt.kind == AttemptKind::Practice {
+                best
+            } else {
+                best.max(reached)
+            });
+        self.attempts += 1;
+        self.total_frames += length;
+    }
+
+    /// The bucket with the most deaths and its count, earliest on ties.
+    pub fn deadliest_bucket(&self) -> Option<(usize, u64)> {
+        self.death_buckets
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .rev()
+            .max_by_key(|&(_, count)| count)
+    }
+}
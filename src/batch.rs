@@ -0,0 +1,236 @@
+//! Multithreaded conversion of whole replay directories.
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    meta::Meta,
+    replay::{ReadOptions, Replay, ReplayError, Version},
+};
+
+/// Options for [`convert_dir`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Amount of worker threads. `0` uses the available parallelism.
+    pub threads: usize,
+    /// Whether existing files in the destination may be replaced.
+    pub overwrite: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            overwrite: true,
+        }
+    }
+}
+
+/// Per-file results of a batch conversion.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Destination paths of the converted files.
+    pub converted: Vec<PathBuf>,
+    /// Source paths of the files that failed, with their error.
+    pub failed: Vec<(PathBuf, ReplayError)>,
+}
+
+impl BatchReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+fn thread_count(threads: usize, jobs: usize) -> usize {
+    match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(jobs.max(1))
+}
+
+fn convert_file(
+    source: &Path,
+    destination: &Path,
+    format: Version,
+    overwrite: bool,
+) -> Result<(), ReplayError> {
+    let mut reader = BufReader::new(File::open(source)?);
+    let replay = Replay::<Vec<u8>>::read(&mut reader)?;
+
+    let file = if overwrite {
+        File::create(destination)?
+    } else {
+        File::create_new(destination)?
+    };
+
+    let mut writer = BufWriter::new(file);
+    match format {
+        Version::V2 => replay.write(&mut writer)?,
+        Version::V3 => replay.write_v3(&mut writer)?,
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Convert every `.slc`/`.slc3` file of a directory to the given format.
+///
+/// Files are converted in parallel and keep their file stem in the destination
+/// directory, which is created if missing, with the extension of the target
+/// format. Metas of v2 replays are preserved
+/// when converting to v2. A failing file never stops the others; its error is
+/// collected in the report instead.
+pub fn convert_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    format: Version,
+    options: &BatchOptions,
+) -> std::io::Result<BatchReport> {
+    let dst = dst.as_ref();
+    fs::create_dir_all(dst)?;
+
+    let mut sources = Vec::new();
+    for entry in fs::read_dir(src)? {
+        let path = entry?.path();
+        let is_replay = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("slc" | "slc3")
+        );
+        if is_replay && path.is_file() {
+            sources.push(path);
+        }
+    }
+    sources.sort();
+
+    let threads = thread_count(options.threads, sources.len());
+
+    let next = AtomicUsize::new(0);
+    let report = Mutex::new(BatchReport::default());
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(source) = sources.get(i) else {
+                    break;
+                };
+
+                let destination = dst
+                    .join(source.file_name().unwrap_or_default())
+                    .with_extension(format.extension());
+                let result = convert_file(source, &destination, format, options.overwrite);
+
+                let mut report = report.lock().unwrap_or_else(|e| e.into_inner());
+                match result {
+                    Ok(()) => report.converted.push(destination),
+                    Err(e) => report.failed.push((source.clone(), e)),
+                }
+            });
+        }
+    });
+
+    let mut report = report.into_inner().unwrap_or_else(|e| e.into_inner());
+    report.converted.sort();
+    report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(report)
+}
+
+/// Reads replay files in parallel.
+///
+/// Each worker reads whole files into its own buffer before decoding them.
+/// Buffers are kept between calls to [`ReadPool::read_many`], so scanning
+/// a library in several passes doesn't reallocate them.
+#[derive(Debug, Default)]
+pub struct ReadPool {
+    threads: usize,
+    options: ReadOptions,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl ReadPool {
+    /// Create a pool of the given amount of workers. `0` uses the available parallelism.
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads,
+            ..Default::default()
+        }
+    }
+
+    /// Limits to read files within, see [`ReadOptions`].
+    pub fn with_read_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Read every file, returning results in the order of the paths.
+    ///
+    /// A failing file never stops the others.
+    pub fn read_many<M, P>(&mut self, paths: &[P]) -> Vec<Result<Replay<M>, ReplayError>>
+    where
+        M: Meta + Send,
+        P: AsRef<Path> + Sync,
+    {
+        let threads = thread_count(self.threads, paths.len());
+        if self.buffers.len() < threads {
+            self.buffers.resize_with(threads, Vec::new);
+        }
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(paths.len()));
+        let options = &self.options;
+
+        thread::scope(|scope| {
+            for buffer in self.buffers.iter_mut().take(threads) {
+                let next = &next;
+                let results = &results;
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(i) else {
+                        break;
+                    };
+
+                    let result = read_file(path.as_ref(), buffer, options);
+                    results
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((i, result));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+fn read_file<M: Meta>(
+    path: &Path,
+    buffer: &mut Vec<u8>,
+    options: &ReadOptions,
+) -> Result<Replay<M>, ReplayError> {
+    buffer.clear();
+    File::open(path)?.read_to_end(buffer)?;
+    Replay::read_with_options(&mut Cursor::new(&buffer[..]), options)
+}
+
+/// Read every file in parallel, returning results in the order of the paths.
+///
+/// Use a [`ReadPool`] to reuse buffers across calls or to set limits.
+pub fn read_many<M, P>(paths: &[P]) -> Vec<Result<Replay<M>, ReplayError>>
+where
+    M: Meta + Send,
+    P: AsRef<Path> + Sync,
+{
+    ReadPool::new(0).read_many(paths)
+}
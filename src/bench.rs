@@ -0,0 +1,79 @@
+//! Workloads used by the benchmark suite.
+//!
+//! They are public so downstream changes can be measured against the same
+//! inputs, see `benches/replay.rs`.
+
+use crate::{
+    replay::Replay,
+    testing::{generate_replay, Profile},
+    v3::{atom::AtomVariant, builtin::ActionAtom},
+};
+
+/// A synthetic replay, pre-encoded in both formats.
+#[derive(Debug, Clone)]
+pub struct Workload {
+    pub name: &'static str,
+    pub replay: Replay<()>,
+    pub v3: crate::v3::Replay,
+    pub v2_bytes: Vec<u8>,
+    pub v3_bytes: Vec<u8>,
+}
+
+impl Workload {
+    /// Generate a workload from a seed and a profile.
+    pub fn new(name: &'static str, seed: u64, profile: &Profile) -> Self {
+        let replay = generate_replay(seed, profile);
+        let v3 = replay.to_v3();
+
+        let mut v2_bytes = Vec::new();
+        replay
+            .write(&mut v2_bytes)
+            .expect("generated replays are valid");
+        let mut v3_bytes = Vec::new();
+        v3.write(&mut v3_bytes)
+            .expect("generated replays are valid");
+
+        Self {
+            name,
+            replay,
+            v3,
+            v2_bytes,
+            v3_bytes,
+        }
+    }
+
+    /// Amount of inputs in the replay.
+    pub fn len(&self) -> usize {
+        self.replay.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.inputs.is_empty()
+    }
+
+    /// The action atom of the v3 replay, for encoding it on its own.
+    pub fn action_atom(&self) -> &ActionAtom {
+        self.v3
+            .atoms
+            .iter()
+            .find_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a),
+                _ => None,
+            })
+            .expect("converted replays have an action atom")
+    }
+}
+
+/// The standard set of workloads, from a short macro to an hour of spam.
+pub fn workloads() -> Vec<Workload> {
+    vec![
+        Workload::new("minute", 1, &Profile::default()),
+        Workload::new("dual_10min", 2, &Profile::dual().with_frames(240 * 600)),
+        Workload::new(
+            "platformer_10min",
+            3,
+            &Profile::platformer().with_frames(240 * 600),
+        ),
+        Workload::new("spam_hour", 4, &Profile::spam().with_frames(240 * 3600)),
+    ]
+}
@@ -0,0 +1,340 @@
+//! `slc` command-line tool.
+//!
+//! Build with `cargo install slc_oxide --features cli`.
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufWriter, Cursor},
+    path::Path,
+    process::ExitCode,
+};
+
+use slc_oxide::{
+    diff,
+    input::InputData,
+    json,
+    profile::{OutputFormat, Profile},
+    repair::{repair_specials, RepairMode, RepairOptions},
+    replay::Version,
+    stress,
+    v3::{self, dump::DumpTarget},
+    validate::find_conflicts,
+    Replay,
+};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+usage:
+  slc info <file>
+  slc convert <input> <output> [--format v2|v3|json] [--profile <file>]
+  slc validate <file>
+  slc repair <input> <output> [--mode fix|strip] [--end-frame <frame>] [--format v2|v3|json]
+  slc trim <input> <output> --to-frame <frame> [--format v2|v3|json]
+  slc diff <left> <right>
+  slc dump <file> [--atom <index>] [--section <index>]
+  slc stress <file>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    V2,
+    V3,
+    Json,
+}
+
+impl Format {
+    fn parse(name: &str) -> CliResult<Self> {
+        match name {
+            "v2" | "slc" => Ok(Self::V2),
+            "v3" | "slc3" => Ok(Self::V3),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown format: {name}").into()),
+        }
+    }
+
+    fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::Json,
+            Some("slc3") => Self::V3,
+            _ => Self::V2,
+        }
+    }
+
+    fn output(self) -> OutputFormat {
+        match self {
+            Self::V2 => OutputFormat::V2,
+            Self::V3 => OutputFormat::V3,
+            Self::Json => OutputFormat::Json,
+        }
+    }
+
+    fn detect(bytes: &[u8]) -> CliResult<Self> {
+        match Version::detect(bytes) {
+            Some(Version::V2) => Ok(Self::V2),
+            Some(Version::V3) => Ok(Self::V3),
+            None if bytes.trim_ascii_start().starts_with(b"{") => Ok(Self::Json),
+            None => Err("unknown replay format".into()),
+        }
+    }
+}
+
+/// Load a replay file of any supported format, keeping its meta as raw bytes.
+fn load(path: &str) -> CliResult<(Replay<Vec<u8>>, Format)> {
+    let bytes = fs::read(path)?;
+    let format = Format::detect(&bytes)?;
+
+    let mut cursor = Cursor::new(&bytes);
+    let replay = match format {
+        Format::Json => json::read(&mut cursor)?,
+        Format::V2 | Format::V3 => Replay::read(&mut cursor)?,
+    };
+
+    Ok((replay, format))
+}
+
+fn save(replay: &Replay<Vec<u8>>, path: &str, format: Format) -> CliResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        Format::V2 => replay.write(&mut writer)?,
+        Format::V3 => replay.write_v3(&mut writer)?,
+        Format::Json => json::write(replay, &mut writer)?,
+    }
+    Ok(())
+}
+
+fn info(path: &str) -> CliResult<ExitCode> {
+    let size = fs::metadata(path)?.len();
+    let (replay, format) = load(path)?;
+
+    let mut counts = [0usize; 6];
+    for input in &replay.inputs {
+        let slot = match input.data {
+            InputData::Player(_) => 0,
+            InputData::Restart => 1,
+            InputData::RestartFull => 2,
+            InputData::Death => 3,
+            InputData::TPS(_) => 4,
+            InputData::Skip => 5,
+        };
+        counts[slot] += 1;
+    }
+
+    println!("format:       {:?}", format);
+    println!("size:         {} bytes", size);
+    println!("tps:          {}", replay.tps);
+    println!("meta size:    {} bytes", replay.meta.len());
+    println!("inputs:       {}", replay.inputs.len());
+    println!("  player:     {}", counts[0]);
+    println!("  restart:    {}", counts[1]);
+    println!("  full reset: {}", counts[2]);
+    println!("  death:      {}", counts[3]);
+    println!("  tps:        {}", counts[4]);
+    println!("  skip:       {}", counts[5]);
+    println!(
+        "last frame:   {}",
+        replay.inputs.last().map(|i| i.frame).unwrap_or(0)
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Convert through a profile, so the result matches other frontends using it.
+///
+/// A format given on the command line overrides the one of the profile.
+fn convert(
+    input: &str,
+    output: &str,
+    format: Option<Format>,
+    mut profile: Profile,
+) -> CliResult<ExitCode> {
+    let (mut replay, _) = load(input)?;
+    profile.format = format
+        .map(Format::output)
+        .or(profile.format)
+        .or(Some(Format::from_path(output).output()));
+
+    profile.apply(&mut replay)?;
+    let mut writer = BufWriter::new(File::create(output)?);
+    profile.write(&replay, &mut writer)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(path: &str) -> CliResult<ExitCode> {
+    let replay = match load(path) {
+        Ok((replay, _)) => replay,
+        Err(e) => {
+            println!("invalid: {e}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let conflicts = find_conflicts(&replay.inputs);
+    for conflict in &conflicts {
+        println!(
+            "input {} (frame {}): {:?} on button {}{}",
+            conflict.index,
+            conflict.frame,
+            conflict.kind,
+            conflict.button,
+            if conflict.player_2 { " (player 2)" } else { "" }
+        );
+    }
+
+    if conflicts.is_empty() {
+        println!("valid");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("{} conflicting inputs", conflicts.len());
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn trim(input: &str, output: &str, format: Format, frame: u64) -> CliResult<ExitCode> {
+    let (mut replay, _) = load(input)?;
+    replay.clip_inputs(frame);
+    save(&replay, output, format)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn repair(
+    input: &str,
+    output: &str,
+    format: Format,
+    options: RepairOptions,
+) -> CliResult<ExitCode> {
+    let (mut replay, _) = load(input)?;
+    let report = repair_specials(&mut replay, &options);
+    print!("{report}");
+    save(&replay, output, format)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff(left: &str, right: &str) -> CliResult<ExitCode> {
+    let (mut left, _) = load(left)?;
+    let (mut right, _) = load(right)?;
+    diff::normalize(&mut left);
+    diff::normalize(&mut right);
+
+    let report = diff::diff(&left, &right);
+    print!("{report}");
+
+    Ok(if report.is_identical() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn dump(path: &str, target: DumpTarget) -> CliResult<ExitCode> {
+    let bytes = fs::read(path)?;
+    let stdout = std::io::stdout();
+    v3::dump::dump(&bytes, target, &mut stdout.lock())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn stress(path: &str) -> CliResult<ExitCode> {
+    let bytes = fs::read(path)?;
+    let report = match Format::detect(&bytes)? {
+        Format::V3 => stress::stress_v3(&v3::Replay::read(&mut Cursor::new(&bytes))?)?,
+        _ => stress::stress(&load(path)?.0)?,
+    };
+    print!("{report}");
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Command-line arguments, split into positional ones and `--flag value` pairs.
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    flags: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Args<'a> {
+    fn parse(args: &'a [String]) -> CliResult<Self> {
+        let mut positional = Vec::new();
+        let mut flags = Vec::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(flag) = arg.strip_prefix("--") {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("missing value for --{flag}"))?;
+                flags.push((flag, value.as_str()));
+            } else {
+                positional.push(arg.as_str());
+            }
+        }
+
+        Ok(Self { positional, flags })
+    }
+
+    fn flag(&self, name: &str) -> Option<&'a str> {
+        self.flags.iter().find(|(f, _)| *f == name).map(|(_, v)| *v)
+    }
+
+    fn output_format(&self, output: &str) -> CliResult<Format> {
+        self.flag("format")
+            .map(Format::parse)
+            .unwrap_or_else(|| Ok(Format::from_path(output)))
+    }
+}
+
+fn run(args: &[String]) -> CliResult<ExitCode> {
+    let args = Args::parse(args)?;
+
+    match args.positional.as_slice() {
+        ["info", input] => info(input),
+        ["convert", input, output] => {
+            let format = args.flag("format").map(Format::parse).transpose()?;
+            let profile = match args.flag("profile") {
+                Some(path) => Profile::load(path)?,
+                None => Profile::default(),
+            };
+            convert(input, output, format, profile)
+        }
+        ["validate", input] => validate(input),
+        ["trim", input, output] => {
+            let frame = args.flag("to-frame").ok_or("missing --to-frame")?.parse()?;
+            trim(input, output, args.output_format(output)?, frame)
+        }
+        ["repair", input, output] => {
+            let mut options = RepairOptions::default();
+            match args.flag("mode") {
+                None | Some("fix") => {}
+                Some("strip") => options = options.with_mode(RepairMode::Strip),
+                Some(mode) => return Err(format!("unknown repair mode: {mode}").into()),
+            }
+            if let Some(frame) = args.flag("end-frame") {
+                options = options.with_end_frame(frame.parse()?);
+            }
+            repair(input, output, args.output_format(output)?, options)
+        }
+        ["diff", left, right] => diff(left, right),
+        ["dump", input] => {
+            let atom = args.flag("atom").map(str::parse).transpose()?.unwrap_or(0);
+            let target = match args.flag("section").map(str::parse).transpose()? {
+                Some(section) => DumpTarget::Section { atom, section },
+                None => DumpTarget::Atom(atom),
+            };
+            dump(input, target)
+        }
+        ["stress", input] => stress(input),
+        _ => {
+            eprintln!("{USAGE}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,9 +1,13 @@
+//! Blobs, the runs of same-width inputs v2 files are made of.
+
 use std::io::{Read, Write};
 
 use thiserror::Error;
 
-use crate::input::Input;
+use crate::{codec, input::Input};
 
+/// A run of inputs written with the same amount of bytes each.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Blob {
     pub byte_size: u64,
     pub start: u64,
@@ -16,18 +20,26 @@ pub enum BlobError {
     IOError(#[from] std::io::Error),
     #[error("Input error: {0}")]
     InputError(#[from] crate::input::InputError),
+    #[error("Invalid byte size: {0}")]
+    InvalidByteSize(u64),
+    #[error("Invalid input range")]
+    InvalidRange,
 }
 
 impl Blob {
-    pub fn read<R: Read>(reader: &mut R) -> Result<Self, BlobError> {
-        let mut buf = [0u8; 8];
+    /// Split inputs into the blobs they are written in.
+    ///
+    /// This is [`BlobPlanner::plan`] with the default memory size.
+    pub fn plan(inputs: &[Input]) -> Vec<Blob> {
+        let mut planner = BlobPlanner::new();
+        planner.extend(inputs);
+        planner.finish()
+    }
 
-        reader.read_exact(&mut buf)?;
-        let byte_size = u64::from_le_bytes(buf);
-        reader.read_exact(&mut buf)?;
-        let start = u64::from_le_bytes(buf);
-        reader.read_exact(&mut buf)?;
-        let length = u64::from_le_bytes(buf);
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, BlobError> {
+        let byte_size: u64 = codec::read(reader)?;
+        let start: u64 = codec::read(reader)?;
+        let length: u64 = codec::read(reader)?;
 
         Ok(Self {
             byte_size,
@@ -41,9 +53,9 @@ impl Blob {
             return Ok(());
         }
 
-        writer.write_all(&self.byte_size.to_le_bytes())?;
-        writer.write_all(&self.start.to_le_bytes())?;
-        writer.write_all(&self.length.to_le_bytes())?;
+        codec::write(writer, self.byte_size)?;
+        codec::write(writer, self.start)?;
+        codec::write(writer, self.length)?;
 
         Ok(())
     }
@@ -54,10 +66,19 @@ impl Blob {
         inputs: &mut Vec<Input>,
         frame: &mut u64,
     ) -> Result<(), BlobError> {
-        for i in (self.start as usize)..((self.start + self.length) as usize) {
-            inputs.push(Input::read(reader, *frame, self.byte_size as usize)?);
+        if !(1..=8).contains(&self.byte_size) {
+            return Err(BlobError::InvalidByteSize(self.byte_size));
+        }
+        // Blobs are stored in order, each starting where the previous one ended.
+        if self.start != inputs.len() as u64 {
+            return Err(BlobError::InvalidRange);
+        }
 
-            *frame = inputs[i].frame;
+        let mut buf = [0u8; 8];
+        for _ in 0..self.length {
+            let input = Input::read(reader, *frame, self.byte_size as usize, &mut buf)?;
+            *frame = input.frame;
+            inputs.push(input);
         }
 
         Ok(())
@@ -81,3 +102,158 @@ impl Blob {
         Ok(())
     }
 }
+
+/// Plans the blobs a list of inputs is written in.
+///
+/// Inputs are appended one at a time: consecutive inputs needing the same
+/// amount of bytes are grouped right away, so the planner only ever holds
+/// one entry per group. [`BlobPlanner::plan`] then merges small groups into
+/// the blob before them when that saves space, from the last group to the
+/// first.
+#[derive(Debug, Clone)]
+pub struct BlobPlanner {
+    mem_size: u64,
+    runs: Vec<Blob>,
+    /// The last plan, kept to reuse its memory.
+    blobs: Vec<Blob>,
+    len: u64,
+}
+
+impl BlobPlanner {
+    /// Size in bytes under which blobs are merged into their neighbours.
+    ///
+    /// A blob header takes 24 bytes, so smaller blobs cost more than they save.
+    pub const DEFAULT_MEM_SIZE: u64 = 24;
+
+    pub fn new() -> Self {
+        Self::with_mem_size(Self::DEFAULT_MEM_SIZE)
+    }
+
+    /// Create a planner merging blobs smaller than `mem_size` bytes.
+    pub fn with_mem_size(mem_size: u64) -> Self {
+        Self {
+            mem_size,
+            runs: Vec::new(),
+            blobs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn mem_size(&self) -> u64 {
+        self.mem_size
+    }
+
+    /// Amount of inputs appended so far.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append an input.
+    pub fn push(&mut self, input: &Input) {
+        let byte_size = input.required_bytes() as u64;
+        match self.runs.last_mut() {
+            Some(run) if run.byte_size == byte_size => run.length += 1,
+            _ => self.runs.push(Blob {
+                byte_size,
+                start: self.len,
+                length: 1,
+            }),
+        }
+        self.len += 1;
+    }
+
+    /// Append several inputs.
+    pub fn extend<'a, I: IntoIterator<Item = &'a Input>>(&mut self, inputs: I) {
+        inputs.into_iter().for_each(|input| self.push(input));
+    }
+
+    /// The byte size `blob` and the blob before it share once merged, if they should be.
+    ///
+    /// Blobs of the same byte size are always merged. A blob smaller than
+    /// the memory size is merged into the previous one when it would take
+    /// less than that memory size at the previous blob's byte size; the
+    /// merged blob uses the larger of both byte sizes.
+    pub fn merged_byte_size(&self, previous: &Blob, blob: &Blob) -> Option<u64> {
+        if blob.byte_size == previous.byte_size {
+            return Some(blob.byte_size);
+        }
+
+        let blob_size = blob.byte_size.saturating_mul(blob.length);
+        if blob_size < self.mem_size
+            && previous.byte_size.saturating_mul(blob.length) < self.mem_size
+        {
+            return Some(blob.byte_size.max(previous.byte_size));
+        }
+
+        None
+    }
+
+    /// Forget every input, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.blobs.clear();
+        self.len = 0;
+    }
+
+    /// Merge the groups into `blobs`.
+    ///
+    /// Groups are merged from the last to the first, so a group is compared
+    /// with everything already merged after it. Blobs are emitted once
+    /// nothing more merges into them, then put back in order.
+    fn plan_into(&self, blobs: &mut Vec<Blob>) {
+        blobs.clear();
+        let mut runs = self.runs.iter().rev();
+        let Some(mut blob) = runs.next().cloned() else {
+            return;
+        };
+
+        for previous in runs {
+            match self.merged_byte_size(previous, &blob) {
+                Some(byte_size) => {
+                    blob.byte_size = byte_size;
+                    blob.start = previous.start;
+                    blob.length += previous.length;
+                }
+                None => {
+                    blobs.push(blob);
+                    blob = previous.clone();
+                }
+            }
+        }
+        blobs.push(blob);
+        blobs.reverse();
+    }
+
+    /// Plan the blobs, keeping them in the planner to reuse their memory.
+    pub(crate) fn plan_cached(&mut self) -> &[Blob] {
+        let mut blobs = std::mem::take(&mut self.blobs);
+        self.plan_into(&mut blobs);
+        self.blobs = blobs;
+        &self.blobs
+    }
+
+    /// The blobs the inputs appended so far are written in.
+    ///
+    /// The returned blobs are in order and cover every input exactly once.
+    pub fn plan(&self) -> Vec<Blob> {
+        let mut blobs = Vec::new();
+        self.plan_into(&mut blobs);
+        blobs
+    }
+
+    /// Plan the blobs, consuming the planner.
+    pub fn finish(mut self) -> Vec<Blob> {
+        self.plan_cached();
+        self.blobs
+    }
+}
+
+impl Default for BlobPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
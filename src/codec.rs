@@ -0,0 +1,131 @@
+//! Byte order of the numbers stored in replay files.
+//!
+//! Every integer and float in the slc formats is little-endian. All of them
+//! are read and written through this module, so the byte order is decided in
+//! one place and never depends on the host: a replay written on a big-endian
+//! machine is byte for byte the one written on a little-endian machine.
+//!
+//! ```
+//! use slc_oxide::codec;
+//!
+//! let mut bytes = Vec::new();
+//! codec::write(&mut bytes, 0x0102u16)?;
+//! assert_eq!(bytes, [0x02, 0x01]);
+//! assert_eq!(codec::read::<u16, _>(&mut bytes.as_slice())?, 0x0102);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io::{self, Read, Write};
+
+/// A number with a fixed size encoding.
+pub trait Number: Copy {
+    /// Size of the encoding in bytes.
+    const SIZE: usize;
+
+    /// Encode into the first [`Self::SIZE`] bytes of `out`, little-endian.
+    fn encode_le(self, out: &mut [u8]);
+    /// Decode from the first [`Self::SIZE`] bytes of `bytes`, little-endian.
+    fn decode_le(bytes: &[u8]) -> Self;
+    /// Encode into the first [`Self::SIZE`] bytes of `out`, big-endian.
+    fn encode_be(self, out: &mut [u8]);
+    /// Decode from the first [`Self::SIZE`] bytes of `bytes`, big-endian.
+    fn decode_be(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_number {
+    ($($ty:ty),*) => {$(
+        impl Number for $ty {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+
+            fn encode_le(self, out: &mut [u8]) {
+                out[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode_le(bytes: &[u8]) -> Self {
+                let mut buf = [0u8; std::mem::size_of::<$ty>()];
+                buf.copy_from_slice(&bytes[..Self::SIZE]);
+                Self::from_le_bytes(buf)
+            }
+
+            fn encode_be(self, out: &mut [u8]) {
+                out[..Self::SIZE].copy_from_slice(&self.to_be_bytes());
+            }
+
+            fn decode_be(bytes: &[u8]) -> Self {
+                let mut buf = [0u8; std::mem::size_of::<$ty>()];
+                buf.copy_from_slice(&bytes[..Self::SIZE]);
+                Self::from_be_bytes(buf)
+            }
+        }
+    )*};
+}
+
+impl_number!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+/// The largest [`Number::SIZE`].
+const MAX_SIZE: usize = 8;
+
+/// Encode a number into the start of `out`.
+///
+/// Panics if `out` is shorter than the number.
+pub fn encode<T: Number>(value: T, out: &mut [u8]) {
+    value.encode_le(out);
+}
+
+/// Decode a number from the start of `bytes`.
+///
+/// Panics if `bytes` is shorter than the number.
+pub fn decode<T: Number>(bytes: &[u8]) -> T {
+    T::decode_le(bytes)
+}
+
+/// Read a number.
+pub fn read<T: Number, R: Read + ?Sized>(reader: &mut R) -> io::Result<T> {
+    let mut buf = [0u8; MAX_SIZE];
+    reader.read_exact(&mut buf[..T::SIZE])?;
+    Ok(T::decode_le(&buf))
+}
+
+/// Write a number.
+pub fn write<T: Number, W: Write + ?Sized>(writer: &mut W, value: T) -> io::Result<()> {
+    let mut buf = [0u8; MAX_SIZE];
+    value.encode_le(&mut buf);
+    writer.write_all(&buf[..T::SIZE])
+}
+
+/// Decode an unsigned integer stored in `bytes.len()` bytes, from 0 to 8.
+///
+/// Panics if `bytes` is longer than 8 bytes.
+pub fn decode_uint(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u64::decode_le(&buf)
+}
+
+/// Decode a big-endian unsigned integer stored in `bytes.len()` bytes, from
+/// 0 to 8.
+///
+/// Panics if `bytes` is longer than 8 bytes.
+pub fn decode_uint_be(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[8 - bytes.len()..].copy_from_slice(bytes);
+    u64::decode_be(&buf)
+}
+
+/// Read an unsigned integer stored in `width` bytes, from 0 to 8.
+///
+/// Panics if `width` is over 8.
+pub fn read_uint<R: Read + ?Sized>(reader: &mut R, width: usize) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf[..width])?;
+    Ok(u64::decode_le(&buf))
+}
+
+/// Write the low `width` bytes of an unsigned integer, from 0 to 8.
+///
+/// Higher bytes are dropped. Panics if `width` is over 8.
+pub fn write_uint<W: Write + ?Sized>(writer: &mut W, value: u64, width: usize) -> io::Result<()> {
+    let mut buf = [0u8; 8];
+    value.encode_le(&mut buf);
+    writer.write_all(&buf[..width])
+}
@@ -0,0 +1,289 @@
+//! Reference vectors for checking other implementations of the format.
+//!
+//! Every [`Vector`] pairs the exact bytes this crate writes for a replay
+//! with the replay itself. [`check_implementation`] feeds the vectors
+//! through a reader and a writer, usually thin wrappers around an FFI or a
+//! subprocess, and reports every mismatch.
+//!
+//! Reading must produce the same inputs or actions as the reference, and
+//! writing must reproduce the reference bytes exactly.
+
+use std::io::Cursor;
+
+use crate::{
+    input::{Button, InputData, PlayerInput},
+    replay::Replay,
+    v3::{atom::AtomVariant, builtin::ActionAtom, ActionType, AttemptKind, Metadata},
+};
+
+/// A decoded replay of either version.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decoded {
+    V2(Replay<Vec<u8>>),
+    V3(crate::v3::Replay),
+}
+
+impl Decoded {
+    /// Whether two decoded replays hold the same data.
+    ///
+    /// v2 replays are compared exactly, deltas included. v3 replays are
+    /// compared with [`crate::v3::Replay::semantically_eq`].
+    pub fn matches(&self, other: &Decoded) -> bool {
+        match (self, other) {
+            (Decoded::V2(a), Decoded::V2(b)) => a == b,
+            (Decoded::V3(a), Decoded::V3(b)) => a.semantically_eq(b),
+            _ => false,
+        }
+    }
+
+    /// Encode the replay with this crate.
+    pub fn write(&self) -> Result<Vec<u8>, crate::ReplayError> {
+        let mut buffer = Vec::new();
+        match self {
+            Decoded::V2(replay) => replay.write(&mut buffer)?,
+            Decoded::V3(replay) => replay.write(&mut buffer)?,
+        }
+        Ok(buffer)
+    }
+
+    /// Decode bytes with this crate, following the version of the reference.
+    fn read_like(&self, bytes: &[u8]) -> Result<Decoded, crate::ReplayError> {
+        let mut cursor = Cursor::new(bytes);
+        Ok(match self {
+            Decoded::V2(_) => Decoded::V2(Replay::read(&mut cursor)?),
+            Decoded::V3(_) => Decoded::V3(crate::v3::Replay::read(&mut cursor)?),
+        })
+    }
+}
+
+/// A reference replay along with its encoding.
+#[derive(Debug, Clone, Copy)]
+pub struct Vector {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub bytes: &'static [u8],
+    decoded: fn() -> Decoded,
+}
+
+impl Vector {
+    /// The replay the bytes decode to.
+    pub fn decoded(&self) -> Decoded {
+        (self.decoded)()
+    }
+}
+
+/// Every reference vector.
+pub const VECTORS: &[Vector] = &[
+    Vector {
+        name: "v2_empty",
+        description: "v2 replay without inputs or meta",
+        bytes: include_bytes!("vectors/v2_empty.slc"),
+        decoded: v2_empty,
+    },
+    Vector {
+        name: "v2_inputs",
+        description: "v2 player inputs for both players, with a raw meta",
+        bytes: include_bytes!("vectors/v2_inputs.slc"),
+        decoded: v2_inputs,
+    },
+    Vector {
+        name: "v2_specials",
+        description: "v2 restarts, deaths, tps changes, skips and wide deltas",
+        bytes: include_bytes!("vectors/v2_specials.slc"),
+        decoded: v2_specials,
+    },
+    Vector {
+        name: "v3_inputs",
+        description: "v3 player actions with repeats and swift pairs",
+        bytes: include_bytes!("vectors/v3_inputs.slc"),
+        decoded: v3_inputs,
+    },
+    Vector {
+        name: "v3_specials",
+        description: "v3 restarts with attempt kinds, deaths and tps changes",
+        bytes: include_bytes!("vectors/v3_specials.slc"),
+        decoded: v3_specials,
+    },
+    Vector {
+        name: "v3_subframe",
+        description: "v3 player actions at sub-frame positions",
+        bytes: include_bytes!("vectors/v3_subframe.slc"),
+        decoded: v3_subframe,
+    },
+];
+
+/// Which half of an implementation a failure was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Read,
+    Write,
+}
+
+/// A vector an implementation didn't handle like the reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub vector: &'static str,
+    pub stage: Stage,
+    pub message: String,
+}
+
+/// Run every vector through an implementation.
+///
+/// `read_fn` decodes the bytes of a vector, and `write_fn` encodes its
+/// reference replay. Either may return an error message, which is reported
+/// as a failure. Returns every failure found, so an empty list means the
+/// implementation conforms.
+pub fn check_implementation<R, W>(mut read_fn: R, mut write_fn: W) -> Vec<Failure>
+where
+    R: FnMut(&[u8]) -> Result<Decoded, String>,
+    W: FnMut(&Decoded) -> Result<Vec<u8>, String>,
+{
+    let mut failures = Vec::new();
+
+    for vector in VECTORS {
+        let expected = vector.decoded();
+        let mut fail = |stage, message| {
+            failures.push(Failure {
+                vector: vector.name,
+                stage,
+                message,
+            })
+        };
+
+        match read_fn(vector.bytes) {
+            Ok(decoded) if decoded.matches(&expected) => {}
+            Ok(decoded) => fail(Stage::Read, format!("decoded to {:?}", decoded)),
+            Err(e) => fail(Stage::Read, e),
+        }
+
+        match write_fn(&expected) {
+            Ok(bytes) if bytes == vector.bytes => {}
+            Ok(bytes) => {
+                let at = bytes
+                    .iter()
+                    .zip(vector.bytes)
+                    .position(|(a, b)| a != b)
+                    .unwrap_or(bytes.len().min(vector.bytes.len()));
+                fail(
+                    Stage::Write,
+                    format!(
+                        "wrote {} bytes instead of {}, first difference at byte {}",
+                        bytes.len(),
+                        vector.bytes.len(),
+                        at
+                    ),
+                )
+            }
+            Err(e) => fail(Stage::Write, e),
+        }
+    }
+
+    failures
+}
+
+/// Run every vector through this crate's own reader and writer.
+pub fn check_self() -> Vec<Failure> {
+    check_implementation(
+        |bytes| {
+            let reference = VECTORS
+                .iter()
+                .find(|v| v.bytes == bytes)
+                .map(|v| v.decoded())
+                .ok_or("unknown vector")?;
+            reference.read_like(bytes).map_err(|e| e.to_string())
+        },
+        |decoded| decoded.write().map_err(|e| e.to_string()),
+    )
+}
+
+fn player(button: Button, hold: bool, player_2: bool) -> InputData {
+    InputData::Player(PlayerInput::new(button, hold, player_2))
+}
+
+fn v2_empty() -> Decoded {
+    Decoded::V2(Replay::new(240.0, Vec::new()))
+}
+
+fn v2_inputs() -> Decoded {
+    let mut replay = Replay::new(240.0, vec![0x5a, 0x4c, 0x43, 0x00, 0x01, 0x02, 0x03, 0x04]);
+    replay.add_input(10, player(Button::Jump, true, false));
+    replay.add_input(15, player(Button::Jump, false, false));
+    replay.add_input(15, player(Button::Left, true, true));
+    replay.add_input(300, player(Button::Left, false, true));
+    replay.add_input(301, player(Button::Right, true, false));
+    replay.add_input(302, player(Button::Right, false, false));
+    Decoded::V2(replay)
+}
+
+fn v2_specials() -> Decoded {
+    let mut replay = Replay::new(360.0, Vec::new());
+    replay.add_input(5, player(Button::Jump, true, false));
+    replay.add_input(6, InputData::Death);
+    replay.add_input(6, InputData::Restart);
+    replay.add_input(70_000, InputData::TPS(480.0));
+    replay.add_input(70_001, player(Button::Jump, false, false));
+    replay.add_input(70_001, InputData::Skip);
+    replay.add_input(5_000_000_000, InputData::RestartFull);
+    Decoded::V2(replay)
+}
+
+fn v3_replay(atom: ActionAtom) -> Decoded {
+    let mut replay = crate::v3::Replay::new(Metadata::new(240.0, 0x5eed, 1));
+    replay.add_atom(AtomVariant::Action(atom));
+    Decoded::V3(replay)
+}
+
+fn v3_inputs() -> Decoded {
+    let mut atom = ActionAtom::new();
+    for i in 0..8 {
+        let frame = 100 + i * 4;
+        atom.add_player_action(frame, ActionType::Jump, true, false)
+            .unwrap();
+        atom.add_player_action(frame + 2, ActionType::Jump, false, false)
+            .unwrap();
+    }
+    atom.add_player_action(200, ActionType::Left, true, true)
+        .unwrap();
+    atom.add_player_action(200, ActionType::Left, false, true)
+        .unwrap();
+    atom.add_player_action(5_000, ActionType::Right, true, false)
+        .unwrap();
+    atom.add_player_action(5_001, ActionType::Right, false, false)
+        .unwrap();
+    v3_replay(atom)
+}
+
+fn v3_specials() -> Decoded {
+    let mut atom = ActionAtom::new();
+    atom.add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_death_action(20, ActionType::Death, 0).unwrap();
+    atom.add_death_action(20, ActionType::Restart, 1234)
+        .unwrap();
+    atom.actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Practice);
+    atom.add_tps_action(100, 480.0).unwrap();
+    atom.add_player_tps_action(150, 120.0, true).unwrap();
+    atom.add_death_action(1_000, ActionType::RestartFull, 99)
+        .unwrap();
+    atom.actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Startpos);
+    v3_replay(atom)
+}
+
+fn v3_subframe() -> Decoded {
+    let mut atom = ActionAtom::new();
+    atom.add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_subframe_player_action(12, 0x8000, ActionType::Jump, false, false)
+        .unwrap();
+    atom.add_subframe_player_action(12, 0xc000, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_player_action(40, ActionType::Jump, false, false)
+        .unwrap();
+    v3_replay(atom)
+}
@@ -0,0 +1,143 @@
+//! Reusable buffers for encoding and decoding many replays.
+//!
+//! Writing or reading a replay allocates planning tables and scratch
+//! buffers. A context keeps them between calls, so a service processing
+//! thousands of replays only grows them to the size of the largest one.
+
+use std::io::{Cursor, Read, Write};
+
+use crate::{
+    blob::{Blob, BlobPlanner},
+    input::Input,
+    meta::Meta,
+    replay::{ReadOptions, Replay, ReplayError, Version},
+    v3::{builtin::PlanScratch, WriteOptions},
+};
+
+/// Buffers reused across replay writes.
+#[derive(Default)]
+pub struct EncodeContext {
+    blobs: BlobPlanner,
+    scratch: PlanScratch,
+    buffer: Vec<u8>,
+}
+
+impl EncodeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a replay in v2 format, like [`Replay::write`].
+    pub fn write<M: Meta, W: Write>(
+        &mut self,
+        replay: &Replay<M>,
+        writer: &mut W,
+    ) -> Result<(), ReplayError> {
+        self.blobs.clear();
+        self.blobs.extend(&replay.inputs);
+        replay.write_v2_with(writer, self.blobs.plan_cached())
+    }
+
+    /// Write a v3 replay, like [`crate::v3::Replay::write_with_options`].
+    pub fn write_v3<W: Write>(
+        &mut self,
+        replay: &crate::v3::Replay,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), crate::v3::replay::ReplayError> {
+        replay.write_with_scratch(writer, options, &mut self.scratch)
+    }
+
+    /// Encode a replay in v2 format into the context's own buffer.
+    ///
+    /// The returned bytes are valid until the next call.
+    pub fn encode<M: Meta>(&mut self, replay: &Replay<M>) -> Result<&[u8], ReplayError> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        let result = self.write(replay, &mut buffer);
+        self.buffer = buffer;
+        result.map(|()| self.buffer.as_slice())
+    }
+
+    /// Encode a v3 replay into the context's own buffer.
+    ///
+    /// The returned bytes are valid until the next call.
+    pub fn encode_v3(
+        &mut self,
+        replay: &crate::v3::Replay,
+        options: &WriteOptions,
+    ) -> Result<&[u8], crate::v3::replay::ReplayError> {
+        self.buffer.clear();
+        replay.write_with_scratch(&mut self.buffer, options, &mut self.scratch)?;
+        Ok(self.buffer.as_slice())
+    }
+}
+
+/// Buffers reused across replay reads.
+///
+/// Replays that are no longer needed can be handed back with
+/// [`DecodeContext::recycle`], so the next replay is decoded into their
+/// input buffer.
+#[derive(Debug, Default)]
+pub struct DecodeContext {
+    options: ReadOptions,
+    buffer: Vec<u8>,
+    blobs: Vec<Blob>,
+    inputs: Vec<Input>,
+}
+
+impl DecodeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits to read replays within, see [`ReadOptions`].
+    pub fn with_read_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Read a replay of either version, like [`Replay::read`].
+    ///
+    /// The stream is read whole into the context's buffer first, so it
+    /// doesn't need to be seekable.
+    pub fn read<M: Meta, R: Read>(&mut self, reader: &mut R) -> Result<Replay<M>, ReplayError> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        let result = reader
+            .read_to_end(&mut buffer)
+            .map_err(ReplayError::from)
+            .and_then(|_| self.decode(&buffer));
+        self.buffer = buffer;
+        result
+    }
+
+    /// Decode a replay of either version from bytes.
+    pub fn decode<M: Meta>(&mut self, bytes: &[u8]) -> Result<Replay<M>, ReplayError> {
+        if bytes.len() < 8 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let inputs = std::mem::take(&mut self.inputs);
+        let mut cursor = Cursor::new(bytes);
+        match Version::detect(bytes) {
+            Some(Version::V2) => {
+                Replay::read_v2(&mut cursor, &self.options, &mut self.blobs, inputs)
+            }
+            Some(Version::V3) => {
+                let v3 = crate::v3::Replay::read_with_options(&mut cursor, &self.options)?;
+                Ok(Replay::from_v3(&v3, inputs))
+            }
+            None => Err(ReplayError::UnknownFormat),
+        }
+    }
+
+    /// Hand back a replay, so its input buffer is reused by the next read.
+    pub fn recycle<M: Meta>(&mut self, replay: Replay<M>) {
+        let mut inputs = replay.inputs;
+        if inputs.capacity() > self.inputs.capacity() {
+            inputs.clear();
+            self.inputs = inputs;
+        }
+    }
+}
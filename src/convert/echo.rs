@@ -0,0 +1,188 @@
+//! Echo macros.
+//!
+//! Echo saves a macro either as JSON or in a binary layout:
+//!
+//! | Offset | Size | Field                                  |
+//! |--------|------|----------------------------------------|
+//! | 0      | 4    | Magic, `MYBO`                          |
+//! | 4      | 4    | Replay type, 0 for frames, 1 for x positions |
+//! | 8      | 4    | FPS, as an `f32`                       |
+//! | 12     | 10n  | Actions                                |
+//!
+//! Each action is the `f32` x position of player 1, the `u32` frame, and
+//! one byte each for whether the button is held and whether it is player 2.
+//! Echo only records the jump button. All values are little endian.
+//!
+//! JSON macros are read with the `json` feature. Both the older keys
+//! (`"FPS"`, `"Echo Replay"`, `"Hold"`, ...) and the newer snake case
+//! ones are accepted.
+
+use std::io::{Read, Write};
+
+use super::ConvertError;
+use crate::{
+    codec,
+    input::{Button, InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+    xpos::PositionInput,
+};
+
+const MAGIC: &[u8; 4] = b"MYBO";
+
+/// What an Echo macro is keyed by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EchoReplayType {
+    #[default]
+    Frames,
+    XPosition,
+}
+
+/// A jump press or release in an Echo macro.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EchoAction {
+    #[cfg_attr(feature = "serde", serde(alias = "Frame"))]
+    pub frame: u32,
+    #[cfg_attr(feature = "serde", serde(alias = "Hold", alias = "hold"))]
+    pub holding: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, alias = "Player 2", alias = "player2")
+    )]
+    pub player_2: bool,
+    #[cfg_attr(feature = "serde", serde(default, alias = "X Position", alias = "x"))]
+    pub x_position: f32,
+}
+
+/// A decoded Echo macro.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EchoReplay {
+    #[cfg_attr(feature = "serde", serde(alias = "FPS"))]
+    pub fps: f32,
+    /// Frame of the start position the macro was recorded from.
+    #[cfg_attr(feature = "serde", serde(default, alias = "Starting Frame"))]
+    pub starting_frame: u32,
+    #[cfg_attr(feature = "serde", serde(default, alias = "Replay Type"))]
+    pub replay_type: EchoReplayType,
+    #[cfg_attr(feature = "serde", serde(alias = "Echo Replay", alias = "inputs"))]
+    pub actions: Vec<EchoAction>,
+}
+
+impl EchoReplay {
+    /// Read a binary Echo macro.
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<Self, ConvertError> {
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(ConvertError::UnknownFormat);
+        }
+        let replay_type = match codec::decode::<u32>(&header[4..8]) {
+            0 => EchoReplayType::Frames,
+            1 => EchoReplayType::XPosition,
+            _ => return Err(ConvertError::InvalidField("replay type")),
+        };
+        let fps = codec::decode::<f32>(&header[8..12]);
+
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        if body.len() % 10 != 0 {
+            return Err(ConvertError::InvalidField("actions"));
+        }
+
+        let actions = body
+            .chunks_exact(10)
+            .map(|record| EchoAction {
+                x_position: codec::decode::<f32>(&record[0..4]),
+                frame: codec::decode::<u32>(&record[4..8]),
+                holding: record[8] != 0,
+                player_2: record[9] != 0,
+            })
+            .collect();
+
+        Ok(Self {
+            fps,
+            starting_frame: 0,
+            replay_type,
+            actions,
+        })
+    }
+
+    /// Write the macro in the binary layout.
+    ///
+    /// The starting frame isn't part of the layout and is dropped.
+    pub fn write_binary<W: Write>(&self, writer: &mut W) -> Result<(), ConvertError> {
+        writer.write_all(MAGIC)?;
+        codec::write(writer, self.replay_type as u32)?;
+        codec::write(writer, self.fps)?;
+        for action in &self.actions {
+            codec::write(writer, action.x_position)?;
+            codec::write(writer, action.frame)?;
+            writer.write_all(&[action.holding as u8, action.player_2 as u8])?;
+        }
+        Ok(())
+    }
+
+    /// Read a JSON Echo macro.
+    #[cfg(feature = "json")]
+    pub fn read_json<R: Read>(reader: &mut R) -> Result<Self, ConvertError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Read an Echo macro, detecting whether it is binary or JSON.
+    ///
+    /// Without the `json` feature, JSON macros are reported as an unknown format.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, ConvertError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.starts_with(MAGIC) {
+            return Self::read_binary(&mut bytes.as_slice());
+        }
+
+        #[cfg(feature = "json")]
+        if bytes.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+            return Self::read_json(&mut bytes.as_slice());
+        }
+        Err(ConvertError::UnknownFormat)
+    }
+
+    fn input(action: &EchoAction) -> InputData {
+        InputData::Player(PlayerInput::new(
+            Button::Jump,
+            action.holding,
+            action.player_2,
+        ))
+    }
+
+    /// Convert the macro to a replay at its fps.
+    ///
+    /// Actions are ordered by frame, keeping the file's order within a
+    /// frame. Frames are kept as Echo recorded them, counting from the start
+    /// of the level even when recorded from a start position.
+    pub fn to_replay<M: Meta>(&self, meta: M) -> Replay<M> {
+        let mut actions: Vec<&EchoAction> = self.actions.iter().collect();
+        actions.sort_by_key(|a| a.frame);
+
+        let mut replay = Replay::new(self.fps as f64, meta);
+        for action in actions {
+            replay.add_input(action.frame as u64, Self::input(action));
+        }
+        replay
+    }
+
+    /// The actions as position-keyed inputs, see [`crate::xpos`].
+    ///
+    /// Useful for macros of [`EchoReplayType::XPosition`], whose frames
+    /// don't hold across fps or physics changes.
+    pub fn positions(&self) -> Vec<PositionInput> {
+        self.actions
+            .iter()
+            .map(|action| PositionInput {
+                x: action.x_position as f64,
+                data: Self::input(action),
+            })
+            .collect()
+    }
+}
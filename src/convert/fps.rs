@@ -0,0 +1,108 @@
+//! Conversion of framerate-based macros to a fixed tps.
+//!
+//! Bots for 2.1 and earlier recorded frames at the game's framerate,
+//! commonly 60 or 360 fps, while 2.2 steps physics at 240 tps. Playing such
+//! a macro back needs every frame mapped to the tick happening at the same
+//! time, which rarely lands exactly on a tick.
+
+use crate::{
+    input::{Input, InputData},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// How frames landing between two ticks are placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// The closest tick, later on ties.
+    #[default]
+    Nearest,
+    /// The tick before.
+    Down,
+    /// The tick after.
+    Up,
+}
+
+/// Options for [`retime`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FpsOptions {
+    /// The tps to convert to.
+    pub tps: f64,
+    pub rounding: Rounding,
+    /// Insert a tps change to the target tps on the first frame, for players
+    /// that follow tps inputs instead of the replay's base tps.
+    pub insert_tps: bool,
+}
+
+impl Default for FpsOptions {
+    fn default() -> Self {
+        Self {
+            tps: 240.0,
+            rounding: Rounding::Nearest,
+            insert_tps: false,
+        }
+    }
+}
+
+/// Tolerance for floating point error before rounding, in ticks.
+const EPSILON: f64 = 1e-9;
+
+fn round(ticks: f64, rounding: Rounding) -> u64 {
+    let nearest = ticks.round();
+    let ticks = if (ticks - nearest).abs() < EPSILON {
+        nearest
+    } else {
+        ticks
+    };
+    let rounded = match rounding {
+        Rounding::Nearest => (ticks + 0.5).floor(),
+        Rounding::Down => ticks.floor(),
+        Rounding::Up => ticks.ceil(),
+    };
+    rounded.max(0.0) as u64
+}
+
+/// Map a frame at a constant framerate to a tick.
+pub fn convert_frame(frame: u64, fps: f64, options: &FpsOptions) -> u64 {
+    round(frame as f64 * options.tps / fps, options.rounding)
+}
+
+/// Convert a replay recorded at its framerate to the target tps.
+///
+/// Frames are mapped through the replay's base tps and any tps changes it
+/// contains, which are removed since the result plays at a single tps.
+/// Inputs keep their order, even when several land on the same tick.
+pub fn retime<M: Meta>(replay: &mut Replay<M>, options: &FpsOptions) {
+    let mut fps = replay.tps;
+    // Start of the current constant framerate segment, in source frames and ticks.
+    let mut segment_frame = 0;
+    let mut segment_ticks = 0.0;
+
+    replay.inputs.retain_mut(|input| {
+        let ticks =
+            segment_ticks + input.frame.saturating_sub(segment_frame) as f64 * options.tps / fps;
+        if let InputData::TPS(new_fps) = input.data {
+            if new_fps > 0.0 {
+                segment_frame = input.frame;
+                segment_ticks = ticks;
+                fps = new_fps;
+            }
+            return false;
+        }
+        input.frame = round(ticks, options.rounding);
+        true
+    });
+
+    if options.insert_tps {
+        replay.inputs.insert(
+            0,
+            Input {
+                frame: 0,
+                delta: 0,
+                data: InputData::TPS(options.tps),
+            },
+        );
+    }
+    replay.tps = options.tps;
+    replay.recalculate_deltas();
+}
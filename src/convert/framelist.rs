@@ -0,0 +1,298 @@
+//! Frame-list macros described by a [`FrameListDescriptor`].
+//!
+//! Many legacy bots store a macro as nothing more than a list of frames,
+//! either one entry per line of text or as fixed-size binary records.
+//! Rather than a module per bot, a descriptor says where the frame and the
+//! optional hold, player and button fields are, and [`read`] does the rest.
+//!
+//! When a layout has no hold field, entries alternate between press and
+//! release, separately for each player and button, starting with a press.
+
+use std::io::{BufRead, BufReader, Read};
+
+use super::ConvertError;
+use crate::{
+    codec,
+    input::{Button, InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// An integer field of a binary record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// Offset from the start of the record, in bytes.
+    pub offset: usize,
+    /// Width of the field in bytes, from 1 to 8.
+    pub size: usize,
+}
+
+impl Field {
+    pub const fn new(offset: usize, size: usize) -> Self {
+        Self { offset, size }
+    }
+
+    fn read(&self, record: &[u8], big_endian: bool) -> Result<u64, ConvertError> {
+        let bytes = record
+            .get(self.offset..self.offset + self.size)
+            .filter(|_| (1..=8).contains(&self.size))
+            .ok_or(ConvertError::InvalidField("descriptor"))?;
+        if big_endian {
+            Ok(codec::decode_uint_be(bytes))
+        } else {
+            Ok(codec::decode_uint(bytes))
+        }
+    }
+}
+
+/// Lines of text, each holding one entry split into columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextLayout {
+    /// Column separator, or `None` to split on whitespace.
+    pub separator: Option<char>,
+    /// Lines starting with this prefix are ignored, as are blank lines.
+    pub comment: Option<String>,
+    /// Lines to ignore at the start, such as a header naming the columns.
+    pub skip_lines: usize,
+    pub frame: usize,
+    pub hold: Option<usize>,
+    pub player: Option<usize>,
+    pub button: Option<usize>,
+}
+
+impl Default for TextLayout {
+    /// One frame per line, alternating presses and releases.
+    fn default() -> Self {
+        Self {
+            separator: None,
+            comment: Some("#".to_owned()),
+            skip_lines: 0,
+            frame: 0,
+            hold: None,
+            player: None,
+            button: None,
+        }
+    }
+}
+
+/// Fixed-size binary records after an optional header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryLayout {
+    /// Bytes to ignore at the start of the file.
+    pub header: usize,
+    pub record_size: usize,
+    pub big_endian: bool,
+    pub frame: Field,
+    pub hold: Option<Field>,
+    pub player: Option<Field>,
+    pub button: Option<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    Text(TextLayout),
+    Binary(BinaryLayout),
+}
+
+/// How to read a frame-list macro.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameListDescriptor {
+    pub layout: Layout,
+    /// Framerate the frames count at, as the format itself doesn't store it.
+    pub tps: f64,
+    /// Value of the player field meaning player 2. Any other value is player 1.
+    pub player_2: u64,
+    /// Button of every entry when the layout has no button field.
+    pub button: Button,
+}
+
+impl FrameListDescriptor {
+    pub fn new(layout: Layout, tps: f64) -> Self {
+        Self {
+            layout,
+            tps,
+            player_2: 2,
+            button: Button::Jump,
+        }
+    }
+
+    /// One frame per line of text, alternating presses and releases.
+    pub fn lines(tps: f64) -> Self {
+        Self::new(Layout::Text(TextLayout::default()), tps)
+    }
+
+    /// Binary records of a single little-endian frame of `size` bytes,
+    /// alternating presses and releases.
+    pub fn records(size: usize, tps: f64) -> Self {
+        Self::new(
+            Layout::Binary(BinaryLayout {
+                header: 0,
+                record_size: size,
+                big_endian: false,
+                frame: Field::new(0, size),
+                hold: None,
+                player: None,
+                button: None,
+            }),
+            tps,
+        )
+    }
+}
+
+/// An entry of a frame-list macro, before holds are resolved.
+struct Entry {
+    frame: u64,
+    hold: Option<bool>,
+    player: Option<u64>,
+    button: Option<u64>,
+}
+
+fn parse_hold(column: &str) -> Option<bool> {
+    match column.to_ascii_lowercase().as_str() {
+        "1" | "true" | "p" | "press" | "down" | "hold" => Some(true),
+        "0" | "false" | "r" | "release" | "up" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_button(column: &str) -> Option<u64> {
+    match column.to_ascii_lowercase().as_str() {
+        "jump" => Some(Button::Jump as u64),
+        "left" => Some(Button::Left as u64),
+        "right" => Some(Button::Right as u64),
+        other => other.parse().ok(),
+    }
+}
+
+fn read_text<R: Read>(reader: &mut R, layout: &TextLayout) -> Result<Vec<Entry>, ConvertError> {
+    let mut entries = Vec::new();
+    for line in BufReader::new(reader).lines().skip(layout.skip_lines) {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty()
+            || layout
+                .comment
+                .as_deref()
+                .is_some_and(|prefix| line.starts_with(prefix))
+        {
+            continue;
+        }
+
+        let columns: Vec<&str> = match layout.separator {
+            Some(separator) => line.split(separator).map(str::trim).collect(),
+            None => line.split_whitespace().collect(),
+        };
+        let column = |index: usize, name: &'static str| {
+            columns
+                .get(index)
+                .copied()
+                .ok_or(ConvertError::InvalidField(name))
+        };
+
+        let frame = column(layout.frame, "frame")?
+            .parse()
+            .map_err(|_| ConvertError::InvalidField("frame"))?;
+        let hold = match layout.hold {
+            Some(i) => {
+                Some(parse_hold(column(i, "hold")?).ok_or(ConvertError::InvalidField("hold"))?)
+            }
+            None => None,
+        };
+        let player = match layout.player {
+            Some(i) => Some(
+                column(i, "player")?
+                    .parse()
+                    .map_err(|_| ConvertError::InvalidField("player"))?,
+            ),
+            None => None,
+        };
+        let button = match layout.button {
+            Some(i) => Some(
+                parse_button(column(i, "button")?).ok_or(ConvertError::InvalidField("button"))?,
+            ),
+            None => None,
+        };
+
+        entries.push(Entry {
+            frame,
+            hold,
+            player,
+            button,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_binary<R: Read>(reader: &mut R, layout: &BinaryLayout) -> Result<Vec<Entry>, ConvertError> {
+    if layout.record_size == 0 {
+        return Err(ConvertError::InvalidField("descriptor"));
+    }
+
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let body = bytes
+        .get(layout.header..)
+        .ok_or(ConvertError::InvalidField("header"))?;
+    if body.len() % layout.record_size != 0 {
+        return Err(ConvertError::InvalidField("records"));
+    }
+
+    let optional = |field: Option<Field>, record: &[u8]| {
+        field
+            .map(|field| field.read(record, layout.big_endian))
+            .transpose()
+    };
+    body.chunks_exact(layout.record_size)
+        .map(|record| {
+            Ok(Entry {
+                frame: layout.frame.read(record, layout.big_endian)?,
+                hold: optional(layout.hold, record)?.map(|hold| hold != 0),
+                player: optional(layout.player, record)?,
+                button: optional(layout.button, record)?,
+            })
+        })
+        .collect()
+}
+
+/// Read a frame-list macro into a replay.
+///
+/// Entries are ordered by frame, keeping the file's order within a frame.
+pub fn read<M: Meta, R: Read>(
+    reader: &mut R,
+    descriptor: &FrameListDescriptor,
+    meta: M,
+) -> Result<Replay<M>, ConvertError> {
+    if !descriptor.tps.is_finite() || descriptor.tps <= 0.0 {
+        return Err(ConvertError::InvalidField("tps"));
+    }
+
+    let mut entries = match &descriptor.layout {
+        Layout::Text(layout) => read_text(reader, layout)?,
+        Layout::Binary(layout) => read_binary(reader, layout)?,
+    };
+    entries.sort_by_key(|e| e.frame);
+
+    // Whether each player's buttons are held, for layouts without a hold field.
+    let mut held = [[false; 3]; 2];
+    let mut replay = Replay::new(descriptor.tps, meta);
+    for entry in entries {
+        let player_2 = entry.player == Some(descriptor.player_2);
+        let button = match entry.button {
+            Some(button) => u8::try_from(button)
+                .ok()
+                .and_then(|b| Button::try_from(b).ok())
+                .ok_or(ConvertError::InvalidField("button"))?,
+            None => descriptor.button,
+        };
+
+        let state = &mut held[player_2 as usize][button as usize - 1];
+        let hold = entry.hold.unwrap_or(!*state);
+        *state = hold;
+
+        replay.add_input(
+            entry.frame,
+            InputData::Player(PlayerInput::new(button, hold, player_2)),
+        );
+    }
+    Ok(replay)
+}
@@ -0,0 +1,399 @@
+//! GDR2 macros.
+//!
+//! GDR2 is a binary layout where every integer is an unsigned LEB128
+//! varint, strings and blobs are a varint length followed by their bytes,
+//! and frames are stored as deltas from the previous entry of their list:
+//!
+//! | Field                 | Encoding                                  |
+//! |-----------------------|-------------------------------------------|
+//! | Magic                 | `GDR`                                     |
+//! | Version               | varint, 2                                 |
+//! | Input tag             | string, empty if inputs have no extension |
+//! | Author, description   | strings                                   |
+//! | Duration              | `f32`, in seconds                         |
+//! | Game version          | varint                                    |
+//! | Framerate             | `f64`                                     |
+//! | Seed, coins           | varints                                   |
+//! | LDM, platformer       | one byte each                             |
+//! | Bot name, version     | string, varint                            |
+//! | Level id, name        | varint, string                            |
+//! | Extension             | blob                                      |
+//! | Deaths                | varint count, then a frame delta for each |
+//! | Inputs                | varint count, then each input             |
+//!
+//! Each input is its frame delta, then `button << 2 | player 2 << 1 | down`
+//! as a varint, followed by its extension blob when the input tag isn't
+//! empty. Floats are little endian.
+//!
+//! The extension blobs belong to the bot that wrote the macro. Converting
+//! to a v3 replay keeps them, along with the rest of the header, in a
+//! [`CustomAtom`] with id [`GDR2_ATOM_ID`], so they survive a round trip
+//! through slc.
+
+use std::io::{Read, Write};
+
+use super::ConvertError;
+use crate::{
+    codec,
+    v3::{
+        atom::{AtomVariant, CustomAtom},
+        builtin::ActionAtom,
+        ActionType, Metadata, Replay,
+    },
+};
+
+const MAGIC: &[u8; 3] = b"GDR";
+const VERSION: u64 = 2;
+
+/// Id of the custom atom holding the GDR2 header and extension blobs.
+pub const GDR2_ATOM_ID: u32 = u32::from_le_bytes(*b"GDR2");
+
+/// A press or release in a GDR2 macro.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gdr2Input {
+    pub frame: u64,
+    /// 1 for jump, 2 for left and 3 for right.
+    pub button: u8,
+    pub down: bool,
+    pub player_2: bool,
+    /// Data the recording bot attached to the input.
+    pub extension: Vec<u8>,
+}
+
+/// A decoded GDR2 macro.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gdr2Replay {
+    /// Names the format of the input extensions. Inputs can only carry
+    /// extensions if it is set.
+    pub input_tag: String,
+    pub author: String,
+    pub description: String,
+    pub duration: f32,
+    pub game_version: u64,
+    pub framerate: f64,
+    pub seed: u64,
+    pub coins: u64,
+    pub ldm: bool,
+    pub platformer: bool,
+    pub bot_name: String,
+    pub bot_version: u64,
+    pub level_id: u64,
+    pub level_name: String,
+    /// Data the recording bot attached to the macro.
+    pub extension: Vec<u8>,
+    /// Frames the player died on.
+    pub deaths: Vec<u64>,
+    pub inputs: Vec<Gdr2Input>,
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64, ConvertError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte: u8 = codec::read(reader)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ConvertError::InvalidField("varint"))
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> Result<(), ConvertError> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[byte])?;
+            return Ok(());
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+/// Read a varint length and that many bytes, without trusting the length
+/// for the allocation.
+fn read_blob<R: Read>(reader: &mut R) -> Result<Vec<u8>, ConvertError> {
+    let length = read_varint(reader)?;
+    let mut blob = Vec::new();
+    reader.take(length).read_to_end(&mut blob)?;
+    if blob.len() as u64 != length {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(blob)
+}
+
+fn write_blob<W: Write>(writer: &mut W, blob: &[u8]) -> Result<(), ConvertError> {
+    write_varint(writer, blob.len() as u64)?;
+    writer.write_all(blob)?;
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R, field: &'static str) -> Result<String, ConvertError> {
+    String::from_utf8(read_blob(reader)?).map_err(|_| ConvertError::InvalidField(field))
+}
+
+fn read_bool<R: Read>(reader: &mut R) -> Result<bool, ConvertError> {
+    Ok(codec::read::<u8, _>(reader)? != 0)
+}
+
+/// Add a frame delta to the previous frame, failing if it overflows.
+fn next_frame(previous: u64, delta: u64) -> Result<u64, ConvertError> {
+    previous
+        .checked_add(delta)
+        .ok_or(ConvertError::InvalidField("frame"))
+}
+
+impl Gdr2Replay {
+    /// Read a GDR2 macro.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, ConvertError> {
+        let mut magic = [0u8; 3];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(ConvertError::UnknownFormat);
+        }
+        if read_varint(reader)? != VERSION {
+            return Err(ConvertError::InvalidField("version"));
+        }
+
+        let mut replay = Self::default();
+        replay.read_header(reader)?;
+
+        let mut frame = 0;
+        for _ in 0..read_varint(reader)? {
+            frame = next_frame(frame, read_varint(reader)?)?;
+            replay.deaths.push(frame);
+        }
+
+        let mut frame = 0;
+        for _ in 0..read_varint(reader)? {
+            frame = next_frame(frame, read_varint(reader)?)?;
+            let state = read_varint(reader)?;
+            let button = u8::try_from(state >> 2)
+                .ok()
+                .filter(|button| (1..=3).contains(button))
+                .ok_or(ConvertError::InvalidField("button"))?;
+            let extension = if replay.input_tag.is_empty() {
+                Vec::new()
+            } else {
+                read_blob(reader)?
+            };
+
+            replay.inputs.push(Gdr2Input {
+                frame,
+                button,
+                down: state & 1 == 1,
+                player_2: state & 2 == 2,
+                extension,
+            });
+        }
+
+        Ok(replay)
+    }
+
+    /// Write the macro.
+    ///
+    /// Fails if an input has an extension but the input tag is empty, or if
+    /// deaths or inputs aren't ordered by frame.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), ConvertError> {
+        writer.write_all(MAGIC)?;
+        write_varint(writer, VERSION)?;
+        self.write_header(writer)?;
+
+        write_varint(writer, self.deaths.len() as u64)?;
+        let mut previous = 0;
+        for &frame in &self.deaths {
+            let delta = frame
+                .checked_sub(previous)
+                .ok_or(ConvertError::InvalidField("death frame"))?;
+            write_varint(writer, delta)?;
+            previous = frame;
+        }
+
+        write_varint(writer, self.inputs.len() as u64)?;
+        let mut previous = 0;
+        for input in &self.inputs {
+            let delta = input
+                .frame
+                .checked_sub(previous)
+                .ok_or(ConvertError::InvalidField("input frame"))?;
+            write_varint(writer, delta)?;
+            let state =
+                (input.button as u64) << 2 | (input.player_2 as u64) << 1 | input.down as u64;
+            write_varint(writer, state)?;
+
+            if !self.input_tag.is_empty() {
+                write_blob(writer, &input.extension)?;
+            } else if !input.extension.is_empty() {
+                return Err(ConvertError::InvalidField("input tag"));
+            }
+            previous = input.frame;
+        }
+
+        Ok(())
+    }
+
+    /// Read everything from the input tag to the macro's extension.
+    fn read_header<R: Read>(&mut self, reader: &mut R) -> Result<(), ConvertError> {
+        self.input_tag = read_string(reader, "input tag")?;
+        self.author = read_string(reader, "author")?;
+        self.description = read_string(reader, "description")?;
+        self.duration = codec::read(reader)?;
+        self.game_version = read_varint(reader)?;
+        self.framerate = codec::read(reader)?;
+        self.seed = read_varint(reader)?;
+        self.coins = read_varint(reader)?;
+        self.ldm = read_bool(reader)?;
+        self.platformer = read_bool(reader)?;
+        self.bot_name = read_string(reader, "bot name")?;
+        self.bot_version = read_varint(reader)?;
+        self.level_id = read_varint(reader)?;
+        self.level_name = read_string(reader, "level name")?;
+        self.extension = read_blob(reader)?;
+        Ok(())
+    }
+
+    fn write_header<W: Write>(&self, writer: &mut W) -> Result<(), ConvertError> {
+        write_blob(writer, self.input_tag.as_bytes())?;
+        write_blob(writer, self.author.as_bytes())?;
+        write_blob(writer, self.description.as_bytes())?;
+        codec::write(writer, self.duration)?;
+        write_varint(writer, self.game_version)?;
+        codec::write(writer, self.framerate)?;
+        write_varint(writer, self.seed)?;
+        write_varint(writer, self.coins)?;
+        writer.write_all(&[self.ldm as u8, self.platformer as u8])?;
+        write_blob(writer, self.bot_name.as_bytes())?;
+        write_varint(writer, self.bot_version)?;
+        write_varint(writer, self.level_id)?;
+        write_blob(writer, self.level_name.as_bytes())?;
+        write_blob(writer, &self.extension)?;
+        Ok(())
+    }
+
+    /// The header and extension blobs, as stored in the [`GDR2_ATOM_ID`] atom.
+    ///
+    /// The header is followed by the inputs with an extension, each as its
+    /// index among the inputs and its blob.
+    fn custom_atom(&self) -> Result<CustomAtom, ConvertError> {
+        let mut data = Vec::new();
+        self.write_header(&mut data)?;
+
+        let extended: Vec<_> = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| !input.extension.is_empty())
+            .collect();
+        write_varint(&mut data, extended.len() as u64)?;
+        for (index, input) in extended {
+            write_varint(&mut data, index as u64)?;
+            write_blob(&mut data, &input.extension)?;
+        }
+
+        Ok(CustomAtom::new(GDR2_ATOM_ID, data))
+    }
+
+    /// Convert the macro to a v3 replay at its framerate.
+    ///
+    /// Inputs become player actions and deaths become death actions. The
+    /// header and every extension blob go in a [`GDR2_ATOM_ID`] custom atom,
+    /// which [`Gdr2Replay::from_replay_v3`] restores them from.
+    pub fn to_replay_v3(&self) -> Result<Replay, ConvertError> {
+        let mut atom = ActionAtom::new();
+        let mut deaths = self.deaths.iter().peekable();
+        for input in &self.inputs {
+            while let Some(frame) = deaths.next_if(|&&frame| frame < input.frame) {
+                atom.add_death_action(*frame, ActionType::Death, 0)
+                    .map_err(|_| ConvertError::InvalidField("death frame"))?;
+            }
+            let action_type = match input.button {
+                1 => ActionType::Jump,
+                2 => ActionType::Left,
+                3 => ActionType::Right,
+                _ => return Err(ConvertError::InvalidField("button")),
+            };
+            atom.add_player_action(input.frame, action_type, input.down, input.player_2)
+                .map_err(|_| ConvertError::InvalidField("input frame"))?;
+        }
+        for frame in deaths {
+            atom.add_death_action(*frame, ActionType::Death, 0)
+                .map_err(|_| ConvertError::InvalidField("death frame"))?;
+        }
+
+        let mut replay = Replay::new(Metadata::new(self.framerate, self.seed, 0));
+        replay.add_atom(AtomVariant::Action(atom));
+        replay.add_atom(AtomVariant::Custom(self.custom_atom()?));
+        Ok(replay)
+    }
+
+    /// Convert a v3 replay to a GDR2 macro.
+    ///
+    /// Player and death actions of every action atom are kept, other actions
+    /// are dropped. The framerate and seed come from the replay's metadata,
+    /// the rest of the header and the extension blobs from its
+    /// [`GDR2_ATOM_ID`] atom, if it has one.
+    pub fn from_replay_v3(replay: &Replay) -> Result<Self, ConvertError> {
+        let mut actions: Vec<_> = replay
+            .atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a.actions.iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        actions.sort_by_key(|a| a.frame);
+
+        let mut gdr = Self::default();
+        for action in actions {
+            let button = match action.action_type {
+                ActionType::Jump => 1,
+                ActionType::Left => 2,
+                ActionType::Right => 3,
+                ActionType::Death => {
+                    gdr.deaths.push(action.frame);
+                    continue;
+                }
+                _ => continue,
+            };
+            gdr.inputs.push(Gdr2Input {
+                frame: action.frame,
+                button,
+                down: action.holding,
+                player_2: action.player2,
+                extension: Vec::new(),
+            });
+        }
+
+        let custom = replay.atoms.iter().find_map(|atom| match atom {
+            AtomVariant::Custom(a) if a.id == GDR2_ATOM_ID => Some(a),
+            _ => None,
+        });
+        match custom {
+            Some(custom) => gdr.restore(&mut custom.data.as_slice())?,
+            None => {
+                let last_frame = gdr.inputs.last().map_or(0, |i| i.frame);
+                gdr.duration = (last_frame as f64 / replay.metadata.tps) as f32;
+            }
+        }
+        gdr.framerate = replay.metadata.tps;
+        gdr.seed = replay.metadata.seed;
+
+        Ok(gdr)
+    }
+
+    /// Restore the header and input extensions from a [`GDR2_ATOM_ID`] atom.
+    fn restore<R: Read>(&mut self, reader: &mut R) -> Result<(), ConvertError> {
+        self.read_header(reader)?;
+        for _ in 0..read_varint(reader)? {
+            let index = usize::try_from(read_varint(reader)?)
+                .map_err(|_| ConvertError::InvalidField("input extension"))?;
+            let input = self
+                .inputs
+                .get_mut(index)
+                .ok_or(ConvertError::InvalidField("input extension"))?;
+            input.extension = read_blob(reader)?;
+        }
+        Ok(())
+    }
+}
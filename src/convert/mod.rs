@@ -0,0 +1,26 @@
+//! Importers for the macro formats of other bots.
+//!
+//! Each converter parses its format into a small struct mirroring the
+//! file, which is then turned into a [`crate::Replay`].
+
+pub mod echo;
+pub mod fps;
+pub mod framelist;
+pub mod gdr2;
+#[cfg(feature = "json")]
+pub mod urf;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unknown format")]
+    UnknownFormat,
+    #[error("Invalid field: {0}")]
+    InvalidField(&'static str),
+}
@@ -0,0 +1,223 @@
+//! Universal replay format macros.
+//!
+//! The universal replay format is a JSON list of inputs, each a frame, a
+//! button, a player and whether the button is held. Bots writing it
+//! disagree on the details, so reading is lenient:
+//!
+//! - The inputs are either the whole document, or under `"inputs"`,
+//!   `"actions"`, `"macro"` or `"replay"`, next to the framerate under
+//!   `"tps"`, `"fps"` or `"framerate"`. A missing framerate means 240.
+//! - The frame is `"frame"` or `"f"`, as a number or a string of one.
+//! - Holding is `"hold"`, `"holding"`, `"down"` or `"pressed"`, as a
+//!   boolean or 0 and 1.
+//! - The player is `"player"`, 1 or 2 with 0 taken as player 1, or a
+//!   boolean `"player2"`, `"player_2"` or `"p2"`. It defaults to player 1.
+//! - The button is `"button"`, `"btn"` or `"key"`, as a number (1 to 3) or
+//!   a name (`"jump"`, `"left"`, `"right"`). It defaults to jump.
+//!
+//! Available with the `json` feature. [`UrfReplay::write`] produces the
+//! plain dialect: `tps` and `inputs` with `frame`, `hold`, `player` (1 or 2)
+//! and `button` (1 to 3).
+
+use std::io::{Read, Write};
+
+use serde_json::{json, Map, Value};
+
+use super::ConvertError;
+use crate::{
+    input::{Button, InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+};
+
+const DEFAULT_TPS: f64 = 240.0;
+
+/// A button press or release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UrfInput {
+    pub frame: u64,
+    pub hold: bool,
+    pub player_2: bool,
+    pub button: Button,
+}
+
+/// A decoded universal replay format macro.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UrfReplay {
+    pub tps: f64,
+    pub inputs: Vec<UrfInput>,
+}
+
+fn field<'a>(object: &'a Map<String, Value>, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| object.get(*key))
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        other => match as_u64(other)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        },
+    }
+}
+
+fn as_button(value: &Value) -> Option<Button> {
+    match value {
+        Value::String(s) => match s.trim().to_ascii_lowercase().as_str() {
+            "jump" | "click" => Some(Button::Jump),
+            "left" => Some(Button::Left),
+            "right" => Some(Button::Right),
+            other => Button::try_from(other.parse::<u8>().ok()?).ok(),
+        },
+        other => Button::try_from(u8::try_from(as_u64(other)?).ok()?).ok(),
+    }
+}
+
+fn parse_input(value: &Value) -> Result<UrfInput, ConvertError> {
+    let object = value
+        .as_object()
+        .ok_or(ConvertError::InvalidField("input"))?;
+
+    let frame = field(object, &["frame", "f"])
+        .and_then(as_u64)
+        .ok_or(ConvertError::InvalidField("frame"))?;
+    let hold = field(object, &["hold", "holding", "down", "pressed"])
+        .and_then(as_bool)
+        .ok_or(ConvertError::InvalidField("hold"))?;
+
+    let player_2 = if let Some(player) = field(object, &["player2", "player_2", "p2"]) {
+        as_bool(player).ok_or(ConvertError::InvalidField("player"))?
+    } else if let Some(player) = object.get("player") {
+        match as_u64(player) {
+            Some(0 | 1) => false,
+            Some(2) => true,
+            _ => return Err(ConvertError::InvalidField("player")),
+        }
+    } else {
+        false
+    };
+
+    let button = match field(object, &["button", "btn", "key"]) {
+        Some(button) => as_button(button).ok_or(ConvertError::InvalidField("button"))?,
+        None => Button::Jump,
+    };
+
+    Ok(UrfInput {
+        frame,
+        hold,
+        player_2,
+        button,
+    })
+}
+
+impl UrfReplay {
+    /// Parse a macro from a JSON value, in any of the accepted dialects.
+    pub fn from_value(value: &Value) -> Result<Self, ConvertError> {
+        let (tps, inputs) = match value {
+            Value::Array(inputs) => (None, inputs),
+            Value::Object(object) => {
+                let inputs = field(object, &["inputs", "actions", "macro", "replay"])
+                    .and_then(Value::as_array)
+                    .ok_or(ConvertError::InvalidField("inputs"))?;
+                let tps = match field(object, &["tps", "fps", "framerate"]) {
+                    Some(tps) => Some(tps.as_f64().ok_or(ConvertError::InvalidField("tps"))?),
+                    None => None,
+                };
+                (tps, inputs)
+            }
+            _ => return Err(ConvertError::UnknownFormat),
+        };
+
+        let tps = tps.unwrap_or(DEFAULT_TPS);
+        if !tps.is_finite() || tps <= 0.0 {
+            return Err(ConvertError::InvalidField("tps"));
+        }
+
+        Ok(Self {
+            tps,
+            inputs: inputs.iter().map(parse_input).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Read a macro, in any of the accepted dialects.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, ConvertError> {
+        let value: Value = serde_json::from_reader(reader)?;
+        Self::from_value(&value)
+    }
+
+    /// The macro as a JSON value in the plain dialect.
+    pub fn to_value(&self) -> Value {
+        let inputs: Vec<Value> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                json!({
+                    "frame": input.frame,
+                    "hold": input.hold,
+                    "player": if input.player_2 { 2 } else { 1 },
+                    "button": input.button as u8,
+                })
+            })
+            .collect();
+        json!({ "tps": self.tps, "inputs": inputs })
+    }
+
+    /// Write the macro as JSON in the plain dialect.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), ConvertError> {
+        serde_json::to_writer(writer, &self.to_value())?;
+        Ok(())
+    }
+
+    /// Convert the macro to a replay.
+    ///
+    /// Inputs are ordered by frame, keeping the file's order within a frame.
+    pub fn to_replay<M: Meta>(&self, meta: M) -> Replay<M> {
+        let mut inputs: Vec<&UrfInput> = self.inputs.iter().collect();
+        inputs.sort_by_key(|i| i.frame);
+
+        let mut replay = Replay::new(self.tps, meta);
+        for input in inputs {
+            replay.add_input(
+                input.frame,
+                InputData::Player(PlayerInput::new(input.button, input.hold, input.player_2)),
+            );
+        }
+        replay
+    }
+
+    /// Collect the player inputs of a replay.
+    ///
+    /// Inputs other than button presses and releases have no equivalent and
+    /// are dropped, as are player inputs with an invalid button.
+    pub fn from_replay<M: Meta>(replay: &Replay<M>) -> Self {
+        let inputs = replay
+            .iter()
+            .filter_map(|input| match &input.data {
+                InputData::Player(p) => Some(UrfInput {
+                    frame: input.frame,
+                    hold: p.hold,
+                    player_2: p.player_2,
+                    button: p.button_type().ok()?,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            tps: replay.tps,
+            inputs,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Comparison of replays and replay files.
+
+use std::{fmt::Display, fs::File, io::BufReader, path::Path};
+
+use crate::{
+    input::{Input, InputData},
+    meta::Meta,
+    order::SameFrameOrder,
+    replay::{Replay, ReplayError},
+};
+
+/// A single difference between two replays.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// The replays start with a different tps.
+    Tps { left: f64, right: f64 },
+    /// Both replays have a meta, and their bytes differ.
+    Meta,
+    /// An input only exists in the left replay.
+    OnlyLeft(Input),
+    /// An input only exists in the right replay.
+    OnlyRight(Input),
+    /// Both replays have an input on the same frame, with different data.
+    Changed { left: Input, right: Input },
+}
+
+impl Divergence {
+    /// The frame the divergence happens on, if it concerns an input.
+    pub fn frame(&self) -> Option<u64> {
+        match self {
+            Self::Tps { .. } | Self::Meta => None,
+            Self::OnlyLeft(input) | Self::OnlyRight(input) => Some(input.frame),
+            Self::Changed { left, .. } => Some(left.frame),
+        }
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tps { left, right } => write!(f, "tps: {} != {}", left, right),
+            Self::Meta => write!(f, "meta differs"),
+            Self::OnlyLeft(input) => write!(f, "- {}", input),
+            Self::OnlyRight(input) => write!(f, "+ {}", input),
+            Self::Changed { left, right } => {
+                write!(
+                    f,
+                    "~ frame: {}, {} -> {}",
+                    left.frame, left.data, right.data
+                )
+            }
+        }
+    }
+}
+
+/// The structured result of comparing two replays.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    pub divergences: Vec<Divergence>,
+}
+
+impl DiffReport {
+    /// Whether the replays are equivalent.
+    pub fn is_identical(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// The first divergence concerning an input, in frame order.
+    pub fn first_divergence(&self) -> Option<&Divergence> {
+        self.divergences.iter().find(|d| d.frame().is_some())
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.divergences.is_empty() {
+            return writeln!(f, "replays are identical");
+        }
+
+        for divergence in &self.divergences {
+            writeln!(f, "{}", divergence)?;
+        }
+        writeln!(f, "{} divergences", self.divergences.len())
+    }
+}
+
+/// Bring a replay into a canonical shape for comparison.
+///
+/// Inputs are stably sorted by frame, skips are removed (v3 has no way to
+/// represent them) and deltas are recalculated.
+pub fn normalize<M: Meta>(replay: &mut Replay<M>) {
+    normalize_with(replay, SameFrameOrder::Insertion);
+}
+
+/// Like [`normalize`], also ordering player inputs on the same frame.
+pub fn normalize_with<M: Meta>(replay: &mut Replay<M>, order: SameFrameOrder) {
+    replay.inputs.retain(|i| i.data != InputData::Skip);
+    order.sort_inputs(&mut replay.inputs);
+    replay.recalculate_deltas();
+}
+
+/// Compare two input lists, walking both in frame order.
+///
+/// Inputs are matched by frame and data; deltas are ignored.
+pub fn diff_inputs(left: &[Input], right: &[Input]) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < left.len() && j < right.len() {
+        let (l, r) = (&left[i], &right[j]);
+
+        if l.frame < r.frame {
+            divergences.push(Divergence::OnlyLeft(l.clone()));
+            i += 1;
+        } else if r.frame < l.frame {
+            divergences.push(Divergence::OnlyRight(r.clone()));
+            j += 1;
+        } else {
+            if l.data != r.data {
+                divergences.push(Divergence::Changed {
+                    left: l.clone(),
+                    right: r.clone(),
+                });
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    divergences.extend(left[i..].iter().cloned().map(Divergence::OnlyLeft));
+    divergences.extend(right[j..].iter().cloned().map(Divergence::OnlyRight));
+
+    divergences
+}
+
+/// Compare two replays, possibly with different metas.
+///
+/// Metas are only compared when both replays have one, since v3 replays carry none.
+pub fn diff<M: Meta, N: Meta>(left: &Replay<M>, right: &Replay<N>) -> DiffReport {
+    let mut divergences = Vec::new();
+
+    if left.tps != right.tps {
+        divergences.push(Divergence::Tps {
+            left: left.tps,
+            right: right.tps,
+        });
+    }
+
+    let (left_meta, right_meta) = (left.meta.to_bytes(), right.meta.to_bytes());
+    if !left_meta.is_empty() && !right_meta.is_empty() && left_meta != right_meta {
+        divergences.push(Divergence::Meta);
+    }
+
+    divergences.extend(diff_inputs(&left.inputs, &right.inputs));
+
+    DiffReport { divergences }
+}
+
+/// Load two replay files of any version, normalize both and compare them.
+pub fn diff_files<P: AsRef<Path>, Q: AsRef<Path>>(
+    left: P,
+    right: Q,
+) -> Result<DiffReport, ReplayError> {
+    let load = |path: &Path| -> Result<Replay<Vec<u8>>, ReplayError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut replay = Replay::read(&mut reader)?;
+        normalize(&mut replay);
+        Ok(replay)
+    };
+
+    Ok(diff(&load(left.as_ref())?, &load(right.as_ref())?))
+}
@@ -0,0 +1,80 @@
+//! Differential testing of the v2 and v3 codecs.
+//!
+//! Both formats describe the same inputs, but are encoded by unrelated
+//! code. [`roundtrip`] runs inputs through both and reports where the
+//! decoded results stop agreeing.
+
+use std::io::Cursor;
+
+use thiserror::Error;
+
+use crate::{
+    input::{Input, InputData},
+    replay::{Replay, ReplayError},
+};
+
+/// The first input the two codecs decoded differently.
+///
+/// Either side is `None` when its decoded inputs ended early.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Index in the decoded inputs.
+    pub index: usize,
+    pub v2: Option<(u64, InputData)>,
+    pub v3: Option<(u64, InputData)>,
+}
+
+#[derive(Debug, Error)]
+pub enum DifferentialError {
+    #[error("v2 codec error: {0}")]
+    V2(ReplayError),
+    #[error("v3 codec error: {0}")]
+    V3(ReplayError),
+    #[error("codecs diverge at input {}", .0.index)]
+    Diverged(Divergence),
+}
+
+fn through_v2(replay: &Replay<()>) -> Result<Replay<()>, ReplayError> {
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer)?;
+    Replay::read(&mut Cursor::new(buffer))
+}
+
+fn through_v3(replay: &Replay<()>) -> Result<Replay<()>, ReplayError> {
+    let mut buffer = Vec::new();
+    replay.write_v3(&mut buffer)?;
+    Replay::read(&mut Cursor::new(buffer))
+}
+
+fn normalized(replay: &Replay<()>) -> Vec<(u64, InputData)> {
+    let mut inputs: Vec<_> = replay
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.data.clone()))
+        .collect();
+    inputs.sort_by_key(|(frame, _)| *frame);
+    inputs
+}
+
+/// Encode inputs in both formats, decode them back and compare the results.
+///
+/// Inputs are added in order at their frames, so their deltas don't matter.
+pub fn roundtrip(tps: f64, inputs: &[Input]) -> Result<(), DifferentialError> {
+    let mut replay = Replay::new(tps, ());
+    for input in inputs {
+        replay.add_input(input.frame, input.data.clone());
+    }
+
+    let v2 = normalized(&through_v2(&replay).map_err(DifferentialError::V2)?);
+    let v3 = normalized(&through_v3(&replay).map_err(DifferentialError::V3)?);
+
+    let index = (0..v2.len().max(v3.len())).find(|&i| v2.get(i) != v3.get(i));
+    match index {
+        Some(index) => Err(DifferentialError::Diverged(Divergence {
+            index,
+            v2: v2.get(index).cloned(),
+            v3: v3.get(index).cloned(),
+        })),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,241 @@
+//! Undoable editing of a replay.
+//!
+//! An [`EditSession`] owns a replay and records every edit made through it
+//! as a splice of the input list, along with the inputs it replaced. Undoing
+//! an edit splices the old inputs back, so editors get undo and redo without
+//! working out the inverse of each operation themselves.
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::{
+    input::{Input, InputData},
+    meta::Meta,
+    replay::Replay,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EditError {
+    #[error("input range {start}..{end} is out of bounds for {len} inputs")]
+    OutOfBounds {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    #[error("edit would leave inputs out of frame order")]
+    Unordered,
+}
+
+/// One edit: the inputs at `index` that were replaced, and their replacement.
+#[derive(Debug, Clone, PartialEq)]
+struct Edit {
+    index: usize,
+    removed: Vec<Input>,
+    inserted: Vec<Input>,
+}
+
+impl Edit {
+    fn inverse(self) -> Self {
+        Self {
+            index: self.index,
+            removed: self.inserted,
+            inserted: self.removed,
+        }
+    }
+}
+
+/// A replay with undo and redo stacks.
+///
+/// Edits are expressed by index into the replay's inputs, which always stay
+/// sorted by frame: an edit that would break the order fails and leaves the
+/// replay as it was. Deltas are recalculated after every edit. Making an
+/// edit clears the redo stack.
+#[derive(Debug, Clone)]
+pub struct EditSession<M: Meta> {
+    replay: Replay<M>,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl<M: Meta> EditSession<M> {
+    pub fn new(replay: Replay<M>) -> Self {
+        Self {
+            replay,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn replay(&self) -> &Replay<M> {
+        &self.replay
+    }
+
+    /// End the session, keeping the edited replay.
+    pub fn into_replay(self) -> Replay<M> {
+        self.replay
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Amount of edits that can be undone.
+    pub fn undo_depth(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Insert an input after every input on its frame. Returns its index.
+    pub fn insert(&mut self, frame: u64, data: InputData) -> usize {
+        let index = self.replay.inputs.partition_point(|i| i.frame <= frame);
+        let input = Input {
+            delta: 0,
+            frame,
+            data,
+        };
+        self.record(index, 0, vec![input]);
+        index
+    }
+
+    /// Remove a range of inputs, returning them.
+    pub fn remove(&mut self, range: Range<usize>) -> Result<Vec<Input>, EditError> {
+        self.check_range(&range)?;
+        let removed = self.replay.inputs[range.clone()].to_vec();
+        self.record(range.start, range.len(), Vec::new());
+        Ok(removed)
+    }
+
+    /// Move a range of inputs by an amount of frames.
+    ///
+    /// Fails if an input would be moved before frame 0 or past one of its
+    /// neighbours outside of the range.
+    pub fn shift(&mut self, range: Range<usize>, offset: i64) -> Result<(), EditError> {
+        self.check_range(&range)?;
+        let shifted = self.replay.inputs[range.clone()]
+            .iter()
+            .map(|input| {
+                let frame = input
+                    .frame
+                    .checked_add_signed(offset)
+                    .ok_or(EditError::Unordered)?;
+                Ok(Input {
+                    frame,
+                    ..input.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.replace(range, shifted)
+    }
+
+    /// Replace a range of inputs with new ones, given as frames and data.
+    ///
+    /// The new inputs must be sorted by frame and fit between the inputs
+    /// around the range.
+    pub fn splice<I: IntoIterator<Item = (u64, InputData)>>(
+        &mut self,
+        range: Range<usize>,
+        inputs: I,
+    ) -> Result<(), EditError> {
+        self.check_range(&range)?;
+        let inputs = inputs
+            .into_iter()
+            .map(|(frame, data)| Input {
+                delta: 0,
+                frame,
+                data,
+            })
+            .collect();
+        self.replace(range, inputs)
+    }
+
+    /// Undo the last edit. Returns whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        let inverse = self.splice_raw(edit.inverse());
+        self.redo.push(inverse.inverse());
+        true
+    }
+
+    /// Redo the last undone edit. Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        let edit = self.splice_raw(edit);
+        self.undo.push(edit);
+        true
+    }
+
+    fn check_range(&self, range: &Range<usize>) -> Result<(), EditError> {
+        let len = self.replay.inputs.len();
+        if range.start > range.end || range.end > len {
+            return Err(EditError::OutOfBounds {
+                start: range.start,
+                end: range.end,
+                len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replace a checked range, if the replacement keeps the frame order.
+    fn replace(&mut self, range: Range<usize>, inputs: Vec<Input>) -> Result<(), EditError> {
+        let before = range
+            .start
+            .checked_sub(1)
+            .map_or(0, |i| self.replay.inputs[i].frame);
+        let after = self
+            .replay
+            .inputs
+            .get(range.end)
+            .map_or(u64::MAX, |i| i.frame);
+
+        let mut previous = before;
+        for input in &inputs {
+            if input.frame < previous {
+                return Err(EditError::Unordered);
+            }
+            previous = input.frame;
+        }
+        if previous > after {
+            return Err(EditError::Unordered);
+        }
+
+        self.record(range.start, range.len(), inputs);
+        Ok(())
+    }
+
+    /// Apply a new edit and push it on the undo stack.
+    fn record(&mut self, index: usize, count: usize, inserted: Vec<Input>) {
+        let removed = self.replay.inputs[index..index + count].to_vec();
+        let edit = self.splice_raw(Edit {
+            index,
+            removed,
+            inserted,
+        });
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+
+    /// Splice the inputs of an edit in, returning the edit as applied.
+    fn splice_raw(&mut self, edit: Edit) -> Edit {
+        let end = edit.index + edit.removed.len();
+        self.replay
+            .inputs
+            .splice(edit.index..end, edit.inserted.iter().cloned());
+        self.replay.recalculate_deltas();
+        edit
+    }
+}
+
+impl<M: Meta> From<Replay<M>> for EditSession<M> {
+    fn from(replay: Replay<M>) -> Self {
+        Self::new(replay)
+    }
+}
@@ -0,0 +1,139 @@
+//! Click timings for click sound renderers.
+//!
+//! Click renderers place a press or release sound at each button event,
+//! picking a softer sample for clicks following each other quickly. The
+//! events are timed in seconds following the tps changes of the replay,
+//! since renderers work on audio time rather than frames.
+
+use std::io::Write;
+
+use crate::{input::InputData, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// Whether a click event presses or releases its button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ClickKind {
+    Press,
+    Release,
+}
+
+/// A button press or release, timed for audio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Click {
+    /// Seconds from the start of the replay.
+    pub time: f64,
+    pub frame: u64,
+    /// Player 1 or 2.
+    pub player: u8,
+    pub button: u8,
+    pub kind: ClickKind,
+    /// Whether the event follows the previous event of the same button closely.
+    pub soft: bool,
+}
+
+/// Options for [`clicks`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClickOptions {
+    /// Events closer than this to the previous event of the same button and
+    /// player are soft, in seconds.
+    pub soft_threshold: f64,
+}
+
+impl Default for ClickOptions {
+    fn default() -> Self {
+        Self {
+            soft_threshold: 0.1,
+        }
+    }
+}
+
+/// The click events of a replay, in order.
+///
+/// Repeated presses of a held button and releases of a button that isn't
+/// held are dropped, as they make no sound. Restarts release every button
+/// silently.
+pub fn clicks<M: Meta>(replay: &Replay<M>, options: &ClickOptions) -> Vec<Click> {
+    let clock = TpsTimeline::from(replay);
+    // Whether each button is held, and the time of its last event.
+    let mut state = [[(false, f64::NEG_INFINITY); 4]; 2];
+    let mut clicks = Vec::new();
+
+    for input in replay.iter() {
+        match &input.data {
+            InputData::Player(p) => {
+                let (held, last) = &mut state[p.player_2 as usize][(p.button as usize).min(3)];
+                if *held == p.hold {
+                    continue;
+                }
+
+                let time = clock.seconds(input.frame);
+                clicks.push(Click {
+                    time,
+                    frame: input.frame,
+                    player: 1 + p.player_2 as u8,
+                    button: p.button,
+                    kind: if p.hold {
+                        ClickKind::Press
+                    } else {
+                        ClickKind::Release
+                    },
+                    soft: time - *last < options.soft_threshold,
+                });
+                *held = p.hold;
+                *last = time;
+            }
+            InputData::Restart | InputData::RestartFull => {
+                state
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|(held, _)| *held = false);
+            }
+            _ => {}
+        }
+    }
+    clicks
+}
+
+/// Write click events as a JSON project, with the replay's base tps.
+///
+/// Available with the `json` feature. The project is an object holding
+/// `tps` and a `clicks` array of [`Click`] objects.
+#[cfg(feature = "json")]
+pub fn write_project<W: Write>(
+    writer: &mut W,
+    tps: f64,
+    clicks: &[Click],
+) -> Result<(), crate::json::JsonError> {
+    #[derive(serde::Serialize)]
+    struct Project<'a> {
+        tps: f64,
+        clicks: &'a [Click],
+    }
+
+    serde_json::to_writer_pretty(writer, &Project { tps, clicks })?;
+    Ok(())
+}
+
+/// Write click events as text, one per line.
+///
+/// Each line holds the time in seconds, the player, the button, `press` or
+/// `release`, and `soft` or `hard`, separated by spaces.
+pub fn write_text<W: Write>(writer: &mut W, clicks: &[Click]) -> std::io::Result<()> {
+    for click in clicks {
+        writeln!(
+            writer,
+            "{:.6} {} {} {} {}",
+            click.time,
+            click.player,
+            click.button,
+            match click.kind {
+                ClickKind::Press => "press",
+                ClickKind::Release => "release",
+            },
+            if click.soft { "soft" } else { "hard" }
+        )?;
+    }
+    Ok(())
+}
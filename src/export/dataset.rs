@@ -0,0 +1,108 @@
+//! Flat arrays of player inputs for machine learning.
+//!
+//! Available with the `dataset` feature. Every player input becomes a row
+//! of a fixed schema, written as a NumPy structured array that
+//! `numpy.load` reads directly:
+//!
+//! | Field     | Type  | Meaning                                         |
+//! |-----------|-------|-------------------------------------------------|
+//! | `frame`   | `u64` | Frame of the input                              |
+//! | `dt`      | `f64` | Seconds since the previous row, following tps changes |
+//! | `jump`    | `u8`  | 1 if the input is for the jump button           |
+//! | `left`    | `u8`  | 1 if the input is for the left button           |
+//! | `right`   | `u8`  | 1 if the input is for the right button          |
+//! | `player`  | `u8`  | 1 or 2                                          |
+//! | `holding` | `u8`  | 1 for a press, 0 for a release                  |
+
+use std::io::{self, Write};
+
+use crate::{codec, input::InputData, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// NumPy description of a [`Row`], as written in `.npy` headers.
+pub const DTYPE: &str = "[('frame', '<u8'), ('dt', '<f8'), ('jump', '|u1'), ('left', '|u1'), ('right', '|u1'), ('player', '|u1'), ('holding', '|u1')]";
+
+/// Size of a row in a `.npy` file, in bytes.
+pub const ROW_SIZE: usize = 21;
+
+/// A player input of a dataset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Row {
+    pub frame: u64,
+    /// Seconds since the previous row, or since the start for the first one.
+    pub dt: f64,
+    /// Which of jump, left and right the input is for.
+    pub buttons: [bool; 3],
+    /// 1 or 2.
+    pub player: u8,
+    pub holding: bool,
+}
+
+impl Row {
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut bytes = [0u8; ROW_SIZE];
+        codec::encode(self.frame, &mut bytes[0..8]);
+        codec::encode(self.dt, &mut bytes[8..16]);
+        for (byte, &held) in bytes[16..19].iter_mut().zip(&self.buttons) {
+            *byte = held as u8;
+        }
+        bytes[19] = self.player;
+        bytes[20] = self.holding as u8;
+        writer.write_all(&bytes)
+    }
+}
+
+/// The player inputs of a replay as rows, in order.
+///
+/// Inputs with invalid buttons are left out, along with every input that
+/// isn't a player input.
+pub fn rows<M: Meta>(replay: &Replay<M>) -> Vec<Row> {
+    let clock = TpsTimeline::from(replay);
+    let mut previous = 0.0;
+
+    replay
+        .iter()
+        .filter_map(|input| {
+            let InputData::Player(p) = &input.data else {
+                return None;
+            };
+            if !(1..=3).contains(&p.button) {
+                return None;
+            }
+
+            let time = clock.seconds(input.frame);
+            let mut buttons = [false; 3];
+            buttons[p.button as usize - 1] = true;
+            let row = Row {
+                frame: input.frame,
+                dt: time - previous,
+                buttons,
+                player: 1 + p.player_2 as u8,
+                holding: p.hold,
+            };
+            previous = time;
+            Some(row)
+        })
+        .collect()
+}
+
+/// Write rows as a version 1.0 `.npy` file.
+pub fn write_npy<W: Write>(writer: &mut W, rows: &[Row]) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': {}, 'fortran_order': False, 'shape': ({},), }}",
+        DTYPE,
+        rows.len()
+    );
+    // The magic, version and header length take 10 bytes, and the data
+    // starts aligned to 64 bytes after a newline.
+    let total = (10 + header.len() + 1).div_ceil(64) * 64;
+    header.extend(std::iter::repeat_n(' ', total - 10 - header.len() - 1));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    codec::write(writer, header.len() as u16)?;
+    writer.write_all(header.as_bytes())?;
+    for row in rows {
+        row.write(writer)?;
+    }
+    Ok(())
+}
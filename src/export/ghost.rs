@@ -0,0 +1,53 @@
+//! Held buttons sampled every few frames, for ghost rendering mods.
+
+use super::resample::Samples;
+use crate::{holds::HoldIndex, meta::Meta, replay::Replay};
+
+/// The held buttons of a replay, sampled every `step` frames.
+///
+/// Each state is a bit mask of the held buttons, laid out like
+/// [`Samples::bit`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ghost {
+    pub step: u64,
+    pub states: Vec<u8>,
+}
+
+impl Ghost {
+    /// The state of the sample covering a frame, or 0 past the end.
+    pub fn state_at(&self, frame: u64) -> u8 {
+        if self.step == 0 {
+            return 0;
+        }
+        usize::try_from(frame / self.step)
+            .ok()
+            .and_then(|i| self.states.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Sample the held buttons of a replay on frames `0, step, 2 * step, ...`,
+/// up to its last input.
+///
+/// Holds are looked up in a [`HoldIndex`]. Returns no states if `step` is 0.
+pub fn ghost<M: Meta>(replay: &Replay<M>, step: u64) -> Ghost {
+    let mut ghost = Ghost {
+        step,
+        states: Vec::new(),
+    };
+    if step == 0 {
+        return ghost;
+    }
+
+    let index = HoldIndex::from(replay);
+    let count = replay.last_frame() / step + 1;
+    ghost.states = (0..count)
+        .map(|k| {
+            index
+                .at(k * step)
+                .fold(0, |state, h| state | Samples::bit(h.button, h.player_2))
+        })
+        .collect();
+    ghost
+}
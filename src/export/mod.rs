@@ -0,0 +1,9 @@
+//! Exporters for tools consuming replays outside of the game.
+
+pub mod clicks;
+#[cfg(feature = "dataset")]
+pub mod dataset;
+pub mod ghost;
+pub mod overlay;
+pub mod resample;
+pub mod timeline;
@@ -0,0 +1,116 @@
+//! Button state keyframes for input display overlays.
+//!
+//! Overlays in video editors and streaming software show which buttons are
+//! held at any time. They only need the moments the held buttons change, as
+//! keyframes holding the full state of both players.
+
+use crate::{
+    input::{Button, InputData},
+    meta::Meta,
+    replay::Replay,
+    tps::TpsTimeline,
+};
+
+/// The buttons a player holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ButtonState {
+    pub jump: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl ButtonState {
+    fn set(&mut self, button: Button, held: bool) {
+        match button {
+            Button::Jump => self.jump = held,
+            Button::Left => self.left = held,
+            Button::Right => self.right = held,
+        }
+    }
+}
+
+/// The buttons held from a point in time until the next keyframe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe {
+    /// Seconds from the start of the replay.
+    pub time: f64,
+    pub frame: u64,
+    pub player_1: ButtonState,
+    pub player_2: ButtonState,
+}
+
+/// The keyframes of a replay, starting with nothing held at frame 0.
+///
+/// Changes on the same frame are merged into a single keyframe, and frames
+/// ending up with the state they started with produce none. Restarts
+/// release every button. Inputs with invalid buttons are ignored.
+pub fn keyframes<M: Meta>(replay: &Replay<M>) -> Vec<Keyframe> {
+    let clock = TpsTimeline::from(replay);
+    let mut state = [ButtonState::default(); 2];
+    let mut keyframes = vec![Keyframe {
+        time: 0.0,
+        frame: 0,
+        player_1: state[0],
+        player_2: state[1],
+    }];
+
+    let mut inputs = replay.iter().peekable();
+    while let Some(input) = inputs.next() {
+        match &input.data {
+            InputData::Player(p) => {
+                if let Ok(button) = p.button_type() {
+                    state[p.player_2 as usize].set(button, p.hold);
+                }
+            }
+            InputData::Restart | InputData::RestartFull => state = Default::default(),
+            _ => {}
+        }
+
+        if inputs.peek().is_some_and(|next| next.frame == input.frame) {
+            continue;
+        }
+        let last = keyframes.last_mut().expect("Keyframe should exist");
+        if [last.player_1, last.player_2] == state {
+            continue;
+        }
+
+        let keyframe = Keyframe {
+            time: clock.seconds(input.frame),
+            frame: input.frame,
+            player_1: state[0],
+            player_2: state[1],
+        };
+        if last.frame == input.frame {
+            *last = keyframe;
+        } else {
+            keyframes.push(keyframe);
+        }
+    }
+    keyframes
+}
+
+/// Write keyframes as JSON.
+///
+/// Available with the `json` feature. The output is an object holding the
+/// `duration` of the replay in seconds and a `keyframes` array of
+/// [`Keyframe`] objects.
+#[cfg(feature = "json")]
+pub fn write_json<M: Meta, W: std::io::Write>(
+    writer: &mut W,
+    replay: &Replay<M>,
+) -> Result<(), crate::json::JsonError> {
+    #[derive(serde::Serialize)]
+    struct Overlay {
+        duration: Option<f64>,
+        keyframes: Vec<Keyframe>,
+    }
+
+    let overlay = Overlay {
+        duration: replay.duration_seconds(),
+        keyframes: keyframes(replay),
+    };
+    serde_json::to_writer_pretty(writer, &overlay)?;
+    Ok(())
+}
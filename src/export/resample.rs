@@ -0,0 +1,79 @@
+//! Button states sampled at a fixed rate.
+//!
+//! Replays step at their tps, which can change mid-replay, while overlays
+//! render at a video framerate and datasets want evenly spaced samples.
+//! [`resample`] produces one sample per output frame, holding the buttons
+//! held at any time during that frame. A click shorter than an output frame
+//! still shows up in the frame it happened in.
+
+use crate::{holds::pair_inputs, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// Tolerance for floating point error at sample boundaries, in samples.
+const EPSILON: f64 = 1e-9;
+
+/// Button states of a replay, sampled at a fixed rate.
+///
+/// Each sample is a bit mask of the held buttons, see [`Samples::bit`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Samples {
+    /// Samples per second.
+    pub rate: f64,
+    pub samples: Vec<u8>,
+}
+
+impl Samples {
+    /// The bit of a button of a player in the samples.
+    ///
+    /// Buttons 1 to 3 of player 1 take the bits 0 to 2, and those of player
+    /// 2 the bits 3 to 5. Invalid buttons have no bit.
+    pub const fn bit(button: u8, player_2: bool) -> u8 {
+        if button < 1 || button > 3 {
+            return 0;
+        }
+        1 << (button - 1 + 3 * player_2 as u8)
+    }
+
+    /// Whether a button of a player is held in a sample.
+    pub fn is_held(&self, sample: usize, button: u8, player_2: bool) -> bool {
+        self.samples
+            .get(sample)
+            .is_some_and(|s| s & Self::bit(button, player_2) != 0)
+    }
+}
+
+/// Sample the held buttons of a replay at a fixed rate.
+///
+/// Sample `k` covers the time from `k / rate` to `(k + 1) / rate` seconds,
+/// following the tps changes of the replay, and the samples end with the one
+/// covering the last input. Holds are paired like [`pair_inputs`] does.
+///
+/// Returns no samples if `rate` isn't a positive number.
+pub fn resample<M: Meta>(replay: &Replay<M>, rate: f64) -> Samples {
+    let mut samples = Samples {
+        rate,
+        samples: Vec::new(),
+    };
+    if rate.is_nan() || rate <= 0.0 {
+        return samples;
+    }
+
+    let clock = TpsTimeline::from(replay);
+    let sample_at = |frame: u64| (clock.seconds(frame) * rate + EPSILON).floor() as usize;
+    let count = sample_at(replay.last_frame()) + 1;
+    samples.samples = vec![0; count];
+
+    for hold in pair_inputs(&replay.inputs) {
+        let bit = Samples::bit(hold.button, hold.player_2);
+        let first = sample_at(hold.start);
+        // The hold ends right before its end time, so an end landing exactly
+        // on a sample boundary doesn't reach into that sample.
+        let end = clock.seconds(hold.end) * rate;
+        let last = ((end - EPSILON).ceil() as usize)
+            .saturating_sub(1)
+            .max(first);
+        for sample in &mut samples.samples[first..=last.min(count - 1)] {
+            *sample |= bit;
+        }
+    }
+    samples
+}
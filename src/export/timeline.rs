@@ -0,0 +1,149 @@
+//! Geometry for drawing a replay on a timeline.
+//!
+//! Macro editors draw holds as bars in a lane per button, specials as
+//! markers and tps changes as background segments. [`timeline`] derives all
+//! of them from the inputs, positioned both in frames and in seconds, so
+//! editors can lay them out on either axis.
+
+use crate::{holds::pair_inputs, input::InputData, meta::Meta, replay::Replay, tps::TpsTimeline};
+
+/// A button being held, drawn as a bar.
+///
+/// The bar covers the half-open frame range `start..end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+    pub start: u64,
+    pub end: u64,
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Whether the hold ended with a release, rather than a restart or the
+    /// end of the replay.
+    pub released: bool,
+}
+
+/// The holds of one button of one player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lane {
+    pub button: u8,
+    pub player_2: bool,
+    /// Bars ordered by start frame.
+    pub bars: Vec<Bar>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    Restart,
+    RestartFull,
+    Death,
+}
+
+/// A special input, drawn as a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    pub frame: u64,
+    pub time: f64,
+    pub kind: MarkerKind,
+}
+
+/// A span of frames playing at the same tps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TpsSegment {
+    pub start: u64,
+    pub end: u64,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub tps: f64,
+}
+
+/// Everything needed to draw a replay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    /// Lanes of buttons that were held, player 1 first, then by button.
+    pub lanes: Vec<Lane>,
+    /// Markers ordered by frame.
+    pub markers: Vec<Marker>,
+    /// Segments covering the replay from frame 0 to its last input.
+    pub tps_segments: Vec<TpsSegment>,
+    /// Frame of the last input.
+    pub end: u64,
+    /// Seconds from the start of the replay to its last input.
+    pub duration: f64,
+}
+
+/// Lay out a replay for drawing.
+///
+/// Holds are paired like [`pair_inputs`] does. Tps changes on the same frame
+/// only keep the last one.
+pub fn timeline<M: Meta>(replay: &Replay<M>) -> Timeline {
+    let clock = TpsTimeline::from(replay);
+    let end = replay.last_frame();
+
+    let mut lanes: Vec<Lane> = Vec::new();
+    for hold in pair_inputs(&replay.inputs) {
+        let bar = Bar {
+            start: hold.start,
+            end: hold.end,
+            start_time: clock.seconds(hold.start),
+            end_time: clock.seconds(hold.end),
+            released: hold.released,
+        };
+        match lanes
+            .iter_mut()
+            .find(|l| l.button == hold.button && l.player_2 == hold.player_2)
+        {
+            Some(lane) => lane.bars.push(bar),
+            None => lanes.push(Lane {
+                button: hold.button,
+                player_2: hold.player_2,
+                bars: vec![bar],
+            }),
+        }
+    }
+    lanes.sort_by_key(|l| (l.player_2, l.button));
+
+    let mut markers = Vec::new();
+    let mut changes = vec![(0, replay.tps)];
+    for input in replay.iter() {
+        let kind = match input.data {
+            InputData::Restart => MarkerKind::Restart,
+            InputData::RestartFull => MarkerKind::RestartFull,
+            InputData::Death => MarkerKind::Death,
+            InputData::TPS(tps) => {
+                match changes.last_mut() {
+                    Some(last) if last.0 == input.frame => last.1 = tps,
+                    _ => changes.push((input.frame, tps)),
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        markers.push(Marker {
+            frame: input.frame,
+            time: clock.seconds(input.frame),
+            kind,
+        });
+    }
+
+    let tps_segments = changes
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, tps))| {
+            let segment_end = changes.get(i + 1).map_or(end.max(start), |next| next.0);
+            TpsSegment {
+                start,
+                end: segment_end,
+                start_time: clock.seconds(start),
+                end_time: clock.seconds(segment_end),
+                tps,
+            }
+        })
+        .collect();
+
+    Timeline {
+        lanes,
+        markers,
+        tps_segments,
+        end,
+        duration: clock.seconds(end),
+    }
+}
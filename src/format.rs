@@ -0,0 +1,78 @@
+//! Constants of the slc formats, and what this build of the crate supports.
+//!
+//! Tools reading slc files can check [`capabilities`] at runtime instead of
+//! assuming which optional features the crate was built with.
+
+use std::ops::RangeInclusive;
+
+pub use crate::replay::{Version, V2_FOOTER, V2_HEADER, V3_HEADER};
+use crate::v3::atom::AtomId;
+pub use crate::v3::metadata::METADATA_SIZE as V3_METADATA_SIZE;
+
+/// The byte ending every v3 replay.
+pub const V3_FOOTER: u8 = 0xCC;
+
+/// Versions the crate reads and writes.
+pub const SUPPORTED_VERSIONS: [Version; 2] = [Version::V2, Version::V3];
+
+/// Sizes a delta can be written in, in bytes, in both versions.
+pub const DELTA_SIZES: [u8; 4] = [1, 2, 4, 8];
+
+/// Largest delta of a v2 input. The 5 lowest bits of an input hold its type.
+pub const V2_MAX_DELTA: u64 = (1 << 59) - 1;
+
+/// Largest delta of a v3 player input. The 4 lowest bits of an input hold
+/// its button and flags.
+pub const V3_MAX_PLAYER_DELTA: u64 = (1 << 60) - 1;
+
+/// Largest delta of a v3 special action, which has its delta to itself.
+pub const V3_MAX_SPECIAL_DELTA: u64 = u64::MAX;
+
+/// Ids of the atoms defined by the format. Atoms with other ids are read as
+/// [`crate::v3::atom::CustomAtom`]s.
+pub const BUILTIN_ATOM_IDS: RangeInclusive<u32> = AtomId::Null as u32..=AtomId::ChangeLog as u32;
+
+/// Optional parts of the crate enabled in this build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub versions: &'static [Version],
+    /// Serde support for inputs, the `serde` feature.
+    pub serde: bool,
+    /// JSON reading and writing, the `json` feature.
+    pub json: bool,
+    /// Uploading and downloading replays, the `net` feature.
+    pub net: bool,
+    /// `.npy` dataset export, the `dataset` feature.
+    pub dataset: bool,
+    /// Conversion profiles, the `profile` feature.
+    pub profile: bool,
+    /// Reloading replays when their file changes, the `notify` feature.
+    pub watch: bool,
+    /// Reference vectors, the `conformance` feature.
+    pub conformance: bool,
+    /// Whether unsafe code is forbidden, the `safe` feature.
+    pub safe: bool,
+    /// Names of the modules of [`crate::convert`] available.
+    pub converters: Vec<&'static str>,
+}
+
+/// What this build of the crate supports.
+pub fn capabilities() -> Capabilities {
+    let mut converters = vec!["echo", "fps", "framelist"];
+    if cfg!(feature = "json") {
+        converters.push("urf");
+    }
+
+    Capabilities {
+        versions: &SUPPORTED_VERSIONS,
+        serde: cfg!(feature = "serde"),
+        json: cfg!(feature = "json"),
+        net: cfg!(feature = "net"),
+        dataset: cfg!(feature = "dataset"),
+        profile: cfg!(feature = "profile"),
+        watch: cfg!(feature = "notify"),
+        conformance: cfg!(feature = "conformance"),
+        safe: cfg!(feature = "safe"),
+        converters,
+    }
+}
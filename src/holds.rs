@@ -0,0 +1,296 @@
+//! Press/release pairing and an interval index over holds.
+
+use crate::{
+    input::{Input, InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// A button being held down between two frames.
+///
+/// The interval is half-open: the button is held on `start..end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hold {
+    pub start: u64,
+    pub end: u64,
+    pub button: u8,
+    pub player_2: bool,
+    /// Whether the hold was ended by an explicit release.
+    ///
+    /// Holds can also be ended by a restart, or by the replay ending.
+    pub released: bool,
+}
+
+impl Hold {
+    /// Length of the hold in frames.
+    pub const fn length(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether the button is held on the given frame.
+    pub const fn contains(&self, frame: u64) -> bool {
+        self.start <= frame && frame < self.end
+    }
+}
+
+/// Pair presses with their releases.
+///
+/// Pressing an already held button is ignored, as is releasing a button that
+/// isn't held. Restarts end every open hold. Holds still open at the end of
+/// the inputs end on the last input's frame.
+///
+/// Holds are returned ordered by their start frame.
+pub fn pair_inputs(inputs: &[Input]) -> Vec<Hold> {
+    let mut holds: Vec<Hold> = Vec::new();
+    let mut open = [[None::<usize>; 4]; 2];
+
+    for input in inputs {
+        match &input.data {
+            InputData::Player(p) => {
+                let slot = &mut open[p.player_2 as usize][(p.button as usize).min(3)];
+                if p.hold {
+                    if slot.is_none() {
+                        *slot = Some(holds.len());
+                        holds.push(Hold {
+                            start: input.frame,
+                            end: input.frame,
+                            button: p.button,
+                            player_2: p.player_2,
+                            released: false,
+                        });
+                    }
+                } else if let Some(i) = slot.take() {
+                    holds[i].end = input.frame;
+                    holds[i].released = true;
+                }
+            }
+            InputData::Restart | InputData::RestartFull => {
+                for i in open.iter_mut().flatten().filter_map(Option::take) {
+                    holds[i].end = input.frame;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let last_frame = inputs.last().map(|i| i.frame).unwrap_or(0);
+    for i in open.iter_mut().flatten().filter_map(Option::take) {
+        holds[i].end = last_frame;
+    }
+
+    holds
+}
+
+/// An interval tree over the holds of a replay.
+///
+/// Holds are stored sorted by start frame, forming an implicit balanced tree
+/// where every node knows the furthest end frame in its subtree. Overlap
+/// queries only visit subtrees that can contain a match.
+///
+/// # Examples
+/// ```
+/// use slc_oxide::holds::HoldIndex;
+/// use slc_oxide::{InputData, PlayerInput, Replay};
+///
+/// let mut replay = Replay::<()>::new(240.0, ());
+/// replay.add_input(10, InputData::Player(PlayerInput { hold: true, player_2: false, button: 1 }));
+/// replay.add_input(30, InputData::Player(PlayerInput { hold: false, player_2: false, button: 1 }));
+///
+/// let index = HoldIndex::from(&replay);
+/// assert_eq!(index.at(20).count(), 1);
+/// assert_eq!(index.longest().unwrap().length(), 20);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HoldIndex {
+    holds: Vec<Hold>,
+    max_end: Vec<u64>,
+}
+
+impl HoldIndex {
+    /// Build an index over the holds of a list of inputs.
+    pub fn new(inputs: &[Input]) -> Self {
+        Self::from_holds(pair_inputs(inputs))
+    }
+
+    /// Build an index over an arbitrary list of holds.
+    pub fn from_holds(mut holds: Vec<Hold>) -> Self {
+        holds.sort_by_key(|h| (h.start, h.end));
+
+        let mut max_end = vec![0; holds.len()];
+        Self::build(&holds, &mut max_end, 0, holds.len());
+
+        Self { holds, max_end }
+    }
+
+    fn build(holds: &[Hold], max_end: &mut [u64], lo: usize, hi: usize) -> u64 {
+        if lo >= hi {
+            return 0;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::build(holds, max_end, lo, mid);
+        let right = Self::build(holds, max_end, mid + 1, hi);
+        max_end[mid] = holds[mid].end.max(left).max(right);
+        max_end[mid]
+    }
+
+    fn query<'a>(&'a self, lo: usize, hi: usize, start: u64, end: u64, out: &mut Vec<&'a Hold>) {
+        if lo >= hi {
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] <= start {
+            return;
+        }
+
+        self.query(lo, mid, start, end, out);
+
+        let hold = &self.holds[mid];
+        if hold.start < end {
+            if hold.end > start {
+                out.push(hold);
+            }
+            self.query(mid + 1, hi, start, end, out);
+        }
+    }
+
+    /// All holds, ordered by start frame.
+    pub fn holds(&self) -> &[Hold] {
+        &self.holds
+    }
+
+    pub fn len(&self) -> usize {
+        self.holds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holds.is_empty()
+    }
+
+    /// Holds overlapping the half-open frame range `start..end`, ordered by start frame.
+    pub fn overlapping(&self, start: u64, end: u64) -> Vec<&Hold> {
+        let mut out = Vec::new();
+        if start < end {
+            self.query(0, self.holds.len(), start, end, &mut out);
+        }
+        out
+    }
+
+    /// Holds active on the given frame.
+    ///
+    /// Holds end before `u64::MAX`, so none are active on it.
+    pub fn at(&self, frame: u64) -> impl Iterator<Item = &Hold> {
+        frame
+            .checked_add(1)
+            .map_or_else(Vec::new, |end| self.overlapping(frame, end))
+            .into_iter()
+    }
+
+    /// Whether a button of a player is held on the given frame.
+    pub fn is_held(&self, frame: u64, button: u8, player_2: bool) -> bool {
+        self.at(frame)
+            .any(|h| h.button == button && h.player_2 == player_2)
+    }
+
+    /// The longest hold in the replay. Ties are resolved in favor of the earliest hold.
+    pub fn longest(&self) -> Option<&Hold> {
+        self.holds.iter().rev().max_by_key(|h| h.length())
+    }
+
+    /// The longest hold of a single button and player.
+    pub fn longest_for(&self, button: u8, player_2: bool) -> Option<&Hold> {
+        self.holds
+            .iter()
+            .rev()
+            .filter(|h| h.button == button && h.player_2 == player_2)
+            .max_by_key(|h| h.length())
+    }
+
+    /// Total amount of frames a button of a player is held for.
+    pub fn coverage(&self, button: u8, player_2: bool) -> u64 {
+        self.holds
+            .iter()
+            .filter(|h| h.button == button && h.player_2 == player_2)
+            .map(Hold::length)
+            .sum()
+    }
+
+    /// Amount of frames within `start..end` a button of a player is held for.
+    pub fn coverage_in(&self, start: u64, end: u64, button: u8, player_2: bool) -> u64 {
+        self.overlapping(start, end)
+            .into_iter()
+            .filter(|h| h.button == button && h.player_2 == player_2)
+            .map(|h| h.end.min(end) - h.start.max(start))
+            .sum()
+    }
+}
+
+impl<M: Meta> From<&Replay<M>> for HoldIndex {
+    fn from(replay: &Replay<M>) -> Self {
+        Self::new(&replay.inputs)
+    }
+}
+
+/// A replay rebased to a later frame, along with the buttons held there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint<M: Meta> {
+    /// The replay rebased with [`Replay::rebase`].
+    pub replay: Replay<M>,
+    /// Presses that must happen on frame 0 for the held buttons to match
+    /// the original replay, ordered by player and button.
+    pub held: Vec<PlayerInput>,
+}
+
+impl<M: Meta> Checkpoint<M> {
+    /// The rebased replay with the presses of [`Checkpoint::held`] added on
+    /// frame 0, before every other input.
+    pub fn into_primed(self) -> Replay<M> {
+        let mut replay = Replay::new(self.replay.tps, self.replay.meta);
+        replay
+            .inputs
+            .reserve(self.held.len() + self.replay.inputs.len());
+        for press in self.held {
+            replay.add_input(0, InputData::Player(press));
+        }
+        for input in self.replay.inputs {
+            replay.add_input(input.frame, input.data);
+        }
+        replay
+    }
+}
+
+/// Rebase a replay to start on `start_frame`, keeping track of held buttons.
+///
+/// Buttons are held the way [`pair_inputs`] pairs them, by the inputs
+/// before `start_frame`. Inputs on `start_frame` are part of the rebased
+/// replay, so they aren't taken into account.
+pub fn checkpoint<M: Meta + Clone>(replay: &Replay<M>, start_frame: u64) -> Checkpoint<M> {
+    let mut open = [[false; 4]; 2];
+    for input in replay.inputs.iter().take_while(|i| i.frame < start_frame) {
+        match &input.data {
+            InputData::Player(p) => {
+                open[p.player_2 as usize][(p.button as usize).min(3)] = p.hold;
+            }
+            InputData::Restart | InputData::RestartFull => open = [[false; 4]; 2],
+            _ => {}
+        }
+    }
+
+    let held = (0..2)
+        .flat_map(|player| (1..4).map(move |button| (player, button)))
+        .filter(|&(player, button)| open[player][button])
+        .map(|(player, button)| PlayerInput {
+            hold: true,
+            player_2: player == 1,
+            button: button as u8,
+        })
+        .collect();
+
+    let mut rebased = replay.clone();
+    rebased.rebase(start_frame);
+    Checkpoint {
+        replay: rebased,
+        held,
+    }
+}
@@ -0,0 +1,193 @@
+//! Accepting replays from untrusted uploads.
+//!
+//! [`ingest`] runs the whole pipeline a server needs before storing an
+//! uploaded replay: reading it within hard limits, normalizing the order of
+//! its actions, stripping atoms the server doesn't want, re-encoding it and
+//! hashing the result. What was changed is returned as an [`IngestReport`].
+
+use std::io::Cursor;
+
+use thiserror::Error;
+
+use crate::{
+    invariants::{check_v3, InvariantError},
+    order::SameFrameOrder,
+    replay::{ReadOptions, Replay, ReplayError, Version},
+    v3::{
+        atom::{AtomId, AtomVariant},
+        WriteOptions,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum IngestError {
+    #[error("Upload is larger than {0} bytes")]
+    TooLarge(usize),
+    #[error("Unknown format")]
+    UnknownFormat,
+    #[error("Replay error: {0}")]
+    Replay(#[from] ReplayError),
+    #[error("V3 error: {0}")]
+    V3(#[from] crate::v3::replay::ReplayError),
+    #[error("Inconsistent replay: {0}")]
+    Invariant(#[from] InvariantError),
+}
+
+/// Limits and policies for [`ingest`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestOptions {
+    /// Largest upload, in bytes.
+    pub max_bytes: usize,
+    /// Limits for parsing, [`ReadOptions::untrusted`] by default.
+    pub read: ReadOptions,
+    /// Atoms other than action atoms larger than this are stripped, in bytes.
+    pub max_atom_size: u64,
+    /// Atom kinds that are always stripped.
+    pub disallowed: Vec<AtomId>,
+    /// Options the cleaned replay is encoded with.
+    pub write: WriteOptions,
+    /// Order of player actions on the same frame.
+    pub order: SameFrameOrder,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: 16 << 20,
+            read: ReadOptions::untrusted(),
+            max_atom_size: 64 << 10,
+            disallowed: vec![AtomId::Null],
+            write: WriteOptions::default(),
+            order: SameFrameOrder::default(),
+        }
+    }
+}
+
+/// Why an atom was stripped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripReason {
+    Disallowed,
+    TooLarge,
+    /// An action atom without actions.
+    Empty,
+}
+
+/// An atom removed from the replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrippedAtom {
+    /// Index of the atom in the uploaded file.
+    pub index: usize,
+    pub id: AtomId,
+    pub size: usize,
+    pub reason: StripReason,
+}
+
+/// What [`ingest`] found and changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestReport {
+    /// Version of the uploaded file. v2 uploads are converted to v3, and
+    /// their meta is dropped.
+    pub source: Version,
+    pub uploaded_bytes: usize,
+    pub encoded_bytes: usize,
+    /// Amount of actions across every action atom.
+    pub actions: usize,
+    pub stripped: Vec<StrippedAtom>,
+    /// [`content_hash`] of the encoded replay.
+    pub hash: u64,
+}
+
+/// A cleaned replay, ready to be stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ingested {
+    pub replay: crate::v3::Replay,
+    /// The replay encoded with [`IngestOptions::write`].
+    pub bytes: Vec<u8>,
+    pub report: IngestReport,
+}
+
+/// Hash of an encoded replay, for spotting duplicate uploads.
+///
+/// This is 64-bit FNV-1a. It is stable across versions of the crate, but
+/// isn't cryptographic: don't rely on it where collisions can be forced.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Read, clean up and re-encode an uploaded replay.
+///
+/// Replays of either version are accepted, v2 ones being converted to v3.
+/// Atoms are stripped according to the options, as are empty action atoms.
+/// Actions of the remaining action atoms are stably sorted by frame with
+/// their deltas recalculated, and the result is checked with [`check_v3`]
+/// before it is encoded.
+pub fn ingest(bytes: &[u8], options: &IngestOptions) -> Result<Ingested, IngestError> {
+    if bytes.len() > options.max_bytes {
+        return Err(IngestError::TooLarge(options.max_bytes));
+    }
+
+    let source = Version::detect(bytes).ok_or(IngestError::UnknownFormat)?;
+    let mut replay = match source {
+        Version::V2 => {
+            Replay::<Vec<u8>>::read_with_options(&mut Cursor::new(bytes), &options.read)?.to_v3()
+        }
+        Version::V3 => {
+            crate::v3::Replay::read_with_options(&mut Cursor::new(bytes), &options.read)?
+        }
+    };
+
+    let mut stripped = Vec::new();
+    let mut index = 0;
+    replay.atoms.atoms.retain(|atom| {
+        let reason = if options.disallowed.contains(&atom.id()) {
+            Some(StripReason::Disallowed)
+        } else {
+            match atom {
+                AtomVariant::Action(a) if a.actions.is_empty() => Some(StripReason::Empty),
+                AtomVariant::Action(_) => None,
+                _ if atom.size() as u64 > options.max_atom_size => Some(StripReason::TooLarge),
+                _ => None,
+            }
+        };
+
+        if let Some(reason) = reason {
+            stripped.push(StrippedAtom {
+                index,
+                id: atom.id(),
+                size: atom.size(),
+                reason,
+            });
+        }
+        index += 1;
+        reason.is_none()
+    });
+
+    let mut actions = 0;
+    for atom in replay.atoms.iter_mut() {
+        if let AtomVariant::Action(atom) = atom {
+            actions += atom.actions.len();
+            options.order.sort_actions(&mut atom.actions);
+            atom.recalculate_deltas();
+        }
+    }
+    check_v3(&replay)?;
+
+    let mut encoded = Vec::new();
+    replay.write_with_options(&mut encoded, &options.write)?;
+
+    let report = IngestReport {
+        source,
+        uploaded_bytes: bytes.len(),
+        encoded_bytes: encoded.len(),
+        actions,
+        stripped,
+        hash: content_hash(&encoded),
+    };
+    Ok(Ingested {
+        replay,
+        bytes: encoded,
+        report,
+    })
+}
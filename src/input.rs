@@ -5,6 +5,49 @@ use std::{
 
 use thiserror::Error;
 
+use crate::codec;
+
+/// An in-game player button.
+///
+/// Values match the buttons of [`PlayerInput::button`] and of slc3 sections.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Button {
+    Jump = 1,
+    Left = 2,
+    Right = 3,
+}
+
+impl TryFrom<u8> for Button {
+    type Error = InputError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Jump),
+            2 => Ok(Self::Left),
+            3 => Ok(Self::Right),
+            _ => Err(InputError::InvalidButton),
+        }
+    }
+}
+
+impl From<Button> for u8 {
+    fn from(button: Button) -> Self {
+        button as u8
+    }
+}
+
+impl From<Button> for crate::v3::section::Button {
+    fn from(button: Button) -> Self {
+        match button {
+            Button::Jump => Self::Jump,
+            Button::Left => Self::Left,
+            Button::Right => Self::Right,
+        }
+    }
+}
+
 /// A player input.
 ///
 /// This input assumes the following buttons:
@@ -14,19 +57,45 @@ use thiserror::Error;
 ///
 /// Buttons match the in-game buttons directly provided in `GJBaseGameLayer::handleButton`.
 /// You may safely use them without any further processing.
+///
+/// Prefer [`PlayerInput::new`] or [`PlayerInput::try_new`] over building the
+/// struct directly, so invalid buttons are caught right away.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerInput {
     pub hold: bool,
     pub player_2: bool,
+    /// The raw button value, see [`PlayerInput::button_type`] for a typed one.
     pub button: u8,
 }
 
+impl PlayerInput {
+    pub const fn new(button: Button, hold: bool, player_2: bool) -> Self {
+        Self {
+            hold,
+            player_2,
+            button: button as u8,
+        }
+    }
+
+    /// Create an input from a raw button value, failing if it isn't a valid button.
+    pub fn try_new(button: u8, hold: bool, player_2: bool) -> Result<Self, InputError> {
+        Ok(Self::new(Button::try_from(button)?, hold, player_2))
+    }
+
+    /// The typed button of the input.
+    pub fn button_type(&self) -> Result<Button, InputError> {
+        Button::try_from(self.button)
+    }
+}
+
 /// Backwards compatibility alias for PlayerInput.
 #[deprecated(since = "0.2.0", note = "Use `PlayerInput` instead")]
 pub type PlayerData = PlayerInput;
 
 /// Data specifying an input's action.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InputData {
     /// This input does nothing.
     Skip,
@@ -64,7 +133,10 @@ impl Display for InputData {
 /// Replay inputs are identified by the frame they're on. Do note
 /// that different bots count frames differently (e.g. using GJGameState's `m_currentProgress`).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Input {
+    /// Frames since the previous input, see [`Input::delta`].
+    #[cfg_attr(feature = "serde", serde(default))]
     pub delta: u64,
     pub frame: u64,
     pub data: InputData,
@@ -76,8 +148,45 @@ impl Display for Input {
     }
 }
 
+impl Input {
+    /// Create an input on a frame, with the delta to the previous input.
+    pub const fn new(frame: u64, delta: u64, data: InputData) -> Self {
+        Self { delta, frame, data }
+    }
+
+    /// The delta stored for this input in v2 files.
+    ///
+    /// This is the amount of frames since the previous input of the replay,
+    /// or since frame 0 for the first input, so `frame` is always the sum of
+    /// all deltas up to and including this input. Deltas are kept up to date by
+    /// [`crate::Replay::add_input`]; after editing frames directly, call
+    /// [`crate::Replay::recalculate_deltas`].
+    pub const fn delta(&self) -> u64 {
+        self.delta
+    }
+}
+
 // IO
 
+/// Amount of bytes a state of a given bit length is written in.
+const REQUIRED_BYTES: [u8; 65] = {
+    let mut table = [0u8; 65];
+    let mut bits = 0;
+    while bits <= 64 {
+        table[bits] = if bits <= 8 {
+            1
+        } else if bits <= 16 {
+            2
+        } else if bits <= 32 {
+            4
+        } else {
+            8
+        };
+        bits += 1;
+    }
+    table
+};
+
 #[derive(Debug, Error)]
 pub enum InputError {
     #[error("IO error: {0}")]
@@ -86,22 +195,28 @@ pub enum InputError {
     InvalidTPS,
     #[error("Invalid button type")]
     InvalidButton,
+    #[error("Frame overflow")]
+    FrameOverflow,
 }
 
 impl Input {
+    /// Read an input of `byte_size` bytes, which must be at most 8.
+    ///
+    /// `buf` is scratch space, shared across calls so reading a blob doesn't
+    /// allocate for each of its inputs.
     pub(crate) fn read<R: Read>(
         reader: &mut R,
         current_frame: u64,
         byte_size: usize,
+        buf: &mut [u8; 8],
     ) -> Result<Self, InputError> {
-        let mut buf = vec![0u8; byte_size];
-        reader.read_exact(&mut buf)?;
-        buf.resize(8, 0);
-
-        let state = u64::from_le_bytes(*unsafe { &*buf.as_ptr().cast::<[u8; 8]>() });
+        reader.read_exact(&mut buf[..byte_size])?;
+        let state = codec::decode_uint(&buf[..byte_size]);
 
         let delta = state >> 5;
-        let frame = current_frame + delta;
+        let frame = current_frame
+            .checked_add(delta)
+            .ok_or(InputError::FrameOverflow)?;
         let button = (state & 0b11100) >> 2;
 
         let data = match button {
@@ -115,8 +230,7 @@ impl Input {
             5 => InputData::RestartFull,
             6 => InputData::Death,
             7 => {
-                reader.read_exact(&mut buf)?;
-                let tps = f64::from_le_bytes(*unsafe { &*buf.as_ptr().cast::<[u8; 8]>() });
+                let tps = codec::read(reader)?;
 
                 InputData::TPS(tps)
             }
@@ -126,6 +240,7 @@ impl Input {
         Ok(Input { delta, frame, data })
     }
 
+    #[inline]
     const fn to_state(&self) -> u64 {
         let state: u64 = match self.data {
             InputData::Skip => 0 << 2,
@@ -143,24 +258,23 @@ impl Input {
         state | (self.delta << 5)
     }
 
+    #[inline]
     pub(crate) const fn required_bytes(&self) -> u8 {
         if let InputData::TPS(_) = self.data {
             return 8;
         }
 
-        let state = self.to_state();
-        match state {
-            0..0x100 => 1,
-            0x100..0x10000 => 2,
-            0x10000..0x100000000 => 4,
-            _ => 8,
-        }
+        REQUIRED_BYTES[(u64::BITS - self.to_state().leading_zeros()) as usize]
     }
 
     pub(crate) fn write<W: Write>(&self, writer: &mut W, byte_size: u64) -> Result<(), InputError> {
-        writer.write_all(&self.to_state().to_le_bytes()[0..byte_size as usize])?;
+        if let InputData::Player(p) = &self.data {
+            p.button_type()?;
+        }
+
+        codec::write_uint(writer, self.to_state(), byte_size as usize)?;
         if let InputData::TPS(tps) = self.data {
-            writer.write_all(&tps.to_le_bytes())?;
+            codec::write(writer, tps)?;
         }
 
         Ok(())
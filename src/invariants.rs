@@ -0,0 +1,157 @@
+//! Internal consistency checks for replays.
+//!
+//! These are the checks the test suite relies on, exposed so code
+//! implementing its own transforms can verify the replays it produces
+//! still encode correctly.
+
+use thiserror::Error;
+
+use crate::{
+    blob::Blob,
+    input::InputData,
+    meta::Meta,
+    replay::Replay,
+    v3::{atom::AtomVariant, section::SectionIdentifier, WriteOptions},
+};
+
+/// The first inconsistency found in a replay.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvariantError {
+    /// An input's frame is before the one of the input preceding it.
+    #[error("input {index} goes back in time")]
+    FrameOrder { index: usize },
+    /// An input's delta doesn't match the distance to the input preceding it.
+    #[error("input {index} has delta {found}, expected {expected}")]
+    DeltaMismatch {
+        index: usize,
+        expected: u64,
+        found: u64,
+    },
+    /// A player input uses a button outside the valid range.
+    #[error("input {index} has an invalid button")]
+    InvalidButton { index: usize },
+    /// The blobs don't cover every input exactly once, in order.
+    #[error("blob {blob} doesn't continue where the previous one ended")]
+    BlobRange { blob: usize },
+    /// A blob is too narrow for one of its inputs.
+    #[error("blob {blob} is too narrow for input {index}")]
+    BlobByteSize { blob: usize, index: usize },
+    /// A section holds a different amount of inputs than it declares.
+    #[error("section {section} of atom {atom} holds the wrong amount of inputs")]
+    SectionInputCount { atom: usize, section: usize },
+    /// The sections of an action atom decode to a different amount of actions.
+    #[error("sections of atom {atom} decode to {covered} actions instead of {actions}")]
+    SectionCoverage {
+        atom: usize,
+        covered: u64,
+        actions: usize,
+    },
+    /// An action atom can't be split into sections.
+    #[error("atom {atom} can't be encoded: {message}")]
+    Encoding { atom: usize, message: String },
+}
+
+fn check_frames<I>(frames: I) -> Result<(), InvariantError>
+where
+    I: IntoIterator<Item = (u64, u64)>,
+{
+    let mut previous_frame = 0;
+    for (index, (frame, delta)) in frames.into_iter().enumerate() {
+        if frame < previous_frame {
+            return Err(InvariantError::FrameOrder { index });
+        }
+        if delta != frame - previous_frame {
+            return Err(InvariantError::DeltaMismatch {
+                index,
+                expected: frame - previous_frame,
+                found: delta,
+            });
+        }
+        previous_frame = frame;
+    }
+    Ok(())
+}
+
+/// Check a v2 replay.
+///
+/// Frames must never decrease, deltas must add up to the frames, player
+/// inputs must use valid buttons, and the blobs the replay is written in
+/// must partition its inputs.
+pub fn check<M: Meta>(replay: &Replay<M>) -> Result<(), InvariantError> {
+    check_frames(replay.inputs.iter().map(|i| (i.frame, i.delta)))?;
+
+    for (index, input) in replay.inputs.iter().enumerate() {
+        if let InputData::Player(p) = &input.data {
+            if p.button_type().is_err() {
+                return Err(InvariantError::InvalidButton { index });
+            }
+        }
+    }
+
+    let blobs = Blob::plan(&replay.inputs);
+    let mut next = 0u64;
+    for (blob, b) in blobs.iter().enumerate() {
+        if b.start != next || b.length == 0 {
+            return Err(InvariantError::BlobRange { blob });
+        }
+        next = b.start + b.length;
+
+        let range = b.start as usize..next as usize;
+        for (index, input) in replay.inputs[range.clone()].iter().enumerate() {
+            if input.required_bytes() as u64 > b.byte_size {
+                return Err(InvariantError::BlobByteSize {
+                    blob,
+                    index: range.start + index,
+                });
+            }
+        }
+    }
+    if next != replay.inputs.len() as u64 {
+        return Err(InvariantError::BlobRange { blob: blobs.len() });
+    }
+
+    Ok(())
+}
+
+/// Check a v3 replay.
+///
+/// In every action atom, frames must never decrease and deltas must add up
+/// to the frames. The sections each atom is written in must hold the
+/// amount of inputs they declare, and decode to exactly its actions.
+pub fn check_v3(replay: &crate::v3::Replay) -> Result<(), InvariantError> {
+    for (atom, variant) in replay.atoms.iter().enumerate() {
+        let AtomVariant::Action(action_atom) = variant else {
+            continue;
+        };
+
+        check_frames(action_atom.actions.iter().map(|a| (a.frame, a.delta())))?;
+
+        let sections = action_atom
+            .sections(&WriteOptions::default())
+            .map_err(|e| InvariantError::Encoding {
+                atom,
+                message: e.to_string(),
+            })?;
+
+        for (section, s) in sections.iter().enumerate() {
+            let declared = match s.id {
+                SectionIdentifier::Input | SectionIdentifier::Repeat => s.input_count(),
+                SectionIdentifier::Special | SectionIdentifier::SubFrame => continue,
+            };
+            if s.player_inputs.len() as u64 != declared {
+                return Err(InvariantError::SectionInputCount { atom, section });
+            }
+        }
+
+        let covered: u64 = sections.iter().map(|s| s.action_count()).sum();
+        if covered != action_atom.actions.len() as u64 {
+            return Err(InvariantError::SectionCoverage {
+                atom,
+                covered,
+                actions: action_atom.actions.len(),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! JSON representation of replays.
+//!
+//! Available with the `json` feature. The meta is stored as a plain byte array,
+//! so any [`Meta`] implementation round-trips without knowing its layout.
+
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{input::Input, meta::Meta, replay::Replay};
+
+#[derive(Debug, Error)]
+pub enum JsonError {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Meta size mismatch error")]
+    MetaSizeMismatchError,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonReplay {
+    tps: f64,
+    meta: Vec<u8>,
+    inputs: Vec<Input>,
+}
+
+/// Write a replay as pretty-printed JSON.
+pub fn write<M: Meta, W: Write>(replay: &Replay<M>, writer: &mut W) -> Result<(), JsonError> {
+    let json = JsonReplay {
+        tps: replay.tps,
+        meta: replay.meta.to_bytes().into_vec(),
+        inputs: replay.inputs.clone(),
+    };
+
+    serde_json::to_writer_pretty(writer, &json)?;
+    Ok(())
+}
+
+/// Read a replay from JSON produced by [`write`].
+///
+/// Deltas are recalculated from the frames, so they may be omitted or stale in the input.
+pub fn read<M: Meta, R: Read>(reader: &mut R) -> Result<Replay<M>, JsonError> {
+    let json: JsonReplay = serde_json::from_reader(reader)?;
+    if !M::accepts_size(json.meta.len() as u64) {
+        return Err(JsonError::MetaSizeMismatchError);
+    }
+
+    let mut replay = Replay::new(json.tps, M::from_bytes(&json.meta));
+    replay.inputs = json.inputs;
+    replay.inputs.sort_by_key(|i| i.frame);
+    replay.recalculate_deltas();
+
+    Ok(replay)
+}
@@ -0,0 +1,296 @@
+//! Descriptions of how replays are laid out when encoded.
+//!
+//! A layout lists the blobs of a v2 replay, or the atoms and sections of a
+//! v3 replay, without the data they hold. Comparing layouts catches codec
+//! changes that alter the encoding, even when the bytes are too large to
+//! diff by hand. The [`Display`](std::fmt::Display) output is stable, and
+//! can be stored as a snapshot.
+//!
+//! [`delta_sizes`] and [`delta_sizes_v3`] go down to single inputs, listing
+//! the size each one is written in, to find the inputs that make a file grow.
+
+use std::fmt::{self, Display};
+
+use crate::{
+    blob::Blob,
+    meta::Meta,
+    replay::Replay,
+    v3::{
+        atom::{AtomId, AtomVariant},
+        section::{SectionIdentifier, SpecialType},
+        WriteOptions,
+    },
+};
+
+/// A v2 blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobLayout {
+    /// Bytes per input.
+    pub byte_size: u64,
+    /// Index of the first input.
+    pub start: u64,
+    pub length: u64,
+}
+
+impl From<&Blob> for BlobLayout {
+    fn from(blob: &Blob) -> Self {
+        Self {
+            byte_size: blob.byte_size,
+            start: blob.start,
+            length: blob.length,
+        }
+    }
+}
+
+/// A v3 section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionLayout {
+    Input {
+        delta_size: u64,
+        inputs: u64,
+    },
+    Repeat {
+        delta_size: u64,
+        inputs: u64,
+        repeats: u64,
+    },
+    Special {
+        kind: SpecialType,
+        delta_size: u64,
+    },
+    SubFrame {
+        delta_size: u64,
+    },
+}
+
+/// A v3 atom, with its sections if it is an action atom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomLayout {
+    pub id: AtomId,
+    pub sections: Vec<SectionLayout>,
+}
+
+/// The size one input or action is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaSize {
+    /// Index of the input, or of the action among every action of the replay.
+    pub index: usize,
+    pub frame: u64,
+    pub delta: u64,
+    /// Bytes the encoder writes: the whole input for v2, the delta for v3.
+    pub bytes: u64,
+    /// Bytes the input would need on its own.
+    pub required: u64,
+}
+
+impl DeltaSize {
+    /// Whether the input is written wider than it needs, because it shares
+    /// a blob with wider inputs.
+    pub fn widened(&self) -> bool {
+        self.bytes > self.required
+    }
+}
+
+/// Amount of inputs written in each size, from 1 to 8 bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeDistribution {
+    counts: [u64; 4],
+}
+
+impl SizeDistribution {
+    pub fn new(sizes: &[DeltaSize]) -> Self {
+        let mut distribution = Self::default();
+        for size in sizes {
+            distribution.counts[size.bytes.trailing_zeros().min(3) as usize] += 1;
+        }
+        distribution
+    }
+
+    /// Amount of inputs written in `bytes` bytes. Only 1, 2, 4 and 8 are used.
+    pub fn count(&self, bytes: u64) -> u64 {
+        match bytes {
+            1 | 2 | 4 | 8 => self.counts[bytes.trailing_zeros() as usize],
+            _ => 0,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Amount of inputs written in 4 or 8 bytes.
+    pub fn wide(&self) -> u64 {
+        self.counts[2] + self.counts[3]
+    }
+}
+
+/// The layout of an encoded replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    V2(Vec<BlobLayout>),
+    V3(Vec<AtomLayout>),
+}
+
+/// The layout of a replay written in v2 format.
+pub fn snapshot<M: Meta>(replay: &Replay<M>) -> Layout {
+    Layout::V2(
+        Blob::plan(&replay.inputs)
+            .iter()
+            .map(BlobLayout::from)
+            .collect(),
+    )
+}
+
+/// The size every input of a replay is written in, in v2 format.
+///
+/// Inputs are written in the size of their blob, which can be wider than
+/// the input needs when blobs are merged.
+pub fn delta_sizes<M: Meta>(replay: &Replay<M>) -> Vec<DeltaSize> {
+    let mut sizes = Vec::with_capacity(replay.inputs.len());
+    for blob in Blob::plan(&replay.inputs) {
+        let start = blob.start as usize;
+        let end = start + blob.length as usize;
+        sizes.extend(
+            replay.inputs[start..end]
+                .iter()
+                .enumerate()
+                .map(|(i, input)| DeltaSize {
+                    index: start + i,
+                    frame: input.frame,
+                    delta: input.delta,
+                    bytes: blob.byte_size,
+                    required: input.required_bytes() as u64,
+                }),
+        );
+    }
+    sizes
+}
+
+/// The size the delta of every action of a v3 replay is written in.
+///
+/// Sections only join actions needing the same delta size, so every delta
+/// is written in the size it needs, and the write options make no
+/// difference. Swift releases are listed with the size of their pair.
+pub fn delta_sizes_v3(replay: &crate::v3::Replay) -> Vec<DeltaSize> {
+    replay
+        .atoms
+        .iter()
+        .filter_map(|atom| match atom {
+            AtomVariant::Action(a) => Some(&a.actions),
+            _ => None,
+        })
+        .flatten()
+        .enumerate()
+        .map(|(index, action)| {
+            let bytes = 1u64 << action.minimum_size();
+            DeltaSize {
+                index,
+                frame: action.frame,
+                delta: action.delta(),
+                bytes,
+                required: bytes,
+            }
+        })
+        .collect()
+}
+
+/// The layout of a v3 replay, written with the given options.
+///
+/// Action atoms that can't be encoded are listed without sections.
+pub fn snapshot_v3(replay: &crate::v3::Replay, options: &WriteOptions) -> Layout {
+    let atoms = replay
+        .atoms
+        .iter()
+        .map(|atom| {
+            let sections = match atom {
+                AtomVariant::Action(a) => a.sections(options).unwrap_or_default(),
+                _ => Vec::new(),
+            };
+
+            AtomLayout {
+                id: atom.id(),
+                sections: sections
+                    .iter()
+                    .map(|s| {
+                        let delta_size = s.real_delta_size();
+                        match s.id {
+                            SectionIdentifier::Input => SectionLayout::Input {
+                                delta_size,
+                                inputs: s.input_count(),
+                            },
+                            SectionIdentifier::Repeat => SectionLayout::Repeat {
+                                delta_size,
+                                inputs: s.input_count(),
+                                repeats: s.repeat_count(),
+                            },
+                            SectionIdentifier::Special => SectionLayout::Special {
+                                kind: s.special_type().unwrap_or(SpecialType::Restart),
+                                delta_size,
+                            },
+                            SectionIdentifier::SubFrame => SectionLayout::SubFrame { delta_size },
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Layout::V3(atoms)
+}
+
+impl Display for SectionLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SectionLayout::Input { delta_size, inputs } => {
+                write!(f, "input {}B x{}", delta_size, inputs)
+            }
+            SectionLayout::Repeat {
+                delta_size,
+                inputs,
+                repeats,
+            } => write!(f, "repeat {}B x{} *{}", delta_size, inputs, repeats),
+            SectionLayout::Special { kind, delta_size } => {
+                write!(f, "special {:?} {}B", kind, delta_size)
+            }
+            SectionLayout::SubFrame { delta_size } => write!(f, "subframe {}B", delta_size),
+        }
+    }
+}
+
+/// One line per size, such as `4B 12`.
+impl Display for SizeDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (exponent, count) in self.counts.iter().enumerate() {
+            writeln!(f, "{}B {}", 1u64 << exponent, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per blob, or per atom followed by its sections.
+impl Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layout::V2(blobs) => {
+                for b in blobs {
+                    writeln!(
+                        f,
+                        "blob {}B {}..{}",
+                        b.byte_size,
+                        b.start,
+                        b.start + b.length
+                    )?;
+                }
+            }
+            Layout::V3(atoms) => {
+                for atom in atoms {
+                    writeln!(f, "atom {:?}", atom.id)?;
+                    for section in &atom.sections {
+                        writeln!(f, "  {}", section)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
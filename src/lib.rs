@@ -2,15 +2,65 @@
 //!
 //! Provides a compact and fast replay format to use
 //! for bots and converters. Silicate's official format.
+//!
+//! The crate contains no unsafe code. Enabling the `safe` feature makes
+//! that a compile-time guarantee, by forbidding `unsafe` crate-wide.
+
+#![cfg_attr(feature = "safe", forbid(unsafe_code))]
 
-pub(crate) mod blob;
+pub mod align;
+pub mod analysis;
+pub mod batch;
+#[cfg(feature = "testing")]
+pub mod bench;
+pub mod blob;
+pub mod codec;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod context;
+pub mod convert;
+pub mod diff;
+pub mod differential;
+pub mod edit;
+pub mod export;
+pub mod format;
+pub mod holds;
+pub mod ingest;
 pub mod input;
+pub mod invariants;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod layout;
+pub mod library;
 pub mod meta;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod order;
+pub mod pack;
+pub mod patch;
+pub mod prelude;
+#[cfg(feature = "profile")]
+pub mod profile;
+pub mod repair;
 pub mod replay;
+pub(crate) mod rng;
+pub mod splice;
+#[cfg(feature = "testing")]
+pub mod stress;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tps;
+pub mod transform;
+pub mod v2;
 pub mod v3;
+pub mod validate;
+#[cfg(feature = "notify")]
+pub mod watch;
+pub mod xpos;
 
 #[allow(deprecated)]
 pub use input::PlayerData;
-pub use input::{Input, InputData, PlayerInput};
-pub use meta::Meta;
-pub use replay::{Replay, ReplayError};
+pub use input::{Button, Input, InputData, PlayerInput};
+pub use meta::{Meta, RawMeta};
+pub use replay::{ReadOptions, Replay, ReplayError};
+pub use tps::TpsTimeline;
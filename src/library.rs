@@ -0,0 +1,213 @@
+//! Indexing of replay directories.
+//!
+//! Entries are built from file headers only, so scanning a large archive
+//! stays fast regardless of how long the replays are.
+
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    codec,
+    replay::{ReadOptions, ReplayError, Version},
+    v2,
+    v3::{
+        atom::{AtomId, AtomVariant},
+        metadata::METADATA_SIZE,
+        replay::{ReplayError as V3ReplayError, FOOTER},
+    },
+};
+
+/// Header information of a single replay file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryEntry {
+    pub path: PathBuf,
+    pub version: Version,
+    pub tps: f64,
+    /// Amount of inputs (v2) or actions (v3) stored in the file.
+    pub input_count: u64,
+    pub file_size: u64,
+    pub modified: Option<SystemTime>,
+    /// Seed stored in v3 metadata.
+    pub seed: Option<u64>,
+    /// Bot build stored in v3 metadata.
+    pub build: Option<u32>,
+    /// Size of the v2 meta, in bytes.
+    pub meta_size: Option<u64>,
+}
+
+impl LibraryEntry {
+    /// Read the header of a replay file, without decoding its inputs.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let version = Version::detect(&header).ok_or(ReplayError::UnknownFormat)?;
+
+        let mut entry = Self {
+            path: path.to_path_buf(),
+            version,
+            tps: 0.0,
+            input_count: 0,
+            file_size: metadata.len(),
+            modified: metadata.modified().ok(),
+            seed: None,
+            build: None,
+            meta_size: None,
+        };
+
+        match version {
+            Version::V2 => {
+                // The first 8 bytes were already read to detect the version.
+                let layout = v2::inspect(&mut header.as_slice().chain(&mut reader))?;
+                entry.tps = layout.tps;
+                entry.meta_size = Some(layout.meta.len() as u64);
+                entry.input_count = layout.input_count;
+            }
+            Version::V3 => {
+                if codec::read::<u16, _>(&mut reader)? != METADATA_SIZE as u16 {
+                    return Err(V3ReplayError::InvalidMetadataSize.into());
+                }
+
+                let metadata = crate::v3::Metadata::read(&mut reader)?;
+                entry.tps = metadata.tps;
+                entry.seed = Some(metadata.seed);
+                entry.build = Some(metadata.build);
+
+                entry.input_count = count_actions(&mut reader)?;
+            }
+        }
+
+        Ok(entry)
+    }
+
+    /// Level name of the replay, taken from its file name.
+    pub fn level(&self) -> Option<&str> {
+        self.path.file_stem().and_then(|s| s.to_str())
+    }
+}
+
+/// Sum the action counts stored in the action atoms of a v3 replay, skipping
+/// every other atom by its size. The reader must be at the first atom.
+fn count_actions<R: Read + Seek>(reader: &mut R) -> Result<u64, ReplayError> {
+    let options = ReadOptions::default();
+    let mut position = reader.stream_position()?;
+    let mut count = 0u64;
+
+    loop {
+        let mut byte = [0u8; 1];
+        if reader.read(&mut byte)? == 0 || byte[0] == FOOTER {
+            return Ok(count);
+        }
+
+        reader.seek(SeekFrom::Start(position))?;
+        let (id, size) = AtomVariant::read_header(reader, &options).map_err(V3ReplayError::from)?;
+        if id == AtomId::Action as u32 && size >= 8 {
+            count = count.saturating_add(codec::read(reader)?);
+        }
+        position = position
+            .checked_add(12 + size as u64)
+            .ok_or(V3ReplayError::InvalidFooter)?;
+        reader.seek(SeekFrom::Start(position))?;
+    }
+}
+
+/// Field to sort library entries by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Level,
+    Tps,
+    Length,
+    Date,
+}
+
+/// An index of the replays in a directory.
+#[derive(Debug, Default)]
+pub struct Library {
+    pub entries: Vec<LibraryEntry>,
+    /// Files that looked like replays but could not be read.
+    pub errors: Vec<(PathBuf, ReplayError)>,
+}
+
+impl Library {
+    /// Scan a directory (non-recursively) for `.slc` and `.slc3` files.
+    pub fn scan<P: AsRef<Path>>(dir: P) -> std::io::Result<Self> {
+        let mut library = Self::default();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_replay = matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("slc" | "slc3")
+            );
+
+            if !is_replay || !path.is_file() {
+                continue;
+            }
+
+            match LibraryEntry::read(&path) {
+                Ok(entry) => library.entries.push(entry),
+                Err(e) => library.errors.push((path, e)),
+            }
+        }
+
+        library.sort_by(SortKey::Level, true);
+        Ok(library)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sort entries by a key, ascending or descending.
+    pub fn sort_by(&mut self, key: SortKey, ascending: bool) {
+        self.entries.sort_by(|a, b| {
+            let ordering = match key {
+                SortKey::Level => a.level().cmp(&b.level()),
+                SortKey::Tps => a.tps.partial_cmp(&b.tps).unwrap_or(Ordering::Equal),
+                SortKey::Length => a.input_count.cmp(&b.input_count),
+                SortKey::Date => a.modified.cmp(&b.modified),
+            };
+
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+
+    /// Entries whose level name contains the needle, ignoring case.
+    pub fn search_level(&self, needle: &str) -> impl Iterator<Item = &LibraryEntry> {
+        let needle = needle.to_lowercase();
+        self.entries.iter().filter(move |e| {
+            e.level()
+                .is_some_and(|l| l.to_lowercase().contains(&needle))
+        })
+    }
+
+    /// Entries matching an arbitrary predicate.
+    pub fn filter<'a, F>(&'a self, predicate: F) -> impl Iterator<Item = &'a LibraryEntry>
+    where
+        F: Fn(&LibraryEntry) -> bool + 'a,
+    {
+        self.entries.iter().filter(move |e| predicate(e))
+    }
+
+    /// Entries recorded at the given tps.
+    pub fn with_tps(&self, tps: f64) -> impl Iterator<Item = &LibraryEntry> {
+        self.filter(move |e| e.tps == tps)
+    }
+}
@@ -49,6 +49,14 @@ pub trait Meta {
 
     /// Converts a meta object to an array of bytes, heap-allocated.
     fn to_bytes(&self) -> Box<[u8]>;
+
+    /// Whether a meta of the given size, in bytes, can be read into this type.
+    ///
+    /// Defaults to only accepting `Meta::size`. Types storing raw bytes
+    /// may accept any size.
+    fn accepts_size(size: u64) -> bool {
+        size == Self::size()
+    }
 }
 
 impl Meta for () {
@@ -61,3 +69,55 @@ impl Meta for () {
         Box::new([])
     }
 }
+
+/// A meta of any size, kept as raw bytes.
+///
+/// Reading a replay with a `Vec<u8>` meta accepts whatever meta the file
+/// contains, which is useful for tools working with replays from many bots.
+/// `Meta::size` reports 0, the size of an empty meta.
+impl Meta for Vec<u8> {
+    fn size() -> u64 {
+        0
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        bytes.to_vec()
+    }
+
+    fn to_bytes(&self) -> Box<[u8]> {
+        self.clone().into_boxed_slice()
+    }
+
+    fn accepts_size(_size: u64) -> bool {
+        true
+    }
+}
+
+/// A meta object made of `N` raw bytes.
+///
+/// Useful for tools that need to preserve a meta without knowing its layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawMeta<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> Default for RawMeta<N> {
+    fn default() -> Self {
+        Self([0; N])
+    }
+}
+
+impl<const N: usize> Meta for RawMeta<N> {
+    fn size() -> u64 {
+        N as u64
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut data = [0u8; N];
+        let len = bytes.len().min(N);
+        data[..len].copy_from_slice(&bytes[..len]);
+        Self(data)
+    }
+
+    fn to_bytes(&self) -> Box<[u8]> {
+        Box::from(self.0.as_slice())
+    }
+}
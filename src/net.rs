@@ -0,0 +1,179 @@
+//! Sharing replays over HTTP.
+//!
+//! Available with the `net` feature. A [`Client`] talks to a sharing
+//! server:
+//!
+//! - `POST {base}/macros` takes a `multipart/form-data` body with a
+//!   `replay` part holding the replay file and a `metadata` part holding
+//!   JSON, and answers with a JSON object holding the new macro's `id`.
+//! - `GET {base}/macros/{id}` answers with the replay file.
+//!
+//! ```no_run
+//! use slc_oxide::net::Client;
+//! # let replay = slc_oxide::Replay::<()>::new(240.0, ());
+//!
+//! let client = Client::new("https://example.com/api");
+//! let id = client.upload(&replay, &serde_json::json!({ "level": 128 }))?;
+//! let shared = client.download::<()>(&id)?;
+//! # Ok::<(), slc_oxide::net::NetError>(())
+//! ```
+
+use std::io::{Cursor, Read};
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    diff::normalize,
+    meta::Meta,
+    replay::{ReadOptions, Replay, ReplayError},
+};
+
+const BOUNDARY: &str = "slc-oxide-7d1c0f3a9e2b4856";
+
+/// Default largest download, in bytes.
+pub const DEFAULT_MAX_DOWNLOAD: u64 = 64 << 20;
+
+#[derive(Debug, Error)]
+pub enum NetError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Replay error: {0}")]
+    Replay(#[from] ReplayError),
+    #[error("Response is missing the macro id")]
+    MissingId,
+    #[error("Download larger than {0} bytes")]
+    TooLarge(u64),
+}
+
+impl From<ureq::Error> for NetError {
+    fn from(error: ureq::Error) -> Self {
+        Self::Http(Box::new(error))
+    }
+}
+
+/// A client for a replay sharing server.
+#[derive(Debug, Clone)]
+pub struct Client {
+    base: String,
+    agent: ureq::Agent,
+    options: ReadOptions,
+    max_download: u64,
+}
+
+impl Client {
+    /// Create a client for the server at a base URL.
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.trim_end_matches('/').to_owned(),
+            agent: ureq::Agent::new(),
+            options: ReadOptions::default(),
+            max_download: DEFAULT_MAX_DOWNLOAD,
+        }
+    }
+
+    /// Use an agent configured by the caller, for timeouts or proxies.
+    pub fn with_agent(mut self, agent: ureq::Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Limits to read downloaded replays within, see [`ReadOptions`].
+    pub fn with_read_options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Largest download to accept, in bytes.
+    pub fn with_max_download(mut self, bytes: u64) -> Self {
+        self.max_download = bytes;
+        self
+    }
+
+    /// Upload a replay with its metadata, returning the id the server gave it.
+    ///
+    /// The replay is normalized with [`normalize`] and written as v2, so the
+    /// same inputs always upload the same bytes.
+    pub fn upload<M: Meta + Clone>(
+        &self,
+        replay: &Replay<M>,
+        metadata: &Value,
+    ) -> Result<String, NetError> {
+        let mut canonical = replay.clone();
+        normalize(&mut canonical);
+        let mut bytes = Vec::new();
+        canonical.write(&mut bytes)?;
+        self.upload_bytes(&bytes, metadata)
+    }
+
+    /// Upload an already encoded replay with its metadata.
+    pub fn upload_bytes(&self, replay: &[u8], metadata: &Value) -> Result<String, NetError> {
+        let body = multipart(replay, &serde_json::to_vec(metadata)?);
+        let response = self
+            .agent
+            .post(&format!("{}/macros", self.base))
+            .set(
+                "Content-Type",
+                &format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .send_bytes(&body)?;
+
+        let answer: Value = serde_json::from_reader(response.into_reader())?;
+        match &answer["id"] {
+            Value::String(id) => Ok(id.clone()),
+            Value::Number(id) => Ok(id.to_string()),
+            _ => Err(NetError::MissingId),
+        }
+    }
+
+    /// Download the bytes of a shared replay.
+    pub fn download_bytes(&self, id: &str) -> Result<Vec<u8>, NetError> {
+        let response = self
+            .agent
+            .get(&format!("{}/macros/{}", self.base, id))
+            .call()?;
+
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_download.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > self.max_download {
+            return Err(NetError::TooLarge(self.max_download));
+        }
+        Ok(bytes)
+    }
+
+    /// Download and parse a shared replay of either version.
+    pub fn download<M: Meta>(&self, id: &str) -> Result<Replay<M>, NetError> {
+        let bytes = self.download_bytes(id)?;
+        Ok(Replay::read_with_options(
+            &mut Cursor::new(bytes),
+            &self.options,
+        )?)
+    }
+}
+
+fn multipart(replay: &[u8], metadata: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(replay.len() + metadata.len() + 512);
+    body.extend_from_slice(
+        format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"replay\"; filename=\"replay.slc\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(replay);
+    body.extend_from_slice(
+        format!(
+            "\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"metadata\"\r\nContent-Type: application/json\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(metadata);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
@@ -0,0 +1,113 @@
+//! Order of player inputs sharing a frame.
+//!
+//! The formats keep inputs on the same frame in the order they were added,
+//! and some playback engines act on them in that order. A
+//! [`SameFrameOrder`] picks a canonical order instead, so two replays of the
+//! same run play back identically no matter how they were recorded.
+
+use crate::{
+    input::{Input, InputData},
+    v3::{Action, ActionType},
+};
+
+/// How player inputs on the same frame are ordered.
+///
+/// Only runs of consecutive player inputs on a frame are reordered. Other
+/// inputs, such as restarts and tps changes, stay where they are, and player
+/// inputs are never moved across them. Sorting is stable, so inputs the
+/// policy considers equal keep the order they were added in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SameFrameOrder {
+    /// Keep the order the inputs were added in.
+    #[default]
+    Insertion,
+    /// Player 1 inputs before player 2 inputs.
+    PlayerFirst,
+    /// Ordered by button, then player 1 before player 2.
+    ButtonFirst,
+}
+
+/// Where a player input sorts within its frame.
+#[derive(Debug, Clone, Copy)]
+struct PlayerKey {
+    subframe: u16,
+    button: u8,
+    player_2: bool,
+}
+
+impl SameFrameOrder {
+    /// Sort inputs by frame, then order the player inputs of every frame.
+    pub fn sort_inputs(self, inputs: &mut [Input]) {
+        self.sort(
+            inputs,
+            |i| i.frame,
+            |i| match &i.data {
+                InputData::Player(p) => Some(PlayerKey {
+                    subframe: 0,
+                    button: p.button,
+                    player_2: p.player_2,
+                }),
+                _ => None,
+            },
+        );
+    }
+
+    /// Sort actions by frame, then order the player actions of every frame.
+    ///
+    /// Actions on the same frame are kept in order of their sub-frame
+    /// position first.
+    pub fn sort_actions(self, actions: &mut [Action]) {
+        self.sort(
+            actions,
+            |a| a.frame,
+            |a| match a.action_type {
+                ActionType::Jump | ActionType::Left | ActionType::Right => Some(PlayerKey {
+                    subframe: a.subframe,
+                    button: a.action_type as u8,
+                    player_2: a.player2,
+                }),
+                _ => None,
+            },
+        );
+    }
+
+    fn sort<T>(
+        self,
+        items: &mut [T],
+        frame: impl Fn(&T) -> u64,
+        player: impl Fn(&T) -> Option<PlayerKey>,
+    ) {
+        items.sort_by_key(&frame);
+        if self == Self::Insertion {
+            return;
+        }
+
+        let mut start = 0;
+        while start < items.len() {
+            if player(&items[start]).is_none() {
+                start += 1;
+                continue;
+            }
+            let run_frame = frame(&items[start]);
+            let len = items[start..]
+                .iter()
+                .take_while(|item| frame(item) == run_frame && player(item).is_some())
+                .count();
+
+            items[start..start + len].sort_by_key(|item| {
+                let key = player(item).expect("runs only hold player inputs");
+                match self {
+                    Self::Insertion => (key.subframe, 0, false),
+                    Self::PlayerFirst => (key.subframe, 0, key.player_2),
+                    Self::ButtonFirst => (key.subframe, key.button, key.player_2),
+                }
+            });
+            start += len;
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Multi-replay containers.
+//!
+//! A pack stores several independent replay files, each under a name, behind
+//! a table of contents. Members keep their exact bytes, so any version and
+//! meta survives packing untouched.
+//!
+//! Layout: the `SLCPACK1` header, the member count (u64), then one table of
+//! contents entry per member (name length as u16, UTF-8 name, offset and
+//! length as u64, relative to the start of the data), followed by the data.
+
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use thiserror::Error;
+
+use crate::{
+    codec,
+    meta::Meta,
+    replay::{Replay, ReplayError, Version},
+};
+
+pub const PACK_HEADER: [u8; 8] = *b"SLCPACK1";
+
+#[derive(Debug, Error)]
+pub enum PackError {
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Header mismatch error")]
+    HeaderMismatchError,
+    #[error("Invalid member name")]
+    InvalidName,
+    #[error("Duplicate member: {0}")]
+    DuplicateMember(String),
+    #[error("Missing member: {0}")]
+    MissingMember(String),
+    #[error("Member out of bounds: {0}")]
+    MemberOutOfBounds(String),
+    #[error("Replay error: {0}")]
+    Replay(#[from] ReplayError),
+}
+
+/// A table of contents entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub name: String,
+    /// Offset of the member, relative to the end of the table of contents.
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A single replay file stored in a pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackMember {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl PackMember {
+    /// The format of the stored replay, if it is a known one.
+    pub fn version(&self) -> Option<Version> {
+        Version::detect(&self.data)
+    }
+
+    /// Decode the member as a replay of any version.
+    pub fn replay<M: Meta>(&self) -> Result<Replay<M>, ReplayError> {
+        Replay::read(&mut std::io::Cursor::new(&self.data))
+    }
+
+    /// Decode the member as a v3 replay.
+    pub fn replay_v3(&self) -> Result<crate::v3::Replay, ReplayError> {
+        Ok(crate::v3::Replay::read(&mut std::io::Cursor::new(
+            &self.data,
+        ))?)
+    }
+}
+
+/// A collection of named replay files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pack {
+    members: Vec<PackMember>,
+}
+
+impl Pack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Iterate over the members, in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, PackMember> {
+        self.members.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PackMember> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    /// Add an encoded replay file under a name.
+    pub fn add(&mut self, name: &str, data: Vec<u8>) -> Result<(), PackError> {
+        if name.is_empty() || name.len() > u16::MAX as usize {
+            return Err(PackError::InvalidName);
+        }
+        if self.get(name).is_some() {
+            return Err(PackError::DuplicateMember(name.to_string()));
+        }
+
+        self.members.push(PackMember {
+            name: name.to_string(),
+            data,
+        });
+        Ok(())
+    }
+
+    /// Encode a replay in v2 format and add it under a name.
+    pub fn add_replay<M: Meta>(&mut self, name: &str, replay: &Replay<M>) -> Result<(), PackError> {
+        let mut data = Vec::new();
+        replay.write(&mut data)?;
+        self.add(name, data)
+    }
+
+    /// Encode a v3 replay and add it under a name.
+    pub fn add_v3(&mut self, name: &str, replay: &crate::v3::Replay) -> Result<(), PackError> {
+        let mut data = Vec::new();
+        replay.write(&mut data).map_err(ReplayError::from)?;
+        self.add(name, data)
+    }
+
+    /// Remove a member from the pack and return it.
+    pub fn remove(&mut self, name: &str) -> Option<PackMember> {
+        let index = self.members.iter().position(|m| m.name == name)?;
+        Some(self.members.remove(index))
+    }
+
+    /// Write every member to a directory, as a file named after it.
+    pub fn extract_to<P: AsRef<Path>>(&self, dir: P) -> Result<(), PackError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        for member in &self.members {
+            let file_name = Path::new(&member.name)
+                .file_name()
+                .ok_or(PackError::InvalidName)?;
+            fs::write(dir.join(file_name), &member.data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read only the table of contents of a pack.
+    ///
+    /// Returns the entries and the position member offsets are relative to.
+    pub fn read_toc<R: Read + Seek>(reader: &mut R) -> Result<(Vec<TocEntry>, u64), PackError> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header != PACK_HEADER {
+            return Err(PackError::HeaderMismatchError);
+        }
+
+        let count: u64 = codec::read(reader)?;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let mut name = vec![0u8; codec::read::<u16, _>(reader)? as usize];
+            reader.read_exact(&mut name)?;
+
+            entries.push(TocEntry {
+                name: String::from_utf8(name).map_err(|_| PackError::InvalidName)?,
+                offset: codec::read(reader)?,
+                length: codec::read(reader)?,
+            });
+        }
+
+        Ok((entries, reader.stream_position()?))
+    }
+
+    /// Read a single member of a pack, seeking past every other one.
+    pub fn read_member<R: Read + Seek>(
+        reader: &mut R,
+        name: &str,
+    ) -> Result<PackMember, PackError> {
+        let (entries, data_start) = Self::read_toc(reader)?;
+        let entry = entries
+            .into_iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| PackError::MissingMember(name.to_string()))?;
+
+        let data = Self::read_data(reader, &entry, data_start)?;
+        Ok(PackMember {
+            name: entry.name,
+            data,
+        })
+    }
+
+    /// Read the bytes of a member, checking them against the stream length
+    /// first so a corrupt entry can't claim more than the pack holds.
+    fn read_data<R: Read + Seek>(
+        reader: &mut R,
+        entry: &TocEntry,
+        data_start: u64,
+    ) -> Result<Vec<u8>, PackError> {
+        let stream_len = reader.seek(SeekFrom::End(0))?;
+        let start = data_start
+            .checked_add(entry.offset)
+            .filter(|start| {
+                start
+                    .checked_add(entry.length)
+                    .is_some_and(|end| end <= stream_len)
+            })
+            .ok_or_else(|| PackError::MemberOutOfBounds(entry.name.clone()))?;
+
+        reader.seek(SeekFrom::Start(start))?;
+        let mut data = Vec::new();
+        reader.take(entry.length).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Read a whole pack.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, PackError> {
+        let (entries, data_start) = Self::read_toc(reader)?;
+        let mut pack = Self::new();
+
+        for entry in entries {
+            let data = Self::read_data(reader, &entry, data_start)?;
+            pack.add(&entry.name, data)?;
+        }
+
+        Ok(pack)
+    }
+
+    /// Write the pack to a stream.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), PackError> {
+        writer.write_all(&PACK_HEADER)?;
+        codec::write(writer, self.members.len() as u64)?;
+
+        let mut offset = 0u64;
+        for member in &self.members {
+            codec::write(writer, member.name.len() as u16)?;
+            writer.write_all(member.name.as_bytes())?;
+            codec::write(writer, offset)?;
+            codec::write(writer, member.data.len() as u64)?;
+            offset += member.data.len() as u64;
+        }
+
+        for member in &self.members {
+            writer.write_all(&member.data)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Pack {
+    type Item = &'a PackMember;
+    type IntoIter = std::slice::Iter<'a, PackMember>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
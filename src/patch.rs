@@ -0,0 +1,140 @@
+//! Editing the header of a replay file in place.
+//!
+//! The tps and meta of a v2 replay, and the metadata of a v3 replay, sit at
+//! the start of the file with a size that doesn't depend on the inputs.
+//! [`patch_metadata`] rewrites them there, without decoding the inputs or
+//! writing the rest of the file again, which makes renaming or re-seeding a
+//! whole archive cheap.
+//!
+//! ```no_run
+//! use slc_oxide::patch::{patch_metadata, FileMeta};
+//!
+//! patch_metadata("replay.slc3", |meta| {
+//!     if let FileMeta::V3(metadata) = meta {
+//!         metadata.seed = 1234;
+//!     }
+//! })?;
+//! # Ok::<(), slc_oxide::ReplayError>(())
+//! ```
+
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    codec,
+    format::Version,
+    replay::{ReplayError, V2_HEADER, V3_HEADER},
+    v3::{
+        metadata::{Metadata, METADATA_SIZE},
+        replay::ReplayError as V3ReplayError,
+    },
+};
+
+/// The header fields of a replay file that can be patched in place.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileMeta {
+    /// The tps and meta bytes of a v2 replay. The meta must keep its size.
+    V2 {
+        tps: f64,
+        meta: Vec<u8>,
+    },
+    V3(Metadata),
+}
+
+impl FileMeta {
+    pub fn tps(&self) -> f64 {
+        match self {
+            Self::V2 { tps, .. } => *tps,
+            Self::V3(metadata) => metadata.tps,
+        }
+    }
+
+    pub fn set_tps(&mut self, tps: f64) {
+        match self {
+            Self::V2 { tps: t, .. } => *t = tps,
+            Self::V3(metadata) => metadata.tps = tps,
+        }
+    }
+}
+
+/// Read the header fields of a replay of either slc version.
+///
+/// The reader is left right after them.
+pub fn read_metadata<R: Read + Seek>(reader: &mut R) -> Result<FileMeta, ReplayError> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+
+    match Version::detect(&header) {
+        Some(Version::V2) => {
+            reader.seek(SeekFrom::Start(V2_HEADER.len() as u64))?;
+            let tps: f64 = codec::read(reader)?;
+            let size: u64 = codec::read(reader)?;
+
+            let mut meta = Vec::new();
+            reader.take(size).read_to_end(&mut meta)?;
+            if meta.len() as u64 != size {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            Ok(FileMeta::V2 { tps, meta })
+        }
+        Some(Version::V3) => {
+            if codec::read::<u16, _>(reader)? != METADATA_SIZE as u16 {
+                return Err(V3ReplayError::InvalidMetadataSize.into());
+            }
+            Ok(FileMeta::V3(Metadata::read(reader)?))
+        }
+        None => Err(ReplayError::UnknownFormat),
+    }
+}
+
+/// Patch the header fields of a replay in a stream, see [`patch_metadata`].
+pub fn patch_metadata_in<F, P>(file: &mut F, patch: P) -> Result<FileMeta, ReplayError>
+where
+    F: Read + Write + Seek,
+    P: FnOnce(&mut FileMeta),
+{
+    file.seek(SeekFrom::Start(0))?;
+    let original = read_metadata(file)?;
+    let mut patched = original.clone();
+    patch(&mut patched);
+    if patched == original {
+        return Ok(patched);
+    }
+
+    match (&original, &patched) {
+        (FileMeta::V2 { meta: old, .. }, FileMeta::V2 { tps, meta }) => {
+            if meta.len() != old.len() {
+                return Err(ReplayError::MetaSizeMismatchError);
+            }
+            file.seek(SeekFrom::Start(V2_HEADER.len() as u64))?;
+            codec::write(file, *tps)?;
+            file.seek(SeekFrom::Current(8))?;
+            file.write_all(meta)?;
+        }
+        (FileMeta::V3(_), FileMeta::V3(metadata)) => {
+            file.seek(SeekFrom::Start(V3_HEADER.len() as u64 + 2))?;
+            metadata.write(file)?;
+        }
+        _ => return Err(ReplayError::HeaderMismatchError),
+    }
+    file.flush()?;
+
+    Ok(patched)
+}
+
+/// Patch the header fields of a replay file in place, returning them as
+/// patched.
+///
+/// Only the header is rewritten, the inputs are neither decoded nor
+/// checked. The file is left untouched if the patch changes nothing, or if
+/// it fails: when a v2 meta changes size, or the version is changed.
+pub fn patch_metadata<P: AsRef<Path>, F: FnOnce(&mut FileMeta)>(
+    path: P,
+    patch: F,
+) -> Result<FileMeta, ReplayError> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    patch_metadata_in(&mut file, patch)
+}
@@ -0,0 +1,22 @@
+//! Commonly used types, re-exported with names that don't collide.
+//!
+//! ```
+//! use slc_oxide::prelude::*;
+//!
+//! let mut replay = Replay::<()>::new(240.0, ());
+//! replay.add_input(10, InputData::Player(PlayerInput::new(Button::Jump, true, false)));
+//!
+//! let mut atom = ActionAtom::new();
+//! atom.add_player_action(10, ActionType::Jump, true, false).unwrap();
+//! let mut v3 = ReplayV3::new(Metadata::new(240.0, 0, 1));
+//! v3.add_atom(AtomVariant::Action(atom));
+//! ```
+
+pub use crate::input::{Button, Input, InputData, PlayerInput};
+pub use crate::meta::{Meta, RawMeta};
+pub use crate::replay::{Replay, ReplayError, Version};
+pub use crate::v3::atom::{AtomRegistry, AtomVariant};
+pub use crate::v3::builtin::ActionAtom;
+pub use crate::v3::replay::ReplayError as ReplayV3Error;
+pub use crate::v3::section::Button as SectionButton;
+pub use crate::v3::{Action, ActionType, AttemptKind, Metadata, Replay as ReplayV3, WriteOptions};
@@ -0,0 +1,258 @@
+//! Conversion profiles shared by converter frontends.
+//!
+//! Available with the `profile` feature. A [`Profile`] describes a whole
+//! conversion: the format to write, v3 write options, transforms to apply
+//! and limits the result must stay within. Profiles are loaded from TOML or
+//! JSON, so the CLI and other frontends converting with the same profile
+//! produce the same files.
+//!
+//! ```toml
+//! format = "v3"
+//!
+//! [write]
+//! swift = false
+//!
+//! [limits]
+//! max_tps = 1000.0
+//!
+//! [[transforms]]
+//! type = "dedup_tps"
+//!
+//! [[transforms]]
+//! type = "trim"
+//! to_frame = 24000
+//! ```
+
+use std::{
+    fs,
+    io::{Read, Seek, Write},
+    ops::Range,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    diff::diff,
+    input::InputData,
+    json::{self, JsonError},
+    meta::Meta,
+    order::SameFrameOrder,
+    replay::{ByteCounter, Limits, ReadOptions, Replay, ReplayError},
+    transform::{
+        pipeline::{DedupTps, Mirror, Normalize, Rebase, Reverse, StripSkips, Trim},
+        HumanizeOptions, Pipeline, Preview, Report, RespawnOptions, Transform, TransformError,
+    },
+    v3::WriteOptions,
+};
+
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON profile: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid TOML profile: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("replay error: {0}")]
+    Replay(#[from] ReplayError),
+    #[error("JSON replay error: {0}")]
+    JsonReplay(#[from] JsonError),
+    #[error("transform error: {0}")]
+    Transform(#[from] TransformError),
+    #[error("converted replay exceeds the {0} limit")]
+    LimitExceeded(&'static str),
+}
+
+/// The format a profile writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    #[serde(alias = "slc")]
+    V2,
+    #[serde(alias = "slc3")]
+    V3,
+    Json,
+}
+
+/// A transform in a profile, tagged by its `type`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransformSpec {
+    Normalize {
+        #[serde(default)]
+        order: SameFrameOrder,
+    },
+    DedupTps,
+    StripSkips,
+    Trim {
+        to_frame: u64,
+    },
+    Rebase {
+        start_frame: u64,
+    },
+    Reverse,
+    Mirror {
+        ranges: Vec<Range<u64>>,
+    },
+    Humanize(HumanizeOptions),
+    Respawn(RespawnOptions),
+}
+
+impl TransformSpec {
+    /// Add the transform to the end of a pipeline.
+    pub fn push_to<M: Meta>(&self, pipeline: &mut Pipeline<M>) {
+        match self {
+            Self::Normalize { order } => pipeline.push(Normalize { order: *order }),
+            Self::DedupTps => pipeline.push(DedupTps),
+            Self::StripSkips => pipeline.push(StripSkips),
+            Self::Trim { to_frame } => pipeline.push(Trim {
+                to_frame: *to_frame,
+            }),
+            Self::Rebase { start_frame } => pipeline.push(Rebase {
+                start_frame: *start_frame,
+            }),
+            Self::Reverse => pipeline.push(Reverse),
+            Self::Mirror { ranges } => pipeline.push(Mirror {
+                ranges: ranges.clone(),
+            }),
+            Self::Humanize(options) => pipeline.push(options.clone()),
+            Self::Respawn(options) => pipeline.push(options.clone()),
+        }
+    }
+}
+
+/// What converting a replay with a profile would produce, from [`Profile::dry_run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilePreview {
+    /// What the transforms would change.
+    pub transforms: Preview,
+    pub format: OutputFormat,
+    /// Size of the converted replay, in bytes.
+    pub bytes: u64,
+}
+
+/// Everything about a conversion but the replay.
+///
+/// Every field is optional in a profile file. The defaults write v2 and
+/// change nothing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Format to write. `None` leaves the choice to the frontend, and
+    /// [`Profile::write`] falls back to v2.
+    pub format: Option<OutputFormat>,
+    /// Options used when writing v3.
+    pub write: WriteOptions,
+    /// Transforms applied in order.
+    pub transforms: Vec<TransformSpec>,
+    /// Bounds checked on read and after the transforms.
+    pub limits: Limits,
+}
+
+impl Profile {
+    pub fn from_json(source: &str) -> Result<Self, ProfileError> {
+        Ok(serde_json::from_str(source)?)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ProfileError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Load a profile file, as TOML if its extension is `.toml` and as JSON otherwise.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ProfileError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            _ => Self::from_json(&source),
+        }
+    }
+
+    /// Options for reading replays within the profile's limits.
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions::default().with_limits(self.limits)
+    }
+
+    /// The profile's transforms as a pipeline.
+    pub fn pipeline<M: Meta>(&self) -> Pipeline<M> {
+        let mut pipeline = Pipeline::new();
+        for transform in &self.transforms {
+            transform.push_to(&mut pipeline);
+        }
+        pipeline
+    }
+
+    /// Apply the transforms, then check the result against the limits.
+    pub fn apply<M: Meta>(&self, replay: &mut Replay<M>) -> Result<Report, ProfileError> {
+        let report = self.pipeline().apply(replay)?;
+
+        if !self.limits.accepts_tps(replay.tps) {
+            return Err(ProfileError::LimitExceeded("tps"));
+        }
+        for (count, input) in replay.inputs.iter().enumerate() {
+            let tps = match input.data {
+                InputData::TPS(tps) => Some(tps),
+                _ => None,
+            };
+            self.limits
+                .check(count, input.frame, tps)
+                .map_err(ProfileError::LimitExceeded)?;
+        }
+        Ok(report)
+    }
+
+    /// Write a replay in the profile's format.
+    pub fn write<M: Meta, W: Write>(
+        &self,
+        replay: &Replay<M>,
+        writer: &mut W,
+    ) -> Result<(), ProfileError> {
+        match self.format.unwrap_or_default() {
+            OutputFormat::V2 => replay.write(writer)?,
+            OutputFormat::V3 => replay
+                .to_v3()
+                .write_with_options(writer, &self.write)
+                .map_err(ReplayError::from)?,
+            OutputFormat::Json => json::write(replay, writer)?,
+        }
+        Ok(())
+    }
+
+    /// Convert a copy of a replay without writing it anywhere, and report
+    /// what the conversion would do.
+    ///
+    /// Fails wherever [`Profile::apply`] or [`Profile::write`] would.
+    pub fn dry_run<M: Meta + Clone>(
+        &self,
+        replay: &Replay<M>,
+    ) -> Result<ProfilePreview, ProfileError> {
+        let mut copy = replay.clone();
+        let report = self.apply(&mut copy)?;
+        let mut counter = ByteCounter::default();
+        self.write(&copy, &mut counter)?;
+
+        Ok(ProfilePreview {
+            transforms: Preview {
+                report,
+                changes: diff(replay, &copy),
+            },
+            format: self.format.unwrap_or_default(),
+            bytes: counter.0,
+        })
+    }
+
+    /// Read a replay of either slc version, apply the profile and write the result.
+    pub fn convert<M: Meta, R: Read + Seek, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<Report, ProfileError> {
+        let mut replay = Replay::<M>::read_with_options(reader, &self.read_options())?;
+        let report = self.apply(&mut replay)?;
+        self.write(&replay, writer)?;
+        Ok(report)
+    }
+}
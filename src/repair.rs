@@ -0,0 +1,184 @@
+//! Repair of impossible special inputs.
+//!
+//! Some older bots wrote specials no game could have produced: tps changes
+//! to 0, deaths after the replay ended, and restarts going back in time.
+//! [`find_special_issues`] lists them, and [`repair_specials`] fixes or
+//! strips them so the files can be used again without hex editing.
+
+use std::fmt::{self, Display};
+
+use crate::{input::InputData, meta::Meta, replay::Replay};
+
+/// The kind of impossible special a [`SpecialIssue`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialIssueKind {
+    /// A tps change to 0, a negative tps or a tps that isn't finite.
+    InvalidTps,
+    /// A death after the end of the replay.
+    DeathAfterEnd,
+    /// A restart on an earlier frame than the input before it.
+    RestartBackwards,
+}
+
+/// An impossible special input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecialIssue {
+    pub kind: SpecialIssueKind,
+    /// Index of the offending input.
+    pub index: usize,
+    pub frame: u64,
+}
+
+/// How [`repair_specials`] deals with the issues it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepairMode {
+    /// Rewrite offending inputs into possible ones.
+    ///
+    /// Invalid tps changes keep the tps that was in effect before them,
+    /// deaths are moved to the end frame and restarts are moved to the
+    /// frame of the input before them.
+    #[default]
+    Fix,
+    /// Remove every offending input.
+    Strip,
+}
+
+/// Options for [`find_special_issues`] and [`repair_specials`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RepairOptions {
+    pub mode: RepairMode,
+    /// Last frame of the replay, deaths after it are issues.
+    ///
+    /// The formats don't store where a replay ends, and replays of failed
+    /// attempts end with a death, so deaths aren't checked without one.
+    pub end_frame: Option<u64>,
+}
+
+impl RepairOptions {
+    pub fn with_mode(mut self, mode: RepairMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_end_frame(mut self, end_frame: u64) -> Self {
+        self.end_frame = Some(end_frame);
+        self
+    }
+}
+
+/// What [`repair_specials`] did to a replay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The issues found before the repair.
+    pub issues: Vec<SpecialIssue>,
+    pub fixed: usize,
+    pub stripped: usize,
+}
+
+impl RepairReport {
+    /// Whether the replay had nothing to repair.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// One line per issue, then a summary line.
+impl Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(
+                f,
+                "input {} (frame {}): {:?}",
+                issue.index, issue.frame, issue.kind
+            )?;
+        }
+        writeln!(f, "{} fixed, {} stripped", self.fixed, self.stripped)
+    }
+}
+
+fn valid_tps(tps: f64) -> bool {
+    tps.is_finite() && tps > 0.0
+}
+
+/// Find impossible specials in a replay's inputs.
+///
+/// Deaths are only checked if `end_frame` is given.
+pub fn find_special_issues<M: Meta>(
+    replay: &Replay<M>,
+    end_frame: Option<u64>,
+) -> Vec<SpecialIssue> {
+    let end_frame = end_frame.unwrap_or(u64::MAX);
+    let mut issues = Vec::new();
+    let mut previous_frame = 0;
+    for (index, input) in replay.inputs.iter().enumerate() {
+        let kind = match input.data {
+            InputData::TPS(tps) if !valid_tps(tps) => Some(SpecialIssueKind::InvalidTps),
+            InputData::Death if input.frame > end_frame => Some(SpecialIssueKind::DeathAfterEnd),
+            InputData::Restart | InputData::RestartFull if input.frame < previous_frame => {
+                Some(SpecialIssueKind::RestartBackwards)
+            }
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            issues.push(SpecialIssue {
+                kind,
+                index,
+                frame: input.frame,
+            });
+        }
+        previous_frame = previous_frame.max(input.frame);
+    }
+
+    issues
+}
+
+/// Find impossible specials and fix or strip them according to the options.
+///
+/// Inputs are sorted by frame and deltas are recalculated afterwards.
+pub fn repair_specials<M: Meta>(replay: &mut Replay<M>, options: &RepairOptions) -> RepairReport {
+    let issues = find_special_issues(replay, options.end_frame);
+    let mut report = RepairReport {
+        issues,
+        ..Default::default()
+    };
+    if report.is_clean() {
+        return report;
+    }
+
+    let end_frame = options.end_frame.unwrap_or(u64::MAX);
+    let mut tps = replay.tps;
+    let mut previous_frame = 0;
+    let mut pending = report.issues.iter().peekable();
+    let mut inputs = Vec::with_capacity(replay.inputs.len());
+
+    for (index, mut input) in std::mem::take(&mut replay.inputs).into_iter().enumerate() {
+        let Some(issue) = pending.next_if(|issue| issue.index == index) else {
+            if let InputData::TPS(t) = input.data {
+                tps = t;
+            }
+            previous_frame = previous_frame.max(input.frame);
+            inputs.push(input);
+            continue;
+        };
+
+        if options.mode == RepairMode::Strip {
+            report.stripped += 1;
+            continue;
+        }
+
+        match issue.kind {
+            SpecialIssueKind::InvalidTps => input.data = InputData::TPS(tps),
+            SpecialIssueKind::DeathAfterEnd => input.frame = end_frame,
+            SpecialIssueKind::RestartBackwards => input.frame = previous_frame,
+        }
+        report.fixed += 1;
+        inputs.push(input);
+    }
+
+    inputs.sort_by_key(|i| i.frame);
+    replay.inputs = inputs;
+    replay.recalculate_deltas();
+
+    report
+}
@@ -1,14 +1,16 @@
 use std::{
-    cmp::Ordering,
     io::{Read, Seek, Write},
+    ops::Range,
 };
 
 use thiserror::Error;
 
 use crate::{
     blob::Blob,
+    codec,
     input::{Input, InputData},
     meta::Meta,
+    v3::WriteOptions,
 };
 
 /// An slc replay.
@@ -18,10 +20,19 @@ use crate::{
 /// You may specify your own custom meta through the `M` generic type. See [`slc_oxide::meta::Meta`] for further details.
 ///
 /// # Examples
-/// ```
+/// ```no_run
+/// # use slc_oxide::{Replay, Meta, InputData, PlayerInput};
+/// # use std::fs::File;
+/// # use std::io::BufWriter;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// struct ReplayMeta {
 ///   pub seed: u64
 /// }
+/// # impl Meta for ReplayMeta {
+/// #   fn size() -> u64 { 8 }
+/// #   fn from_bytes(bytes: &[u8]) -> Self { Self { seed: u64::from_le_bytes(bytes.try_into().unwrap()) } }
+/// #   fn to_bytes(&self) -> Box<[u8]> { Box::new(self.seed.to_le_bytes()) }
+/// # }
 ///
 /// let mut replay = Replay::<ReplayMeta>::new(
 ///   240.0,
@@ -38,7 +49,7 @@ use crate::{
 /// replay.tps = 480.0;
 ///
 /// // Add inputs using the `add_input` function
-/// replay.add_input(200, InputData::Player(PlayerData {
+/// replay.add_input(200, InputData::Player(PlayerInput {
 ///   button: 1,
 ///   hold: true,
 ///   player_2: false
@@ -49,10 +60,13 @@ use crate::{
 /// replay.add_input(600, InputData::TPS(480.0));
 ///
 /// // Save the replay
-/// let file = File::open("replay.slc")?;
-/// let bw = BufWriter::new(file); // RECOMMENDED!
-/// replay.write(bw)?;
+/// let file = File::create("replay.slc")?;
+/// let mut bw = BufWriter::new(file); // RECOMMENDED!
+/// replay.write(&mut bw)?;
+/// # Ok(())
+/// # }
 /// ```
+#[derive(Debug, Clone, PartialEq)]
 pub struct Replay<M: Meta> {
     pub tps: f64,
     pub meta: M,
@@ -76,12 +90,298 @@ pub enum ReplayError {
     Blob(#[from] crate::blob::BlobError),
     #[error("V3 error: {0}")]
     V3Error(#[from] crate::v3::replay::ReplayError),
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(&'static str),
+    #[error("Appended input on frame {0} lands before the end of the replay")]
+    AppendOverlap(u64),
+}
+
+/// Bounds applied while reading, for replays from untrusted sources.
+///
+/// The defaults accept anything the format can represent. Files from
+/// elsewhere should be read with [`ReadOptions::untrusted`]: a few bytes of
+/// v3 repeat section expand into billions of actions, which only
+/// `max_inputs` stops. Length fields are never trusted for preallocations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadOptions {
+    /// Most inputs in a v2 replay, or actions in a v3 action atom.
+    pub max_inputs: u64,
+    /// Largest v2 meta, in bytes.
+    pub max_meta_size: u64,
+    /// Largest v3 atom, in bytes.
+    pub max_atom_size: u64,
+    /// Whether v3 replays may have bytes after their footer.
+    pub allow_trailing_data: bool,
+    /// Latest frame of an input or action.
+    pub max_frame: u64,
+    /// Highest tps of the replay or of a tps change, see [`Limits::max_tps`].
+    pub max_tps: f64,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_inputs: u64::MAX,
+            max_meta_size: u64::MAX,
+            max_atom_size: u64::MAX,
+            allow_trailing_data: true,
+            max_frame: u64::MAX,
+            max_tps: f64::INFINITY,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// Limits suited to replays uploaded by users.
+    ///
+    /// Allows 64 KiB metas and 256 MiB atoms, rejects trailing data, and
+    /// applies [`Limits::sane`].
+    pub const fn untrusted() -> Self {
+        let limits = Limits::sane();
+        Self {
+            max_inputs: limits.max_inputs,
+            max_meta_size: 1 << 16,
+            max_atom_size: 1 << 28,
+            allow_trailing_data: false,
+            max_frame: limits.max_frame,
+            max_tps: limits.max_tps,
+        }
+    }
+
+    /// Apply the bounds of [`Limits`] on read.
+    pub const fn with_limits(mut self, limits: Limits) -> Self {
+        self.max_inputs = limits.max_inputs;
+        self.max_frame = limits.max_frame;
+        self.max_tps = limits.max_tps;
+        self
+    }
+
+    /// The bounds of [`Limits`] applied on read.
+    pub const fn limits(&self) -> Limits {
+        Limits {
+            max_frame: self.max_frame,
+            max_tps: self.max_tps,
+            max_inputs: self.max_inputs,
+        }
+    }
+}
+
+/// Sanity bounds on the inputs of a replay.
+///
+/// Values past them are usually the result of a bug, and overflow the math
+/// of consumers long before the format's own limits. They are applied by
+/// [`Replay::add_input_within`], by action atoms built with
+/// [`crate::v3::builtin::ActionAtom::with_limits`], and on read through
+/// [`ReadOptions::with_limits`]. The defaults accept anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Limits {
+    /// Latest frame of an input.
+    pub max_frame: u64,
+    /// Highest tps. When finite, tps values that aren't positive are
+    /// rejected too.
+    pub max_tps: f64,
+    /// Most inputs in a replay, or actions in an action atom.
+    pub max_inputs: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_frame: u64::MAX,
+            max_tps: f64::INFINITY,
+            max_inputs: u64::MAX,
+        }
+    }
+}
+
+impl Limits {
+    /// Bounds no real replay gets close to.
+    ///
+    /// Allows frames up to 2^32, over 200 days at 240 tps, tps values up to
+    /// 100000 and 16 million inputs.
+    pub const fn sane() -> Self {
+        Self {
+            max_frame: 1 << 32,
+            max_tps: 100_000.0,
+            max_inputs: 1 << 24,
+        }
+    }
+
+    /// Check an input about to be added after `count` others, returning the
+    /// name of the exceeded limit.
+    pub(crate) fn check(
+        &self,
+        count: usize,
+        frame: u64,
+        tps: Option<f64>,
+    ) -> Result<(), &'static str> {
+        if count as u64 >= self.max_inputs {
+            return Err("inputs");
+        }
+        if frame > self.max_frame {
+            return Err("frame");
+        }
+        match tps {
+            Some(tps) if !self.accepts_tps(tps) => Err("tps"),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn accepts_tps(&self, tps: f64) -> bool {
+        self.max_tps == f64::INFINITY || (tps > 0.0 && tps <= self.max_tps)
+    }
+}
+
+/// Most elements preallocated from a length field.
+pub(crate) const MAX_PREALLOCATION: u64 = 1 << 16;
+
+/// A writer throwing its data away, counting the bytes written.
+#[derive(Debug, Default)]
+pub(crate) struct ByteCounter(pub u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 pub const V2_HEADER: [u8; 4] = [0x53, 0x49, 0x4C, 0x4C];
 pub const V2_FOOTER: [u8; 3] = [0x45, 0x4F, 0x4D];
 pub const V3_HEADER: [u8; 8] = [b'S', b'L', b'C', b'3', b'R', b'P', b'L', b'Y'];
 
+/// The version of an slc file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V2,
+    V3,
+}
+
+/// Seconds taken by an amount of frames at a tps.
+///
+/// `None` if frames pass at a tps that isn't finite and positive, or if the
+/// result isn't finite.
+pub(crate) fn span_seconds(frames: u64, tps: f64) -> Option<f64> {
+    if frames == 0 {
+        return Some(0.0);
+    }
+    Some(frames as f64 / tps).filter(|s| tps.is_finite() && tps > 0.0 && s.is_finite())
+}
+
+/// Seconds elapsed between frame 0 and a frame, given the starting tps and
+/// the tps changes in frame order.
+///
+/// A tps change applies from its own frame onwards. `None` if frames pass at
+/// a tps that isn't finite and positive, see [`span_seconds`].
+pub(crate) fn elapsed_seconds<I: IntoIterator<Item = (u64, f64)>>(
+    mut tps: f64,
+    changes: I,
+    frame: u64,
+) -> Option<f64> {
+    let mut seconds = 0.0;
+    let mut previous_frame = 0;
+    for (change_frame, new_tps) in changes {
+        if change_frame >= frame {
+            break;
+        }
+        seconds += span_seconds(change_frame - previous_frame, tps)?;
+        previous_frame = change_frame;
+        tps = new_tps;
+    }
+
+    Some(seconds + span_seconds(frame - previous_frame, tps)?).filter(|s| s.is_finite())
+}
+
+/// Read a v2 replay up to its input data: the tps, the meta bytes and the
+/// amount of inputs, with the blob table in `blobs`.
+pub(crate) fn read_v2_preamble<R: Read>(
+    reader: &mut R,
+    options: &ReadOptions,
+    blobs: &mut Vec<Blob>,
+    accepts_meta_size: impl Fn(u64) -> bool,
+) -> Result<(f64, Vec<u8>, u64), ReplayError> {
+    let mut header_buf = [0u8; 4];
+    reader.read_exact(&mut header_buf)?;
+
+    if header_buf != V2_HEADER {
+        return Err(ReplayError::HeaderMismatchError);
+    }
+
+    let tps: f64 = codec::read(reader)?;
+    let limits = options.limits();
+    if !limits.accepts_tps(tps) {
+        return Err(ReplayError::LimitExceeded("tps"));
+    }
+
+    let meta_size: u64 = codec::read(reader)?;
+    if !accepts_meta_size(meta_size) {
+        return Err(ReplayError::MetaSizeMismatchError);
+    }
+    if meta_size > options.max_meta_size {
+        return Err(ReplayError::LimitExceeded("meta size"));
+    }
+
+    let mut meta_buf = Vec::new();
+    reader.take(meta_size).read_to_end(&mut meta_buf)?;
+    if meta_buf.len() as u64 != meta_size {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+
+    let length: u64 = codec::read(reader)?;
+    if length > options.max_inputs {
+        return Err(ReplayError::LimitExceeded("inputs"));
+    }
+
+    let blob_count: u64 = codec::read(reader)?;
+
+    blobs.clear();
+    blobs.reserve(blob_count.min(MAX_PREALLOCATION) as usize);
+    let mut total = 0u64;
+    for _ in 0..blob_count {
+        let blob = Blob::read(reader)?;
+        total = total.saturating_add(blob.length);
+        if total > options.max_inputs {
+            return Err(ReplayError::LimitExceeded("inputs"));
+        }
+        blobs.push(blob);
+    }
+    if total != length {
+        return Err(crate::blob::BlobError::InvalidRange.into());
+    }
+
+    Ok((tps, meta_buf, length))
+}
+
+impl Version {
+    /// Detect the version of a file from its leading bytes.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&V3_HEADER) {
+            Some(Self::V3)
+        } else if header.starts_with(&V2_HEADER) {
+            Some(Self::V2)
+        } else {
+            None
+        }
+    }
+
+    /// The file extension of the version, without a leading dot.
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::V2 => "slc",
+            Self::V3 => "slc3",
+        }
+    }
+}
+
 impl<M: Meta> Replay<M> {
     /// Create a new slc replay with the specified tps and meta.
     pub fn new(tps: f64, meta: M) -> Self {
@@ -113,59 +413,329 @@ impl<M: Meta> Replay<M> {
         })
     }
 
-    /// Read the replay from a stream.
-    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, ReplayError> {
-        let mut header_buf = [0u8; 8];
-        reader.read_exact(&mut header_buf)?;
-        reader.seek(std::io::SeekFrom::Start(0))?;
+    /// Add an input like [`Replay::add_input`], failing if it is past the given limits.
+    ///
+    /// The base tps of the replay isn't checked.
+    pub fn add_input_within(
+        &mut self,
+        frame: u64,
+        data: InputData,
+        limits: &Limits,
+    ) -> Result<(), ReplayError> {
+        let tps = match data {
+            InputData::TPS(tps) => Some(tps),
+            _ => None,
+        };
+        limits
+            .check(self.inputs.len(), frame, tps)
+            .map_err(ReplayError::LimitExceeded)?;
+        self.add_input(frame, data);
+        Ok(())
+    }
 
-        if header_buf[0..4] == V2_HEADER {
-            Self::read_v2(reader)
-        } else if header_buf[0..8] == V3_HEADER {
-            Self::read_v3(reader)
-        } else {
-            Err(ReplayError::UnknownFormat)
+    /// Frame of the last input, or 0 if there are none.
+    pub fn last_frame(&self) -> u64 {
+        self.inputs.last().map_or(0, |i| i.frame)
+    }
+
+    /// Amount of frames from the start of the replay to its last input.
+    pub fn duration_frames(&self) -> u64 {
+        self.last_frame()
+    }
+
+    /// Time from the start of the replay to its last input, following tps changes.
+    ///
+    /// `None` if some of that time runs at a tps that isn't finite and positive.
+    pub fn duration_seconds(&self) -> Option<f64> {
+        let changes = self.inputs.iter().filter_map(|i| match i.data {
+            InputData::TPS(tps) => Some((i.frame, tps)),
+            _ => None,
+        });
+        elapsed_seconds(self.tps, changes, self.last_frame())
+    }
+
+    /// Iterate over the inputs.
+    pub fn iter(&self) -> std::slice::Iter<'_, Input> {
+        self.inputs.iter()
+    }
+
+    /// Iterate mutably over the inputs.
+    ///
+    /// Call `recalculate_deltas` after moving inputs to other frames.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Input> {
+        self.inputs.iter_mut()
+    }
+
+    /// Remove every input on or after the given frame.
+    pub fn clip_inputs(&mut self, frame: u64) {
+        self.inputs.retain(|i| i.frame < frame);
+    }
+
+    /// Recalculate every input's delta from its frame.
+    ///
+    /// Call this after editing input frames directly. Inputs are assumed
+    /// to already be sorted by frame.
+    pub fn recalculate_deltas(&mut self) {
+        let mut previous_frame = 0;
+        for input in &mut self.inputs {
+            input.delta = input.frame - previous_frame;
+            previous_frame = input.frame;
         }
     }
 
-    fn read_v2<R: Read>(reader: &mut R) -> Result<Self, ReplayError> {
-        let mut header_buf = [0u8; 4];
-        reader.read_exact(&mut header_buf)?;
+    /// Insert a skip input on a frame, after every input already on it.
+    ///
+    /// Skips do nothing in-game. They split large deltas so the inputs around
+    /// them can be packed with fewer bytes, and become section boundaries when
+    /// writing v3.
+    pub fn insert_skip(&mut self, frame: u64) {
+        let index = self.inputs.partition_point(|i| i.frame <= frame);
+        self.inputs.insert(
+            index,
+            Input {
+                delta: 0,
+                frame,
+                data: InputData::Skip,
+            },
+        );
+        self.recalculate_deltas();
+    }
+
+    /// Remove every skip input. Returns the amount of skips removed.
+    pub fn strip_skips(&mut self) -> usize {
+        let count = self.inputs.len();
+        self.inputs.retain(|i| i.data != InputData::Skip);
+        self.recalculate_deltas();
+        count - self.inputs.len()
+    }
 
-        if header_buf != V2_HEADER {
-            return Err(ReplayError::HeaderMismatchError);
+    /// The tps in effect on a frame, after every input on it.
+    pub fn tps_at(&self, frame: u64) -> f64 {
+        self.inputs
+            .iter()
+            .take_while(|i| i.frame <= frame)
+            .fold(self.tps, |tps, input| match input.data {
+                InputData::TPS(new_tps) => new_tps,
+                _ => tps,
+            })
+    }
+
+    /// Shift the replay so it starts on a later frame, for playback from a startpos.
+    ///
+    /// Inputs before `start_frame` are removed and the rest are moved back
+    /// by it, so inputs on `start_frame` end up on frame 0. The base tps
+    /// becomes the one in effect just before `start_frame`, so removed tps
+    /// changes still apply. Buttons held across `start_frame` lose their
+    /// press; see [`crate::holds::checkpoint`] to keep them.
+    /// Returns the amount of inputs removed.
+    pub fn rebase(&mut self, start_frame: u64) -> usize {
+        let count = self.inputs.len();
+        let kept = self.inputs.partition_point(|i| i.frame < start_frame);
+        self.tps = self.inputs[..kept]
+            .iter()
+            .fold(self.tps, |tps, input| match input.data {
+                InputData::TPS(new_tps) => new_tps,
+                _ => tps,
+            });
+
+        self.inputs.drain(..kept);
+        for input in &mut self.inputs {
+            input.frame -= start_frame;
         }
+        self.recalculate_deltas();
+        count - self.inputs.len()
+    }
 
-        let mut big_buf = [0u8; 8];
-        reader.read_exact(&mut big_buf)?;
-        let tps = f64::from_le_bytes(big_buf);
+    /// Append the inputs of another replay, shifted by an offset.
+    ///
+    /// If the tps in effect at the junction differs from the base tps of the
+    /// appended replay, a tps change is inserted so it keeps playing at its
+    /// own speed. Redundant tps changes are removed afterwards.
+    ///
+    /// Fails without changing the replay if a shifted input would land
+    /// before its last input, or past the last representable frame.
+    pub fn append<N: Meta>(&mut self, other: &Replay<N>, offset: u64) -> Result<(), ReplayError> {
+        let last_frame = self.inputs.last().map_or(0, |i| i.frame);
+        let start = offset.max(last_frame);
+
+        let frames = other
+            .inputs
+            .iter()
+            .map(|input| {
+                let frame = input
+                    .frame
+                    .checked_add(offset)
+                    .ok_or(ReplayError::LimitExceeded("frame"))?;
+                if frame < start {
+                    return Err(ReplayError::AppendOverlap(frame));
+                }
+                Ok(frame)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        reader.read_exact(&mut big_buf)?;
-        let meta_size = u64::from_le_bytes(big_buf);
-        if meta_size != M::size() {
-            return Err(ReplayError::MetaSizeMismatchError);
+        if self.tps_at(start) != other.tps {
+            self.add_input(start, InputData::TPS(other.tps));
         }
 
-        let mut meta_buf = vec![0u8; M::size() as usize];
-        reader.read_exact(meta_buf.as_mut_slice())?;
-        let meta = M::from_bytes(meta_buf.as_slice());
+        for (input, frame) in other.inputs.iter().zip(frames) {
+            self.add_input(frame, input.data.clone());
+        }
 
-        reader.read_exact(&mut big_buf)?;
-        let length = u64::from_le_bytes(big_buf);
-        let mut inputs: Vec<Input> = Vec::with_capacity(length as usize);
+        self.dedup_tps();
+        Ok(())
+    }
+
+    /// Remove tps changes that don't change anything.
+    ///
+    /// A tps change is redundant if it sets the tps already in effect, or if
+    /// another tps change follows it on the same frame.
+    /// Returns the amount of tps changes removed.
+    pub fn dedup_tps(&mut self) -> usize {
+        let count = self.inputs.len();
+        let mut tps = self.tps;
+        let mut keep = vec![true; count];
+
+        for (i, input) in self.inputs.iter().enumerate() {
+            let InputData::TPS(new_tps) = input.data else {
+                continue;
+            };
+
+            let overridden = self.inputs[i + 1..]
+                .iter()
+                .take_while(|next| next.frame == input.frame)
+                .any(|next| matches!(next.data, InputData::TPS(_)));
+
+            if overridden || new_tps == tps {
+                keep[i] = false;
+            } else {
+                tps = new_tps;
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.inputs.retain(|_| keep.next().unwrap_or(true));
+        self.recalculate_deltas();
+        count - self.inputs.len()
+    }
+
+    /// Split the inputs into attempts.
+    ///
+    /// Every restart ends an attempt and starts the next one. Returns the
+    /// start frame of each attempt along with the index range of its inputs,
+    /// restarts excluded.
+    pub fn attempts(&self) -> Vec<(u64, Range<usize>)> {
+        let mut attempts = Vec::new();
+        let (mut start_frame, mut start) = (0, 0);
+
+        for (i, input) in self.inputs.iter().enumerate() {
+            if matches!(input.data, InputData::Restart | InputData::RestartFull) {
+                attempts.push((start_frame, start..i));
+                (start_frame, start) = (input.frame, i + 1);
+            }
+        }
+        attempts.push((start_frame, start..self.inputs.len()));
+
+        attempts
+    }
+
+    /// Extract the attempt that lasted the longest as a standalone replay.
+    ///
+    /// An attempt lasts from its start until its last input, usually a death.
+    /// The extracted replay starts on frame 0, at the tps in effect when the
+    /// attempt started. Ties are resolved in favor of the earliest attempt.
+    pub fn best_attempt(&self) -> Option<Replay<M>>
+    where
+        M: Clone,
+    {
+        let (start_frame, range) = self
+            .attempts()
+            .into_iter()
+            .filter(|(_, range)| !range.is_empty())
+            .rev()
+            .max_by_key(|(start_frame, range)| self.inputs[range.end - 1].frame - start_frame)?;
+
+        let mut replay = Replay::new(self.tps_at(start_frame), self.meta.clone());
+        for input in &self.inputs[range] {
+            replay.add_input(input.frame - start_frame, input.data.clone());
+        }
+
+        Some(replay)
+    }
+
+    /// Whether two replays, possibly with different metas, play the same way.
+    ///
+    /// Inputs are compared by frame and data: deltas and skips are ignored,
+    /// and inputs are stably sorted by frame first. Metas are only compared
+    /// when both replays have one, since v3 replays carry none.
+    pub fn semantically_eq<N: Meta>(&self, other: &Replay<N>) -> bool {
+        let (meta, other_meta) = (self.meta.to_bytes(), other.meta.to_bytes());
+        if self.tps != other.tps
+            || (!meta.is_empty() && !other_meta.is_empty() && meta != other_meta)
+        {
+            return false;
+        }
+
+        fn normalized(inputs: &[Input]) -> Vec<(u64, &InputData)> {
+            let mut inputs: Vec<_> = inputs
+                .iter()
+                .filter(|i| i.data != InputData::Skip)
+                .map(|i| (i.frame, &i.data))
+                .collect();
+            inputs.sort_by_key(|(frame, _)| *frame);
+            inputs
+        }
+
+        normalized(&self.inputs) == normalized(&other.inputs)
+    }
+
+    /// Read the replay from a stream.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, ReplayError> {
+        Self::read_with_options(reader, &ReadOptions::default())
+    }
 
-        reader.read_exact(&mut big_buf)?;
-        let blob_count = u64::from_le_bytes(big_buf);
+    /// Read the replay from a stream, within the given limits.
+    pub fn read_with_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Self, ReplayError> {
+        let mut header_buf = [0u8; 8];
+        reader.read_exact(&mut header_buf)?;
+        reader.seek(std::io::SeekFrom::Start(0))?;
 
-        let mut blobs: Vec<Blob> = Vec::with_capacity(blob_count as usize);
-        for _ in 0..blob_count {
-            blobs.push(Blob::read(reader)?);
+        match Version::detect(&header_buf) {
+            Some(Version::V2) => Self::read_v2(reader, options, &mut Vec::new(), Vec::new()),
+            Some(Version::V3) => Self::read_v3(reader, options),
+            None => Err(ReplayError::UnknownFormat),
         }
+    }
+
+    /// Read a v2 replay, using `blobs` as scratch space for the blob table
+    /// and appending to `inputs`, which must be empty.
+    pub(crate) fn read_v2<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+        blobs: &mut Vec<Blob>,
+        mut inputs: Vec<Input>,
+    ) -> Result<Self, ReplayError> {
+        let (tps, meta, length) = read_v2_preamble(reader, options, blobs, M::accepts_size)?;
+        let meta = M::from_bytes(meta.as_slice());
+        inputs.reserve(length.min(MAX_PREALLOCATION) as usize);
+        let limits = options.limits();
 
         let mut current_frame = 0;
-        for blob in blobs {
+        for blob in blobs.iter() {
             blob.read_inputs(reader, &mut inputs, &mut current_frame)?;
         }
+        if current_frame > limits.max_frame {
+            return Err(ReplayError::LimitExceeded("frame"));
+        }
+        for input in &inputs {
+            if let InputData::TPS(tps) = input.data {
+                if !limits.accepts_tps(tps) {
+                    return Err(ReplayError::LimitExceeded("tps"));
+                }
+            }
+        }
 
         let mut footer_buf = [0u8; 3];
         reader.read_exact(&mut footer_buf)?;
@@ -176,36 +746,44 @@ impl<M: Meta> Replay<M> {
         Ok(Self { tps, meta, inputs })
     }
 
-    fn read_v3<R: Read + Seek>(reader: &mut R) -> Result<Self, ReplayError> {
+    fn read_v3<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> Result<Self, ReplayError> {
+        let v3_replay = crate::v3::Replay::read_with_options(reader, options)?;
+        Ok(Self::from_v3(&v3_replay, Vec::new()))
+    }
+
+    /// Convert a v3 replay, appending to `inputs`, which must be empty.
+    pub(crate) fn from_v3(v3_replay: &crate::v3::Replay, inputs: Vec<Input>) -> Self {
         use crate::v3::atom::AtomVariant;
         use crate::v3::ActionType;
 
-        let v3_replay = crate::v3::Replay::read(reader)?;
-
-        let mut replay = Self::new(v3_replay.metadata.tps, M::from_bytes(&[]));
+        let mut replay = Self {
+            tps: v3_replay.metadata.tps,
+            meta: M::from_bytes(&[]),
+            inputs,
+        };
 
         for atom in &v3_replay.atoms.atoms {
             if let AtomVariant::Action(action_atom) = atom {
                 for action in &action_atom.actions {
                     let data = match action.action_type {
+                        // v2 frames are integers, sub-frame positions are dropped.
                         ActionType::Jump | ActionType::Left | ActionType::Right => {
-                            let button = match action.action_type {
-                                ActionType::Jump => 1,
-                                ActionType::Left => 2,
-                                ActionType::Right => 3,
-                                _ => 1,
-                            };
-                            InputData::Player(crate::input::PlayerInput {
-                                hold: action.holding,
-                                player_2: action.player2,
-                                button,
-                            })
+                            match action.try_into() {
+                                Ok(p) => InputData::Player(p),
+                                Err(_) => continue,
+                            }
                         }
                         ActionType::Restart => InputData::Restart,
                         ActionType::RestartFull => InputData::RestartFull,
                         ActionType::Death => InputData::Death,
                         ActionType::TPS => InputData::TPS(action.tps),
-                        ActionType::Reserved => InputData::Skip,
+                        ActionType::Reserved | ActionType::Skip => InputData::Skip,
+                        // v2 has no way to represent analog input.
+                        ActionType::Analog => continue,
+                        // v2 tps changes are global, and can't be scoped to a player.
+                        ActionType::PlayerTPS => continue,
+                        // Frames already exclude pauses, and v2 has no marker for them.
+                        ActionType::Pause => continue,
                     };
 
                     replay.add_input(action.frame, data);
@@ -213,7 +791,7 @@ impl<M: Meta> Replay<M> {
             }
         }
 
-        Ok(replay)
+        replay
     }
 
     /// Write the replay to a stream in v2 format.
@@ -222,85 +800,25 @@ impl<M: Meta> Replay<M> {
     }
 
     fn write_v2<W: Write>(&self, writer: &mut W) -> Result<(), ReplayError> {
-        writer.write_all(&V2_HEADER)?;
-
-        writer.write_all(&self.tps.to_le_bytes())?;
-        writer.write_all(&M::size().to_le_bytes())?;
-        writer.write_all(&self.meta.to_bytes())?;
-
-        writer.write_all(&(self.inputs.len() as u64).to_le_bytes())?;
+        self.write_v2_with(writer, &Blob::plan(&self.inputs))
+    }
 
-        let mut blobs: Vec<Blob> = Vec::new();
+    /// Write the replay in v2 format, in the given blobs.
+    pub(crate) fn write_v2_with<W: Write>(
+        &self,
+        writer: &mut W,
+        blobs: &[Blob],
+    ) -> Result<(), ReplayError> {
+        writer.write_all(&V2_HEADER)?;
 
-        // First blob pass
-        self.inputs.iter().enumerate().for_each(|(i, input)| {
-            let byte_size = input.required_bytes();
-            if blobs.is_empty() {
-                blobs.push(Blob {
-                    byte_size: byte_size as u64,
-                    start: i as u64,
-                    length: 1,
-                });
-                return;
-            }
+        codec::write(writer, self.tps)?;
+        let meta = self.meta.to_bytes();
+        codec::write(writer, meta.len() as u64)?;
+        writer.write_all(&meta)?;
 
-            let blob = blobs
-                .last_mut()
-                .expect("Blobs should have an element already");
-
-            match blob.byte_size.cmp(&(byte_size as u64)) {
-                Ordering::Less | Ordering::Greater => {
-                    blobs.push(Blob {
-                        byte_size: byte_size as u64,
-                        start: i as u64,
-                        length: 1,
-                    });
-                }
-                Ordering::Equal => {
-                    blob.length += 1;
-                }
-            }
-        });
+        codec::write(writer, self.inputs.len() as u64)?;
 
-        let mut zero_sized_blobs = 0;
-
-        // Second blob pass
-        for i in (1..blobs.len()).rev() {
-            let [previous, blob] = blobs
-                .get_disjoint_mut([i - 1, i])
-                .expect("Blob should exist");
-
-            let blob_size = blob.byte_size * blob.length;
-            const BLOB_MEM_SIZE: u64 = 24;
-
-            if blob_size < BLOB_MEM_SIZE {
-                if blob.byte_size > previous.byte_size
-                    && (previous.byte_size * blob.length) < BLOB_MEM_SIZE
-                {
-                    previous.length += blob.length;
-                    previous.byte_size = blob.byte_size;
-                    blob.length = 0;
-                    zero_sized_blobs += 1;
-                    continue;
-                } else if blob.byte_size < previous.byte_size
-                    && (previous.byte_size * blob.length) < BLOB_MEM_SIZE
-                {
-                    previous.length += blob.length;
-                    blob.length = 0;
-                    zero_sized_blobs += 1;
-                    continue;
-                }
-            }
-
-            if blob.byte_size == previous.byte_size {
-                previous.length += blob.length;
-                blob.length = 0;
-                zero_sized_blobs += 1;
-            }
-        }
-
-        let blob_length: u64 = blobs.len() as u64 - zero_sized_blobs;
-        writer.write_all(&blob_length.to_le_bytes())?;
+        codec::write(writer, blobs.len() as u64)?;
 
         blobs.iter().try_for_each(|b| b.write(writer))?;
         blobs
@@ -313,6 +831,28 @@ impl<M: Meta> Replay<M> {
     }
 
     pub fn write_v3<W: Write>(&self, writer: &mut W) -> Result<(), ReplayError> {
+        self.to_v3().write(writer)?;
+
+        Ok(())
+    }
+
+    /// Size in bytes of the replay written with [`Replay::write`], without
+    /// keeping the written bytes anywhere.
+    pub fn encoded_size(&self) -> Result<u64, ReplayError> {
+        let mut counter = ByteCounter::default();
+        self.write(&mut counter)?;
+        Ok(counter.0)
+    }
+
+    /// Size in bytes of the replay written with [`Replay::write_v3`].
+    pub fn encoded_size_v3(&self) -> Result<u64, ReplayError> {
+        Ok(self.to_v3().encoded_size(&WriteOptions::default())?)
+    }
+
+    /// Convert the replay to a v3 replay, the way [`Replay::write_v3`] encodes it.
+    ///
+    /// The meta is dropped, and the seed and build are left at 0 and 1.
+    pub fn to_v3(&self) -> crate::v3::Replay {
         use crate::v3::atom::AtomVariant;
         use crate::v3::builtin::ActionAtom;
         use crate::v3::{ActionType, Metadata};
@@ -353,13 +893,40 @@ impl<M: Meta> Replay<M> {
                 InputData::TPS(tps) => {
                     action_atom.add_tps_action(input.frame, *tps).ok();
                 }
-                InputData::Skip => {}
+                InputData::Skip => {
+                    action_atom.add_skip_action(input.frame).ok();
+                }
             }
         }
 
         v3_replay.add_atom(AtomVariant::Action(action_atom));
-        v3_replay.write(writer)?;
+        v3_replay
+    }
+}
 
-        Ok(())
+impl<M: Meta> IntoIterator for Replay<M> {
+    type Item = Input;
+    type IntoIter = std::vec::IntoIter<Input>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inputs.into_iter()
+    }
+}
+
+impl<'a, M: Meta> IntoIterator for &'a Replay<M> {
+    type Item = &'a Input;
+    type IntoIter = std::slice::Iter<'a, Input>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inputs.iter()
+    }
+}
+
+impl<'a, M: Meta> IntoIterator for &'a mut Replay<M> {
+    type Item = &'a mut Input;
+    type IntoIter = std::slice::IterMut<'a, Input>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inputs.iter_mut()
     }
 }
@@ -0,0 +1,249 @@
+//! Assembling full runs from startpos segments.
+//!
+//! Segments are spliced from memory with [`splice_many`], or from files
+//! listed in a [`Manifest`].
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    input::{InputData, PlayerInput},
+    meta::Meta,
+    replay::{Replay, ReplayError},
+};
+
+/// What went wrong at a seam between two segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpliceConflictKind {
+    /// The segment ran past the start of the next one, and its last inputs
+    /// were trimmed.
+    Overlap { trimmed: usize },
+    /// A button was still held when the next segment started, and a release
+    /// was inserted at the seam.
+    HeldAtSeam { button: u8, player_2: bool },
+    /// The segment released a button that wasn't held, and the release was dropped.
+    OrphanRelease { button: u8, player_2: bool },
+}
+
+/// A problem found and fixed while splicing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpliceConflict {
+    pub kind: SpliceConflictKind,
+    /// Index of the segment the conflict was found in, in the given order.
+    pub segment: usize,
+    /// Frame of the conflict on the assembled timeline.
+    pub frame: u64,
+}
+
+/// The result of [`splice_many`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Splice<M: Meta> {
+    pub replay: Replay<M>,
+    pub conflicts: Vec<SpliceConflict>,
+}
+
+/// Assemble a full run from segments starting at different frames.
+///
+/// Segments may be given in any order, each with the frame of the full run
+/// it starts on. A segment covers the run until the next segment starts:
+/// inputs past that are trimmed. Buttons still held at a seam are released
+/// there, and tps changes are inserted wherever a segment's tps differs from
+/// the one in effect. The assembled replay uses the meta of the earliest
+/// segment.
+///
+/// Returns `None` if there are no segments.
+pub fn splice_many<M: Meta + Clone>(segments: &[(Replay<M>, u64)]) -> Option<Splice<M>> {
+    let mut order: Vec<usize> = (0..segments.len()).collect();
+    order.sort_by_key(|&i| segments[i].1);
+
+    let first = &segments[*order.first()?].0;
+    let mut replay = Replay::new(first.tps, first.meta.clone());
+    let mut conflicts = Vec::new();
+    let mut held = [[false; 4]; 2];
+
+    for (k, &segment) in order.iter().enumerate() {
+        let (part, start) = (&segments[segment].0, segments[segment].1);
+        let end = order.get(k + 1).map(|&next| segments[next].1);
+
+        for (player_2, buttons) in held.iter_mut().enumerate() {
+            for (button, is_held) in buttons.iter_mut().enumerate() {
+                if std::mem::take(is_held) {
+                    let (button, player_2) = (button as u8, player_2 == 1);
+                    replay.add_input(
+                        start,
+                        InputData::Player(PlayerInput {
+                            hold: false,
+                            player_2,
+                            button,
+                        }),
+                    );
+                    conflicts.push(SpliceConflict {
+                        kind: SpliceConflictKind::HeldAtSeam { button, player_2 },
+                        segment,
+                        frame: start,
+                    });
+                }
+            }
+        }
+
+        if replay.tps_at(start) != part.tps {
+            replay.add_input(start, InputData::TPS(part.tps));
+        }
+
+        let mut trimmed = 0;
+        for input in &part.inputs {
+            let frame = input.frame + start;
+            if end.is_some_and(|end| frame >= end) {
+                trimmed += 1;
+                continue;
+            }
+
+            if let InputData::Player(p) = &input.data {
+                let slot = &mut held[p.player_2 as usize][(p.button as usize).min(3)];
+                if !p.hold && !*slot {
+                    conflicts.push(SpliceConflict {
+                        kind: SpliceConflictKind::OrphanRelease {
+                            button: p.button,
+                            player_2: p.player_2,
+                        },
+                        segment,
+                        frame,
+                    });
+                    continue;
+                }
+                *slot = p.hold;
+            }
+
+            replay.add_input(frame, input.data.clone());
+        }
+
+        if trimmed > 0 {
+            conflicts.push(SpliceConflict {
+                kind: SpliceConflictKind::Overlap { trimmed },
+                segment,
+                frame: end.unwrap_or(start),
+            });
+        }
+    }
+
+    replay.dedup_tps();
+
+    Some(Splice { replay, conflicts })
+}
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Invalid manifest line {0}")]
+    InvalidLine(usize),
+    #[error("Manifest lists no segments")]
+    Empty,
+    #[error("Segment {}: {source}", .path.display())]
+    Segment {
+        path: PathBuf,
+        #[source]
+        source: ReplayError,
+    },
+}
+
+/// A segment file and the frame of the full run it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub start: u64,
+}
+
+/// A list of segment files making up a full run.
+///
+/// Manifests are text, one segment per line: the start frame, whitespace,
+/// then the path to the segment file, which may contain spaces. Blank lines
+/// and lines starting with `#` are ignored. Relative paths are relative to
+/// the manifest when it is loaded with [`Manifest::load`].
+///
+/// ```text
+/// # start path
+/// 0 intro.slc
+/// 1830 second part.slc
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a segment.
+    pub fn push<P: Into<PathBuf>>(&mut self, path: P, start: u64) {
+        self.entries.push(ManifestEntry {
+            path: path.into(),
+            start,
+        });
+    }
+
+    /// Read a manifest, keeping its paths as they are.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, ManifestError> {
+        let mut manifest = Self::new();
+        for (index, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || ManifestError::InvalidLine(index + 1);
+            let (start, path) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let start = start.parse().map_err(|_| invalid())?;
+            manifest.push(path.trim(), start);
+        }
+        Ok(manifest)
+    }
+
+    /// Read a manifest file, resolving its relative paths against its directory.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        let mut manifest = Self::read(&mut File::open(path)?)?;
+        if let Some(dir) = path.parent() {
+            for entry in &mut manifest.entries {
+                entry.path = dir.join(&entry.path);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest in the format [`Manifest::read`] accepts.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), ManifestError> {
+        for entry in &self.entries {
+            writeln!(writer, "{} {}", entry.start, entry.path.display())?;
+        }
+        Ok(())
+    }
+
+    /// Read every segment and splice them with [`splice_many`].
+    pub fn assemble<M: Meta + Clone>(&self) -> Result<Splice<M>, ManifestError> {
+        let segments = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let replay = File::open(&entry.path)
+                    .map_err(ReplayError::from)
+                    .and_then(|file| Replay::read(&mut BufReader::new(file)))
+                    .map_err(|source| ManifestError::Segment {
+                        path: entry.path.clone(),
+                        source,
+                    })?;
+                Ok((replay, entry.start))
+            })
+            .collect::<Result<Vec<_>, ManifestError>>()?;
+
+        splice_many(&segments).ok_or(ManifestError::Empty)
+    }
+}
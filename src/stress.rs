@@ -0,0 +1,203 @@
+//! Memory and I/O accounting for large replays.
+//!
+//! [`stress`] and [`stress_v3`] write a replay and read it back, reporting
+//! the buffers involved and how the streams were used. Buffer sizes are
+//! computed from the capacities of the crate's own buffers, not by hooking
+//! the allocator, so they leave out the allocator's overhead.
+
+use std::{
+    fmt::{self, Display},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    mem::size_of,
+};
+
+use crate::{
+    blob::Blob,
+    input::Input,
+    meta::Meta,
+    replay::{Replay, ReplayError},
+    v3::{
+        action::Action,
+        atom::AtomVariant,
+        section::{Section, SectionInput},
+        WriteOptions,
+    },
+};
+
+/// What writing and reading back a replay costs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StressReport {
+    /// Amount of inputs, or actions for v3 replays.
+    pub inputs: usize,
+    pub encoded_bytes: usize,
+    /// Calls to `write` made by the writer, each a syscall on an unbuffered file.
+    pub write_calls: u64,
+    /// Calls to `read` made by the reader.
+    pub read_calls: u64,
+    pub seeks: u64,
+    /// Named buffers and their sizes, in bytes.
+    pub buffers: Vec<(&'static str, usize)>,
+    /// Largest amount of buffer memory held at once, in bytes.
+    pub peak_bytes: usize,
+}
+
+impl Display for StressReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} inputs, {} bytes encoded, peak {} bytes",
+            self.inputs, self.encoded_bytes, self.peak_bytes
+        )?;
+        writeln!(
+            f,
+            "{} writes, {} reads, {} seeks",
+            self.write_calls, self.read_calls, self.seeks
+        )?;
+        for (name, bytes) in &self.buffers {
+            writeln!(f, "  {}: {} bytes", name, bytes)?;
+        }
+        Ok(())
+    }
+}
+
+struct Counting<S> {
+    inner: S,
+    reads: u64,
+    writes: u64,
+    seeks: u64,
+}
+
+impl<S> Counting<S> {
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            reads: 0,
+            writes: 0,
+            seeks: 0,
+        }
+    }
+}
+
+impl<S: Read> Read for Counting<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reads += 1;
+        self.inner.read(buf)
+    }
+}
+
+impl<S: Write> Write for Counting<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writes += 1;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Seek> Seek for Counting<S> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.seeks += 1;
+        self.inner.seek(pos)
+    }
+}
+
+fn inputs_bytes(inputs: &Vec<Input>) -> usize {
+    inputs.capacity() * size_of::<Input>()
+}
+
+fn actions_bytes(replay: &crate::v3::Replay) -> usize {
+    replay
+        .atoms
+        .iter()
+        .map(|atom| match atom {
+            AtomVariant::Action(a) => a.actions.capacity() * size_of::<Action>(),
+            _ => size_of::<AtomVariant>(),
+        })
+        .sum()
+}
+
+/// Write a v2 replay and read it back, accounting for memory and I/O.
+pub fn stress<M: Meta>(replay: &Replay<M>) -> Result<StressReport, ReplayError> {
+    let mut writer = Counting::new(Vec::new());
+    replay.write(&mut writer)?;
+
+    let mut reader = Counting::new(Cursor::new(&writer.inner));
+    let decoded = Replay::<M>::read(&mut reader)?;
+
+    let meta_bytes = replay.meta.to_bytes().len();
+    let replay_bytes = inputs_bytes(&replay.inputs) + meta_bytes;
+    let blobs = Blob::plan(&replay.inputs);
+    let blob_bytes = blobs.capacity() * size_of::<Blob>();
+    let encoded = writer.inner.capacity();
+    let decoded_bytes = inputs_bytes(&decoded.inputs) + meta_bytes;
+
+    Ok(StressReport {
+        inputs: replay.inputs.len(),
+        encoded_bytes: writer.inner.len(),
+        write_calls: writer.writes,
+        read_calls: reader.reads,
+        seeks: reader.seeks,
+        buffers: vec![
+            ("replay", replay_bytes),
+            ("blob table", blob_bytes),
+            ("encoded", encoded),
+            ("decoded replay", decoded_bytes),
+        ],
+        peak_bytes: (replay_bytes + blob_bytes + encoded).max(encoded + decoded_bytes),
+    })
+}
+
+/// Write a v3 replay and read it back, accounting for memory and I/O.
+///
+/// The writer works on a copy of the actions of each atom, which is
+/// accounted for as scratch memory along with the planned sections.
+pub fn stress_v3(
+    replay: &crate::v3::Replay,
+) -> Result<StressReport, crate::v3::replay::ReplayError> {
+    let mut writer = Counting::new(Vec::new());
+    replay.write(&mut writer)?;
+
+    let mut reader = Counting::new(Cursor::new(&writer.inner));
+    let decoded = crate::v3::Replay::read(&mut reader)?;
+
+    let mut inputs = 0;
+    let mut scratch_actions = 0;
+    let mut section_bytes = 0;
+    for atom in &replay.atoms {
+        if let AtomVariant::Action(a) = atom {
+            inputs += a.actions.len();
+            scratch_actions = scratch_actions.max(a.actions.len() * size_of::<Action>());
+            let sections = a.sections(&WriteOptions::default())?;
+            section_bytes = section_bytes.max(
+                sections.capacity() * size_of::<Section>()
+                    + sections
+                        .iter()
+                        .map(|s| s.player_inputs.capacity() * size_of::<SectionInput>())
+                        .sum::<usize>(),
+            );
+        }
+    }
+
+    let replay_bytes = actions_bytes(replay);
+    let encoded = writer.inner.capacity();
+    let decoded_bytes = actions_bytes(&decoded);
+
+    Ok(StressReport {
+        inputs,
+        encoded_bytes: writer.inner.len(),
+        write_calls: writer.writes,
+        read_calls: reader.reads,
+        seeks: reader.seeks,
+        buffers: vec![
+            ("replay", replay_bytes),
+            ("action copy", scratch_actions),
+            ("sections", section_bytes),
+            ("encoded", encoded),
+            ("decoded replay", decoded_bytes),
+        ],
+        peak_bytes: (replay_bytes + scratch_actions + section_bytes + encoded)
+            .max(encoded + decoded_bytes),
+    })
+}
@@ -0,0 +1,403 @@
+//! Synthetic replays for benchmarks and integration tests.
+//!
+//! Replays are generated from a seed and a [`Profile`], so the same pair
+//! always produces the same replay.
+
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::{
+    codec,
+    input::{Button, InputData, PlayerInput},
+    replay::{ReadOptions, Replay},
+    rng::SplitMix64,
+};
+
+/// The shape of a generated replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// Length of the replay, in frames.
+    pub frames: u64,
+    pub tps: f64,
+    /// Average amount of clicks per second.
+    pub cps: f64,
+    /// Longest hold, in frames. Holds are between 1 frame and this long.
+    pub max_hold: u64,
+    /// Chance of a click being followed by a death and a restart.
+    pub death_chance: f64,
+    /// Chance of a click being made by player 2.
+    pub player_2_chance: f64,
+    /// Whether clicks also use the left and right buttons.
+    pub platformer: bool,
+}
+
+impl Default for Profile {
+    /// A minute long one player macro, clicking 4 times a second.
+    fn default() -> Self {
+        Self {
+            frames: 240 * 60,
+            tps: 240.0,
+            cps: 4.0,
+            max_hold: 12,
+            death_chance: 0.01,
+            player_2_chance: 0.0,
+            platformer: false,
+        }
+    }
+}
+
+impl Profile {
+    /// Fast, short clicks, like in wave or spam sections.
+    pub fn spam() -> Self {
+        Self {
+            cps: 20.0,
+            max_hold: 2,
+            ..Self::default()
+        }
+    }
+
+    /// Clicks split between both players.
+    pub fn dual() -> Self {
+        Self {
+            player_2_chance: 0.5,
+            ..Self::default()
+        }
+    }
+
+    /// Long holds on every button.
+    pub fn platformer() -> Self {
+        Self {
+            cps: 2.0,
+            max_hold: 120,
+            platformer: true,
+            ..Self::default()
+        }
+    }
+
+    /// The same profile, with a different length.
+    pub fn with_frames(self, frames: u64) -> Self {
+        Self { frames, ..self }
+    }
+}
+
+/// Generate a replay from a seed.
+///
+/// Every click is a press followed by a release of the same button. A death
+/// is followed by a restart on the next frame.
+pub fn generate_replay(seed: u64, profile: &Profile) -> Replay<()> {
+    let mut rng = SplitMix64::new(seed);
+    let mut replay = Replay::new(profile.tps, ());
+
+    let gap = (profile.tps / profile.cps.max(f64::MIN_POSITIVE)).max(1.0) as u64;
+    let idle = gap.saturating_sub(profile.max_hold.div_ceil(2));
+
+    let mut frame = rng.below_inclusive(idle);
+    while frame < profile.frames {
+        let button = if profile.platformer {
+            [Button::Jump, Button::Left, Button::Right][rng.below_inclusive(2) as usize]
+        } else {
+            Button::Jump
+        };
+        let player_2 = rng.next_f64() < profile.player_2_chance;
+        let release = frame + 1 + rng.below_inclusive(profile.max_hold.saturating_sub(1));
+
+        replay.add_input(
+            frame,
+            InputData::Player(PlayerInput::new(button, true, player_2)),
+        );
+        replay.add_input(
+            release,
+            InputData::Player(PlayerInput::new(button, false, player_2)),
+        );
+        frame = release;
+
+        if rng.next_f64() < profile.death_chance {
+            replay.add_input(frame, InputData::Death);
+            replay.add_input(frame + 1, InputData::Restart);
+            frame += 1;
+        }
+
+        frame += 1 + rng.below_inclusive(idle.saturating_mul(2));
+    }
+
+    replay
+}
+
+/// Generate a v3 replay from a seed.
+///
+/// Holds the same actions as [`generate_replay`], with the seed stored in
+/// the metadata.
+pub fn generate_replay_v3(seed: u64, profile: &Profile) -> crate::v3::Replay {
+    let mut replay = generate_replay(seed, profile).to_v3();
+    replay.metadata.seed = seed;
+    replay
+}
+
+/// A check of [`self_check`] that didn't pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfCheckFailure {
+    pub check: &'static str,
+    pub message: String,
+}
+
+/// Run round trip checks on generated replays, with the crate as built.
+///
+/// Covers the byte order of encoded numbers, v2 and v3 round trips,
+/// conversion between them, and rejection of truncated files. Meant for consumers that can't run the test suite, for
+/// example to verify a build at startup.
+pub fn self_check() -> Result<(), SelfCheckFailure> {
+    let fail = |check, message: String| Err(SelfCheckFailure { check, message });
+
+    let mut bytes = [0u8; 12];
+    codec::encode(0x0102_0304u32, &mut bytes);
+    codec::encode(240.0f64, &mut bytes[4..]);
+    if bytes != [4, 3, 2, 1, 0, 0, 0, 0, 0, 0, 0x6e, 0x40] {
+        return fail("byte order", format!("encoded as {bytes:02x?}"));
+    }
+
+    let profiles = [
+        Profile::default(),
+        Profile::spam(),
+        Profile::dual(),
+        Profile::platformer(),
+    ];
+    for (seed, profile) in profiles.iter().enumerate() {
+        let replay = generate_replay(seed as u64, &profile.clone().with_frames(240 * 10));
+
+        let mut v2 = Vec::new();
+        if let Err(e) = replay.write(&mut v2) {
+            return fail("v2 write", e.to_string());
+        }
+        match Replay::<()>::read(&mut Cursor::new(&v2)) {
+            Ok(read) if read == replay => {}
+            Ok(_) => return fail("v2 round trip", "decoded replay differs".to_string()),
+            Err(e) => return fail("v2 round trip", e.to_string()),
+        }
+
+        let mut v3 = Vec::new();
+        if let Err(e) = replay.write_v3(&mut v3) {
+            return fail("v3 write", e.to_string());
+        }
+        match Replay::<()>::read(&mut Cursor::new(&v3)) {
+            Ok(read) if read.semantically_eq(&replay) => {}
+            Ok(_) => return fail("v3 round trip", "decoded replay differs".to_string()),
+            Err(e) => return fail("v3 round trip", e.to_string()),
+        }
+
+        for bytes in [&v2, &v3] {
+            let truncated = &bytes[..bytes.len() / 2];
+            let options = ReadOptions::untrusted();
+            if Replay::<()>::read_with_options(&mut Cursor::new(truncated), &options).is_ok() {
+                return fail("truncation", "truncated replay was accepted".to_string());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A fault injected by a [`FaultyReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The stream ends at the given offset.
+    Truncate { at: u64 },
+    /// A bit of the byte at the given offset is flipped.
+    FlipBit { offset: u64, bit: u8 },
+    /// Every read returns at most the given amount of bytes.
+    ShortRead { max: usize },
+    /// Reading the byte at the given offset fails with an error.
+    Error { at: u64 },
+}
+
+/// A reader wrapper injecting faults into the stream it reads.
+///
+/// Offsets are absolute positions in the wrapped stream. Truncation is
+/// visible to seeks from the end, so readers locating a footer see the
+/// truncated stream.
+///
+/// # Examples
+/// ```
+/// use slc_oxide::testing::FaultyReader;
+/// use std::io::{Cursor, Read};
+///
+/// let mut reader = FaultyReader::new(Cursor::new(vec![1u8, 2, 3, 4]))
+///     .truncate_at(3)
+///     .flip_bit(0, 1);
+/// let mut buf = Vec::new();
+/// reader.read_to_end(&mut buf).unwrap();
+/// assert_eq!(buf, [3, 2, 3]);
+/// ```
+#[derive(Debug)]
+pub struct FaultyReader<R> {
+    inner: R,
+    faults: Vec<Fault>,
+    position: u64,
+}
+
+impl<R: Read + Seek> FaultyReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            faults: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn with_fault(mut self, fault: Fault) -> Self {
+        self.faults.push(fault);
+        self
+    }
+
+    pub fn truncate_at(self, at: u64) -> Self {
+        self.with_fault(Fault::Truncate { at })
+    }
+
+    pub fn flip_bit(self, offset: u64, bit: u8) -> Self {
+        self.with_fault(Fault::FlipBit { offset, bit })
+    }
+
+    pub fn short_reads(self, max: usize) -> Self {
+        self.with_fault(Fault::ShortRead { max })
+    }
+
+    pub fn fail_at(self, at: u64) -> Self {
+        self.with_fault(Fault::Error { at })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn len(&mut self) -> std::io::Result<u64> {
+        let len = self.inner.seek(SeekFrom::End(0))?;
+        Ok(self.faults.iter().fold(len, |len, fault| match fault {
+            Fault::Truncate { at } => len.min(*at),
+            _ => len,
+        }))
+    }
+}
+
+impl<R: Read + Seek> Read for FaultyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut end = self.len()?.max(self.position);
+        let mut max = buf.len();
+        for fault in &self.faults {
+            match *fault {
+                Fault::ShortRead { max: short } => max = max.min(short.max(1)),
+                Fault::Error { at } if at >= self.position => end = end.min(at),
+                _ => {}
+            }
+        }
+
+        let wanted = (max as u64).min(end - self.position) as usize;
+        if wanted == 0 && !buf.is_empty() {
+            let fails = self
+                .faults
+                .iter()
+                .any(|f| matches!(f, Fault::Error { at } if *at == self.position));
+            if fails {
+                return Err(std::io::Error::other("injected fault"));
+            }
+            return Ok(0);
+        }
+
+        self.inner.seek(SeekFrom::Start(self.position))?;
+        let read = self.inner.read(&mut buf[..wanted])?;
+        for fault in &self.faults {
+            if let Fault::FlipBit { offset, bit } = *fault {
+                if (self.position..self.position + read as u64).contains(&offset) {
+                    buf[(offset - self.position) as usize] ^= 1 << (bit % 8);
+                }
+            }
+        }
+
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Read + Seek> Seek for FaultyReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len()?.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = target.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// A decoder panicking on, or misreading, a faulty stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RobustnessFailure {
+    /// The injected fault, or `None` if the intact replay failed.
+    pub fault: Option<Fault>,
+    pub message: String,
+}
+
+/// Check that the decoders handle faults in an encoded replay gracefully.
+///
+/// The replay is decoded with every fault that fits in it: truncation at
+/// every offset, a flipped bit in every byte, an error at every offset and
+/// short reads. Decoding must never panic, errors and truncations of v2
+/// replays must be reported, and short reads must not change the result.
+/// Replays are read with [`ReadOptions::untrusted`].
+///
+/// Panics are caught with [`std::panic::catch_unwind`], so this can't detect
+/// them in builds that abort on panic.
+pub fn check_robustness(bytes: &[u8]) -> Result<(), RobustnessFailure> {
+    type Decoded = Result<Replay<Vec<u8>>, String>;
+
+    fn decode(reader: FaultyReader<Cursor<&[u8]>>) -> std::thread::Result<Decoded> {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let mut reader = reader;
+            Replay::<Vec<u8>>::read_with_options(&mut reader, &ReadOptions::untrusted())
+                .map_err(|e| e.to_string())
+        }))
+    }
+
+    let clean = decode(FaultyReader::new(Cursor::new(bytes))).map_err(|_| RobustnessFailure {
+        fault: None,
+        message: "decoding the intact replay panicked".to_string(),
+    })?;
+    let is_v2 = bytes.starts_with(&crate::replay::V2_HEADER);
+
+    let len = bytes.len() as u64;
+    let faults = (0..len)
+        .map(|at| Fault::Truncate { at })
+        .chain((0..len).map(|at| Fault::Error { at }))
+        .chain((0..len).flat_map(|offset| [0, 3, 7].map(|bit| Fault::FlipBit { offset, bit })))
+        .chain([1, 3, 7].map(|max| Fault::ShortRead { max }));
+
+    for fault in faults {
+        let result = decode(FaultyReader::new(Cursor::new(bytes)).with_fault(fault));
+        let fail = |message: &str| {
+            Err(RobustnessFailure {
+                fault: Some(fault),
+                message: message.to_string(),
+            })
+        };
+
+        let Ok(result) = result else {
+            return fail("decoder panicked");
+        };
+        match fault {
+            Fault::Truncate { .. } if is_v2 && result.is_ok() => {
+                return fail("truncated replay was accepted")
+            }
+            Fault::Error { .. } if result.is_ok() => return fail("error was swallowed"),
+            Fault::ShortRead { .. } if result != clean => {
+                return fail("short reads changed the result")
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
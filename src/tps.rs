@@ -0,0 +1,204 @@
+//! Tps changes over a replay, and the time math following them.
+//!
+//! A [`TpsTimeline`] is the starting tps of a replay along with its global
+//! tps changes. It converts between frames and seconds, and can be built
+//! from either version or serialized on its own with the `serde` feature.
+
+use crate::{
+    input::InputData,
+    meta::Meta,
+    replay::Replay,
+    v3::{atom::AtomVariant, ActionType},
+};
+
+/// Tolerance for floating point error when converting seconds to frames.
+const EPSILON: f64 = 1e-9;
+
+/// A tps change on a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TpsChange {
+    pub frame: u64,
+    pub tps: f64,
+}
+
+/// The serialized form of a timeline, without the cached seconds.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TpsChanges {
+    initial: f64,
+    changes: Vec<TpsChange>,
+}
+
+/// The tps of a replay over time.
+///
+/// A change applies from its own frame on, and when several are on one
+/// frame the last one wins. Spans with a tps that isn't positive take no
+/// time. Lookups are a binary search over the changes, so converting every
+/// input of a long replay stays linearithmic.
+///
+/// # Examples
+/// ```
+/// use slc_oxide::TpsTimeline;
+///
+/// let mut timeline = TpsTimeline::new(240.0);
+/// timeline.push(480, 480.0);
+/// assert_eq!(timeline.seconds(960), 3.0);
+/// assert_eq!(timeline.frame_at(3.0), 960);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "TpsChanges", into = "TpsChanges")
+)]
+pub struct TpsTimeline {
+    initial: f64,
+    changes: Vec<TpsChange>,
+    /// Seconds elapsed at the frame of each change.
+    seconds: Vec<f64>,
+}
+
+impl From<TpsChanges> for TpsTimeline {
+    fn from(raw: TpsChanges) -> Self {
+        Self::from_changes(raw.initial, raw.changes)
+    }
+}
+
+impl From<TpsTimeline> for TpsChanges {
+    fn from(timeline: TpsTimeline) -> Self {
+        Self {
+            initial: timeline.initial,
+            changes: timeline.changes,
+        }
+    }
+}
+
+impl TpsTimeline {
+    /// A timeline with no changes.
+    pub fn new(initial: f64) -> Self {
+        Self {
+            initial,
+            changes: Vec::new(),
+            seconds: Vec::new(),
+        }
+    }
+
+    /// A timeline from a starting tps and changes in any order.
+    ///
+    /// Changes on the same frame keep their order.
+    pub fn from_changes<I: IntoIterator<Item = TpsChange>>(initial: f64, changes: I) -> Self {
+        let mut changes: Vec<_> = changes.into_iter().collect();
+        changes.sort_by_key(|c| c.frame);
+        let mut timeline = Self {
+            initial,
+            changes,
+            seconds: Vec::new(),
+        };
+        timeline.compute_seconds(0);
+        timeline
+    }
+
+    /// Add a change, after every change on the same frame.
+    pub fn push(&mut self, frame: u64, tps: f64) {
+        let index = self.changes.partition_point(|c| c.frame <= frame);
+        self.changes.insert(index, TpsChange { frame, tps });
+        self.compute_seconds(index);
+    }
+
+    /// Recompute the seconds of every change from an index on.
+    fn compute_seconds(&mut self, from: usize) {
+        self.seconds.truncate(from);
+        for i in from..self.changes.len() {
+            let seconds = self.seconds_with(i, self.changes[i].frame);
+            self.seconds.push(seconds);
+        }
+    }
+
+    /// Seconds at a frame, within the span started by the first `changes` changes.
+    fn seconds_with(&self, changes: usize, frame: u64) -> f64 {
+        let (start, seconds, tps) = match changes.checked_sub(1) {
+            Some(i) => (self.changes[i].frame, self.seconds[i], self.changes[i].tps),
+            None => (0, 0.0, self.initial),
+        };
+        if tps > 0.0 {
+            seconds + (frame - start) as f64 / tps
+        } else {
+            seconds
+        }
+    }
+
+    /// The tps the replay starts at.
+    pub fn initial(&self) -> f64 {
+        self.initial
+    }
+
+    /// The changes, ordered by frame.
+    pub fn changes(&self) -> &[TpsChange] {
+        &self.changes
+    }
+
+    /// The tps in effect on a frame, after every change on it.
+    pub fn tps_at(&self, frame: u64) -> f64 {
+        match self.changes.partition_point(|c| c.frame <= frame) {
+            0 => self.initial,
+            i => self.changes[i - 1].tps,
+        }
+    }
+
+    /// Seconds elapsed from frame 0 to a frame.
+    pub fn seconds(&self, frame: u64) -> f64 {
+        self.seconds_with(self.changes.partition_point(|c| c.frame <= frame), frame)
+    }
+
+    /// The frame playing at a time, in seconds from frame 0.
+    ///
+    /// This is the last frame starting at or before that time, so
+    /// `frame_at(seconds(frame)) == frame` whenever the tps is positive.
+    /// Returns the frame a span starts on if time doesn't pass during it.
+    pub fn frame_at(&self, seconds: f64) -> u64 {
+        let count = self.seconds.partition_point(|&s| s <= seconds + EPSILON);
+        let (start, start_seconds, tps) = match count.checked_sub(1) {
+            Some(i) => (self.changes[i].frame, self.seconds[i], self.changes[i].tps),
+            None => (0, 0.0, self.initial),
+        };
+        if tps > 0.0 && seconds > start_seconds {
+            start + ((seconds - start_seconds) * tps + EPSILON).floor() as u64
+        } else {
+            start
+        }
+    }
+}
+
+impl<M: Meta> From<&Replay<M>> for TpsTimeline {
+    fn from(replay: &Replay<M>) -> Self {
+        let changes = replay.iter().filter_map(|input| match input.data {
+            InputData::TPS(tps) => Some(TpsChange {
+                frame: input.frame,
+                tps,
+            }),
+            _ => None,
+        });
+        Self::from_changes(replay.tps, changes)
+    }
+}
+
+/// Only global tps changes are taken into account, per-player ones are ignored.
+impl From<&crate::v3::Replay> for TpsTimeline {
+    fn from(replay: &crate::v3::Replay) -> Self {
+        let changes = replay
+            .atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a.actions.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter(|a| a.action_type == ActionType::TPS)
+            .map(|a| TpsChange {
+                frame: a.frame,
+                tps: a.tps,
+            });
+        Self::from_changes(replay.metadata.tps, changes)
+    }
+}
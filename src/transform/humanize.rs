@@ -0,0 +1,82 @@
+//! Input timing humanization.
+
+use crate::{input::InputData, meta::Meta, replay::Replay, rng::SplitMix64};
+
+/// Options for [`humanize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct HumanizeOptions {
+    /// Maximum amount of frames an input may be moved in either direction.
+    pub max_jitter: u64,
+    /// Minimum hold length, in frames, kept between a press and its release.
+    pub min_hold: u64,
+    /// Seed of the jitter. The same seed always produces the same result.
+    pub seed: u64,
+}
+
+impl Default for HumanizeOptions {
+    fn default() -> Self {
+        Self {
+            max_jitter: 2,
+            min_hold: 1,
+            seed: 0,
+        }
+    }
+}
+
+/// Jitter press and release frames of a replay.
+///
+/// Only player inputs are moved. Every other input (restarts, deaths, TPS changes
+/// and skips) keeps its frame, and player inputs are never moved across them.
+/// The relative order of all inputs is preserved, so a release always stays after
+/// its press. Releases are kept at least `min_hold` frames after their press
+/// unless that would move them past a fixed input.
+pub fn humanize<M: Meta>(replay: &mut Replay<M>, options: &HumanizeOptions) {
+    let mut rng = SplitMix64::new(options.seed);
+    let inputs = &mut replay.inputs;
+
+    let mut next_fixed = vec![u64::MAX; inputs.len()];
+    let mut bound = u64::MAX;
+    for (i, input) in inputs.iter().enumerate().rev() {
+        next_fixed[i] = bound;
+        if !matches!(input.data, InputData::Player(_)) {
+            bound = input.frame;
+        }
+    }
+
+    let mut presses = [[None::<u64>; 4]; 2];
+    let mut previous_frame = 0;
+
+    for (i, input) in inputs.iter_mut().enumerate() {
+        if let InputData::Player(p) = &input.data {
+            let jitter = rng.below_inclusive(options.max_jitter.saturating_mul(2));
+            let candidate = if jitter >= options.max_jitter {
+                input.frame.saturating_add(jitter - options.max_jitter)
+            } else {
+                input.frame.saturating_sub(options.max_jitter - jitter)
+            };
+
+            let slot = &mut presses[p.player_2 as usize][(p.button as usize).min(3)];
+            let mut lower = previous_frame;
+            if !p.hold {
+                if let Some(press) = slot.take() {
+                    lower = lower.max(press.saturating_add(options.min_hold));
+                }
+            }
+
+            input.frame = candidate.max(lower).min(next_fixed[i]);
+
+            if p.hold {
+                *slot = Some(input.frame);
+            }
+        }
+
+        previous_frame = input.frame;
+    }
+
+    replay.recalculate_deltas();
+}
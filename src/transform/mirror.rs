@@ -0,0 +1,84 @@
+//! Left/right remapping over mirrored parts of a level.
+
+use std::ops::Range;
+
+use crate::{
+    input::InputData,
+    meta::Meta,
+    replay::Replay,
+    v3::{builtin::ActionAtom, ActionType},
+};
+
+/// Tracks which held buttons were pressed while mirrored.
+///
+/// A release is remapped the same way its press was, so holds that cross a
+/// range boundary stay paired.
+struct MirrorState<'a> {
+    ranges: &'a [Range<u64>],
+    /// Whether the held left/right button of each player was swapped.
+    swapped: [[bool; 2]; 2],
+}
+
+impl<'a> MirrorState<'a> {
+    fn new(ranges: &'a [Range<u64>]) -> Self {
+        Self {
+            ranges,
+            swapped: [[false; 2]; 2],
+        }
+    }
+
+    /// Whether a left (`right == false`) or right button action should be swapped.
+    fn swap(&mut self, frame: u64, right: bool, hold: bool, player_2: bool) -> bool {
+        let mirrored = self.ranges.iter().any(|r| r.contains(&frame));
+        let held = &mut self.swapped[player_2 as usize];
+
+        if hold {
+            held[right as usize] = mirrored;
+            mirrored
+        } else {
+            held[right as usize]
+        }
+    }
+}
+
+/// Swap left and right inputs within mirrored frame ranges.
+///
+/// Whether a hold is swapped is decided by the frame of its press, and its
+/// release is swapped along with it, even if it falls outside of the range.
+pub fn mirror_ranges<M: Meta>(replay: &mut Replay<M>, ranges: &[Range<u64>]) {
+    let mut state = MirrorState::new(ranges);
+
+    for input in &mut replay.inputs {
+        if let InputData::Player(p) = &mut input.data {
+            if (p.button == 2 || p.button == 3)
+                && state.swap(input.frame, p.button == 3, p.hold, p.player_2)
+            {
+                p.button ^= 1;
+            }
+        }
+    }
+}
+
+/// Swap left and right actions of an slc3 action atom within mirrored frame ranges.
+///
+/// Behaves like [`mirror_ranges`]. Swift pairs only exist for jumps and are
+/// left untouched.
+pub fn mirror_action_ranges(atom: &mut ActionAtom, ranges: &[Range<u64>]) {
+    let mut state = MirrorState::new(ranges);
+
+    for action in &mut atom.actions {
+        let right = match action.action_type {
+            ActionType::Left => false,
+            ActionType::Right => true,
+            _ => continue,
+        };
+
+        if state.swap(action.frame, right, action.holding, action.player2) {
+            action.action_type = if right {
+                ActionType::Left
+            } else {
+                ActionType::Right
+            };
+        }
+    }
+}
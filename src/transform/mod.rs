@@ -0,0 +1,17 @@
+//! Transforms that edit a decoded replay in place.
+
+pub mod humanize;
+pub mod mirror;
+pub mod pipeline;
+pub mod respawn;
+pub mod reverse;
+pub mod seeds;
+pub mod startpos;
+
+pub use humanize::{humanize, HumanizeOptions};
+pub use mirror::{mirror_action_ranges, mirror_ranges};
+pub use pipeline::{Pipeline, Preview, Report, Transform, TransformError};
+pub use respawn::{compensate_respawn, RespawnOptions};
+pub use reverse::reverse;
+pub use seeds::{apply_seed_sequence, regenerate_seeds};
+pub use startpos::{apply_startpos, rebase_startpos};
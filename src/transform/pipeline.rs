@@ -0,0 +1,340 @@
+//! A common interface for transforms, and pipelines composing them.
+//!
+//! Every transform in this module implements [`Transform`], so a list of
+//! them can be built once and run over many replays with a [`Pipeline`].
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+use super::{
+    compensate_respawn, humanize, mirror_ranges, reverse, HumanizeOptions, RespawnOptions,
+};
+use crate::{
+    diff::{diff, normalize_with, DiffReport},
+    meta::Meta,
+    order::SameFrameOrder,
+    replay::Replay,
+};
+
+#[derive(Debug, Error)]
+pub enum TransformError {
+    #[error("invalid transform options: {0}")]
+    InvalidOptions(String),
+    #[error("step {index} ({name}) failed: {source}")]
+    Step {
+        index: usize,
+        name: &'static str,
+        source: Box<TransformError>,
+    },
+}
+
+/// What a transform did to a replay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Name of the transform.
+    pub name: &'static str,
+    pub inputs_before: usize,
+    pub inputs_after: usize,
+    /// Reports of the steps of a [`Pipeline`], in order. Empty for other transforms.
+    pub steps: Vec<Report>,
+}
+
+impl Report {
+    pub fn new(name: &'static str, inputs_before: usize, inputs_after: usize) -> Self {
+        Self {
+            name,
+            inputs_before,
+            inputs_after,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Amount of inputs removed, or `0` if inputs were added.
+    pub fn removed(&self) -> usize {
+        self.inputs_before.saturating_sub(self.inputs_after)
+    }
+
+    /// Amount of inputs added, or `0` if inputs were removed.
+    pub fn added(&self) -> usize {
+        self.inputs_after.saturating_sub(self.inputs_before)
+    }
+}
+
+/// What a transform would do to a replay, from [`Transform::dry_run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    pub report: Report,
+    /// Inputs removed, added and changed, and whether the tps or meta changed.
+    pub changes: DiffReport,
+}
+
+/// An edit of a replay in place.
+///
+/// Closures taking a replay and returning a [`Report`] are transforms too.
+pub trait Transform<M: Meta = ()> {
+    /// Name of the transform, used in reports and errors.
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError>;
+
+    /// Apply the transform to a copy of a replay, and report what changed.
+    ///
+    /// The replay itself is left untouched.
+    fn dry_run(&self, replay: &Replay<M>) -> Result<Preview, TransformError>
+    where
+        M: Clone,
+    {
+        let mut copy = replay.clone();
+        let report = self.apply(&mut copy)?;
+        Ok(Preview {
+            report,
+            changes: diff(replay, &copy),
+        })
+    }
+}
+
+impl<M: Meta, F: Fn(&mut Replay<M>) -> Result<Report, TransformError>> Transform<M> for F {
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        self(replay)
+    }
+}
+
+/// Run an infallible edit and report the change in input count.
+fn counted<M: Meta>(
+    name: &'static str,
+    replay: &mut Replay<M>,
+    edit: impl FnOnce(&mut Replay<M>),
+) -> Result<Report, TransformError> {
+    let before = replay.inputs.len();
+    edit(replay);
+    Ok(Report::new(name, before, replay.inputs.len()))
+}
+
+/// Sort inputs, remove skips and recalculate deltas, see [`normalize_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Normalize {
+    /// Order of player inputs on the same frame.
+    pub order: SameFrameOrder,
+}
+
+impl<M: Meta> Transform<M> for Normalize {
+    fn name(&self) -> &'static str {
+        "normalize"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            normalize_with(r, self.order)
+        })
+    }
+}
+
+/// Remove redundant tps changes, see [`Replay::dedup_tps`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupTps;
+
+impl<M: Meta> Transform<M> for DedupTps {
+    fn name(&self) -> &'static str {
+        "dedup_tps"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            r.dedup_tps();
+        })
+    }
+}
+
+/// Remove every skip, see [`Replay::strip_skips`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StripSkips;
+
+impl<M: Meta> Transform<M> for StripSkips {
+    fn name(&self) -> &'static str {
+        "strip_skips"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            r.strip_skips();
+        })
+    }
+}
+
+/// Remove every input on or after a frame, see [`Replay::clip_inputs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trim {
+    pub to_frame: u64,
+}
+
+impl<M: Meta> Transform<M> for Trim {
+    fn name(&self) -> &'static str {
+        "trim"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            r.clip_inputs(self.to_frame)
+        })
+    }
+}
+
+/// Move a replay to start on a frame, see [`Replay::rebase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rebase {
+    pub start_frame: u64,
+}
+
+impl<M: Meta> Transform<M> for Rebase {
+    fn name(&self) -> &'static str {
+        "rebase"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            r.rebase(self.start_frame);
+        })
+    }
+}
+
+/// Reverse the timeline, see [`reverse`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reverse;
+
+impl<M: Meta> Transform<M> for Reverse {
+    fn name(&self) -> &'static str {
+        "reverse"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, reverse)
+    }
+}
+
+/// Swap left and right within frame ranges, see [`mirror_ranges`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mirror {
+    pub ranges: Vec<Range<u64>>,
+}
+
+impl<M: Meta> Transform<M> for Mirror {
+    fn name(&self) -> &'static str {
+        "mirror"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        if let Some(range) = self.ranges.iter().find(|r| r.start > r.end) {
+            return Err(TransformError::InvalidOptions(format!(
+                "mirror range {}..{} ends before it starts",
+                range.start, range.end
+            )));
+        }
+        counted(Transform::<M>::name(self), replay, |r| {
+            mirror_ranges(r, &self.ranges)
+        })
+    }
+}
+
+impl<M: Meta> Transform<M> for HumanizeOptions {
+    fn name(&self) -> &'static str {
+        "humanize"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| humanize(r, self))
+    }
+}
+
+impl<M: Meta> Transform<M> for RespawnOptions {
+    fn name(&self) -> &'static str {
+        "respawn"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        counted(Transform::<M>::name(self), replay, |r| {
+            compensate_respawn(r, self)
+        })
+    }
+}
+
+/// Transforms applied one after another.
+///
+/// A pipeline stops at the first failing step, leaving the replay with the
+/// edits of the steps before it. It is a transform itself, so pipelines nest.
+///
+/// # Examples
+/// ```
+/// use slc_oxide::transform::pipeline::{DedupTps, Pipeline, Transform, Trim};
+/// use slc_oxide::{InputData, Replay};
+///
+/// let mut replay = Replay::<()>::new(240.0, ());
+/// replay.add_input(10, InputData::TPS(240.0));
+/// replay.add_input(500, InputData::Death);
+///
+/// let pipeline = Pipeline::new().then(DedupTps).then(Trim { to_frame: 100 });
+/// let report = pipeline.apply(&mut replay).unwrap();
+/// assert_eq!(report.removed(), 2);
+/// assert_eq!(report.steps.len(), 2);
+/// ```
+pub struct Pipeline<M: Meta = ()> {
+    steps: Vec<Box<dyn Transform<M>>>,
+}
+
+impl<M: Meta> Default for Pipeline<M> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<M: Meta> Pipeline<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a step at the end of the pipeline.
+    pub fn then<T: Transform<M> + 'static>(mut self, transform: T) -> Self {
+        self.push(transform);
+        self
+    }
+
+    /// Add a step at the end of the pipeline.
+    pub fn push<T: Transform<M> + 'static>(&mut self, transform: T) {
+        self.steps.push(Box::new(transform));
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Names of the steps, in order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.steps.iter().map(|step| step.name()).collect()
+    }
+}
+
+impl<M: Meta> Transform<M> for Pipeline<M> {
+    fn name(&self) -> &'static str {
+        "pipeline"
+    }
+
+    fn apply(&self, replay: &mut Replay<M>) -> Result<Report, TransformError> {
+        let mut report = Report::new(self.name(), replay.inputs.len(), 0);
+        for (index, step) in self.steps.iter().enumerate() {
+            let step_report = step.apply(replay).map_err(|source| TransformError::Step {
+                index,
+                name: step.name(),
+                source: Box::new(source),
+            })?;
+            report.steps.push(step_report);
+        }
+        report.inputs_after = replay.inputs.len();
+        Ok(report)
+    }
+}
@@ -0,0 +1,59 @@
+//! Respawn time compensation.
+
+use crate::{input::InputData, meta::Meta, replay::Replay};
+
+/// Options for [`compensate_respawn`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RespawnOptions {
+    /// Frames added after every respawn. Negative values shorten respawns.
+    pub delay: i64,
+    /// Whether deaths are followed by a respawn.
+    pub deaths: bool,
+    /// Whether restarts (full or not) are followed by a respawn.
+    pub restarts: bool,
+}
+
+impl Default for RespawnOptions {
+    fn default() -> Self {
+        Self {
+            delay: 0,
+            deaths: true,
+            restarts: true,
+        }
+    }
+}
+
+/// Shift every input after a death or restart by a respawn delay.
+///
+/// Delays add up, so an input after three respawns is moved by three times
+/// the delay. Respawn times differ between game versions, which makes this
+/// necessary when moving macros between them.
+///
+/// When shortening respawns, inputs are never moved before the input
+/// preceding them, so the order of inputs is always preserved.
+pub fn compensate_respawn<M: Meta>(replay: &mut Replay<M>, options: &RespawnOptions) {
+    let mut offset = 0i64;
+    let mut previous_frame = 0u64;
+
+    for input in &mut replay.inputs {
+        let frame = (input.frame as i64 + offset).max(0) as u64;
+        input.frame = frame.max(previous_frame);
+        previous_frame = input.frame;
+
+        let respawns = match input.data {
+            InputData::Death => options.deaths,
+            InputData::Restart | InputData::RestartFull => options.restarts,
+            _ => false,
+        };
+        if respawns {
+            offset += options.delay;
+        }
+    }
+
+    replay.recalculate_deltas();
+}
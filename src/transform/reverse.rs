@@ -0,0 +1,51 @@
+//! Time-reversed replays, for mods playing levels backwards.
+
+use crate::{
+    holds::pair_inputs,
+    input::{InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// Reverse the timeline of a replay.
+///
+/// Frame `f` becomes `last_frame - f`, so every hold is pressed where it
+/// was released and released where it was pressed. Holds are paired like
+/// [`pair_inputs`] does, and tps changes are moved so every span plays at
+/// the same tps as before. Restarts, deaths and skips have no meaning
+/// backwards, and are removed.
+pub fn reverse<M: Meta>(replay: &mut Replay<M>) {
+    let end = replay.last_frame();
+
+    // Inputs sorted by frame, then by kind: tps changes first, then
+    // releases, then presses, so back to back holds stay paired. Releases
+    // of holds on a single frame have to come after their press.
+    let mut events: Vec<(u64, u8, InputData)> = Vec::new();
+    let mut tps = replay.tps;
+    for input in &replay.inputs {
+        if let InputData::TPS(new_tps) = input.data {
+            events.push((end - input.frame, 0, InputData::TPS(tps)));
+            tps = new_tps;
+        }
+    }
+    for hold in pair_inputs(&replay.inputs) {
+        let input = |hold_state| {
+            InputData::Player(PlayerInput {
+                hold: hold_state,
+                player_2: hold.player_2,
+                button: hold.button,
+            })
+        };
+        let release_order = if hold.length() == 0 { 3 } else { 1 };
+        events.push((end - hold.end, 2, input(true)));
+        events.push((end - hold.start, release_order, input(false)));
+    }
+    events.sort_by_key(|(frame, order, _)| (*frame, *order));
+
+    replay.tps = tps;
+    replay.inputs.clear();
+    for (frame, _, data) in events {
+        replay.add_input(frame, data);
+    }
+    replay.dedup_tps();
+}
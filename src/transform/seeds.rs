@@ -0,0 +1,62 @@
+//! Seed regeneration for re-recorded attempts.
+
+use crate::v3::{atom::AtomVariant, ActionType, Replay};
+
+/// Replace the seed of every restart and death with one from a generator.
+///
+/// The generator is called once per restart or death, in order, so any RNG
+/// can be plugged in with a closure. Everything but the seeds is left as is.
+/// Returns the amount of seeds replaced.
+pub fn regenerate_seeds<F: FnMut() -> u64>(replay: &mut Replay, mut next_seed: F) -> usize {
+    let mut count = 0;
+
+    for atom in &mut replay.atoms.atoms {
+        let AtomVariant::Action(action_atom) = atom else {
+            continue;
+        };
+
+        for action in &mut action_atom.actions {
+            if matches!(
+                action.action_type,
+                ActionType::Restart | ActionType::RestartFull | ActionType::Death
+            ) {
+                action.seed = next_seed();
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+/// Replace the seeds of restarts and deaths with a sequence of seeds.
+///
+/// Once the sequence runs out, the remaining seeds are kept.
+/// Returns the amount of seeds replaced.
+pub fn apply_seed_sequence<I: IntoIterator<Item = u64>>(replay: &mut Replay, seeds: I) -> usize {
+    let mut seeds = seeds.into_iter();
+    let mut count = 0;
+
+    for atom in &mut replay.atoms.atoms {
+        let AtomVariant::Action(action_atom) = atom else {
+            continue;
+        };
+
+        for action in &mut action_atom.actions {
+            if !matches!(
+                action.action_type,
+                ActionType::Restart | ActionType::RestartFull | ActionType::Death
+            ) {
+                continue;
+            }
+
+            let Some(seed) = seeds.next() else {
+                return count;
+            };
+            action.seed = seed;
+            count += 1;
+        }
+    }
+
+    count
+}
@@ -0,0 +1,63 @@
+//! Moving startpos recordings onto the full level timeline and back.
+
+use crate::v3::{atom::AtomVariant, builtin::StartposAtom, Replay};
+
+/// The startpos a replay was recorded from, if it has one.
+pub fn startpos(replay: &Replay) -> Option<StartposAtom> {
+    replay.atoms.atoms.iter().find_map(|atom| match atom {
+        AtomVariant::Startpos(startpos) => Some(*startpos),
+        _ => None,
+    })
+}
+
+/// Move a startpos recording onto the frame timeline of the full level.
+///
+/// Every action is shifted by the startpos frame offset and the startpos atom
+/// is removed. Returns the removed startpos, or `None` if the replay wasn't
+/// recorded from one, in which case it is left untouched.
+pub fn rebase_startpos(replay: &mut Replay) -> Option<StartposAtom> {
+    let startpos = startpos(replay)?;
+    replay
+        .atoms
+        .atoms
+        .retain(|atom| !matches!(atom, AtomVariant::Startpos(_)));
+
+    for atom in &mut replay.atoms.atoms {
+        if let AtomVariant::Action(action_atom) = atom {
+            for action in &mut action_atom.actions {
+                action.frame += startpos.frame_offset;
+            }
+            action_atom.recalculate_deltas();
+        }
+    }
+
+    Some(startpos)
+}
+
+/// Turn a full level recording into a recording from a startpos.
+///
+/// Actions before the startpos frame offset are dropped, the rest are
+/// shifted so the startpos is frame 0. Any previous startpos atom is replaced.
+pub fn apply_startpos(replay: &mut Replay, startpos: StartposAtom) {
+    replay
+        .atoms
+        .atoms
+        .retain(|atom| !matches!(atom, AtomVariant::Startpos(_)));
+
+    for atom in &mut replay.atoms.atoms {
+        if let AtomVariant::Action(action_atom) = atom {
+            action_atom
+                .actions
+                .retain(|a| a.frame >= startpos.frame_offset);
+            for action in &mut action_atom.actions {
+                action.frame -= startpos.frame_offset;
+            }
+            action_atom.recalculate_deltas();
+        }
+    }
+
+    replay
+        .atoms
+        .atoms
+        .insert(0, AtomVariant::Startpos(startpos));
+}
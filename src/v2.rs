@@ -0,0 +1,56 @@
+//! Inspection of v2 replay files.
+//!
+//! [`inspect`] reads the header, meta and blob table of a v2 replay and
+//! stops before its inputs, which is enough for indexing tools that only
+//! need counts and tps, at a fraction of the cost of
+//! [`Replay::read`](crate::Replay::read).
+
+use std::io::Read;
+
+use crate::{
+    layout::BlobLayout,
+    replay::{read_v2_preamble, ReadOptions, ReplayError, V2_HEADER},
+};
+
+/// Size of a blob in the blob table.
+const BLOB_ENTRY_SIZE: u64 = 3 * 8;
+
+/// The structure of a v2 replay file, without its inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLayout {
+    pub tps: f64,
+    pub meta: Vec<u8>,
+    pub input_count: u64,
+    /// The blob table, in order.
+    pub blobs: Vec<BlobLayout>,
+    /// Offset of the input data from the start of the replay.
+    pub inputs_offset: u64,
+}
+
+/// Read the layout of a v2 replay, see [`inspect_with_options`].
+pub fn inspect<R: Read>(reader: &mut R) -> Result<FileLayout, ReplayError> {
+    inspect_with_options(reader, &ReadOptions::default())
+}
+
+/// Read the layout of a v2 replay, within the given limits.
+///
+/// The reader is left at the start of the input data. Inputs and the footer
+/// aren't read, so a file cut short within its inputs isn't noticed.
+pub fn inspect_with_options<R: Read>(
+    reader: &mut R,
+    options: &ReadOptions,
+) -> Result<FileLayout, ReplayError> {
+    let mut blobs = Vec::new();
+    let (tps, meta, input_count) = read_v2_preamble(reader, options, &mut blobs, |_| true)?;
+
+    let inputs_offset =
+        V2_HEADER.len() as u64 + 8 * 4 + meta.len() as u64 + blobs.len() as u64 * BLOB_ENTRY_SIZE;
+
+    Ok(FileLayout {
+        tps,
+        meta,
+        input_count,
+        blobs: blobs.iter().map(BlobLayout::from).collect(),
+        inputs_offset,
+    })
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActionType {
@@ -9,6 +11,56 @@ pub enum ActionType {
     RestartFull = 5,
     Death = 6,
     TPS = 7,
+    /// An analog stick position, for controller-based platformer play.
+    Analog = 8,
+    /// A physics rate change affecting a single player.
+    PlayerTPS = 9,
+    /// The recording was paused, see [`Action::paused_frames`].
+    Pause = 10,
+    /// Does nothing, like v2 skips, which it keeps through conversions.
+    Skip = 11,
+}
+
+impl Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Reserved => "reserved",
+            Self::Jump => "jump",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Restart => "restart",
+            Self::RestartFull => "full restart",
+            Self::Death => "death",
+            Self::TPS => "tps",
+            Self::Analog => "analog",
+            Self::PlayerTPS => "player tps",
+            Self::Pause => "pause",
+            Self::Skip => "skip",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The kind of attempt a restart or death belongs to.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttemptKind {
+    #[default]
+    Normal = 0,
+    Practice = 1,
+    /// An attempt started from a start position.
+    Startpos = 2,
+}
+
+impl AttemptKind {
+    pub(crate) const fn from_bits(bits: u16) -> Option<Self> {
+        match bits {
+            0 => Some(Self::Normal),
+            1 => Some(Self::Practice),
+            2 => Some(Self::Startpos),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,12 +71,20 @@ pub struct Action {
     pub player2: bool,
     pub seed: u64,
     pub tps: f64,
+    /// Analog stick axis, in the `-1.0..=1.0` range.
+    pub axis: f32,
+    /// Position within the frame, in 1/65536ths of a frame.
+    ///
+    /// Only player actions carry a sub-frame position; it is zero for
+    /// integer-frame inputs, which encode exactly as before.
+    pub subframe: u16,
     pub(crate) swift: bool,
+    attempt: AttemptKind,
     delta: u64,
 }
 
 impl Action {
-    pub fn player(
+    pub const fn player(
         current_frame: u64,
         delta: u64,
         action_type: ActionType,
@@ -38,12 +98,15 @@ impl Action {
             player2,
             seed: 0,
             tps: 240.0,
+            axis: 0.0,
+            subframe: 0,
             swift: false,
+            attempt: AttemptKind::Normal,
             delta,
         }
     }
 
-    pub fn death(current_frame: u64, delta: u64, action_type: ActionType, seed: u64) -> Self {
+    pub const fn death(current_frame: u64, delta: u64, action_type: ActionType, seed: u64) -> Self {
         Self {
             frame: current_frame + delta,
             action_type,
@@ -51,12 +114,15 @@ impl Action {
             player2: false,
             seed,
             tps: 240.0,
+            axis: 0.0,
+            subframe: 0,
             swift: false,
+            attempt: AttemptKind::Normal,
             delta,
         }
     }
 
-    pub fn tps_change(current_frame: u64, delta: u64, tps: f64) -> Self {
+    pub const fn tps_change(current_frame: u64, delta: u64, tps: f64) -> Self {
         Self {
             frame: current_frame + delta,
             action_type: ActionType::TPS,
@@ -64,7 +130,93 @@ impl Action {
             player2: false,
             seed: 0,
             tps,
+            axis: 0.0,
+            subframe: 0,
+            swift: false,
+            attempt: AttemptKind::Normal,
+            delta,
+        }
+    }
+
+    /// A tps change scoped to one player, for mods that tick players separately.
+    pub const fn player_tps_change(
+        current_frame: u64,
+        delta: u64,
+        tps: f64,
+        player2: bool,
+    ) -> Self {
+        Self {
+            frame: current_frame + delta,
+            action_type: ActionType::PlayerTPS,
+            holding: false,
+            player2,
+            seed: 0,
+            tps,
+            axis: 0.0,
+            subframe: 0,
+            swift: false,
+            attempt: AttemptKind::Normal,
+            delta,
+        }
+    }
+
+    /// A pause of the recording that lasted `paused_frames` frames.
+    ///
+    /// The frames of the replay exclude the pause, its length is only kept
+    /// for reference, in the seed field.
+    pub const fn pause(current_frame: u64, delta: u64, paused_frames: u64) -> Self {
+        Self {
+            frame: current_frame + delta,
+            action_type: ActionType::Pause,
+            holding: false,
+            player2: false,
+            seed: paused_frames,
+            tps: 240.0,
+            axis: 0.0,
+            subframe: 0,
+            swift: false,
+            attempt: AttemptKind::Normal,
+            delta,
+        }
+    }
+
+    /// An action doing nothing, splitting a large delta like a v2 skip.
+    pub const fn skip(current_frame: u64, delta: u64) -> Self {
+        Self {
+            frame: current_frame + delta,
+            action_type: ActionType::Skip,
+            holding: false,
+            player2: false,
+            seed: 0,
+            tps: 240.0,
+            axis: 0.0,
+            subframe: 0,
             swift: false,
+            attempt: AttemptKind::Normal,
+            delta,
+        }
+    }
+
+    /// How long a pause lasted, or `None` for other actions.
+    pub const fn paused_frames(&self) -> Option<u64> {
+        match self.action_type {
+            ActionType::Pause => Some(self.seed),
+            _ => None,
+        }
+    }
+
+    pub fn analog(current_frame: u64, delta: u64, axis: f32, player2: bool) -> Self {
+        Self {
+            frame: current_frame + delta,
+            action_type: ActionType::Analog,
+            holding: false,
+            player2,
+            seed: 0,
+            tps: 240.0,
+            axis: axis.clamp(-1.0, 1.0),
+            subframe: 0,
+            swift: false,
+            attempt: AttemptKind::Normal,
             delta,
         }
     }
@@ -76,6 +228,30 @@ impl Action {
         )
     }
 
+    /// The frame of the action including its sub-frame position.
+    pub fn precise_frame(&self) -> f64 {
+        self.frame as f64 + self.subframe as f64 / 65536.0
+    }
+
+    /// The kind of attempt a restart or death belongs to.
+    ///
+    /// Always [`AttemptKind::Normal`] for other actions.
+    pub const fn attempt_kind(&self) -> AttemptKind {
+        self.attempt
+    }
+
+    /// Tag a restart or death with the kind of attempt it belongs to.
+    ///
+    /// The tag is only encoded for restarts and deaths.
+    pub fn set_attempt_kind(&mut self, kind: AttemptKind) {
+        self.attempt = kind;
+    }
+
+    pub const fn is_practice(&self) -> bool {
+        matches!(self.attempt, AttemptKind::Practice)
+    }
+
+    #[inline]
     pub const fn delta(&self) -> u64 {
         self.delta
     }
@@ -88,22 +264,119 @@ impl Action {
         self.delta = self.frame - previous_frame;
     }
 
+    #[inline]
     pub const fn minimum_size(&self) -> u8 {
-        let offset = if self.is_player() { 4 } else { 8 };
-        let delta = self.delta;
-
-        let one_byte_threshold = 1u64 << offset;
-        let two_bytes_threshold = 1u64 << (offset + 8);
-        let four_bytes_threshold = 1u64 << (offset + 24);
-
-        if delta < one_byte_threshold {
-            0
-        } else if delta < two_bytes_threshold {
-            1
-        } else if delta < four_bytes_threshold {
-            2
-        } else {
-            3
+        minimum_delta_size(self.delta, self.is_player())
+    }
+}
+
+/// Exponent of the amount of bytes a delta of a given bit length is written
+/// in, for player actions and for other actions.
+///
+/// Player deltas share their bytes with 4 bits of flags, other deltas are
+/// written on their own.
+const SIZE_TABLES: [[u8; 65]; 2] = {
+    let mut tables = [[0u8; 65]; 2];
+    let mut bits = 0;
+    while bits <= 64 {
+        let mut flags = 0;
+        while flags < 2 {
+            let total = bits + 4 * flags;
+            tables[flags][bits] = if total <= 8 {
+                0
+            } else if total <= 16 {
+                1
+            } else if total <= 32 {
+                2
+            } else {
+                3
+            };
+            flags += 1;
+        }
+        bits += 1;
+    }
+    tables
+};
+
+/// Exponent of the amount of bytes a delta is written in.
+#[inline]
+pub(crate) const fn minimum_delta_size(delta: u64, is_player: bool) -> u8 {
+    SIZE_TABLES[is_player as usize][(u64::BITS - delta.leading_zeros()) as usize]
+}
+
+/// [`Action::minimum_size`] of every action, computed in bulk.
+pub fn minimum_sizes(actions: &[Action]) -> Vec<u8> {
+    let mut sizes = Vec::with_capacity(actions.len());
+    minimum_sizes_into(actions, &mut sizes);
+    sizes
+}
+
+/// [`minimum_sizes`], replacing the contents of `sizes`.
+pub(crate) fn minimum_sizes_into(actions: &[Action], sizes: &mut Vec<u8>) {
+    sizes.clear();
+    sizes.extend(
+        actions
+            .iter()
+            .map(|a| minimum_delta_size(a.delta, a.is_player())),
+    );
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frame: {}", self.frame)?;
+        if self.subframe != 0 {
+            write!(f, " (+{}/65536)", self.subframe)?;
+        }
+        write!(f, ", action: {}", self.action_type)?;
+
+        match self.action_type {
+            ActionType::Jump | ActionType::Left | ActionType::Right => {
+                write!(f, ", hold: {}, p2: {}", self.holding, self.player2)
+            }
+            ActionType::Restart | ActionType::RestartFull | ActionType::Death => {
+                write!(f, ", seed: {}", self.seed)?;
+                if self.attempt != AttemptKind::Normal {
+                    write!(f, ", attempt: {:?}", self.attempt)?;
+                }
+                Ok(())
+            }
+            ActionType::TPS => write!(f, ", tps: {}", self.tps),
+            ActionType::PlayerTPS => write!(f, ", tps: {}, p2: {}", self.tps, self.player2),
+            ActionType::Analog => write!(f, ", axis: {}, p2: {}", self.axis, self.player2),
+            ActionType::Pause => write!(f, ", paused for: {}", self.seed),
+            ActionType::Reserved | ActionType::Skip => Ok(()),
         }
     }
 }
+
+/// Converts player actions, failing for every other action type.
+impl TryFrom<&Action> for crate::input::PlayerInput {
+    type Error = crate::input::InputError;
+
+    fn try_from(action: &Action) -> Result<Self, Self::Error> {
+        let button = match action.action_type {
+            ActionType::Jump => crate::input::Button::Jump,
+            ActionType::Left => crate::input::Button::Left,
+            ActionType::Right => crate::input::Button::Right,
+            _ => return Err(crate::input::InputError::InvalidButton),
+        };
+        Ok(Self::new(button, action.holding, action.player2))
+    }
+}
+
+/// Actions are compared by what they do: the swift flag and delta only
+/// describe how an action is encoded, and are ignored. Float fields are
+/// compared exactly, so an action with a NaN tps never equals itself.
+impl PartialEq for Action {
+    fn eq(&self, other: &Self) -> bool {
+        self.frame == other.frame
+            && self.action_type == other.action_type
+            && self.holding == other.holding
+            && self.player2 == other.player2
+            && self.seed == other.seed
+            && self.tps == other.tps
+            && self.axis == other.axis
+            && self.subframe == other.subframe
+            && self.attempt == other.attempt
+    }
+}
@@ -1,12 +1,31 @@
 use std::io::{Read, Seek, Write};
 use thiserror::Error;
 
+use super::builtin::PlanScratch;
+use super::options::{GcOptions, NullAtomPolicy, WriteOptions};
+use super::sink::ActionSink;
+use crate::codec;
+use crate::replay::ReadOptions;
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AtomId {
     Null = 0,
     Action = 1,
     Marker = 2,
+    Startpos = 3,
+    Session = 4,
+    ChangeLog = 5,
+    /// Any id outside [`crate::format::BUILTIN_ATOM_IDS`]. This value is
+    /// never written: [`CustomAtom`] keeps the id the atom was read with.
+    Custom = u32::MAX,
+}
+
+impl AtomId {
+    /// The builtin atom with this id, or [`AtomId::Custom`] for any other.
+    pub fn from_raw(id: u32) -> Self {
+        Self::try_from(id).unwrap_or(AtomId::Custom)
+    }
 }
 
 impl TryFrom<u32> for AtomId {
@@ -17,6 +36,9 @@ impl TryFrom<u32> for AtomId {
             0 => Ok(AtomId::Null),
             1 => Ok(AtomId::Action),
             2 => Ok(AtomId::Marker),
+            3 => Ok(AtomId::Startpos),
+            4 => Ok(AtomId::Session),
+            5 => Ok(AtomId::ChangeLog),
             _ => Err(AtomError::UnknownAtomId(value)),
         }
     }
@@ -30,6 +52,52 @@ pub enum AtomError {
     UnknownAtomId(u32),
     #[error("Section error: {0}")]
     SectionError(#[from] crate::v3::section::SectionError),
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(&'static str),
+}
+
+/// An atom the crate doesn't define, kept as opaque bytes.
+///
+/// Other tools store their own data in these. They are written back
+/// unchanged, and [`AtomRegistry::gc`] never merges or drops them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomAtom {
+    /// Id outside [`crate::format::BUILTIN_ATOM_IDS`].
+    pub id: u32,
+    pub data: Vec<u8>,
+}
+
+impl CustomAtom {
+    pub fn new(id: u32, data: Vec<u8>) -> Self {
+        Self { id, data }
+    }
+
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn read<R: Read>(reader: &mut R, id: u32, size: usize) -> Result<Self, AtomError> {
+        let mut data = Vec::new();
+        reader.take(size as u64).read_to_end(&mut data)?;
+        if data.len() != size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(Self { id, data })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+/// Skip over bytes of a stream without buffering them.
+pub(crate) fn skip<R: Read>(reader: &mut R, size: u64) -> Result<(), AtomError> {
+    let skipped = std::io::copy(&mut reader.take(size), &mut std::io::sink())?;
+    if skipped != size {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(())
 }
 
 pub trait Atom: Sized {
@@ -40,6 +108,7 @@ pub trait Atom: Sized {
     fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError>;
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NullAtom {
     pub size: usize,
 }
@@ -52,19 +121,25 @@ impl Atom for NullAtom {
     }
 
     fn read<R: Read>(reader: &mut R, size: usize) -> Result<Self, AtomError> {
-        let mut buf = vec![0u8; size];
-        reader.read_exact(&mut buf)?;
+        skip(reader, size as u64)?;
         Ok(Self { size })
     }
 
-    fn write<W: Write>(&self, _writer: &mut W) -> Result<(), AtomError> {
+    /// Writes `size` zero bytes, so the atom keeps its place in the file.
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        std::io::copy(&mut std::io::repeat(0).take(self.size as u64), writer)?;
         Ok(())
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum AtomVariant {
     Null(NullAtom),
     Action(super::builtin::ActionAtom),
+    Startpos(super::builtin::StartposAtom),
+    Session(super::session::SessionAtom),
+    ChangeLog(super::changelog::ChangeLogAtom),
+    Custom(CustomAtom),
 }
 
 impl AtomVariant {
@@ -72,6 +147,18 @@ impl AtomVariant {
         match self {
             AtomVariant::Null(_) => AtomId::Null,
             AtomVariant::Action(_) => AtomId::Action,
+            AtomVariant::Startpos(_) => AtomId::Startpos,
+            AtomVariant::Session(_) => AtomId::Session,
+            AtomVariant::ChangeLog(_) => AtomId::ChangeLog,
+            AtomVariant::Custom(_) => AtomId::Custom,
+        }
+    }
+
+    /// The id written in the atom header.
+    pub fn raw_id(&self) -> u32 {
+        match self {
+            AtomVariant::Custom(a) => a.id,
+            _ => self.id() as u32,
         }
     }
 
@@ -79,44 +166,179 @@ impl AtomVariant {
         match self {
             AtomVariant::Null(a) => a.size(),
             AtomVariant::Action(a) => a.size(),
+            AtomVariant::Startpos(a) => a.size(),
+            AtomVariant::Session(a) => a.size(),
+            AtomVariant::ChangeLog(a) => a.size(),
+            AtomVariant::Custom(a) => a.size(),
+        }
+    }
+
+    /// Amount of bytes the atom body takes when written with the given options.
+    ///
+    /// This is the size written in the atom header. It differs from
+    /// [`AtomVariant::size`] for action atoms, whose size is only known
+    /// once their sections are planned.
+    pub fn encoded_size(&self, options: &WriteOptions) -> Result<u64, AtomError> {
+        match self {
+            AtomVariant::Action(a) => a.encoded_size(options),
+            _ => Ok(self.size() as u64),
         }
     }
 
     pub fn read<R: Read>(reader: &mut R) -> Result<Self, AtomError> {
-        let mut buf = [0u8; 4];
-        reader.read_exact(&mut buf)?;
-        let id = u32::from_le_bytes(buf);
-        let atom_id = AtomId::try_from(id)?;
+        Self::read_with_options(reader, &ReadOptions::default())
+    }
+
+    /// Read an atom, within the given limits.
+    pub fn read_with_options<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Self, AtomError> {
+        Self::read_inner(reader, options, None)
+    }
 
-        let mut buf8 = [0u8; 8];
-        reader.read_exact(&mut buf8)?;
-        let size = u64::from_le_bytes(buf8) as usize;
+    /// Read an atom, streaming the actions of action atoms into a sink.
+    ///
+    /// Returned action atoms hold no actions.
+    pub fn read_into<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+        sink: &mut dyn ActionSink,
+    ) -> Result<Self, AtomError> {
+        Self::read_inner(reader, options, Some(sink))
+    }
+
+    fn read_inner<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+        sink: Option<&mut dyn ActionSink>,
+    ) -> Result<Self, AtomError> {
+        let (atom_id, size) = Self::read_header(reader, options)?;
+        Self::read_body(reader, atom_id, size, options, sink)
+    }
 
-        match atom_id {
+    /// Read the raw id and body size of an atom.
+    pub(crate) fn read_header<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<(u32, usize), AtomError> {
+        let id: u32 = codec::read(reader)?;
+
+        let size: u64 = codec::read(reader)?;
+        if size > options.max_atom_size {
+            return Err(AtomError::LimitExceeded("atom size"));
+        }
+        Ok((id, size as usize))
+    }
+
+    pub(crate) fn read_body<R: Read>(
+        reader: &mut R,
+        id: u32,
+        size: usize,
+        options: &ReadOptions,
+        sink: Option<&mut dyn ActionSink>,
+    ) -> Result<Self, AtomError> {
+        match AtomId::from_raw(id) {
             AtomId::Null => Ok(AtomVariant::Null(NullAtom::read(reader, size)?)),
-            AtomId::Action => Ok(AtomVariant::Action(super::builtin::ActionAtom::read(
+            AtomId::Action => Ok(AtomVariant::Action(match sink {
+                Some(sink) => super::builtin::ActionAtom::read_into(reader, size, options, sink)?,
+                None => super::builtin::ActionAtom::read_with_options(reader, size, options)?,
+            })),
+            AtomId::Marker => Ok(AtomVariant::Null(NullAtom::read(reader, size)?)),
+            AtomId::Startpos => Ok(AtomVariant::Startpos(super::builtin::StartposAtom::read(
                 reader, size,
             )?)),
-            AtomId::Marker => Ok(AtomVariant::Null(NullAtom::read(reader, size)?)),
+            AtomId::Session => Ok(AtomVariant::Session(super::session::SessionAtom::read(
+                reader, size,
+            )?)),
+            AtomId::ChangeLog => Ok(AtomVariant::ChangeLog(
+                super::changelog::ChangeLogAtom::read(reader, size)?,
+            )),
+            AtomId::Custom => Ok(AtomVariant::Custom(CustomAtom::read(reader, id, size)?)),
         }
     }
 
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
-        let id = self.id() as u32;
-        writer.write_all(&id.to_le_bytes())?;
+        self.write_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the atom, streaming its body straight to the writer.
+    ///
+    /// Action atoms are written in two passes: their sections are planned
+    /// and sized first, so the header holds the exact body size without
+    /// buffering the encoded body.
+    pub fn write_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), AtomError> {
+        self.write_with_scratch(writer, options, &mut PlanScratch::default())
+    }
+
+    pub(crate) fn write_with_scratch<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        scratch: &mut PlanScratch,
+    ) -> Result<(), AtomError> {
+        codec::write(writer, self.raw_id())?;
+
+        if let AtomVariant::Action(a) = self {
+            a.prepare_sections(scratch, options)?;
+            codec::write(writer, 8 + scratch.plan.encoded_size())?;
+            return a.write_plan(writer, &scratch.plan);
+        }
 
         let size = self.size() as u64;
-        writer.write_all(&size.to_le_bytes())?;
+        codec::write(writer, size)?;
 
         match self {
             AtomVariant::Null(a) => a.write(writer)?,
-            AtomVariant::Action(a) => a.write(writer)?,
+            AtomVariant::Action(_) => unreachable!("action atoms are written above"),
+            AtomVariant::Startpos(a) => a.write(writer)?,
+            AtomVariant::Session(a) => a.write(writer)?,
+            AtomVariant::ChangeLog(a) => a.write(writer)?,
+            AtomVariant::Custom(a) => a.write(writer)?,
         }
 
         Ok(())
     }
 }
 
+impl std::fmt::Display for AtomVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtomVariant::Null(a) => write!(f, "null, {} bytes", a.size),
+            AtomVariant::Action(a) => write!(f, "action, {}", a),
+            AtomVariant::Startpos(a) => write!(
+                f,
+                "startpos, x: {}, y: {}, frame offset: {}",
+                a.x, a.y, a.frame_offset
+            ),
+            AtomVariant::Session(a) => write!(f, "session, {} attempts", a.attempts.len()),
+            AtomVariant::ChangeLog(a) => write!(f, "change log, {} entries", a.entries.len()),
+            AtomVariant::Custom(a) => write!(f, "custom {:#x}, {} bytes", a.id, a.data.len()),
+        }
+    }
+}
+
+/// What [`AtomRegistry::gc`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub empty_actions: usize,
+    /// Atoms merged into an earlier atom of their kind.
+    pub merged: usize,
+    pub nulls: usize,
+}
+
+impl GcReport {
+    /// Amount of atoms removed.
+    pub fn removed(&self) -> usize {
+        self.empty_actions + self.merged + self.nulls
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AtomRegistry {
     pub atoms: Vec<AtomVariant>,
 }
@@ -130,25 +352,182 @@ impl AtomRegistry {
         self.atoms.push(atom);
     }
 
+    /// Iterate over the atoms.
+    pub fn iter(&self) -> std::slice::Iter<'_, AtomVariant> {
+        self.atoms.iter()
+    }
+
+    /// Iterate mutably over the atoms.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, AtomVariant> {
+        self.atoms.iter_mut()
+    }
+
+    /// Remove the atoms edited files accumulate, according to the options.
+    ///
+    /// Identical start position atoms are dropped, while differing ones are
+    /// kept. Later session and change log atoms are merged into the first
+    /// one: their attempts and entries are appended to it, skipping the ones
+    /// it already has. Custom atoms are always kept. The order of the
+    /// remaining atoms is kept.
+    pub fn gc(&mut self, options: &GcOptions) -> GcReport {
+        let mut report = GcReport::default();
+
+        if options.drop_empty_actions {
+            let count = self.atoms.len();
+            self.atoms
+                .retain(|a| !matches!(a, AtomVariant::Action(a) if a.actions.is_empty()));
+            report.empty_actions = count - self.atoms.len();
+        }
+
+        if options.merge_duplicates {
+            let count = self.atoms.len();
+            let mut kept: Vec<AtomVariant> = Vec::with_capacity(count);
+            for atom in std::mem::take(&mut self.atoms) {
+                if !Self::merge_into(&mut kept, &atom) {
+                    kept.push(atom);
+                }
+            }
+            self.atoms = kept;
+            report.merged = count - self.atoms.len();
+        }
+
+        let count = self.atoms.len();
+        match options.null_atoms {
+            NullAtomPolicy::Keep => {}
+            NullAtomPolicy::DropTrailing => {
+                let end = self
+                    .atoms
+                    .iter()
+                    .rposition(|a| !matches!(a, AtomVariant::Null(_)))
+                    .map_or(0, |i| i + 1);
+                self.atoms.truncate(end);
+            }
+            NullAtomPolicy::Drop => self.atoms.retain(|a| !matches!(a, AtomVariant::Null(_))),
+        }
+        report.nulls = count - self.atoms.len();
+
+        report
+    }
+
+    /// Merge an atom into the first kept atom of its kind, if it can be.
+    fn merge_into(kept: &mut [AtomVariant], atom: &AtomVariant) -> bool {
+        let first = kept.iter_mut().find(|a| a.id() == atom.id());
+        match (first, atom) {
+            (Some(AtomVariant::Startpos(first)), AtomVariant::Startpos(a)) => first == a,
+            (Some(AtomVariant::Session(first)), AtomVariant::Session(a)) => {
+                append_missing(&mut first.attempts, &a.attempts);
+                true
+            }
+            (Some(AtomVariant::ChangeLog(first)), AtomVariant::ChangeLog(a)) => {
+                append_missing(&mut first.entries, &a.entries);
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn read_all<R: Read + Seek>(
         &mut self,
         reader: &mut R,
         end_pos: u64,
+    ) -> Result<(), AtomError> {
+        self.read_all_with_options(reader, end_pos, &ReadOptions::default())
+    }
+
+    /// Read atoms until the given position, within the given limits.
+    pub fn read_all_with_options<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        end_pos: u64,
+        options: &ReadOptions,
+    ) -> Result<(), AtomError> {
+        self.read_all_inner(reader, end_pos, options, None)
+    }
+
+    /// Read atoms until the given position, streaming the actions of every
+    /// action atom into a sink.
+    pub fn read_all_into<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        end_pos: u64,
+        options: &ReadOptions,
+        sink: &mut dyn ActionSink,
+    ) -> Result<(), AtomError> {
+        self.read_all_inner(reader, end_pos, options, Some(sink))
+    }
+
+    fn read_all_inner<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        end_pos: u64,
+        options: &ReadOptions,
+        mut sink: Option<&mut dyn ActionSink>,
     ) -> Result<(), AtomError> {
         loop {
             let current_pos = reader.stream_position()?;
             if current_pos >= end_pos {
                 break;
             }
-            let atom = AtomVariant::read(reader)?;
+            let atom = match sink {
+                Some(ref mut sink) => AtomVariant::read_into(reader, options, *sink)?,
+                None => AtomVariant::read_with_options(reader, options)?,
+            };
             self.add(atom);
         }
         Ok(())
     }
 
     pub fn write_all<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
-        for atom in &self.atoms {
-            atom.write(writer)?;
+        self.write_all_with_options(writer, &WriteOptions::default())
+    }
+
+    pub fn write_all_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), AtomError> {
+        self.write_all_with_scratch(writer, options, &mut PlanScratch::default())
+    }
+
+    pub(crate) fn write_all_with_scratch<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        scratch: &mut PlanScratch,
+    ) -> Result<(), AtomError> {
+        self.write_filtered_with_scratch(writer, options, scratch, &mut |_| true)
+    }
+
+    /// Write only the atoms `filter` accepts, in order.
+    pub(crate) fn write_filtered_with_scratch<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        scratch: &mut PlanScratch,
+        filter: &mut dyn FnMut(&AtomVariant) -> bool,
+    ) -> Result<(), AtomError> {
+        for atom in self.atoms.iter().filter(|atom| filter(atom)) {
+            atom.write_with_scratch(writer, options, scratch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Append the items of `other` that `items` doesn't hold yet.
+fn append_missing<T: Clone + PartialEq>(items: &mut Vec<T>, other: &[T]) {
+    for item in other {
+        if !items.contains(item) {
+            items.push(item.clone());
+        }
+    }
+}
+
+/// Lists every atom on its own line.
+impl std::fmt::Display for AtomRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} atoms", self.atoms.len())?;
+        for (i, atom) in self.atoms.iter().enumerate() {
+            writeln!(f, "  atom {}: {}", i, atom)?;
         }
         Ok(())
     }
@@ -159,3 +538,30 @@ impl Default for AtomRegistry {
         Self::new()
     }
 }
+
+impl IntoIterator for AtomRegistry {
+    type Item = AtomVariant;
+    type IntoIter = std::vec::IntoIter<AtomVariant>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.atoms.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a AtomRegistry {
+    type Item = &'a AtomVariant;
+    type IntoIter = std::slice::Iter<'a, AtomVariant>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.atoms.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut AtomRegistry {
+    type Item = &'a mut AtomVariant;
+    type IntoIter = std::slice::IterMut<'a, AtomVariant>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.atoms.iter_mut()
+    }
+}
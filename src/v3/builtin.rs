@@ -1,12 +1,29 @@
 use std::io::{Read, Write};
 
-use super::action::{Action, ActionType};
-use super::atom::{Atom, AtomError, AtomId};
-use super::section::{largest_power_of_two, Section};
+use super::action::{minimum_sizes_into, Action, ActionType};
+use super::atom::{skip, Atom, AtomError, AtomId};
+use super::options::WriteOptions;
+use super::section::{
+    largest_power_of_two, ActionOutput, Button, Section, SectionIdentifier, SectionPlan,
+};
+use super::sink::ActionSink;
+use crate::codec;
+use crate::replay::{Limits, ReadOptions};
 
+/// Buffers used while planning the sections of an action atom.
+#[derive(Default)]
+pub(crate) struct PlanScratch {
+    actions: Vec<Action>,
+    sizes: Vec<u8>,
+    pub(crate) plan: SectionPlan,
+}
+
+#[derive(Debug, Clone)]
 pub struct ActionAtom {
     pub actions: Vec<Action>,
     size: usize,
+    breaks: Vec<usize>,
+    limits: Limits,
 }
 
 impl ActionAtom {
@@ -14,9 +31,45 @@ impl ActionAtom {
         Self {
             actions: Vec::new(),
             size: 0,
+            breaks: Vec::new(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Make the `add_*` methods fail for actions past the given limits.
+    ///
+    /// Limits aren't written to files, and actions pushed directly to
+    /// [`ActionAtom::actions`] aren't checked.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    fn check_limits(&self, frame: u64, tps: Option<f64>) -> Result<(), AtomError> {
+        self.limits
+            .check(self.actions.len(), frame, tps)
+            .map_err(AtomError::LimitExceeded)
+    }
+
+    /// Force the next added action to start a new section.
+    ///
+    /// Breaks only affect how actions are encoded, and aren't read back.
+    pub fn add_section_break(&mut self) {
+        let index = self.actions.len();
+        if self.breaks.last() != Some(&index) {
+            self.breaks.push(index);
         }
     }
 
+    /// Indices of the actions that start a forced section.
+    pub fn section_breaks(&self) -> &[usize] {
+        &self.breaks
+    }
+
     pub fn add_player_action(
         &mut self,
         frame: u64,
@@ -24,6 +77,7 @@ impl ActionAtom {
         holding: bool,
         player2: bool,
     ) -> Result<(), AtomError> {
+        self.check_limits(frame, None)?;
         let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
         let delta = frame - previous_frame;
         self.actions.push(Action::player(
@@ -36,12 +90,27 @@ impl ActionAtom {
         Ok(())
     }
 
+    /// Add a player action at a sub-frame position, in 1/65536ths of a frame.
+    pub fn add_subframe_player_action(
+        &mut self,
+        frame: u64,
+        subframe: u16,
+        action_type: ActionType,
+        holding: bool,
+        player2: bool,
+    ) -> Result<(), AtomError> {
+        self.add_player_action(frame, action_type, holding, player2)?;
+        self.actions.last_mut().unwrap().subframe = subframe;
+        Ok(())
+    }
+
     pub fn add_death_action(
         &mut self,
         frame: u64,
         action_type: ActionType,
         seed: u64,
     ) -> Result<(), AtomError> {
+        self.check_limits(frame, None)?;
         let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
         let delta = frame - previous_frame;
         self.actions
@@ -50,6 +119,7 @@ impl ActionAtom {
     }
 
     pub fn add_tps_action(&mut self, frame: u64, tps: f64) -> Result<(), AtomError> {
+        self.check_limits(frame, Some(tps))?;
         let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
         let delta = frame - previous_frame;
         self.actions
@@ -57,14 +127,113 @@ impl ActionAtom {
         Ok(())
     }
 
+    /// Add a tps change that only affects one player.
+    pub fn add_player_tps_action(
+        &mut self,
+        frame: u64,
+        tps: f64,
+        player2: bool,
+    ) -> Result<(), AtomError> {
+        self.check_limits(frame, Some(tps))?;
+        let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
+        let delta = frame - previous_frame;
+        self.actions.push(Action::player_tps_change(
+            previous_frame,
+            delta,
+            tps,
+            player2,
+        ));
+        Ok(())
+    }
+
+    /// Add an action doing nothing, see [`Action::skip`].
+    pub fn add_skip_action(&mut self, frame: u64) -> Result<(), AtomError> {
+        self.check_limits(frame, None)?;
+        let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
+        let delta = frame - previous_frame;
+        self.actions.push(Action::skip(previous_frame, delta));
+        Ok(())
+    }
+
+    /// Add a pause marker, see [`Action::pause`].
+    pub fn add_pause_action(&mut self, frame: u64, paused_frames: u64) -> Result<(), AtomError> {
+        self.check_limits(frame, None)?;
+        let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
+        let delta = frame - previous_frame;
+        self.actions
+            .push(Action::pause(previous_frame, delta, paused_frames));
+        Ok(())
+    }
+
+    pub fn add_analog_action(
+        &mut self,
+        frame: u64,
+        axis: f32,
+        player2: bool,
+    ) -> Result<(), AtomError> {
+        self.check_limits(frame, None)?;
+        let previous_frame = self.actions.last().map(|a| a.frame).unwrap_or(0);
+        let delta = frame - previous_frame;
+        self.actions
+            .push(Action::analog(previous_frame, delta, axis, player2));
+        Ok(())
+    }
+
+    /// Iterate over the actions.
+    pub fn iter(&self) -> std::slice::Iter<'_, Action> {
+        self.actions.iter()
+    }
+
+    /// Iterate mutably over the actions.
+    ///
+    /// Call `recalculate_deltas` after moving actions to other frames.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Action> {
+        self.actions.iter_mut()
+    }
+
     pub fn clear(&mut self) {
         self.actions.clear();
+        self.breaks.clear();
     }
 
     pub fn clip_actions(&mut self, frame: u64) {
         self.actions.retain(|a| a.frame < frame);
     }
 
+    /// The actions on frames `start..end`, moved to start on frame 0.
+    ///
+    /// Section breaks within the window are kept.
+    pub fn extract_range(&self, start: u64, end: u64) -> Self {
+        let first = self.actions.partition_point(|a| a.frame < start);
+        let last = self.actions.partition_point(|a| a.frame < end).max(first);
+
+        let mut atom = Self {
+            actions: self.actions[first..last].to_vec(),
+            size: 0,
+            breaks: self
+                .breaks
+                .iter()
+                .filter(|&&i| first < i && i < last)
+                .map(|i| i - first)
+                .collect(),
+            limits: self.limits,
+        };
+        atom.actions.iter_mut().for_each(|a| a.frame -= start);
+        atom.recalculate_deltas();
+        atom
+    }
+
+    /// Recalculate the delta of every action from its frame.
+    ///
+    /// Call this after editing action frames directly.
+    pub fn recalculate_deltas(&mut self) {
+        let mut previous_frame = 0;
+        for action in &mut self.actions {
+            action.recalculate_delta(previous_frame);
+            previous_frame = action.frame;
+        }
+    }
+
     fn swift_compatible(actions: &[Action], i: usize) -> bool {
         if i == 0 {
             return false;
@@ -77,39 +246,70 @@ impl ActionAtom {
             && actions[i].action_type == ActionType::Jump
     }
 
-    fn can_join(actions: &[Action], count: usize, i: usize) -> bool {
+    fn can_join(
+        actions: &[Action],
+        sizes: &[u8],
+        breaks: &[usize],
+        count: usize,
+        i: usize,
+    ) -> bool {
         const MAX_SECTION_ACTIONS: usize = 1 << 16;
         i < actions.len() - 1
             && count < MAX_SECTION_ACTIONS
+            && breaks.binary_search(&(i + 1)).is_err()
             && actions[i + 1].is_player()
-            && actions[i + 1].minimum_size() == actions[i].minimum_size()
+            && actions[i + 1].subframe == 0
+            && sizes[i + 1] == sizes[i]
     }
 
-    fn prepare_sections(
-        actions: &mut [Action],
-        sections: &mut Vec<Section>,
+    /// Plan the sections into `scratch.plan`, reusing the scratch buffers.
+    pub(crate) fn prepare_sections(
+        &self,
+        scratch: &mut PlanScratch,
+        options: &WriteOptions,
     ) -> Result<(), AtomError> {
+        let PlanScratch {
+            actions,
+            sizes,
+            plan: sections,
+        } = scratch;
+        let breaks = &self.breaks[..];
+
+        actions.clear();
+        actions.extend_from_slice(&self.actions);
+        sections.clear();
+
+        // Swift pairs are recomputed from scratch, flags left over from
+        // reading may no longer apply to the edited actions.
+        actions.iter_mut().for_each(|a| a.swift = false);
+        minimum_sizes_into(actions, sizes);
+
         let mut i = 0;
         while i < actions.len() {
+            if actions[i].is_player() && actions[i].subframe != 0 {
+                sections.push_subframe(&actions[i]);
+                i += 1;
+                continue;
+            }
+
             if !actions[i].is_player() {
-                let section = Section::special(&actions[i])?;
-                sections.push(section);
+                let mut section = Section::special(&actions[i])?;
+                section.analog_precision = options.analog_precision;
+                sections.push_special(section);
                 i += 1;
                 continue;
             }
 
-            let mut count = 1;
             let mut pure_count = 1;
             let mut swifts = 0;
             let mut pure_swifts = 0;
             let start = i;
-            let min_size = actions[i].minimum_size();
+            let min_size = sizes[i];
 
-            while Self::can_join(actions, pure_count, i) {
+            while Self::can_join(actions, sizes, breaks, pure_count, i) {
                 i += 1;
-                count += 1;
 
-                if Self::swift_compatible(actions, i) {
+                if options.swift && Self::swift_compatible(actions, i) {
                     actions[i - 1].swift = true;
                     actions[i].swift = true;
                     swifts += 1;
@@ -122,16 +322,131 @@ impl ActionAtom {
                 }
             }
 
-            count = largest_power_of_two(pure_count);
+            let count = largest_power_of_two(pure_count);
             i = start + count + pure_swifts;
 
-            let mut section = Section::player_from_range(actions, start, i);
-            section.delta_size = min_size as u16;
+            sections.push_player(&actions[start..i], min_size as u16);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn plan(&self, options: &WriteOptions) -> Result<SectionPlan, AtomError> {
+        let mut scratch = PlanScratch::default();
+        self.prepare_sections(&mut scratch, options)?;
+        Ok(scratch.plan)
+    }
+
+    /// Amount of bytes the atom body takes when written with the given options.
+    ///
+    /// This plans the sections without writing them, so it costs about as
+    /// much as writing the atom.
+    pub fn encoded_size(&self, options: &WriteOptions) -> Result<u64, AtomError> {
+        Ok(8 + self.plan(options)?.encoded_size())
+    }
+
+    /// The sections the actions are encoded in, in order.
+    ///
+    /// Sections removed by run-length encoding are left out.
+    pub fn sections(&self, options: &WriteOptions) -> Result<Vec<Section>, AtomError> {
+        let mut sections = self.plan(options)?.into_sections();
+        sections.retain(|s| !s.marked_for_removal);
+        Ok(sections)
+    }
+
+    /// Indices of the actions that get encoded as swift pairs.
+    ///
+    /// Each index is the press of a pair; its release is the action right after it.
+    pub fn swift_pairs(&self) -> Vec<usize> {
+        let Ok(sections) = self.sections(&WriteOptions::default()) else {
+            return Vec::new();
+        };
+
+        let mut pairs = Vec::new();
+        let mut index = 0;
+        for section in &sections {
+            let repeats = match section.id {
+                SectionIdentifier::Special | SectionIdentifier::SubFrame => {
+                    index += 1;
+                    continue;
+                }
+                SectionIdentifier::Input => 1,
+                SectionIdentifier::Repeat => section.repeat_count(),
+            };
 
-            let real_sections = section.run_length_encode();
-            sections.extend(real_sections);
+            for _ in 0..repeats {
+                for input in &section.player_inputs {
+                    if input.button == Button::Swift {
+                        pairs.push(index);
+                        index += 2;
+                    } else {
+                        index += 1;
+                    }
+                }
+            }
         }
 
+        pairs
+    }
+
+    /// Read the atom body, within the given limits.
+    ///
+    /// Sections decoding to more actions than the atom declares are rejected.
+    pub fn read_with_options<R: Read>(
+        reader: &mut R,
+        size: usize,
+        options: &ReadOptions,
+    ) -> Result<Self, AtomError> {
+        let mut actions = Vec::new();
+        let mut atom = Self::read_into(reader, size, options, &mut actions)?;
+        atom.actions = actions;
+        Ok(atom)
+    }
+
+    /// Read the atom body, streaming its actions into a sink.
+    ///
+    /// The returned atom holds no actions.
+    pub fn read_into<R: Read>(
+        reader: &mut R,
+        size: usize,
+        options: &ReadOptions,
+        sink: &mut dyn ActionSink,
+    ) -> Result<Self, AtomError> {
+        let count: u64 = codec::read(reader)?;
+        if count > options.max_inputs {
+            return Err(AtomError::LimitExceeded("actions"));
+        }
+        let count = usize::try_from(count).map_err(|_| AtomError::LimitExceeded("actions"))?;
+
+        sink.reserve(count);
+        let mut output = ActionOutput::new(sink, 0, 0).with_limits(options.limits());
+        while output.count < count {
+            Section::read_into(reader, &mut output, count)?;
+        }
+
+        Ok(Self {
+            size,
+            ..Self::new()
+        })
+    }
+
+    /// Write the atom body using the given encoding options.
+    pub fn write_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), AtomError> {
+        self.write_plan(writer, &self.plan(options)?)
+    }
+
+    pub(crate) fn write_plan<W: Write>(
+        &self,
+        writer: &mut W,
+        plan: &SectionPlan,
+    ) -> Result<(), AtomError> {
+        codec::write(writer, self.actions.len() as u64)?;
+        plan.write(writer)?;
+
         Ok(())
     }
 }
@@ -144,32 +459,60 @@ impl Atom for ActionAtom {
     }
 
     fn read<R: Read>(reader: &mut R, size: usize) -> Result<Self, AtomError> {
-        let mut buf8 = [0u8; 8];
-        reader.read_exact(&mut buf8)?;
-        let count = u64::from_le_bytes(buf8) as usize;
+        Self::read_with_options(reader, size, &ReadOptions::default())
+    }
 
-        let mut actions = Vec::with_capacity(count);
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        self.write_with_options(writer, &WriteOptions::default())
+    }
+}
 
-        while actions.len() < count {
-            Section::read(reader, &mut actions)?;
-        }
+impl IntoIterator for ActionAtom {
+    type Item = Action;
+    type IntoIter = std::vec::IntoIter<Action>;
 
-        Ok(Self { actions, size })
+    fn into_iter(self) -> Self::IntoIter {
+        self.actions.into_iter()
     }
+}
 
-    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
-        writer.write_all(&(self.actions.len() as u64).to_le_bytes())?;
+impl<'a> IntoIterator for &'a ActionAtom {
+    type Item = &'a Action;
+    type IntoIter = std::slice::Iter<'a, Action>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.actions.iter()
+    }
+}
 
-        let mut sections = Vec::new();
-        let mut actions_copy = self.actions.clone();
+impl<'a> IntoIterator for &'a mut ActionAtom {
+    type Item = &'a mut Action;
+    type IntoIter = std::slice::IterMut<'a, Action>;
 
-        Self::prepare_sections(&mut actions_copy, &mut sections)?;
+    fn into_iter(self) -> Self::IntoIter {
+        self.actions.iter_mut()
+    }
+}
 
-        for section in &sections {
-            section.write(writer)?;
+impl std::fmt::Display for ActionAtom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.actions.first(), self.actions.last()) {
+            (Some(first), Some(last)) => write!(
+                f,
+                "{} actions, frames {}..={}",
+                self.actions.len(),
+                first.frame,
+                last.frame
+            ),
+            _ => write!(f, "0 actions"),
         }
+    }
+}
 
-        Ok(())
+/// Atoms are equal when their actions are, regardless of how they are encoded.
+impl PartialEq for ActionAtom {
+    fn eq(&self, other: &Self) -> bool {
+        self.actions == other.actions
     }
 }
 
@@ -178,3 +521,48 @@ impl Default for ActionAtom {
         Self::new()
     }
 }
+
+/// The start position a replay was recorded from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StartposAtom {
+    pub x: f32,
+    pub y: f32,
+    /// Frame of the full level run the start position is reached on.
+    pub frame_offset: u64,
+}
+
+impl StartposAtom {
+    const SIZE: usize = 16;
+
+    pub fn new(x: f32, y: f32, frame_offset: u64) -> Self {
+        Self { x, y, frame_offset }
+    }
+}
+
+impl Atom for StartposAtom {
+    const ID: AtomId = AtomId::Startpos;
+
+    fn size(&self) -> usize {
+        Self::SIZE
+    }
+
+    fn read<R: Read>(reader: &mut R, size: usize) -> Result<Self, AtomError> {
+        let mut buf = [0u8; Self::SIZE];
+        let read = size.min(Self::SIZE);
+        reader.read_exact(&mut buf[..read])?;
+        skip(reader, (size - read) as u64)?;
+
+        Ok(Self {
+            x: codec::decode::<f32>(&buf[0..4]),
+            y: codec::decode::<f32>(&buf[4..8]),
+            frame_offset: codec::decode::<u64>(&buf[8..16]),
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        codec::write(writer, self.x)?;
+        codec::write(writer, self.y)?;
+        codec::write(writer, self.frame_offset)?;
+        Ok(())
+    }
+}
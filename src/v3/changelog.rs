@@ -0,0 +1,161 @@
+//! Edit history of a replay, for projects edited by several people.
+//!
+//! A [`ChangeLogAtom`] lists who changed a replay, when and with what. The
+//! log is only written if a replay has one, which
+//! [`Replay::log_change`](super::Replay::log_change) adds on first use.
+
+use std::{
+    io::{Read, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::atom::{skip, Atom, AtomError, AtomId};
+use crate::codec;
+use crate::transform::Report;
+
+/// One change made to a replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub author: String,
+    /// What was done, such as the name of a transform.
+    pub operation: String,
+    /// Free-form details, empty if there are none.
+    pub details: String,
+}
+
+impl ChangeEntry {
+    /// An entry dated now, without details.
+    pub fn new(author: impl Into<String>, operation: impl Into<String>) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            author: author.into(),
+            operation: operation.into(),
+            details: String::new(),
+        }
+    }
+
+    /// An entry dated now, for a transform that was applied.
+    pub fn from_report(author: impl Into<String>, report: &Report) -> Self {
+        Self::new(author, report.name).with_details(format!(
+            "{} inputs before, {} after",
+            report.inputs_before, report.inputs_after
+        ))
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = details.into();
+        self
+    }
+}
+
+/// Atom holding the edit history of a replay, oldest change first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeLogAtom {
+    pub entries: Vec<ChangeEntry>,
+}
+
+impl ChangeLogAtom {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: ChangeEntry) {
+        self.entries.push(entry);
+    }
+
+    /// The changes made by an author.
+    pub fn by_author<'a>(&'a self, author: &'a str) -> impl Iterator<Item = &'a ChangeEntry> {
+        self.entries.iter().filter(move |e| e.author == author)
+    }
+
+    /// Remove the changes made before a time. Returns the amount removed.
+    pub fn prune_before(&mut self, timestamp: u64) -> usize {
+        let count = self.entries.len();
+        self.entries.retain(|e| e.timestamp >= timestamp);
+        count - self.entries.len()
+    }
+
+    /// Keep only the latest `count` changes. Returns the amount removed.
+    pub fn keep_last(&mut self, count: usize) -> usize {
+        let removed = self.entries.len().saturating_sub(count);
+        self.entries.drain(..removed);
+        removed
+    }
+}
+
+/// Strings are stored with a 16-bit length, so longer ones are cut.
+fn stored(text: &str) -> &[u8] {
+    &text.as_bytes()[..text.len().min(u16::MAX as usize)]
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, AtomError> {
+    let mut text = vec![0u8; codec::read::<u16, _>(reader)? as usize];
+    reader.read_exact(&mut text)?;
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+fn write_string<W: Write>(writer: &mut W, text: &str) -> Result<(), AtomError> {
+    let bytes = stored(text);
+    codec::write(writer, bytes.len() as u16)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+impl Atom for ChangeLogAtom {
+    const ID: AtomId = AtomId::ChangeLog;
+
+    fn size(&self) -> usize {
+        8 + self
+            .entries
+            .iter()
+            .map(|e| {
+                8 + 3 * 2
+                    + stored(&e.author).len()
+                    + stored(&e.operation).len()
+                    + stored(&e.details).len()
+            })
+            .sum::<usize>()
+    }
+
+    /// Entries running past `size` are an error, and bytes left after the
+    /// last entry are skipped.
+    fn read<R: Read>(reader: &mut R, size: usize) -> Result<Self, AtomError> {
+        let reader = &mut reader.take(size as u64);
+        let count: u64 = codec::read(reader)?;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            entries.push(ChangeEntry {
+                timestamp: codec::read(reader)?,
+                author: read_string(reader)?,
+                operation: read_string(reader)?,
+                details: read_string(reader)?,
+            });
+        }
+
+        skip(reader, reader.limit())?;
+        Ok(Self { entries })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        codec::write(writer, self.entries.len() as u64)?;
+
+        for entry in &self.entries {
+            codec::write(writer, entry.timestamp)?;
+            write_string(writer, &entry.author)?;
+            write_string(writer, &entry.operation)?;
+            write_string(writer, &entry.details)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+//! Struct-of-arrays storage for actions.
+//!
+//! [`ActionColumns`] keeps each field of a list of actions in its own
+//! vector, so passes reading one field across a long replay only touch the
+//! memory of that field.
+
+use super::action::{minimum_delta_size, Action, ActionType, AttemptKind};
+
+/// Fields of an action that are rarely set, stored apart from the columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionExtra {
+    /// Index of the action in the columns.
+    pub index: usize,
+    pub seed: u64,
+    pub tps: f64,
+    pub axis: f32,
+    pub subframe: u16,
+}
+
+/// Actions stored as columns.
+///
+/// Frames, deltas, types and flags are stored for every action. Seeds,
+/// tps values, analog axes and sub-frame positions are only stored for the
+/// actions where they differ from the defaults of [`Action::player`], in
+/// [`ActionColumns::extras`]. Conversions to and from `Vec<Action>` are
+/// lossless.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionColumns {
+    pub frames: Vec<u64>,
+    pub deltas: Vec<u64>,
+    pub types: Vec<ActionType>,
+    /// Flags of each action, see [`ActionColumns::HOLDING`] and the other flag constants.
+    pub flags: Vec<u8>,
+    /// Rare fields, ordered by index.
+    pub extras: Vec<ActionExtra>,
+}
+
+impl ActionColumns {
+    pub const HOLDING: u8 = 1;
+    pub const PLAYER2: u8 = 1 << 1;
+    pub const SWIFT: u8 = 1 << 2;
+    /// Shift of the [`AttemptKind`] stored in the flags.
+    pub const ATTEMPT_SHIFT: u8 = 3;
+    const ATTEMPT_MASK: u8 = 0b11 << Self::ATTEMPT_SHIFT;
+
+    const DEFAULT_TPS: f64 = 240.0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            frames: Vec::with_capacity(capacity),
+            deltas: Vec::with_capacity(capacity),
+            types: Vec::with_capacity(capacity),
+            flags: Vec::with_capacity(capacity),
+            extras: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Append an action.
+    pub fn push(&mut self, action: &Action) {
+        let index = self.len();
+        self.frames.push(action.frame);
+        self.deltas.push(action.delta());
+        self.types.push(action.action_type);
+
+        let mut flags = (action.attempt_kind() as u8) << Self::ATTEMPT_SHIFT;
+        if action.holding {
+            flags |= Self::HOLDING;
+        }
+        if action.player2 {
+            flags |= Self::PLAYER2;
+        }
+        if action.swift() {
+            flags |= Self::SWIFT;
+        }
+        self.flags.push(flags);
+
+        if action.seed != 0
+            || action.tps.to_bits() != Self::DEFAULT_TPS.to_bits()
+            || action.axis.to_bits() != 0
+            || action.subframe != 0
+        {
+            self.extras.push(ActionExtra {
+                index,
+                seed: action.seed,
+                tps: action.tps,
+                axis: action.axis,
+                subframe: action.subframe,
+            });
+        }
+    }
+
+    /// The action at an index, rebuilt from the columns.
+    pub fn get(&self, index: usize) -> Option<Action> {
+        let frame = *self.frames.get(index)?;
+        let extra = self
+            .extras
+            .binary_search_by_key(&index, |e| e.index)
+            .ok()
+            .map(|i| &self.extras[i]);
+        Some(self.build(index, frame, extra))
+    }
+
+    fn build(&self, index: usize, frame: u64, extra: Option<&ActionExtra>) -> Action {
+        let delta = self.deltas[index];
+        let flags = self.flags[index];
+
+        let mut action = Action::player(
+            frame.wrapping_sub(delta),
+            delta,
+            self.types[index],
+            flags & Self::HOLDING != 0,
+            flags & Self::PLAYER2 != 0,
+        );
+        action.frame = frame;
+        action.swift = flags & Self::SWIFT != 0;
+        let attempt = (flags & Self::ATTEMPT_MASK) >> Self::ATTEMPT_SHIFT;
+        action.set_attempt_kind(AttemptKind::from_bits(attempt as u16).unwrap_or_default());
+
+        if let Some(extra) = extra {
+            action.seed = extra.seed;
+            action.tps = extra.tps;
+            action.axis = extra.axis;
+            action.subframe = extra.subframe;
+        }
+        action
+    }
+
+    /// Rebuild every action, in order.
+    pub fn to_actions(&self) -> Vec<Action> {
+        let mut extras = self.extras.iter().peekable();
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(index, &frame)| {
+                let extra = extras.next_if(|e| e.index == index);
+                self.build(index, frame, extra)
+            })
+            .collect()
+    }
+
+    /// Whether the action at an index is a player input.
+    pub fn is_player(&self, index: usize) -> bool {
+        matches!(
+            self.types[index],
+            ActionType::Jump | ActionType::Left | ActionType::Right
+        )
+    }
+
+    /// [`Action::minimum_size`] of every action, computed from the deltas and types only.
+    pub fn minimum_sizes(&self) -> Vec<u8> {
+        (0..self.len())
+            .map(|i| minimum_delta_size(self.deltas[i], self.is_player(i)))
+            .collect()
+    }
+}
+
+impl From<&[Action]> for ActionColumns {
+    fn from(actions: &[Action]) -> Self {
+        let mut columns = Self::with_capacity(actions.len());
+        actions.iter().for_each(|action| columns.push(action));
+        columns
+    }
+}
+
+impl From<&ActionColumns> for Vec<Action> {
+    fn from(columns: &ActionColumns) -> Self {
+        columns.to_actions()
+    }
+}
+
+impl FromIterator<Action> for ActionColumns {
+    fn from_iter<I: IntoIterator<Item = Action>>(iter: I) -> Self {
+        let mut columns = Self::new();
+        iter.into_iter().for_each(|action| columns.push(&action));
+        columns
+    }
+}
@@ -0,0 +1,422 @@
+//! Annotated hex dumps of v3 atoms and sections.
+//!
+//! Meant for debugging desyncs: every byte of the selected atom or section is
+//! printed with its offset in the file, section headers are decoded bit by bit
+//! and every input's raw state is shown next to its interpretation.
+
+use std::io::Write;
+
+use thiserror::Error;
+
+use super::atom::AtomId;
+use super::metadata::METADATA_SIZE;
+use super::options::AnalogPrecision;
+use super::section::{SectionError, SectionInput};
+use crate::codec;
+
+#[derive(Debug, Error)]
+pub enum DumpError {
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Section error: {0}")]
+    SectionError(#[from] SectionError),
+    #[error("Not an slc3 file")]
+    InvalidHeader,
+    #[error("Unexpected end of file at offset {0:#x}")]
+    UnexpectedEof(usize),
+    #[error("Atom {0} not found")]
+    AtomNotFound(usize),
+    #[error("Section {0} not found")]
+    SectionNotFound(usize),
+}
+
+/// What [`dump`] should print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpTarget {
+    /// A whole atom, by index.
+    Atom(usize),
+    /// A single section of an action atom, by atom and section index.
+    Section { atom: usize, section: usize },
+}
+
+fn slice(data: &[u8], start: usize, len: usize) -> Result<&[u8], DumpError> {
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or(DumpError::UnexpectedEof(start))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn dump_raw<W: Write>(out: &mut W, data: &[u8], start: usize, len: usize) -> Result<(), DumpError> {
+    for (row, chunk) in slice(data, start, len)?.chunks(16).enumerate() {
+        writeln!(out, "    {:#010x}  {}", start + row * 16, hex(chunk))?;
+    }
+    Ok(())
+}
+
+fn dump_inputs<W: Write>(
+    out: &mut W,
+    data: &[u8],
+    mut offset: usize,
+    count: u64,
+    byte_size: usize,
+) -> Result<(), DumpError> {
+    let mut previous_frame = 0;
+    for _ in 0..count {
+        let bytes = slice(data, offset, byte_size)?;
+        let state = codec::decode_uint(bytes);
+        let input = SectionInput::from_state(previous_frame, state);
+        previous_frame = input.frame;
+
+        writeln!(
+            out,
+            "    {:#010x}  {:<24} state {:#x}: delta {}, {:?}, {}{}",
+            offset,
+            hex(bytes),
+            state,
+            input.delta,
+            input.button,
+            if input.holding { "hold" } else { "release" },
+            if input.player2 { ", player 2" } else { "" },
+        )?;
+        offset += byte_size;
+    }
+    Ok(())
+}
+
+fn dump_section<W: Write>(
+    out: &mut W,
+    data: &[u8],
+    start: usize,
+    index: usize,
+) -> Result<(), DumpError> {
+    let header = codec::decode::<u16>(slice(data, start, 2)?);
+    let id = header >> 14;
+
+    write!(
+        out,
+        "  section {} @ {:#010x}: header {:#06x} ",
+        index, start, header
+    )?;
+
+    match id {
+        0 | 1 => {
+            let delta_size = (header >> 12) & 0b11;
+            let count_exp = (header >> 8) & 0b1111;
+            let byte_size = 1usize << delta_size;
+            let count = 1u64 << count_exp;
+
+            if id == 0 {
+                writeln!(
+                    out,
+                    "(id 0 = input, delta size {} = {} bytes, count exp {} = {} inputs)",
+                    delta_size, byte_size, count_exp, count
+                )?;
+            } else {
+                let repeats_exp = (header >> 3) & 0b11111;
+                writeln!(
+                    out,
+                    "(id 1 = repeat, delta size {} = {} bytes, count exp {} = {} inputs, repeats exp {} = {} times)",
+                    delta_size,
+                    byte_size,
+                    count_exp,
+                    count,
+                    repeats_exp,
+                    1u64 << repeats_exp
+                )?;
+            }
+
+            dump_inputs(out, data, start + 2, count, byte_size)?;
+        }
+        2 => {
+            let delta_size = (header >> 8) & 0b11;
+            let special_type = (header >> 10) & 0b1111;
+            let byte_size = 1usize << delta_size;
+            let name = match special_type {
+                0 => "restart",
+                1 => "full restart",
+                2 => "death",
+                3 => "tps",
+                4 => "analog",
+                5 => "player tps",
+                6 => "pause",
+                7 => "skip",
+                _ => "unknown",
+            };
+
+            let attempt = if special_type <= 2 {
+                format!(", attempt {}", header & 0b11)
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "(id 2 = special, type {} = {}, delta size {} = {} bytes{})",
+                special_type, name, delta_size, byte_size, attempt
+            )?;
+
+            let delta = slice(data, start + 2, byte_size)?;
+            writeln!(
+                out,
+                "    {:#010x}  {:<24} delta {}",
+                start + 2,
+                hex(delta),
+                codec::decode_uint(delta)
+            )?;
+
+            let payload_offset = start + 2 + byte_size;
+            if special_type == 7 {
+                return Ok(());
+            }
+            if special_type == 4 {
+                let flags = *slice(data, payload_offset, 1)?.first().unwrap_or(&0);
+                let precision = AnalogPrecision::from_bits(flags >> 1);
+                let size = precision.map_or(0, AnalogPrecision::byte_size);
+                let value = slice(data, payload_offset + 1, size)?;
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} flags: precision {:?}{}",
+                    payload_offset,
+                    hex(&[flags]),
+                    precision,
+                    if flags & 1 == 1 { ", player 2" } else { "" }
+                )?;
+                if let Some(precision) = precision {
+                    writeln!(
+                        out,
+                        "    {:#010x}  {:<24} axis {}",
+                        payload_offset + 1,
+                        hex(value),
+                        precision.decode(codec::decode_uint(value) as u32)
+                    )?;
+                }
+                return Ok(());
+            }
+
+            let payload_offset = if special_type == 5 {
+                let flags = *slice(data, payload_offset, 1)?.first().unwrap_or(&0);
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} player {}",
+                    payload_offset,
+                    hex(&[flags]),
+                    if flags & 1 == 1 { 2 } else { 1 }
+                )?;
+                payload_offset + 1
+            } else {
+                payload_offset
+            };
+
+            let payload = slice(data, payload_offset, 8)?;
+            if special_type == 3 || special_type == 5 {
+                let tps = codec::decode::<f64>(payload);
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} tps {}",
+                    payload_offset,
+                    hex(payload),
+                    tps
+                )?;
+            } else if special_type == 6 {
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} paused for {} frames",
+                    payload_offset,
+                    hex(payload),
+                    codec::decode_uint(payload)
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} seed {}",
+                    payload_offset,
+                    hex(payload),
+                    codec::decode_uint(payload)
+                )?;
+            }
+        }
+        3 => {
+            let delta_size = (header >> 12) & 0b11;
+            let byte_size = 1usize << delta_size;
+            writeln!(
+                out,
+                "(id 3 = sub-frame input, delta size {} = {} bytes)",
+                delta_size, byte_size
+            )?;
+
+            dump_inputs(out, data, start + 2, 1, byte_size)?;
+            let subframe = slice(data, start + 2 + byte_size, 2)?;
+            writeln!(
+                out,
+                "    {:#010x}  {:<24} sub-frame {}/65536",
+                start + 2 + byte_size,
+                hex(subframe),
+                codec::decode_uint(subframe)
+            )?;
+        }
+        _ => writeln!(out, "(id {} = invalid)", id)?,
+    }
+
+    Ok(())
+}
+
+/// Byte length of the section at `start` and the amount of actions it
+/// decodes to, worked out from its header without decoding the actions.
+fn section_extent(data: &[u8], start: usize) -> Result<(usize, u64), DumpError> {
+    let header = codec::decode::<u16>(slice(data, start, 2)?);
+
+    match header >> 14 {
+        0 | 1 => {
+            let byte_size = 1usize << ((header >> 12) & 0b11);
+            let count = 1usize << ((header >> 8) & 0b1111);
+            let inputs = slice(data, start + 2, count * byte_size)?;
+
+            // Swift clicks decode to a press and a release.
+            let swifts = inputs
+                .chunks(byte_size)
+                .filter(|state| (codec::decode_uint(state) >> 2) & 0b11 == 0)
+                .count();
+            let mut actions = (count + swifts) as u64;
+            if header >> 14 == 1 {
+                actions = actions.saturating_mul(1 << ((header >> 3) & 0b11111));
+            }
+            Ok((2 + inputs.len(), actions))
+        }
+        2 => {
+            let byte_size = 1usize << ((header >> 8) & 0b11);
+            let payload = match (header >> 10) & 0b1111 {
+                0..=3 | 6 => 8,
+                4 => {
+                    let flags = slice(data, start + 2 + byte_size, 1)?[0];
+                    let precision = AnalogPrecision::from_bits(flags >> 1)
+                        .ok_or(SectionError::InvalidAnalogPrecision)?;
+                    1 + precision.byte_size()
+                }
+                5 => 9,
+                7 => 0,
+                _ => return Err(SectionError::InvalidIdentifier.into()),
+            };
+            let len = 2 + byte_size + payload;
+            slice(data, start, len)?;
+            Ok((len, 1))
+        }
+        _ => {
+            let len = 2 + (1usize << ((header >> 12) & 0b11)) + 2;
+            slice(data, start, len)?;
+            Ok((len, 1))
+        }
+    }
+}
+
+/// Offsets of every section of an action atom body, plus the end of the body.
+///
+/// Sections are walked by their headers alone, so a count and repeat
+/// exponents claiming billions of actions cost no more than the bytes they
+/// take up.
+fn section_offsets(data: &[u8], body: usize) -> Result<(Vec<usize>, usize), DumpError> {
+    let count = codec::decode_uint(slice(data, body, 8)?);
+    let mut offset = body + 8;
+    let mut offsets = Vec::new();
+    let mut actions = 0u64;
+    while actions < count {
+        offsets.push(offset);
+        let (len, decoded) = section_extent(data, offset)?;
+        if decoded > count - actions {
+            return Err(SectionError::TooManyActions.into());
+        }
+        actions += decoded;
+        offset += len;
+    }
+
+    Ok((offsets, offset))
+}
+
+/// Print an annotated hex dump of part of an slc3 file.
+pub fn dump<W: Write>(data: &[u8], target: DumpTarget, out: &mut W) -> Result<(), DumpError> {
+    if !data.starts_with(&super::replay::HEADER) {
+        return Err(DumpError::InvalidHeader);
+    }
+
+    let (atom_index, section_index) = match target {
+        DumpTarget::Atom(atom) => (atom, None),
+        DumpTarget::Section { atom, section } => (atom, Some(section)),
+    };
+
+    let mut offset = super::replay::HEADER.len() + 2 + METADATA_SIZE;
+    let end = data.len().saturating_sub(1);
+    let mut index = 0;
+
+    while offset < end && data[offset] != super::replay::FOOTER {
+        let id = codec::decode_uint(slice(data, offset, 4)?) as u32;
+        let size = usize::try_from(codec::decode_uint(slice(data, offset + 4, 8)?))
+            .map_err(|_| DumpError::UnexpectedEof(offset + 4))?;
+        let body = offset + 12;
+
+        let (sections, body_end) = if id == AtomId::Action as u32 {
+            section_offsets(data, body)?
+        } else {
+            let body_end = body
+                .checked_add(size)
+                .filter(|&end| end <= data.len())
+                .ok_or(DumpError::UnexpectedEof(body))?;
+            (Vec::new(), body_end)
+        };
+
+        if index == atom_index {
+            if let Some(section) = section_index {
+                let start = *sections
+                    .get(section)
+                    .ok_or(DumpError::SectionNotFound(section))?;
+                return dump_section(out, data, start, section);
+            }
+
+            writeln!(
+                out,
+                "atom {} @ {:#010x}: id {}, size field {}",
+                index, offset, id, size
+            )?;
+            writeln!(
+                out,
+                "    {:#010x}  {:<24} id",
+                offset,
+                hex(&data[offset..offset + 4])
+            )?;
+            writeln!(
+                out,
+                "    {:#010x}  {:<24} size",
+                offset + 4,
+                hex(&data[offset + 4..body])
+            )?;
+
+            if id == AtomId::Action as u32 {
+                let count = slice(data, body, 8)?;
+                writeln!(
+                    out,
+                    "    {:#010x}  {:<24} {} actions",
+                    body,
+                    hex(count),
+                    codec::decode_uint(count)
+                )?;
+                for (i, start) in sections.iter().enumerate() {
+                    dump_section(out, data, *start, i)?;
+                }
+            } else {
+                dump_raw(out, data, body, size)?;
+            }
+
+            return Ok(());
+        }
+
+        offset = body_end;
+        index += 1;
+    }
+
+    Err(DumpError::AtomNotFound(atom_index))
+}
@@ -1,9 +1,11 @@
 use std::io::{Read, Write};
 
+use crate::codec;
+
 pub const METADATA_SIZE: usize = 64;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Metadata {
     pub tps: f64,
     pub seed: u64,
@@ -13,7 +15,7 @@ pub struct Metadata {
 }
 
 impl Metadata {
-    pub fn new(tps: f64, seed: u64, build: u32) -> Self {
+    pub const fn new(tps: f64, seed: u64, build: u32) -> Self {
         Self {
             tps,
             seed,
@@ -24,19 +26,13 @@ impl Metadata {
     }
 
     pub fn read<R: Read>(reader: &mut R) -> std::io::Result<Self> {
-        let mut buf = [0u8; 8];
-        reader.read_exact(&mut buf)?;
-        let tps = f64::from_le_bytes(buf);
+        let tps: f64 = codec::read(reader)?;
 
-        reader.read_exact(&mut buf)?;
-        let seed = u64::from_le_bytes(buf);
+        let seed: u64 = codec::read(reader)?;
 
-        let mut buf4 = [0u8; 4];
-        reader.read_exact(&mut buf4)?;
-        let version = u32::from_le_bytes(buf4);
+        let version: u32 = codec::read(reader)?;
 
-        reader.read_exact(&mut buf4)?;
-        let build = u32::from_le_bytes(buf4);
+        let build: u32 = codec::read(reader)?;
 
         let mut padding = [0u8; 40];
         reader.read_exact(&mut padding)?;
@@ -51,10 +47,10 @@ impl Metadata {
     }
 
     pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
-        writer.write_all(&self.tps.to_le_bytes())?;
-        writer.write_all(&self.seed.to_le_bytes())?;
-        writer.write_all(&self.version.to_le_bytes())?;
-        writer.write_all(&self.build.to_le_bytes())?;
+        codec::write(writer, self.tps)?;
+        codec::write(writer, self.seed)?;
+        codec::write(writer, self.version)?;
+        codec::write(writer, self.build)?;
         writer.write_all(&self.padding)?;
         Ok(())
     }
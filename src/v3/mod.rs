@@ -6,10 +6,23 @@
 pub mod action;
 pub mod atom;
 pub mod builtin;
+pub mod changelog;
+pub mod columns;
+pub mod dump;
 pub mod metadata;
+pub mod options;
+pub mod recorder;
 pub mod replay;
+pub mod scan;
 pub mod section;
+pub mod session;
+pub mod sink;
 
-pub use action::{Action, ActionType};
+pub use crate::replay::ReadOptions;
+pub use action::{Action, ActionType, AttemptKind};
+pub use columns::ActionColumns;
 pub use metadata::Metadata;
+pub use options::{AnalogPrecision, GcOptions, NullAtomPolicy, WriteOptions};
+pub use recorder::Recorder;
 pub use replay::Replay;
+pub use scan::{scan, Visitor};
@@ -0,0 +1,137 @@
+/// Options controlling how a v3 replay is encoded.
+///
+/// The defaults produce the smallest output and match [`Replay::write`](super::Replay::write).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct WriteOptions {
+    /// Join a press and a release on the same frame into a single swift input.
+    ///
+    /// Some playback engines mishandle the 0-delta release frames produced
+    /// by swift inputs; disabling this encodes both actions separately.
+    pub swift: bool,
+    /// Precision used to store analog stick axes.
+    pub analog_precision: AnalogPrecision,
+    /// Collect the atoms before writing, see
+    /// [`AtomRegistry::gc`](super::atom::AtomRegistry::gc).
+    ///
+    /// The replay itself is left untouched, a collected copy of its atoms
+    /// is written instead.
+    pub gc: Option<GcOptions>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            swift: true,
+            analog_precision: AnalogPrecision::default(),
+            gc: None,
+        }
+    }
+}
+
+/// What [`AtomRegistry::gc`](super::atom::AtomRegistry::gc) removes.
+///
+/// The defaults drop empty action atoms and merge duplicates, but keep null
+/// atoms, which can be padding left on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GcOptions {
+    /// Drop action atoms holding no actions.
+    pub drop_empty_actions: bool,
+    /// Merge start position, session and change log atoms into the first
+    /// atom of their kind.
+    pub merge_duplicates: bool,
+    /// What to do with null atoms, which also hold markers and atoms of
+    /// unknown kinds.
+    pub null_atoms: NullAtomPolicy,
+}
+
+impl Default for GcOptions {
+    fn default() -> Self {
+        Self {
+            drop_empty_actions: true,
+            merge_duplicates: true,
+            null_atoms: NullAtomPolicy::default(),
+        }
+    }
+}
+
+/// How [`GcOptions`] treats null atoms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum NullAtomPolicy {
+    #[default]
+    Keep,
+    /// Drop null atoms after the last atom of another kind, left over from
+    /// edits that shrank the file.
+    DropTrailing,
+    Drop,
+}
+
+/// Quantization of analog axis values.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum AnalogPrecision {
+    /// Lossless, 4 bytes per axis.
+    #[default]
+    Float = 0,
+    /// 16-bit fixed point, 2 bytes per axis.
+    I16 = 1,
+    /// 8-bit fixed point, 1 byte per axis.
+    I8 = 2,
+}
+
+impl AnalogPrecision {
+    /// Amount of bytes an axis takes in this precision.
+    pub const fn byte_size(self) -> usize {
+        match self {
+            Self::Float => 4,
+            Self::I16 => 2,
+            Self::I8 => 1,
+        }
+    }
+
+    pub(crate) fn encode(self, axis: f32) -> u32 {
+        let axis = axis.clamp(-1.0, 1.0);
+        match self {
+            Self::Float => axis.to_bits(),
+            Self::I16 => (axis * i16::MAX as f32).round() as i16 as u16 as u32,
+            Self::I8 => (axis * i8::MAX as f32).round() as i8 as u8 as u32,
+        }
+    }
+
+    pub(crate) fn decode(self, bits: u32) -> f32 {
+        match self {
+            Self::Float => f32::from_bits(bits),
+            Self::I16 => (bits as u16 as i16) as f32 / i16::MAX as f32,
+            Self::I8 => (bits as u8 as i8) as f32 / i8::MAX as f32,
+        }
+        .clamp(-1.0, 1.0)
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(Self::Float),
+            1 => Some(Self::I16),
+            2 => Some(Self::I8),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Recording actions with pauses.
+//!
+//! Bots that keep counting frames while the game is paused would otherwise
+//! encode the whole pause as a single huge delta. A [`Recorder`] takes
+//! frames from the bot's own clock and removes paused time from them,
+//! leaving a pause marker where each pause happened.
+
+use super::action::ActionType;
+use super::atom::AtomError;
+use super::builtin::ActionAtom;
+
+/// Records actions into an action atom, keeping pauses out of the timeline.
+///
+/// Every method takes the frame of the bot's clock. Actions added while
+/// paused are placed on the frame the pause started on.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    atom: ActionAtom,
+    /// Paused frames so far, subtracted from clock frames.
+    offset: u64,
+    /// Clock frame the current pause started on.
+    paused_at: Option<u64>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            atom: ActionAtom::new(),
+            offset: 0,
+            paused_at: None,
+        }
+    }
+
+    /// The replay frame a clock frame is recorded on.
+    ///
+    /// Frames never go back before the last recorded action.
+    pub fn frame(&self, clock: u64) -> u64 {
+        let clock = self.paused_at.unwrap_or(clock);
+        let last = self.atom.actions.last().map_or(0, |a| a.frame);
+        clock.saturating_sub(self.offset).max(last)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Total amount of paused frames, excluding a pause still going on.
+    pub fn paused_frames(&self) -> u64 {
+        self.offset
+    }
+
+    /// Pause the recording. Does nothing if it is already paused.
+    pub fn pause(&mut self, clock: u64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(clock);
+        }
+    }
+
+    /// Resume the recording, adding a pause marker on the frame it was
+    /// paused on. Does nothing if it isn't paused.
+    pub fn resume(&mut self, clock: u64) -> Result<(), AtomError> {
+        let Some(paused_at) = self.paused_at else {
+            return Ok(());
+        };
+        let frame = self.frame(paused_at);
+        self.paused_at = None;
+
+        let length = clock.saturating_sub(paused_at);
+        self.offset += length;
+        self.atom.add_pause_action(frame, length)
+    }
+
+    pub fn add_player_action(
+        &mut self,
+        clock: u64,
+        action_type: ActionType,
+        holding: bool,
+        player2: bool,
+    ) -> Result<(), AtomError> {
+        let frame = self.frame(clock);
+        self.atom
+            .add_player_action(frame, action_type, holding, player2)
+    }
+
+    pub fn add_death_action(
+        &mut self,
+        clock: u64,
+        action_type: ActionType,
+        seed: u64,
+    ) -> Result<(), AtomError> {
+        let frame = self.frame(clock);
+        self.atom.add_death_action(frame, action_type, seed)
+    }
+
+    pub fn add_tps_action(&mut self, clock: u64, tps: f64) -> Result<(), AtomError> {
+        let frame = self.frame(clock);
+        self.atom.add_tps_action(frame, tps)
+    }
+
+    /// The actions recorded so far.
+    pub fn atom(&self) -> &ActionAtom {
+        &self.atom
+    }
+
+    /// Finish recording. A pause still going on is left without a marker.
+    pub fn into_atom(self) -> ActionAtom {
+        self.atom
+    }
+}
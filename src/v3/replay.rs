@@ -1,8 +1,15 @@
-use std::io::{Read, Seek, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use thiserror::Error;
 
+use super::action::ActionType;
 use super::atom::{AtomRegistry, AtomVariant};
+use super::builtin::{PlanScratch, StartposAtom};
+use super::changelog::{ChangeEntry, ChangeLogAtom};
 use super::metadata::{Metadata, METADATA_SIZE};
+use super::options::WriteOptions;
+use super::sink::ActionSink;
+use crate::codec;
+use crate::replay::{ByteCounter, ReadOptions};
 
 /// An SLC3 format replay.
 ///
@@ -27,6 +34,7 @@ use super::metadata::{Metadata, METADATA_SIZE};
 /// let mut writer = BufWriter::new(file);
 /// replay.write(&mut writer).unwrap();
 /// ```
+#[derive(Debug, Clone, PartialEq)]
 pub struct Replay {
     pub metadata: Metadata,
     pub atoms: AtomRegistry,
@@ -40,15 +48,46 @@ pub enum ReplayError {
     InvalidMetadataSize,
     #[error("Invalid footer")]
     InvalidFooter,
+    #[error("{0} bytes of trailing data after the footer")]
+    TrailingData(u64),
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(&'static str),
     #[error("IO error: {0}")]
     IOError(#[from] std::io::Error),
     #[error("Atom error: {0}")]
     AtomError(#[from] super::atom::AtomError),
 }
 
+/// Where the footer of a replay is, see [`Replay::locate_footer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footer {
+    /// Offset of the footer byte from the start of the replay.
+    pub position: u64,
+    /// Amount of bytes after the footer.
+    pub trailing: u64,
+}
+
+/// Frames over which one RNG seed is active, see [`Replay::seeds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedSpan {
+    pub start: u64,
+    /// First frame of the next span, or `None` for the last one.
+    pub end: Option<u64>,
+    pub seed: u64,
+}
+
+impl SeedSpan {
+    pub fn contains(&self, frame: u64) -> bool {
+        self.start <= frame && self.end.is_none_or(|end| frame < end)
+    }
+}
+
+pub(crate) const HEADER: [u8; 8] = crate::replay::V3_HEADER;
+pub(crate) const FOOTER: u8 = crate::format::V3_FOOTER;
+
 impl Replay {
-    const HEADER: [u8; 8] = [b'S', b'L', b'C', b'3', b'R', b'P', b'L', b'Y'];
-    const FOOTER: u8 = 0xCC;
+    const HEADER: [u8; 8] = HEADER;
+    const FOOTER: u8 = FOOTER;
 
     pub fn new(metadata: Metadata) -> Self {
         Self {
@@ -58,6 +97,53 @@ impl Replay {
     }
 
     pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, ReplayError> {
+        Self::read_with_options(reader, &ReadOptions::default())
+    }
+
+    /// Read the replay, within the given limits.
+    pub fn read_with_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Self, ReplayError> {
+        Self::read_inner(reader, options, None)
+    }
+
+    /// Read the replay, streaming actions into a sink instead of memory.
+    ///
+    /// Actions of every action atom are passed to the sink in file order,
+    /// and the action atoms of the returned replay are left empty. Other
+    /// atoms are read as usual.
+    pub fn read_into<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        sink: &mut dyn ActionSink,
+    ) -> Result<Self, ReplayError> {
+        Self::read_inner(reader, options, Some(sink))
+    }
+
+    fn read_inner<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        sink: Option<&mut dyn ActionSink>,
+    ) -> Result<Self, ReplayError> {
+        let (metadata, end_pos) = Self::read_preamble(reader, options)?;
+
+        let mut atoms = AtomRegistry::new();
+        match sink {
+            Some(sink) => atoms.read_all_into(reader, end_pos, options, sink)?,
+            None => atoms.read_all_with_options(reader, end_pos, options)?,
+        }
+
+        Self::read_footer(reader)?;
+        Ok(Self { metadata, atoms })
+    }
+
+    /// Read the header and metadata, returning the metadata and the
+    /// position where the atoms end.
+    pub(crate) fn read_preamble<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<(Metadata, u64), ReplayError> {
         let mut header_buf = [0u8; 8];
         reader.read_exact(&mut header_buf)?;
 
@@ -65,44 +151,170 @@ impl Replay {
             return Err(ReplayError::InvalidHeader);
         }
 
-        let mut buf2 = [0u8; 2];
-        reader.read_exact(&mut buf2)?;
-        let meta_size = u16::from_le_bytes(buf2);
+        let meta_size: u16 = codec::read(reader)?;
 
         if meta_size != METADATA_SIZE as u16 {
             return Err(ReplayError::InvalidMetadataSize);
         }
 
         let metadata = Metadata::read(reader)?;
-
-        let mut atoms = AtomRegistry::new();
+        if !options.limits().accepts_tps(metadata.tps) {
+            return Err(ReplayError::LimitExceeded("tps"));
+        }
 
         let current_pos = reader.stream_position()?;
-        reader.seek(std::io::SeekFrom::End(-1))?;
-        let end_pos = reader.stream_position()?;
-        reader.seek(std::io::SeekFrom::Start(current_pos))?;
+        let footer = Self::walk_atoms(reader, options)?;
+        if footer.trailing > 0 && !options.allow_trailing_data {
+            return Err(ReplayError::TrailingData(footer.trailing));
+        }
+        reader.seek(SeekFrom::Start(current_pos))?;
+
+        Ok((metadata, footer.position))
+    }
+
+    /// Find the footer of a replay by walking its atom headers.
+    ///
+    /// The reader must be at the start of the replay, and is left at an
+    /// unspecified position. Atom bodies are skipped without being decoded,
+    /// so this is cheap even for large files. Bytes after the footer, left
+    /// by some writers, are counted in [`Footer::trailing`].
+    pub fn locate_footer<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Footer, ReplayError> {
+        let mut header_buf = [0u8; 10];
+        reader.read_exact(&mut header_buf)?;
+        if header_buf[..8] != Self::HEADER {
+            return Err(ReplayError::InvalidHeader);
+        }
+        reader.seek(SeekFrom::Current(METADATA_SIZE as i64))?;
+        Self::walk_atoms(reader, options)
+    }
+
+    /// Skip atom headers from the reader's position until the footer.
+    ///
+    /// No atom id has 0xCC as its first byte, so a boundary starting with
+    /// it can only be the footer.
+    fn walk_atoms<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Footer, ReplayError> {
+        let mut position = reader.stream_position()?;
+        loop {
+            let mut byte = [0u8; 1];
+            if reader.read(&mut byte)? == 0 {
+                return Err(ReplayError::InvalidFooter);
+            }
+            if byte[0] == Self::FOOTER {
+                break;
+            }
 
-        atoms.read_all(reader, end_pos)?;
+            reader.seek(SeekFrom::Start(position))?;
+            let (_, size) = AtomVariant::read_header(reader, options)?;
+            position = position
+                .checked_add(12 + size as u64)
+                .ok_or(ReplayError::InvalidFooter)?;
+            reader.seek(SeekFrom::Start(position))?;
+        }
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        Ok(Footer {
+            position,
+            trailing: end - position - 1,
+        })
+    }
 
+    pub(crate) fn read_footer<R: Read>(reader: &mut R) -> Result<(), ReplayError> {
         let mut footer_buf = [0u8; 1];
         reader.read_exact(&mut footer_buf)?;
 
         if footer_buf[0] != Self::FOOTER {
             return Err(ReplayError::InvalidFooter);
         }
-
-        Ok(Self { metadata, atoms })
+        Ok(())
     }
 
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), ReplayError> {
+        self.write_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Size in bytes of the replay written with the given options, without
+    /// keeping the written bytes anywhere.
+    pub fn encoded_size(&self, options: &WriteOptions) -> Result<u64, ReplayError> {
+        let mut counter = ByteCounter::default();
+        self.write_with_options(&mut counter, options)?;
+        Ok(counter.0)
+    }
+
+    /// Write the replay using the given encoding options.
+    pub fn write_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<(), ReplayError> {
+        self.write_with_scratch(writer, options, &mut PlanScratch::default())
+    }
+
+    /// Write the replay with only the atoms `filter` accepts.
+    ///
+    /// The metadata is always written. This exports part of a replay, such
+    /// as only its actions, without cloning and editing its atoms first.
+    /// With [`WriteOptions::gc`] set, atoms are collected before filtering.
+    ///
+    /// # Examples
+    /// ```
+    /// use slc_oxide::v3::{atom::AtomVariant, Metadata, Replay, WriteOptions};
+    ///
+    /// let replay = Replay::new(Metadata::new(240.0, 0, 1));
+    /// let mut buffer = Vec::new();
+    /// replay
+    ///     .write_filtered(&mut buffer, &WriteOptions::default(), |atom| {
+    ///         matches!(atom, AtomVariant::Action(_))
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn write_filtered<W: Write, F: FnMut(&AtomVariant) -> bool>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        mut filter: F,
+    ) -> Result<(), ReplayError> {
+        self.write_filtered_with_scratch(writer, options, &mut PlanScratch::default(), &mut filter)
+    }
+
+    pub(crate) fn write_with_scratch<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        scratch: &mut PlanScratch,
+    ) -> Result<(), ReplayError> {
+        self.write_filtered_with_scratch(writer, options, scratch, &mut |_| true)
+    }
+
+    fn write_filtered_with_scratch<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        scratch: &mut PlanScratch,
+        filter: &mut dyn FnMut(&AtomVariant) -> bool,
+    ) -> Result<(), ReplayError> {
         writer.write_all(&Self::HEADER)?;
 
         let meta_size = METADATA_SIZE as u16;
-        writer.write_all(&meta_size.to_le_bytes())?;
+        codec::write(writer, meta_size)?;
 
         self.metadata.write(writer)?;
 
-        self.atoms.write_all(writer)?;
+        match &options.gc {
+            Some(gc) => {
+                let mut atoms = self.atoms.clone();
+                atoms.gc(gc);
+                atoms.write_filtered_with_scratch(writer, options, scratch, filter)?;
+            }
+            None => self
+                .atoms
+                .write_filtered_with_scratch(writer, options, scratch, filter)?,
+        }
 
         writer.write_all(&[Self::FOOTER])?;
 
@@ -112,4 +324,219 @@ impl Replay {
     pub fn add_atom(&mut self, atom: AtomVariant) {
         self.atoms.add(atom);
     }
+
+    /// The edit history of the replay, if it has one.
+    pub fn change_log(&self) -> Option<&ChangeLogAtom> {
+        self.atoms.iter().find_map(|atom| match atom {
+            AtomVariant::ChangeLog(log) => Some(log),
+            _ => None,
+        })
+    }
+
+    /// The edit history of the replay, to prune it.
+    pub fn change_log_mut(&mut self) -> Option<&mut ChangeLogAtom> {
+        self.atoms.iter_mut().find_map(|atom| match atom {
+            AtomVariant::ChangeLog(log) => Some(log),
+            _ => None,
+        })
+    }
+
+    /// Record a change in the edit history.
+    ///
+    /// A replay without a history gets a change log atom after its other
+    /// atoms, so it's written along with the replay from then on.
+    pub fn log_change(&mut self, entry: ChangeEntry) {
+        match self.change_log_mut() {
+            Some(log) => log.push(entry),
+            None => self.add_atom(AtomVariant::ChangeLog(ChangeLogAtom {
+                entries: vec![entry],
+            })),
+        }
+    }
+
+    /// A clip of frames `start..end`, moved to start on frame 0.
+    ///
+    /// Every atom is cut to the window along with the actions, so the clip
+    /// is consistent on its own:
+    /// - action and session atoms keep what falls within the window, see
+    ///   [`ActionAtom::extract_range`](super::builtin::ActionAtom::extract_range)
+    ///   and [`SessionAtom::extract_range`](super::session::SessionAtom::extract_range);
+    /// - start positions are reached `start` frames later in the level. Their
+    ///   coordinates are kept, as where the player is at the start of the
+    ///   window isn't stored anywhere;
+    /// - change logs are kept whole;
+    /// - null atoms are dropped, since what they hold is unknown.
+    ///
+    /// The metadata takes the tps and seed active when the window starts.
+    pub fn extract_range(&self, start: u64, end: u64) -> Replay {
+        let tps = self
+            .atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a.actions.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter(|a| a.action_type == ActionType::TPS && a.frame < start)
+            .max_by_key(|a| a.frame)
+            .map_or(self.metadata.tps, |a| a.tps);
+
+        let mut metadata = self.metadata;
+        metadata.tps = tps;
+        if let Some(before) = start.checked_sub(1) {
+            metadata.seed = self.seed_at(before);
+        }
+
+        let mut clip = Replay::new(metadata);
+        for atom in &self.atoms {
+            let atom = match atom {
+                AtomVariant::Null(_) => continue,
+                AtomVariant::Action(a) => AtomVariant::Action(a.extract_range(start, end)),
+                AtomVariant::Startpos(a) => AtomVariant::Startpos(StartposAtom {
+                    frame_offset: a.frame_offset + start,
+                    ..*a
+                }),
+                AtomVariant::Session(a) => AtomVariant::Session(a.extract_range(start, end)),
+                AtomVariant::ChangeLog(a) => AtomVariant::ChangeLog(a.clone()),
+                AtomVariant::Custom(a) => AtomVariant::Custom(a.clone()),
+            };
+            clip.add_atom(atom);
+        }
+        clip
+    }
+
+    /// Frame of the last action across all action atoms, or 0 if there are none.
+    pub fn last_frame(&self) -> u64 {
+        self.atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => a.actions.iter().map(|a| a.frame).max(),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Amount of frames from the start of the replay to its last action.
+    pub fn duration_frames(&self) -> u64 {
+        self.last_frame()
+    }
+
+    /// Time from the start of the replay to its last action, following tps changes.
+    ///
+    /// Only global tps changes are taken into account, per-player ones are ignored.
+    /// `None` if some of that time runs at a tps that isn't finite and positive.
+    pub fn duration_seconds(&self) -> Option<f64> {
+        let mut changes: Vec<(u64, f64)> = self
+            .atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a.actions.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter(|a| a.action_type == super::action::ActionType::TPS)
+            .map(|a| (a.frame, a.tps))
+            .collect();
+        changes.sort_by_key(|(frame, _)| *frame);
+
+        crate::replay::elapsed_seconds(self.metadata.tps, changes, self.last_frame())
+    }
+
+    /// The RNG seed active on a frame, see [`Replay::seeds`].
+    pub fn seed_at(&self, frame: u64) -> u64 {
+        let spans = self.seeds();
+        let index = spans.partition_point(|s| s.start <= frame);
+        spans[index - 1].seed
+    }
+
+    /// The RNG seed active over the whole replay, as consecutive spans.
+    ///
+    /// The metadata seed is active from frame 0. Every restart, full
+    /// restart and death makes its own seed active from its frame on, and
+    /// when several are on one frame the last one wins. Actions of all
+    /// action atoms are merged by frame. Spans with the same seed are
+    /// merged, so there is always at least one span and no two neighbours
+    /// share a seed.
+    pub fn seeds(&self) -> Vec<SeedSpan> {
+        let mut changes: Vec<(u64, u64)> = self
+            .atoms
+            .iter()
+            .filter_map(|atom| match atom {
+                AtomVariant::Action(a) => Some(a.actions.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter(|a| {
+                matches!(
+                    a.action_type,
+                    ActionType::Restart | ActionType::RestartFull | ActionType::Death
+                )
+            })
+            .map(|a| (a.frame, a.seed))
+            .collect();
+        changes.sort_by_key(|(frame, _)| *frame);
+
+        let mut spans = vec![SeedSpan {
+            start: 0,
+            end: None,
+            seed: self.metadata.seed,
+        }];
+        for (frame, seed) in changes {
+            let last = spans.last_mut().unwrap();
+            if last.start == frame {
+                last.seed = seed;
+            } else if last.seed != seed {
+                last.end = Some(frame);
+                spans.push(SeedSpan {
+                    start: frame,
+                    end: None,
+                    seed,
+                });
+                continue;
+            }
+
+            // The last span changed seed, it may now match the one before.
+            if spans.len() > 1 && spans[spans.len() - 2].seed == spans[spans.len() - 1].seed {
+                spans.pop();
+                spans.last_mut().unwrap().end = None;
+            }
+        }
+        spans
+    }
+
+    /// Whether two replays decode to the same actions, regardless of encoding.
+    ///
+    /// The tps, seed and build must match. Actions are compared across all
+    /// action atoms, so swift pairs, section layouts and how actions are split
+    /// between atoms don't matter. Other atoms must match in order, except
+    /// null atoms which are ignored.
+    pub fn semantically_eq(&self, other: &Replay) -> bool {
+        fn actions(replay: &Replay) -> Vec<&super::action::Action> {
+            replay
+                .atoms
+                .atoms
+                .iter()
+                .filter_map(|atom| match atom {
+                    AtomVariant::Action(a) => Some(a.actions.iter()),
+                    _ => None,
+                })
+                .flatten()
+                .collect()
+        }
+        fn others(replay: &Replay) -> Vec<&AtomVariant> {
+            replay
+                .atoms
+                .atoms
+                .iter()
+                .filter(|atom| !matches!(atom, AtomVariant::Action(_) | AtomVariant::Null(_)))
+                .collect()
+        }
+
+        self.metadata.tps == other.metadata.tps
+            && self.metadata.seed == other.metadata.seed
+            && self.metadata.build == other.metadata.build
+            && actions(self) == actions(other)
+            && others(self) == others(other)
+    }
 }
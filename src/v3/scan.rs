@@ -0,0 +1,72 @@
+//! Constant-memory walks over v3 replays.
+//!
+//! [`scan`] reads a replay atom by atom and hands every decoded action to
+//! a [`Visitor`] as soon as it is decoded, without collecting them. Memory
+//! use doesn't grow with the length of the replay, so statistics can be
+//! computed over files too large to load.
+
+use std::io::{self, Read, Seek};
+
+use super::action::Action;
+use super::atom::{AtomId, AtomVariant};
+use super::metadata::Metadata;
+use super::replay::{Replay, ReplayError};
+use super::sink::ActionSink;
+use crate::replay::ReadOptions;
+
+/// Callbacks invoked while scanning a replay.
+///
+/// Every method does nothing by default.
+pub trait Visitor {
+    /// Called once, before any atom.
+    fn metadata(&mut self, _metadata: &Metadata) {}
+
+    /// Called when an atom starts, with its body size in bytes.
+    fn atom_start(&mut self, _id: AtomId, _size: u64) {}
+
+    /// Called for every action of every action atom, in file order.
+    fn action(&mut self, _action: &Action) {}
+
+    /// Called with every atom other than action atoms, once read.
+    fn atom(&mut self, _atom: &AtomVariant) {}
+}
+
+struct VisitorSink<'a, V>(&'a mut V);
+
+impl<V: Visitor> ActionSink for VisitorSink<'_, V> {
+    fn push(&mut self, action: Action) -> io::Result<()> {
+        self.0.action(&action);
+        Ok(())
+    }
+}
+
+/// Walk a replay, invoking the visitor for its metadata, atoms and actions.
+pub fn scan<R: Read + Seek, V: Visitor>(
+    reader: &mut R,
+    visitor: &mut V,
+) -> Result<(), ReplayError> {
+    scan_with_options(reader, visitor, &ReadOptions::default())
+}
+
+/// Walk a replay within the given limits, see [`scan`].
+pub fn scan_with_options<R: Read + Seek, V: Visitor>(
+    reader: &mut R,
+    visitor: &mut V,
+    options: &ReadOptions,
+) -> Result<(), ReplayError> {
+    let (metadata, end_pos) = Replay::read_preamble(reader, options)?;
+    visitor.metadata(&metadata);
+
+    while reader.stream_position()? < end_pos {
+        let (id, size) = AtomVariant::read_header(reader, options)?;
+        visitor.atom_start(AtomId::from_raw(id), size as u64);
+
+        let mut sink = VisitorSink(&mut *visitor);
+        let atom = AtomVariant::read_body(reader, id, size, options, Some(&mut sink))?;
+        if !matches!(atom, AtomVariant::Action(_)) {
+            visitor.atom(&atom);
+        }
+    }
+
+    Replay::read_footer(reader)
+}
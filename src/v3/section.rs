@@ -1,17 +1,30 @@
-use std::io::{Read, Write};
+use std::{
+    io::{Read, Write},
+    ops::Range,
+};
 use thiserror::Error;
 
-use super::action::{Action, ActionType};
+use super::action::{Action, ActionType, AttemptKind};
+use super::options::AnalogPrecision;
+use super::sink::ActionSink;
+use crate::codec;
+use crate::replay::Limits;
 
-pub(crate) fn exponent_of_two(n: u32) -> u16 {
+#[inline]
+pub(crate) const fn exponent_of_two(n: u32) -> u16 {
     if n == 0 {
         return 0;
     }
     let exp = 31 - n.leading_zeros();
-    exp.min(15) as u16
+    if exp > 15 {
+        15
+    } else {
+        exp as u16
+    }
 }
 
-pub(crate) fn largest_power_of_two(n: usize) -> usize {
+#[inline]
+pub(crate) const fn largest_power_of_two(n: usize) -> usize {
     if n == 0 {
         return 0;
     }
@@ -26,6 +39,16 @@ pub enum SectionError {
     InvalidIdentifier,
     #[error("Invalid button type")]
     InvalidButton,
+    #[error("Invalid analog precision")]
+    InvalidAnalogPrecision,
+    #[error("Invalid attempt kind")]
+    InvalidAttemptKind,
+    #[error("Frame overflow")]
+    FrameOverflow,
+    #[error("Too many actions")]
+    TooManyActions,
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(&'static str),
 }
 
 #[repr(u8)]
@@ -34,6 +57,8 @@ pub enum SectionIdentifier {
     Input = 0,
     Repeat = 1,
     Special = 2,
+    /// A single player input with a sub-frame position.
+    SubFrame = 3,
 }
 
 #[repr(u8)]
@@ -43,6 +68,10 @@ pub enum SpecialType {
     RestartFull = 1,
     Death = 2,
     TPS = 3,
+    Analog = 4,
+    PlayerTPS = 5,
+    Pause = 6,
+    Skip = 7,
 }
 
 #[repr(u8)]
@@ -54,8 +83,12 @@ pub enum Button {
     Right = 3,
 }
 
+/// A player input as stored in an slc3 section.
+///
+/// Unlike [`crate::input::PlayerInput`], this carries its frame and delta,
+/// and can be a swift click.
 #[derive(Debug, Clone)]
-pub struct PlayerInput {
+pub struct SectionInput {
     pub frame: u64,
     pub delta: u64,
     pub button: Button,
@@ -63,7 +96,11 @@ pub struct PlayerInput {
     pub player2: bool,
 }
 
-impl PlayerInput {
+/// Old name of [`SectionInput`].
+#[deprecated(since = "0.2.0", note = "Use `SectionInput` instead")]
+pub type PlayerInput = SectionInput;
+
+impl SectionInput {
     pub fn from_action(action: &Action) -> Self {
         let button = if action.swift() {
             Button::Swift
@@ -85,9 +122,10 @@ impl PlayerInput {
         }
     }
 
-    pub fn from_state(prev_frame: u64, state: u64) -> Self {
+    #[inline]
+    pub const fn from_state(prev_frame: u64, state: u64) -> Self {
         let delta = state >> 4;
-        let frame = prev_frame + delta;
+        let frame = prev_frame.wrapping_add(delta);
         let button_val = (state >> 2) & 0b11;
         let button = match button_val {
             0 => Button::Swift,
@@ -108,7 +146,8 @@ impl PlayerInput {
         }
     }
 
-    pub fn prepare_state(&self, byte_size: u8) -> u64 {
+    #[inline]
+    pub const fn prepare_state(&self, byte_size: u8) -> u64 {
         let byte_mask = if byte_size == 8 {
             u64::MAX
         } else {
@@ -122,7 +161,7 @@ impl PlayerInput {
                 | self.holding as u64)
     }
 
-    pub fn weak_eq(&self, other: &PlayerInput) -> bool {
+    pub fn weak_eq(&self, other: &SectionInput) -> bool {
         self.delta == other.delta
             && self.holding == other.holding
             && self.player2 == other.player2
@@ -133,7 +172,7 @@ impl PlayerInput {
 pub struct Section {
     pub id: SectionIdentifier,
     pub delta_size: u16,
-    pub player_inputs: Vec<PlayerInput>,
+    pub player_inputs: Vec<SectionInput>,
     pub marked_for_removal: bool,
     count_exp: u16,
     repeats_exp: u16,
@@ -141,33 +180,40 @@ pub struct Section {
     seed: u64,
     tps: f64,
     special: Option<Action>,
+    pub(crate) analog_precision: AnalogPrecision,
+    subframe: u16,
 }
 
 impl Section {
-    pub fn player_from_range(actions: &[Action], start: usize, end: usize) -> Self {
-        let mut player_inputs = Vec::new();
-        let mut count = 0u32;
-
-        for action in &actions[start..end] {
-            if action.holding || !action.swift() {
-                player_inputs.push(PlayerInput::from_action(action));
-                count += 1;
-            }
-        }
-
-        let count_exp = exponent_of_two(count);
-
+    /// A section of the given kind, without inputs.
+    const fn empty(id: SectionIdentifier, delta_size: u16) -> Self {
         Self {
-            id: SectionIdentifier::Input,
-            delta_size: 0,
-            player_inputs,
+            id,
+            delta_size,
+            player_inputs: Vec::new(),
             marked_for_removal: false,
-            count_exp,
+            count_exp: 0,
             repeats_exp: 0,
             special_type: SpecialType::Restart,
             seed: 0,
             tps: 240.0,
             special: None,
+            analog_precision: AnalogPrecision::Float,
+            subframe: 0,
+        }
+    }
+
+    pub fn player_from_range(actions: &[Action], start: usize, end: usize) -> Self {
+        let player_inputs: Vec<_> = actions[start..end]
+            .iter()
+            .filter(|a| a.holding || !a.swift())
+            .map(SectionInput::from_action)
+            .collect();
+
+        Self {
+            count_exp: exponent_of_two(player_inputs.len() as u32),
+            player_inputs,
+            ..Self::empty(SectionIdentifier::Input, 0)
         }
     }
 
@@ -177,6 +223,10 @@ impl Section {
             ActionType::Death => SpecialType::Death,
             ActionType::Restart => SpecialType::Restart,
             ActionType::RestartFull => SpecialType::RestartFull,
+            ActionType::Analog => SpecialType::Analog,
+            ActionType::PlayerTPS => SpecialType::PlayerTPS,
+            ActionType::Pause => SpecialType::Pause,
+            ActionType::Skip => SpecialType::Skip,
             _ => return Err(SectionError::InvalidIdentifier),
         };
 
@@ -191,120 +241,116 @@ impl Section {
             seed: action.seed,
             tps: action.tps,
             special: Some(action.clone()),
+            analog_precision: AnalogPrecision::Float,
+            subframe: 0,
         })
     }
 
-    pub fn real_delta_size(&self) -> u64 {
+    /// A section holding a single player action with its sub-frame position.
+    pub fn subframe(action: &Action) -> Self {
+        Self {
+            player_inputs: vec![SectionInput::from_action(action)],
+            ..Self::subframe_header(action)
+        }
+    }
+
+    fn subframe_header(action: &Action) -> Self {
+        Self {
+            subframe: action.subframe,
+            ..Self::empty(SectionIdentifier::SubFrame, action.minimum_size() as u16)
+        }
+    }
+
+    pub const fn real_delta_size(&self) -> u64 {
         1u64 << self.delta_size as u64
     }
 
-    pub fn input_count(&self) -> u64 {
+    pub const fn input_count(&self) -> u64 {
         1u64 << self.count_exp as u64
     }
 
-    pub fn repeat_count(&self) -> u64 {
+    pub const fn repeat_count(&self) -> u64 {
         1u64 << self.repeats_exp as u64
     }
 
-    pub fn run_length_encode(&self) -> Vec<Section> {
-        let mut new_sections = Vec::new();
-        let mut free_inputs = Vec::new();
-
-        const MAX_CLUSTER_SIZE: usize = 64;
-
-        let n = self.player_inputs.len();
-        let mut idx = 0;
-
-        while idx < n {
-            let mut found_any_repetitions = false;
-            let mut best_cluster = 0;
-            let mut best_cluster_repetitions = 0;
-            let mut best_cluster_score = 0i64;
-
-            let mut cluster = 1;
-            while cluster <= MAX_CLUSTER_SIZE && cluster <= n {
-                if idx + cluster >= n {
-                    break;
-                }
-
-                let mut offset = 1;
-                loop {
-                    let start = idx + offset * cluster;
-                    let end = idx + (offset + 1) * cluster;
-
-                    if end > n {
-                        break;
-                    }
-
-                    let all_equal = (0..cluster).all(|j| {
-                        self.player_inputs[idx + j].weak_eq(&self.player_inputs[start + j])
-                    });
-
-                    if !all_equal {
-                        break;
-                    }
-
-                    offset += 1;
-                }
-
-                offset = offset.saturating_sub(1);
-                if offset <= 1 {
-                    cluster <<= 1;
-                    continue;
-                }
-
-                offset = largest_power_of_two(offset);
-
-                let score = (cluster as i64) * ((offset as i64) - 1);
-                if score > best_cluster_score {
-                    found_any_repetitions = true;
-                    best_cluster_score = score;
-                    best_cluster = cluster;
-                    best_cluster_repetitions = offset;
-                }
+    /// The kind of a special section.
+    pub const fn special_type(&self) -> Option<SpecialType> {
+        match self.id {
+            SectionIdentifier::Special => Some(self.special_type),
+            _ => None,
+        }
+    }
 
-                cluster <<= 1;
-            }
+    /// Amount of actions the section decodes to.
+    pub fn action_count(&self) -> u64 {
+        if self.marked_for_removal {
+            return 0;
+        }
 
-            if found_any_repetitions {
-                distribute_inputs_to_sections(&mut new_sections, &mut free_inputs, self.delta_size);
-
-                let repeat_section = Section {
-                    id: SectionIdentifier::Repeat,
-                    delta_size: self.delta_size,
-                    player_inputs: self.player_inputs[idx..idx + best_cluster].to_vec(),
-                    marked_for_removal: false,
-                    count_exp: exponent_of_two(best_cluster as u32),
-                    repeats_exp: exponent_of_two(best_cluster_repetitions as u32),
-                    special_type: SpecialType::Restart,
-                    seed: 0,
-                    tps: 240.0,
-                    special: None,
+        match self.id {
+            SectionIdentifier::Special | SectionIdentifier::SubFrame => 1,
+            SectionIdentifier::Input | SectionIdentifier::Repeat => {
+                let repeats = match self.id {
+                    SectionIdentifier::Repeat => self.repeat_count(),
+                    _ => 1,
                 };
-
-                new_sections.push(repeat_section);
-                idx += best_cluster * best_cluster_repetitions;
-            } else {
-                free_inputs.push(self.player_inputs[idx].clone());
-                idx += 1;
+                let per_repeat: u64 = self
+                    .player_inputs
+                    .iter()
+                    .map(|p| if p.button == Button::Swift { 2 } else { 1 })
+                    .sum();
+                per_repeat * repeats
             }
         }
+    }
 
-        distribute_inputs_to_sections(&mut new_sections, &mut free_inputs, self.delta_size);
-
-        new_sections
+    pub fn run_length_encode(&self) -> Vec<Section> {
+        let mut sections = Vec::new();
+        run_length_encode_into(&self.player_inputs, 0, self.delta_size, &mut sections);
+
+        sections
+            .into_iter()
+            .map(|(section, range)| Section {
+                player_inputs: self.player_inputs[range].to_vec(),
+                ..section
+            })
+            .collect()
     }
 
     pub fn read<R: Read>(reader: &mut R, actions: &mut Vec<Action>) -> Result<(), SectionError> {
-        let mut buf2 = [0u8; 2];
-        reader.read_exact(&mut buf2)?;
-        let initial_header = u16::from_le_bytes(buf2);
+        Self::read_with_limit(reader, actions, usize::MAX)
+    }
+
+    /// Read a section, failing if it would take the amount of actions past a limit.
+    ///
+    /// The limit is checked before the actions of a section are decoded, so a
+    /// section can't make the reader allocate more than the limit allows.
+    pub fn read_with_limit<R: Read>(
+        reader: &mut R,
+        actions: &mut Vec<Action>,
+        limit: usize,
+    ) -> Result<(), SectionError> {
+        let count = actions.len();
+        let last_frame = actions.last().map_or(0, |a| a.frame);
+        let mut output = ActionOutput::new(actions, count, last_frame);
+        Self::read_into(reader, &mut output, limit)
+    }
+
+    /// Read a section into an output, failing if it would take the amount
+    /// of actions past a limit.
+    pub(crate) fn read_into<R: Read>(
+        reader: &mut R,
+        actions: &mut ActionOutput<'_>,
+        limit: usize,
+    ) -> Result<(), SectionError> {
+        let initial_header: u16 = codec::read(reader)?;
 
         let id = (initial_header >> 14) as u8;
         let id = match id {
             0 => SectionIdentifier::Input,
             1 => SectionIdentifier::Repeat,
             2 => SectionIdentifier::Special,
+            3 => SectionIdentifier::SubFrame,
             _ => return Err(SectionError::InvalidIdentifier),
         };
 
@@ -315,30 +361,30 @@ impl Section {
 
                 let byte_size = 1u64 << delta_size;
                 let length = 1u64 << count_exp;
+                check_limit(actions.count, length, limit)?;
 
-                let mut previous_frame = actions.last().map(|a| a.frame).unwrap_or(0);
+                let mut previous_frame = actions.last_frame;
 
                 for _ in 0..length {
-                    let state = read_n_bytes(reader, byte_size as usize)?;
-                    let p = PlayerInput::from_state(previous_frame, state);
+                    let state = codec::read_uint(reader, byte_size as usize)?;
+                    let p = SectionInput::from_state(previous_frame, state);
+                    check_frame(previous_frame, p.delta)?;
 
                     if p.button == Button::Swift {
-                        actions.push(Action::player(
+                        actions.push_swift(Action::player(
                             previous_frame,
                             p.delta,
                             ActionType::Jump,
                             true,
                             p.player2,
-                        ));
-                        actions.last_mut().unwrap().swift = true;
-                        actions.push(Action::player(
+                        ))?;
+                        actions.push_swift(Action::player(
                             p.frame,
                             0,
                             ActionType::Jump,
                             false,
                             p.player2,
-                        ));
-                        actions.last_mut().unwrap().swift = true;
+                        ))?;
                     } else {
                         let action_type = match p.button {
                             Button::Jump => ActionType::Jump,
@@ -352,10 +398,10 @@ impl Section {
                             action_type,
                             p.holding,
                             p.player2,
-                        ));
+                        ))?;
                     }
 
-                    previous_frame = actions.last().unwrap().frame;
+                    previous_frame = actions.last_frame;
                 }
             }
             SectionIdentifier::Repeat => {
@@ -366,37 +412,40 @@ impl Section {
                 let byte_size = 1u64 << delta_size;
                 let length = 1u64 << count_exp;
                 let repeats = 1u64 << repeats_exp;
+                let total = length
+                    .checked_mul(repeats)
+                    .ok_or(SectionError::TooManyActions)?;
+                check_limit(actions.count, total, limit)?;
 
                 let mut inputs = Vec::new();
                 let mut prev_input_frame = 0u64;
 
                 for _ in 0..length {
-                    let state = read_n_bytes(reader, byte_size as usize)?;
-                    let p = PlayerInput::from_state(prev_input_frame, state);
+                    let state = codec::read_uint(reader, byte_size as usize)?;
+                    let p = SectionInput::from_state(prev_input_frame, state);
                     prev_input_frame = p.frame;
                     inputs.push(p);
                 }
 
                 for _ in 0..repeats {
-                    let mut previous_frame = actions.last().map(|a| a.frame).unwrap_or(0);
+                    let mut previous_frame = actions.last_frame;
                     for p in &inputs {
+                        check_frame(previous_frame, p.delta)?;
                         if p.button == Button::Swift {
-                            actions.push(Action::player(
+                            actions.push_swift(Action::player(
                                 previous_frame,
                                 p.delta,
                                 ActionType::Jump,
                                 true,
                                 p.player2,
-                            ));
-                            actions.last_mut().unwrap().swift = true;
-                            actions.push(Action::player(
+                            ))?;
+                            actions.push_swift(Action::player(
                                 previous_frame + p.delta,
                                 0,
                                 ActionType::Jump,
                                 false,
                                 p.player2,
-                            ));
-                            actions.last_mut().unwrap().swift = true;
+                            ))?;
                         } else {
                             let action_type = match p.button {
                                 Button::Jump => ActionType::Jump,
@@ -410,9 +459,9 @@ impl Section {
                                 action_type,
                                 p.holding,
                                 p.player2,
-                            ));
+                            ))?;
                         }
-                        previous_frame = actions.last().unwrap().frame;
+                        previous_frame = actions.last_frame;
                     }
                 }
             }
@@ -421,45 +470,151 @@ impl Section {
                 let special_type = (initial_header >> 10) & 0b1111;
 
                 let byte_size = 1u64 << delta_size;
-                let frame_delta = read_n_bytes(reader, byte_size as usize)?;
+                let frame_delta = codec::read_uint(reader, byte_size as usize)?;
 
-                let current_frame = actions.last().map(|a| a.frame).unwrap_or(0);
+                let current_frame = actions.last_frame;
+                check_limit(actions.count, 1, limit)?;
+                check_frame(current_frame, frame_delta)?;
 
                 let special_type = match special_type {
                     0 => SpecialType::Restart,
                     1 => SpecialType::RestartFull,
                     2 => SpecialType::Death,
                     3 => SpecialType::TPS,
+                    4 => SpecialType::Analog,
+                    5 => SpecialType::PlayerTPS,
+                    6 => SpecialType::Pause,
+                    7 => SpecialType::Skip,
                     _ => return Err(SectionError::InvalidIdentifier),
                 };
 
                 match special_type {
                     SpecialType::TPS => {
-                        let mut buf8 = [0u8; 8];
-                        reader.read_exact(&mut buf8)?;
-                        let tps = f64::from_le_bytes(buf8);
-                        actions.push(Action::tps_change(current_frame, frame_delta, tps));
+                        let tps: f64 = codec::read(reader)?;
+                        actions.push(Action::tps_change(current_frame, frame_delta, tps))?;
+                    }
+                    SpecialType::PlayerTPS => {
+                        let mut buf9 = [0u8; 9];
+                        reader.read_exact(&mut buf9)?;
+                        let tps: f64 = codec::decode(&buf9[1..]);
+                        actions.push(Action::player_tps_change(
+                            current_frame,
+                            frame_delta,
+                            tps,
+                            buf9[0] & 1 == 1,
+                        ))?;
+                    }
+                    SpecialType::Pause => {
+                        let paused_frames: u64 = codec::read(reader)?;
+                        actions.push(Action::pause(current_frame, frame_delta, paused_frames))?;
+                    }
+                    SpecialType::Skip => {
+                        actions.push(Action::skip(current_frame, frame_delta))?;
+                    }
+                    SpecialType::Analog => {
+                        let mut flags = [0u8; 1];
+                        reader.read_exact(&mut flags)?;
+                        let precision = AnalogPrecision::from_bits(flags[0] >> 1)
+                            .ok_or(SectionError::InvalidAnalogPrecision)?;
+                        let bits = codec::read_uint(reader, precision.byte_size())? as u32;
+                        actions.push(Action::analog(
+                            current_frame,
+                            frame_delta,
+                            precision.decode(bits),
+                            flags[0] & 1 == 1,
+                        ))?;
                     }
                     SpecialType::Restart | SpecialType::RestartFull | SpecialType::Death => {
-                        let mut buf8 = [0u8; 8];
-                        reader.read_exact(&mut buf8)?;
-                        let seed = u64::from_le_bytes(buf8);
+                        let seed: u64 = codec::read(reader)?;
                         let action_type = match special_type {
                             SpecialType::Restart => ActionType::Restart,
                             SpecialType::RestartFull => ActionType::RestartFull,
                             SpecialType::Death => ActionType::Death,
                             _ => ActionType::Restart,
                         };
-                        actions.push(Action::death(current_frame, frame_delta, action_type, seed));
+                        let attempt = AttemptKind::from_bits(initial_header & 0b11)
+                            .ok_or(SectionError::InvalidAttemptKind)?;
+                        let mut action =
+                            Action::death(current_frame, frame_delta, action_type, seed);
+                        action.set_attempt_kind(attempt);
+                        actions.push(action)?;
                     }
                 }
             }
+            SectionIdentifier::SubFrame => {
+                let delta_size = (initial_header >> 12) & 0b11;
+                let state = codec::read_uint(reader, 1 << delta_size)?;
+                let subframe: u16 = codec::read(reader)?;
+
+                let previous_frame = actions.last_frame;
+                let p = SectionInput::from_state(previous_frame, state);
+                check_limit(actions.count, 1, limit)?;
+                check_frame(previous_frame, p.delta)?;
+                let action_type = match p.button {
+                    Button::Jump => ActionType::Jump,
+                    Button::Left => ActionType::Left,
+                    Button::Right => ActionType::Right,
+                    Button::Swift => return Err(SectionError::InvalidButton),
+                };
+
+                let mut action =
+                    Action::player(previous_frame, p.delta, action_type, p.holding, p.player2);
+                action.subframe = subframe;
+                actions.push(action)?;
+            }
+        }
+
+        // Swift inputs decode to two actions each.
+        if actions.count > limit {
+            return Err(SectionError::TooManyActions);
         }
 
         Ok(())
     }
 
+    /// Amount of bytes [`Section::write`] writes.
+    pub fn encoded_size(&self) -> u64 {
+        self.encoded_size_with(self.player_inputs.len())
+    }
+
+    fn encoded_size_with(&self, player_inputs: usize) -> u64 {
+        if self.marked_for_removal {
+            return 0;
+        }
+
+        let delta_size = self.real_delta_size();
+        let body = match self.id {
+            SectionIdentifier::Input | SectionIdentifier::Repeat => {
+                player_inputs as u64 * delta_size
+            }
+            SectionIdentifier::Special => {
+                delta_size
+                    + match self.special_type {
+                        SpecialType::Restart
+                        | SpecialType::RestartFull
+                        | SpecialType::Death
+                        | SpecialType::TPS
+                        | SpecialType::Pause => 8,
+                        SpecialType::PlayerTPS => 1 + 8,
+                        SpecialType::Skip => 0,
+                        SpecialType::Analog => 1 + self.analog_precision.byte_size() as u64,
+                    }
+            }
+            SectionIdentifier::SubFrame => delta_size + 2,
+        };
+        2 + body
+    }
+
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), SectionError> {
+        self.write_inputs(writer, &self.player_inputs)
+    }
+
+    /// Write the section with the given player inputs in place of its own.
+    fn write_inputs<W: Write>(
+        &self,
+        writer: &mut W,
+        player_inputs: &[SectionInput],
+    ) -> Result<(), SectionError> {
         if self.marked_for_removal {
             return Ok(());
         }
@@ -467,12 +622,12 @@ impl Section {
         match self.id {
             SectionIdentifier::Input => {
                 let header = (self.count_exp << 8) | (self.delta_size << 12);
-                writer.write_all(&header.to_le_bytes())?;
+                codec::write(writer, header)?;
 
                 let byte_size = self.real_delta_size();
-                for input in &self.player_inputs {
+                for input in player_inputs {
                     let state = input.prepare_state(byte_size as u8);
-                    write_n_bytes(writer, state, byte_size as usize)?;
+                    codec::write_uint(writer, state, byte_size as usize)?;
                 }
             }
             SectionIdentifier::Repeat => {
@@ -480,72 +635,314 @@ impl Section {
                     | self.delta_size << 12
                     | self.count_exp << 8
                     | self.repeats_exp << 3;
-                writer.write_all(&header.to_le_bytes())?;
+                codec::write(writer, header)?;
 
                 let byte_size = self.real_delta_size();
-                for input in &self.player_inputs {
+                for input in player_inputs {
                     let state = input.prepare_state(byte_size as u8);
-                    write_n_bytes(writer, state, byte_size as usize)?;
+                    codec::write_uint(writer, state, byte_size as usize)?;
                 }
             }
             SectionIdentifier::Special => {
+                let special = self.special.as_ref().unwrap();
+                let attempt = match self.special_type {
+                    SpecialType::Restart | SpecialType::RestartFull | SpecialType::Death => {
+                        special.attempt_kind() as u16
+                    }
+                    _ => 0,
+                };
                 let header = (SectionIdentifier::Special as u16) << 14
                     | (self.special_type as u16) << 10
-                    | (self.delta_size << 8);
-                writer.write_all(&header.to_le_bytes())?;
+                    | (self.delta_size << 8)
+                    | attempt;
+                codec::write(writer, header)?;
 
-                let delta = self.special.as_ref().unwrap().delta();
-                write_n_bytes(writer, delta, self.real_delta_size() as usize)?;
+                let delta = special.delta();
+                codec::write_uint(writer, delta, self.real_delta_size() as usize)?;
 
                 match self.special_type {
-                    SpecialType::Restart | SpecialType::RestartFull | SpecialType::Death => {
-                        writer.write_all(&self.seed.to_le_bytes())?;
+                    SpecialType::Restart
+                    | SpecialType::RestartFull
+                    | SpecialType::Death
+                    | SpecialType::Pause => {
+                        codec::write(writer, self.seed)?;
                     }
                     SpecialType::TPS => {
-                        writer.write_all(&self.tps.to_le_bytes())?;
+                        codec::write(writer, self.tps)?;
+                    }
+                    SpecialType::Skip => {}
+                    SpecialType::PlayerTPS => {
+                        let player2 = self.special.as_ref().unwrap().player2;
+                        writer.write_all(&[player2 as u8])?;
+                        codec::write(writer, self.tps)?;
+                    }
+                    SpecialType::Analog => {
+                        let action = self.special.as_ref().unwrap();
+                        let precision = self.analog_precision;
+                        let flags = ((precision as u8) << 1) | action.player2 as u8;
+                        writer.write_all(&[flags])?;
+                        let bits = precision.encode(action.axis) as u64;
+                        codec::write_uint(writer, bits, precision.byte_size())?;
                     }
                 }
             }
+            SectionIdentifier::SubFrame => {
+                let header = (SectionIdentifier::SubFrame as u16) << 14 | self.delta_size << 12;
+                codec::write(writer, header)?;
+
+                let byte_size = self.real_delta_size();
+                let state = player_inputs[0].prepare_state(byte_size as u8);
+                codec::write_uint(writer, state, byte_size as usize)?;
+                codec::write(writer, self.subframe)?;
+            }
         }
 
         Ok(())
     }
 }
 
+/// Where decoded actions go, along with the state decoding depends on.
+pub(crate) struct ActionOutput<'a> {
+    sink: &'a mut dyn ActionSink,
+    /// Amount of actions decoded so far.
+    pub(crate) count: usize,
+    last_frame: u64,
+    /// Bounds on frames and tps values. The amount of actions is bounded
+    /// separately, before sections are decoded.
+    limits: Limits,
+}
+
+impl<'a> ActionOutput<'a> {
+    /// Decode after `count` actions, the last of which is on `last_frame`.
+    pub(crate) fn new(sink: &'a mut dyn ActionSink, count: usize, last_frame: u64) -> Self {
+        Self {
+            sink,
+            count,
+            last_frame,
+            limits: Limits::default(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn push(&mut self, action: Action) -> Result<(), SectionError> {
+        if action.frame > self.limits.max_frame {
+            return Err(SectionError::LimitExceeded("frame"));
+        }
+        if matches!(action.action_type, ActionType::TPS | ActionType::PlayerTPS)
+            && !self.limits.accepts_tps(action.tps)
+        {
+            return Err(SectionError::LimitExceeded("tps"));
+        }
+
+        self.count += 1;
+        self.last_frame = action.frame;
+        Ok(self.sink.push(action)?)
+    }
+
+    fn push_swift(&mut self, mut action: Action) -> Result<(), SectionError> {
+        action.swift = true;
+        self.push(action)
+    }
+}
+
+/// Drops the frame and delta. Swift clicks become jumps, holding or not
+/// depending on which half of the click the input stands for.
+impl From<&SectionInput> for crate::input::PlayerInput {
+    fn from(input: &SectionInput) -> Self {
+        let button = match input.button {
+            Button::Swift | Button::Jump => crate::input::Button::Jump,
+            Button::Left => crate::input::Button::Left,
+            Button::Right => crate::input::Button::Right,
+        };
+        Self::new(button, input.holding, input.player2)
+    }
+}
+
 fn distribute_inputs_to_sections(
-    sections: &mut Vec<Section>,
-    inputs: &mut Vec<PlayerInput>,
+    sections: &mut Vec<(Section, Range<usize>)>,
+    inputs: Range<usize>,
     delta_size: u16,
 ) {
-    let mut i = 0;
-    while i < inputs.len() {
-        let count = largest_power_of_two(inputs.len() - i);
+    let mut i = inputs.start;
+    while i < inputs.end {
+        let count = largest_power_of_two(inputs.end - i);
         let section = Section {
-            id: SectionIdentifier::Input,
-            delta_size,
-            player_inputs: inputs[i..i + count].to_vec(),
-            marked_for_removal: false,
             count_exp: exponent_of_two(count as u32),
-            repeats_exp: 0,
-            special_type: SpecialType::Restart,
-            seed: 0,
-            tps: 240.0,
-            special: None,
+            ..Section::empty(SectionIdentifier::Input, delta_size)
         };
+        sections.push((section, i..i + count));
         i += count;
-        sections.push(section);
     }
-    inputs.clear();
 }
 
-fn read_n_bytes<R: Read>(reader: &mut R, n: usize) -> Result<u64, SectionError> {
-    let mut buf = [0u8; 8];
-    reader.read_exact(&mut buf[..n])?;
-    Ok(u64::from_le_bytes(buf))
+/// Split `inputs[start..]` into input and repeat sections.
+///
+/// Sections are pushed without their inputs, along with the range of
+/// `inputs` they hold.
+fn run_length_encode_into(
+    inputs: &[SectionInput],
+    start: usize,
+    delta_size: u16,
+    sections: &mut Vec<(Section, Range<usize>)>,
+) {
+    const MAX_CLUSTER_SIZE: usize = 64;
+
+    let n = inputs.len();
+    let mut idx = start;
+    // Inputs not covered by a repeat section are always contiguous.
+    let mut free_start = start;
+
+    while idx < n {
+        let mut found_any_repetitions = false;
+        let mut best_cluster = 0;
+        let mut best_cluster_repetitions = 0;
+        let mut best_cluster_score = 0i64;
+
+        let mut cluster = 1;
+        while cluster <= MAX_CLUSTER_SIZE && cluster <= n - start {
+            if idx + cluster >= n {
+                break;
+            }
+
+            let mut offset = 1;
+            loop {
+                let start = idx + offset * cluster;
+                let end = idx + (offset + 1) * cluster;
+
+                if end > n {
+                    break;
+                }
+
+                let all_equal = (0..cluster).all(|j| inputs[idx + j].weak_eq(&inputs[start + j]));
+
+                if !all_equal {
+                    break;
+                }
+
+                offset += 1;
+            }
+
+            offset = offset.saturating_sub(1);
+            if offset <= 1 {
+                cluster <<= 1;
+                continue;
+            }
+
+            offset = largest_power_of_two(offset);
+
+            let score = (cluster as i64) * ((offset as i64) - 1);
+            if score > best_cluster_score {
+                found_any_repetitions = true;
+                best_cluster_score = score;
+                best_cluster = cluster;
+                best_cluster_repetitions = offset;
+            }
+
+            cluster <<= 1;
+        }
+
+        if found_any_repetitions {
+            distribute_inputs_to_sections(sections, free_start..idx, delta_size);
+
+            let repeat_section = Section {
+                count_exp: exponent_of_two(best_cluster as u32),
+                repeats_exp: exponent_of_two(best_cluster_repetitions as u32),
+                ..Section::empty(SectionIdentifier::Repeat, delta_size)
+            };
+
+            sections.push((repeat_section, idx..idx + best_cluster));
+            idx += best_cluster * best_cluster_repetitions;
+            free_start = idx;
+        } else {
+            idx += 1;
+        }
+    }
+
+    distribute_inputs_to_sections(sections, free_start..n, delta_size);
+}
+
+/// Sections planned against one shared input buffer.
+///
+/// Player sections refer to their inputs by range instead of owning them,
+/// so planning a long macro only grows two buffers.
+#[derive(Default)]
+pub(crate) struct SectionPlan {
+    inputs: Vec<SectionInput>,
+    sections: Vec<(Section, Range<usize>)>,
+}
+
+impl SectionPlan {
+    pub(crate) fn clear(&mut self) {
+        self.inputs.clear();
+        self.sections.clear();
+    }
+
+    /// Add a range of player actions, run-length encoded.
+    pub(crate) fn push_player(&mut self, actions: &[Action], delta_size: u16) {
+        let start = self.inputs.len();
+        self.inputs.extend(
+            actions
+                .iter()
+                .filter(|a| a.holding || !a.swift())
+                .map(SectionInput::from_action),
+        );
+        run_length_encode_into(&self.inputs, start, delta_size, &mut self.sections);
+    }
+
+    pub(crate) fn push_subframe(&mut self, action: &Action) {
+        let start = self.inputs.len();
+        self.inputs.push(SectionInput::from_action(action));
+        self.sections
+            .push((Section::subframe_header(action), start..start + 1));
+    }
+
+    pub(crate) fn push_special(&mut self, section: Section) {
+        let at = self.inputs.len();
+        self.sections.push((section, at..at));
+    }
+
+    /// Amount of bytes [`SectionPlan::write`] writes.
+    pub(crate) fn encoded_size(&self) -> u64 {
+        self.sections
+            .iter()
+            .map(|(section, range)| section.encoded_size_with(range.len()))
+            .sum()
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> Result<(), SectionError> {
+        for (section, range) in &self.sections {
+            section.write_inputs(writer, &self.inputs[range.clone()])?;
+        }
+        Ok(())
+    }
+
+    /// The planned sections, each owning its inputs.
+    pub(crate) fn into_sections(self) -> Vec<Section> {
+        let inputs = self.inputs;
+        self.sections
+            .into_iter()
+            .map(|(section, range)| Section {
+                player_inputs: inputs[range].to_vec(),
+                ..section
+            })
+            .collect()
+    }
+}
+
+fn check_limit(len: usize, additional: u64, limit: usize) -> Result<(), SectionError> {
+    match usize::try_from(additional) {
+        Ok(additional) if additional <= limit.saturating_sub(len) => Ok(()),
+        _ => Err(SectionError::TooManyActions),
+    }
 }
 
-fn write_n_bytes<W: Write>(writer: &mut W, value: u64, n: usize) -> Result<(), SectionError> {
-    let bytes = value.to_le_bytes();
-    writer.write_all(&bytes[..n])?;
-    Ok(())
+fn check_frame(previous_frame: u64, delta: u64) -> Result<(), SectionError> {
+    previous_frame
+        .checked_add(delta)
+        .map(|_| ())
+        .ok_or(SectionError::FrameOverflow)
 }
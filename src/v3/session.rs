@@ -0,0 +1,265 @@
+//! Grouping of actions into attempts, for files recording whole sessions.
+
+use std::io::{Read, Write};
+
+use super::action::{Action, ActionType, AttemptKind};
+use super::atom::{skip, Atom, AtomError, AtomId};
+use super::builtin::ActionAtom;
+use crate::codec;
+
+/// A single attempt of a session.
+///
+/// The attempt spans the half-open frame range `start_frame..end_frame`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub name: String,
+    pub start_frame: u64,
+    pub end_frame: u64,
+    /// Frame the attempt ended in a death on, if it did.
+    pub death_frame: Option<u64>,
+    /// Progress reached in the attempt, in percent.
+    pub percent: f32,
+    pub kind: AttemptKind,
+}
+
+impl Attempt {
+    pub fn contains(&self, frame: u64) -> bool {
+        self.start_frame <= frame && frame < self.end_frame
+    }
+
+    /// The actions of an atom that belong to this attempt.
+    ///
+    /// Actions are expected to be ordered by frame.
+    pub fn actions<'a>(&self, atom: &'a ActionAtom) -> &'a [Action] {
+        let start = atom.actions.partition_point(|a| a.frame < self.start_frame);
+        let end = atom.actions.partition_point(|a| a.frame < self.end_frame);
+        &atom.actions[start..end.max(start)]
+    }
+}
+
+/// Progress through a level, given how long an attempt lasted.
+///
+/// Implemented by [`LevelLength`] for levels played at a constant speed, and
+/// by closures for anything else, such as a table built from x positions.
+pub trait PercentMap {
+    /// Percent reached after playing a given amount of frames from the start
+    /// of an attempt.
+    fn percent_at(&self, frames: u64) -> f32;
+}
+
+/// The amount of frames a full run of a level lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelLength(pub u64);
+
+impl PercentMap for LevelLength {
+    fn percent_at(&self, frames: u64) -> f32 {
+        if self.0 == 0 {
+            return 100.0;
+        }
+        (frames as f64 / self.0 as f64 * 100.0).min(100.0) as f32
+    }
+}
+
+impl<F: Fn(u64) -> f32> PercentMap for F {
+    fn percent_at(&self, frames: u64) -> f32 {
+        self(frames)
+    }
+}
+
+/// Atom listing the attempts of a recording session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionAtom {
+    pub attempts: Vec<Attempt>,
+}
+
+impl SessionAtom {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split the actions of an atom into attempts.
+    ///
+    /// Every restart starts a new attempt, of the kind the restart is tagged
+    /// with. An attempt's death frame is the frame of its last death.
+    /// Percentages are left at 0, since they depend on the level.
+    pub fn from_actions(atom: &ActionAtom) -> Self {
+        let mut attempts = Vec::new();
+        let mut start_frame = 0;
+        let mut death_frame = None;
+        let mut kind = AttemptKind::Normal;
+
+        for action in &atom.actions {
+            match action.action_type {
+                ActionType::Death => death_frame = Some(action.frame),
+                ActionType::Restart | ActionType::RestartFull => {
+                    attempts.push(Attempt {
+                        name: format!("Attempt {}", attempts.len() + 1),
+                        start_frame,
+                        end_frame: action.frame,
+                        death_frame: death_frame.take(),
+                        percent: 0.0,
+                        kind,
+                    });
+                    start_frame = action.frame;
+                    kind = action.attempt_kind();
+                }
+                _ => {}
+            }
+        }
+
+        let end_frame = atom.actions.last().map_or(0, |a| a.frame + 1);
+        if end_frame > start_frame {
+            attempts.push(Attempt {
+                name: format!("Attempt {}", attempts.len() + 1),
+                start_frame,
+                end_frame,
+                death_frame,
+                percent: 0.0,
+                kind,
+            });
+        }
+
+        Self { attempts }
+    }
+
+    /// The attempts overlapping frames `start..end`, cut to the window and
+    /// moved to start on frame 0.
+    ///
+    /// Deaths outside of the window are forgotten. Attempts cut short lose
+    /// their percent, since how far they got within the window is unknown.
+    pub fn extract_range(&self, start: u64, end: u64) -> Self {
+        let attempts = self
+            .attempts
+            .iter()
+            .filter(|a| a.start_frame < end && start < a.end_frame)
+            .map(|a| {
+                let cut = a.start_frame < start || a.end_frame > end;
+                Attempt {
+                    name: a.name.clone(),
+                    start_frame: a.start_frame.max(start) - start,
+                    end_frame: a.end_frame.min(end) - start,
+                    death_frame: a
+                        .death_frame
+                        .filter(|f| (start..end).contains(f))
+                        .map(|f| f - start),
+                    percent: if cut { 0.0 } else { a.percent },
+                    kind: a.kind,
+                }
+            })
+            .collect();
+        Self { attempts }
+    }
+
+    /// The attempt a frame belongs to.
+    pub fn attempt_at(&self, frame: u64) -> Option<&Attempt> {
+        self.attempts.iter().find(|a| a.contains(frame))
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Attempt> {
+        self.attempts.iter().find(|a| a.name == name)
+    }
+
+    /// Set the percent reached by every attempt.
+    ///
+    /// An attempt reaches the frame it died on, or its end if it didn't
+    /// die. Frames are counted from the start of the attempt, so attempts
+    /// recorded from a start position or using checkpoints need a map
+    /// accounting for where they started.
+    pub fn annotate_percent<P: PercentMap>(&mut self, map: &P) {
+        for attempt in &mut self.attempts {
+            let reached = attempt
+                .death_frame
+                .unwrap_or(attempt.end_frame)
+                .clamp(attempt.start_frame, attempt.end_frame);
+            attempt.percent = map.percent_at(reached - attempt.start_frame);
+        }
+    }
+
+    /// The best percent reached so far, after each attempt.
+    ///
+    /// Practice attempts don't count towards the best, and only repeat it.
+    pub fn best_progress(&self) -> Vec<f32> {
+        let mut best = 0.0f32;
+        self.attempts
+            .iter()
+            .map(|attempt| {
+                if attempt.kind != AttemptKind::Practice {
+                    best = best.max(attempt.percent);
+                }
+                best
+            })
+            .collect()
+    }
+
+    /// The attempt that reached the highest percentage.
+    ///
+    /// Ties are resolved in favor of the earliest attempt.
+    pub fn best(&self) -> Option<&Attempt> {
+        self.attempts
+            .iter()
+            .rev()
+            .max_by(|a, b| a.percent.total_cmp(&b.percent))
+    }
+}
+
+impl Atom for SessionAtom {
+    const ID: AtomId = AtomId::Session;
+
+    fn size(&self) -> usize {
+        8 + self
+            .attempts
+            .iter()
+            .map(|a| 2 + a.name.len().min(u16::MAX as usize) + 8 * 3 + 4 + 1)
+            .sum::<usize>()
+    }
+
+    /// Attempts running past `size` are an error, and bytes left after the
+    /// last attempt are skipped.
+    fn read<R: Read>(reader: &mut R, size: usize) -> Result<Self, AtomError> {
+        let reader = &mut reader.take(size as u64);
+        let count: u64 = codec::read(reader)?;
+        let mut attempts = Vec::new();
+
+        for _ in 0..count {
+            let mut name = vec![0u8; codec::read::<u16, _>(reader)? as usize];
+            reader.read_exact(&mut name)?;
+
+            let start_frame: u64 = codec::read(reader)?;
+            let end_frame: u64 = codec::read(reader)?;
+            let death_frame = Some(codec::read(reader)?).filter(|&f| f != u64::MAX);
+
+            let percent: f32 = codec::read(reader)?;
+            let mut kind = [0u8; 1];
+            reader.read_exact(&mut kind)?;
+
+            attempts.push(Attempt {
+                name: String::from_utf8_lossy(&name).into_owned(),
+                start_frame,
+                end_frame,
+                death_frame,
+                percent,
+                kind: AttemptKind::from_bits(kind[0] as u16).unwrap_or_default(),
+            });
+        }
+
+        skip(reader, reader.limit())?;
+        Ok(Self { attempts })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), AtomError> {
+        codec::write(writer, self.attempts.len() as u64)?;
+
+        for attempt in &self.attempts {
+            let name = &attempt.name.as_bytes()[..attempt.name.len().min(u16::MAX as usize)];
+            codec::write(writer, name.len() as u16)?;
+            writer.write_all(name)?;
+            codec::write(writer, attempt.start_frame)?;
+            codec::write(writer, attempt.end_frame)?;
+            codec::write(writer, attempt.death_frame.unwrap_or(u64::MAX))?;
+            codec::write(writer, attempt.percent)?;
+            writer.write_all(&[attempt.kind as u8])?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+//! Destinations for decoded actions.
+//!
+//! Reading an action atom normally collects its actions in a `Vec`. For
+//! recordings too long to hold in memory, [`crate::v3::Replay::read_into`]
+//! streams them into an [`ActionSink`] instead, such as a [`SpillFile`]
+//! keeping them on disk.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use super::action::{Action, ActionType, AttemptKind};
+use crate::codec;
+use crate::replay::MAX_PREALLOCATION;
+
+/// Receives actions as they are decoded.
+pub trait ActionSink {
+    /// Take the next action.
+    fn push(&mut self, action: Action) -> io::Result<()>;
+
+    /// Hint that about `additional` more actions are coming.
+    ///
+    /// The amount is read from the file, so it isn't trusted.
+    fn reserve(&mut self, _additional: usize) {}
+}
+
+impl ActionSink for Vec<Action> {
+    fn push(&mut self, action: Action) -> io::Result<()> {
+        Vec::push(self, action);
+        Ok(())
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional.min(MAX_PREALLOCATION as usize));
+    }
+}
+
+/// Passes every action to a closure.
+pub struct FnSink<F>(pub F);
+
+impl<F: FnMut(Action) -> io::Result<()>> ActionSink for FnSink<F> {
+    fn push(&mut self, action: Action) -> io::Result<()> {
+        (self.0)(action)
+    }
+}
+
+const RECORD_SIZE: usize = 40;
+const BUFFERED_RECORDS: usize = 1024;
+
+fn encode(action: &Action, record: &mut [u8; RECORD_SIZE]) {
+    let flags = action.holding as u8
+        | (action.player2 as u8) << 1
+        | (action.swift() as u8) << 2
+        | (action.attempt_kind() as u8) << 3;
+
+    codec::encode(action.frame, &mut record[0..8]);
+    codec::encode(action.delta(), &mut record[8..16]);
+    record[16] = action.action_type as u8;
+    record[17] = flags;
+    codec::encode(action.subframe, &mut record[18..20]);
+    codec::encode(action.seed, &mut record[20..28]);
+    codec::encode(action.tps, &mut record[28..36]);
+    codec::encode(action.axis, &mut record[36..40]);
+}
+
+fn decode(record: &[u8; RECORD_SIZE]) -> io::Result<Action> {
+    let frame = codec::decode::<u64>(&record[0..8]);
+    let delta = codec::decode::<u64>(&record[8..16]);
+    let action_type = match record[16] {
+        0 => ActionType::Reserved,
+        1 => ActionType::Jump,
+        2 => ActionType::Left,
+        3 => ActionType::Right,
+        4 => ActionType::Restart,
+        5 => ActionType::RestartFull,
+        6 => ActionType::Death,
+        7 => ActionType::TPS,
+        8 => ActionType::Analog,
+        9 => ActionType::PlayerTPS,
+        10 => ActionType::Pause,
+        11 => ActionType::Skip,
+        _ => return Err(io::Error::from(io::ErrorKind::InvalidData)),
+    };
+    let flags = record[17];
+    let attempt = AttemptKind::from_bits((flags >> 3) as u16)
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+
+    let mut action = Action::player(
+        frame.wrapping_sub(delta),
+        delta,
+        action_type,
+        flags & 1 != 0,
+        flags & 2 != 0,
+    );
+    action.frame = frame;
+    action.swift = flags & 4 != 0;
+    action.set_attempt_kind(attempt);
+    action.subframe = codec::decode::<u16>(&record[18..20]);
+    action.seed = codec::decode::<u64>(&record[20..28]);
+    action.tps = codec::decode::<f64>(&record[28..36]);
+    action.axis = codec::decode::<f32>(&record[36..40]);
+    Ok(action)
+}
+
+/// Actions kept in a file instead of memory.
+///
+/// Each action takes a fixed 40 bytes in the file, and only a small write
+/// buffer is held in memory. The file's previous contents are overwritten.
+pub struct SpillFile<F = File> {
+    file: F,
+    buffer: Vec<u8>,
+    /// Amount of actions written to the file, excluding the buffer.
+    flushed: u64,
+}
+
+impl SpillFile<File> {
+    /// Create or truncate a file to spill actions to.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<F: Read + Write + Seek> SpillFile<F> {
+    pub fn new(file: F) -> Self {
+        Self {
+            file,
+            buffer: Vec::new(),
+            flushed: 0,
+        }
+    }
+
+    /// Amount of actions stored.
+    pub fn len(&self) -> u64 {
+        self.flushed + (self.buffer.len() / RECORD_SIZE) as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn flush_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.file
+            .seek(SeekFrom::Start(self.flushed * RECORD_SIZE as u64))?;
+        self.file.write_all(&self.buffer)?;
+        self.flushed += (self.buffer.len() / RECORD_SIZE) as u64;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Read the stored actions back, in order.
+    pub fn iter(&mut self) -> io::Result<SpillIter<'_, F>> {
+        self.flush_buffer()?;
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(SpillIter {
+            reader: BufReader::new(&mut self.file),
+            remaining: self.flushed,
+        })
+    }
+
+    /// Flush the buffered actions and return the file.
+    pub fn into_inner(mut self) -> io::Result<F> {
+        self.flush_buffer()?;
+        self.file.flush()?;
+        Ok(self.file)
+    }
+}
+
+impl<F: Read + Write + Seek> ActionSink for SpillFile<F> {
+    fn push(&mut self, action: Action) -> io::Result<()> {
+        let mut record = [0u8; RECORD_SIZE];
+        encode(&action, &mut record);
+        self.buffer.extend_from_slice(&record);
+        if self.buffer.len() >= BUFFERED_RECORDS * RECORD_SIZE {
+            self.flush_buffer()?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the actions of a [`SpillFile`].
+pub struct SpillIter<'a, F> {
+    reader: BufReader<&'a mut F>,
+    remaining: u64,
+}
+
+impl<F: Read> Iterator for SpillIter<'_, F> {
+    type Item = io::Result<Action>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let mut record = [0u8; RECORD_SIZE];
+        Some(
+            self.reader
+                .read_exact(&mut record)
+                .and_then(|()| decode(&record)),
+        )
+    }
+}
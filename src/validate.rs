@@ -0,0 +1,148 @@
+//! Validation of decoded inputs.
+
+use crate::{
+    input::{Input, InputData, PlayerInput},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// The kind of contradiction a [`Conflict`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// A button was pressed and released on the same frame.
+    ///
+    /// Note that slc3 swift clicks are exactly this, so replays read from
+    /// slc3 files may legitimately contain them.
+    SameFramePressRelease,
+    /// A button was pressed while it was already held.
+    DuplicatePress,
+    /// A button was released while it wasn't held.
+    OrphanRelease,
+}
+
+/// A contradictory input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    /// Index of the offending input.
+    pub index: usize,
+    pub frame: u64,
+    pub button: u8,
+    pub player_2: bool,
+}
+
+/// How [`resolve_conflicts`] deals with the conflicts it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Resolution {
+    /// Only report conflicts, leaving the inputs untouched.
+    #[default]
+    ReportOnly,
+    /// Remove every offending input.
+    ///
+    /// Same-frame clicks are removed entirely, press included.
+    Drop,
+    /// Rewrite the inputs into an equivalent valid sequence.
+    ///
+    /// Same-frame releases are delayed by one frame, duplicate presses get
+    /// a release inserted right before them and orphan releases are removed.
+    Repair,
+}
+
+/// Find contradictory player inputs.
+///
+/// Restarts release every held button, matching how the game behaves.
+pub fn find_conflicts(inputs: &[Input]) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut held = [[None::<u64>; 4]; 2];
+
+    for (index, input) in inputs.iter().enumerate() {
+        match &input.data {
+            InputData::Player(p) => {
+                let slot = &mut held[p.player_2 as usize][(p.button as usize).min(3)];
+                let kind = match (p.hold, *slot) {
+                    (true, Some(_)) => Some(ConflictKind::DuplicatePress),
+                    (true, None) => {
+                        *slot = Some(input.frame);
+                        None
+                    }
+                    (false, None) => Some(ConflictKind::OrphanRelease),
+                    (false, Some(press)) => {
+                        *slot = None;
+                        (press == input.frame).then_some(ConflictKind::SameFramePressRelease)
+                    }
+                };
+
+                if let Some(kind) = kind {
+                    conflicts.push(Conflict {
+                        kind,
+                        index,
+                        frame: input.frame,
+                        button: p.button,
+                        player_2: p.player_2,
+                    });
+                }
+            }
+            InputData::Restart | InputData::RestartFull => held = [[None; 4]; 2],
+            _ => {}
+        }
+    }
+
+    conflicts
+}
+
+/// Find contradictory player inputs and resolve them according to a policy.
+///
+/// Returns the conflicts found before resolution.
+pub fn resolve_conflicts<M: Meta>(replay: &mut Replay<M>, resolution: Resolution) -> Vec<Conflict> {
+    let conflicts = find_conflicts(&replay.inputs);
+    if conflicts.is_empty() || resolution == Resolution::ReportOnly {
+        return conflicts;
+    }
+
+    let mut inputs = Vec::with_capacity(replay.inputs.len() + conflicts.len());
+    let mut pending = conflicts.iter().peekable();
+
+    for (index, mut input) in std::mem::take(&mut replay.inputs).into_iter().enumerate() {
+        let Some(conflict) = pending.next_if(|c| c.index == index) else {
+            inputs.push(input);
+            continue;
+        };
+
+        match (resolution, conflict.kind) {
+            (Resolution::Repair, ConflictKind::SameFramePressRelease) => {
+                input.frame += 1;
+                inputs.push(input);
+            }
+            (Resolution::Repair, ConflictKind::DuplicatePress) => {
+                inputs.push(Input {
+                    delta: 0,
+                    frame: input.frame,
+                    data: InputData::Player(PlayerInput {
+                        hold: false,
+                        player_2: conflict.player_2,
+                        button: conflict.button,
+                    }),
+                });
+                inputs.push(input);
+            }
+            (Resolution::Drop, ConflictKind::SameFramePressRelease) => {
+                // The click does nothing, so its press goes as well.
+                let press = InputData::Player(PlayerInput {
+                    hold: true,
+                    player_2: conflict.player_2,
+                    button: conflict.button,
+                });
+                if let Some(i) = inputs.iter().rposition(|i| i.data == press) {
+                    inputs.remove(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    inputs.sort_by_key(|i| i.frame);
+    replay.inputs = inputs;
+    replay.recalculate_deltas();
+
+    conflicts
+}
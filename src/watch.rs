@@ -0,0 +1,121 @@
+//! Live reloading of replay files.
+//!
+//! Available with the `notify` feature. [`watch`] reads a replay again every
+//! time its file changes, so a visualizer can follow a macro while a bot is
+//! still writing it.
+//!
+//! ```no_run
+//! use slc_oxide::watch::watch;
+//!
+//! let watcher = watch::<(), _>("replay.slc", |replay| match replay {
+//!     Ok(replay) => println!("{} inputs", replay.inputs.len()),
+//!     Err(e) => eprintln!("reload failed: {e}"),
+//! })?;
+//! // The file is watched until `watcher` is dropped.
+//! # Ok::<(), slc_oxide::watch::WatchError>(())
+//! ```
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use thiserror::Error;
+
+use crate::{
+    meta::Meta,
+    replay::{ReadOptions, Replay, ReplayError},
+};
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("watch error: {0}")]
+    Notify(#[from] notify::Error),
+    #[error("IO error: {0}")]
+    IOError(#[from] std::io::Error),
+    #[error("Replay error: {0}")]
+    Replay(#[from] ReplayError),
+}
+
+/// A watched replay file. Watching stops when it is dropped.
+pub struct Watcher {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+}
+
+impl Watcher {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Read a replay again every time its file changes, see [`watch_with_options`].
+pub fn watch<M, F>(path: impl AsRef<Path>, callback: F) -> Result<Watcher, WatchError>
+where
+    M: Meta + Send + 'static,
+    F: FnMut(Result<Replay<M>, WatchError>) + Send + 'static,
+{
+    watch_with_options(path, ReadOptions::default(), callback)
+}
+
+/// Read a replay of either slc version again every time its file changes,
+/// within the given limits.
+///
+/// The callback runs on a background thread. It gets errors as well, such
+/// as when the file is read while half written, and is called again once
+/// the writer is done. The directory holding the file is watched, so files
+/// replaced by renaming a new one over them are followed too. Changes that
+/// leave the file's bytes as they were aren't reported.
+pub fn watch_with_options<M, F>(
+    path: impl AsRef<Path>,
+    options: ReadOptions,
+    mut callback: F,
+) -> Result<Watcher, WatchError>
+where
+    M: Meta + Send + 'static,
+    F: FnMut(Result<Replay<M>, WatchError>) + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let file = path.clone();
+    let mut last: Option<Vec<u8>> = fs::read(&path).ok();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => return callback(Err(e.into())),
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            || !event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == file.file_name())
+        {
+            return;
+        }
+
+        let bytes = match fs::read(&file) {
+            Ok(bytes) => bytes,
+            Err(e) => return callback(Err(e.into())),
+        };
+        if last.as_ref() == Some(&bytes) {
+            return;
+        }
+        let replay = Replay::read_with_options(&mut Cursor::new(&bytes), &options);
+        if replay.is_ok() {
+            last = Some(bytes);
+        }
+        callback(replay.map_err(WatchError::from));
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+
+    Ok(Watcher {
+        path,
+        _watcher: watcher,
+    })
+}
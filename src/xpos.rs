@@ -0,0 +1,102 @@
+//! Position-keyed inputs.
+//!
+//! Some legacy bots anchor inputs to the player's x position instead of a
+//! frame. This module stores such inputs as they are and converts them from
+//! and to frame-keyed inputs, given where the player was on every frame.
+
+use crate::{
+    input::{Input, InputData},
+    meta::Meta,
+    replay::Replay,
+};
+
+/// An input anchored to the x position of player 1.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionInput {
+    pub x: f64,
+    pub data: InputData,
+}
+
+/// The x position of player 1 on every frame of an attempt.
+///
+/// Positions are expected to never decrease, which holds for any level
+/// without reverse gameplay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PositionMap {
+    positions: Vec<f64>,
+}
+
+impl PositionMap {
+    /// Create a map from the position on each frame, starting at frame 0.
+    pub fn new(positions: Vec<f64>) -> Self {
+        Self { positions }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The position on a frame, if the map covers it.
+    pub fn position_at(&self, frame: u64) -> Option<f64> {
+        self.positions.get(frame as usize).copied()
+    }
+
+    /// The first frame the player reaches the given position on.
+    ///
+    /// Returns `None` if the player never gets there.
+    pub fn frame_at(&self, x: f64) -> Option<u64> {
+        let frame = self.positions.partition_point(|&p| p < x);
+        (frame < self.positions.len()).then_some(frame as u64)
+    }
+}
+
+/// Anchor frame-keyed inputs to positions.
+///
+/// Inputs on frames the map doesn't cover are returned as errors, by index.
+pub fn to_positions(inputs: &[Input], map: &PositionMap) -> Result<Vec<PositionInput>, usize> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let x = map.position_at(input.frame).ok_or(i)?;
+            Ok(PositionInput {
+                x,
+                data: input.data.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Convert position-keyed inputs into a frame-keyed replay.
+///
+/// Each input lands on the first frame the player reaches its position on.
+/// Inputs are stably sorted by frame, so they don't need to be in order.
+/// Inputs whose position is never reached are returned as errors, by index.
+///
+/// Converting inputs produced by [`to_positions`] back gives the original
+/// frames, as long as the player moves on every frame.
+pub fn to_replay<M: Meta>(
+    inputs: &[PositionInput],
+    map: &PositionMap,
+    tps: f64,
+    meta: M,
+) -> Result<Replay<M>, usize> {
+    let mut keyed = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| Ok((map.frame_at(input.x).ok_or(i)?, input.data.clone())))
+        .collect::<Result<Vec<_>, usize>>()?;
+    keyed.sort_by_key(|(frame, _)| *frame);
+
+    let mut replay = Replay::new(tps, meta);
+    for (frame, data) in keyed {
+        replay.add_input(frame, data);
+    }
+
+    Ok(replay)
+}
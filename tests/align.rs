@@ -0,0 +1,80 @@
+use slc_oxide::align::{align, align_replays, AlignOp, AlignOptions};
+use slc_oxide::testing::{generate_replay, Profile};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn press(hold: bool, button: u8) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button,
+    })
+}
+
+#[test]
+fn test_align_edits() {
+    let mut left = Replay::<()>::new(240.0, ());
+    left.add_input(10, press(true, 1));
+    left.add_input(20, press(false, 1));
+    left.add_input(30, press(true, 2));
+    left.add_input(40, press(false, 2));
+    left.add_input(50, InputData::Death);
+
+    let mut right = Replay::<()>::new(240.0, ());
+    right.add_input(10, press(true, 1));
+    right.add_input(22, press(false, 1));
+    right.add_input(32, press(true, 2));
+    right.add_input(42, press(false, 2));
+    right.add_input(45, press(true, 3));
+    right.add_input(52, InputData::Death);
+
+    let alignment = align_replays(&left, &right, &AlignOptions::default());
+    assert_eq!(
+        alignment.ops,
+        vec![
+            AlignOp::Match { left: 0, right: 0 },
+            AlignOp::Shifted { left: 1, right: 1 },
+            AlignOp::Match { left: 2, right: 2 },
+            AlignOp::Match { left: 3, right: 3 },
+            AlignOp::Inserted { right: 4 },
+            AlignOp::Shifted { left: 4, right: 5 },
+        ]
+    );
+    assert_eq!(alignment.distance, 3);
+    assert_eq!(alignment.pairs().count(), 5);
+    assert_eq!(alignment.similarity(), 1.0 - 3.0 / 11.0);
+
+    let reversed = align(&right.inputs, &left.inputs, &AlignOptions { band: 0 });
+    assert_eq!(reversed.distance, 3);
+    assert!(reversed.ops.contains(&AlignOp::Deleted { left: 4 }));
+
+    let empty = align(&[], &left.inputs, &AlignOptions::default());
+    assert_eq!(empty.distance, 5);
+    assert_eq!(empty.similarity(), 0.0);
+    assert_eq!(align(&[], &[], &AlignOptions::default()).similarity(), 1.0);
+}
+
+#[test]
+fn test_align_rerecord() {
+    let original = generate_replay(5, &Profile::default().with_frames(240 * 30));
+    let mut rerecord = original.clone();
+    let middle = rerecord.inputs.len() / 2;
+    rerecord.inputs.drain(middle..middle + 6);
+    rerecord.recalculate_deltas();
+
+    let identical = align_replays(&original, &original, &AlignOptions::default());
+    assert_eq!(identical.distance, 0);
+    assert!(identical
+        .ops
+        .iter()
+        .all(|op| matches!(op, AlignOp::Match { .. })));
+
+    let alignment = align_replays(&original, &rerecord, &AlignOptions::default());
+    let deleted = alignment
+        .ops
+        .iter()
+        .filter(|op| matches!(op, AlignOp::Deleted { .. }))
+        .count();
+    assert_eq!(deleted, 6);
+    assert!(alignment.distance <= 7);
+    assert_eq!(alignment.left_len, original.inputs.len());
+}
@@ -0,0 +1,226 @@
+use slc_oxide::analysis::heuristics::{analyze, FindingKind, HeuristicsOptions};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn click(replay: &mut Replay<()>, frame: u64, length: u64, player_2: bool) {
+    replay.add_input(
+        frame,
+        InputData::Player(PlayerInput {
+            hold: true,
+            player_2,
+            button: 1,
+        }),
+    );
+    replay.add_input(
+        frame + length,
+        InputData::Player(PlayerInput {
+            hold: false,
+            player_2,
+            button: 1,
+        }),
+    );
+}
+
+#[test]
+fn test_heuristics_flags_bot_patterns() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    for i in 0..40 {
+        click(&mut replay, i * 4, 1, false);
+    }
+
+    let report = analyze(&replay, &HeuristicsOptions::default());
+
+    let regular: Vec<_> = report.of_kind(FindingKind::RegularDeltas).collect();
+    assert_eq!(regular.len(), 1);
+    assert_eq!(regular[0].value, 39.0);
+    assert_eq!(regular[0].start_frame, 0);
+    assert_eq!(regular[0].end_frame, 156);
+
+    // 60 presses per second at 240 tps.
+    let bursts: Vec<_> = report.of_kind(FindingKind::CpsBurst).collect();
+    assert_eq!(bursts.len(), 1);
+    assert_eq!(bursts[0].value, 40.0);
+
+    let streaks: Vec<_> = report.of_kind(FindingKind::FramePerfectStreak).collect();
+    assert_eq!(streaks.len(), 1);
+    assert_eq!(streaks[0].value, 40.0);
+
+    assert_eq!(report.score(), 1.0);
+}
+
+#[test]
+fn test_heuristics_invalid_tps() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(0, InputData::TPS(0.0));
+    for i in 0..40 {
+        click(&mut replay, i * 4, 1, false);
+    }
+
+    // Without timing there are no bursts, the other heuristics still run.
+    let report = analyze(&replay, &HeuristicsOptions::default());
+    assert_eq!(report.of_kind(FindingKind::CpsBurst).count(), 0);
+    assert_eq!(report.of_kind(FindingKind::RegularDeltas).count(), 1);
+}
+
+#[test]
+fn test_heuristics_ignores_human_patterns() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    let gaps = [37, 52, 41, 66, 48, 39, 71, 45, 58, 62];
+    let mut frame = 0;
+    for (i, gap) in gaps.iter().enumerate() {
+        frame += gap;
+        click(&mut replay, frame, 3 + (i as u64 % 4), i % 3 == 0);
+    }
+
+    let report = analyze(&replay, &HeuristicsOptions::default());
+    assert!(report.findings.is_empty());
+    assert_eq!(report.score(), 0.0);
+}
+
+#[test]
+fn test_session_stats() {
+    use slc_oxide::analysis::session::SessionStats;
+    use slc_oxide::v3::builtin::ActionAtom;
+    use slc_oxide::v3::{ActionType, AttemptKind};
+
+    let mut atom = ActionAtom::new();
+    atom.add_death_action(100, ActionType::Death, 0).unwrap();
+    atom.add_death_action(110, ActionType::Restart, 0).unwrap();
+    atom.add_death_action(160, ActionType::Death, 0).unwrap();
+    atom.add_death_action(170, ActionType::Restart, 0).unwrap();
+    atom.actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Practice);
+    atom.add_death_action(500, ActionType::Death, 0).unwrap();
+    atom.add_death_action(510, ActionType::RestartFull, 0)
+        .unwrap();
+    atom.add_player_action(700, ActionType::Jump, true, false)
+        .unwrap();
+
+    let stats = SessionStats::new(&atom, 64);
+    assert_eq!(stats.attempts, 4);
+    assert_eq!(stats.total_frames, 701);
+    assert_eq!(stats.average_length, 701.0 / 4.0);
+    assert_eq!(stats.deaths, 3);
+    // Deaths 100, 50 and 330 frames into their attempts.
+    assert_eq!(stats.death_buckets, vec![1, 1, 0, 0, 0, 1]);
+    assert_eq!(stats.deadliest_bucket(), Some((0, 1)));
+    assert_eq!(stats.improvement, vec![100, 100, 100, 191]);
+
+    let empty = SessionStats::new(&ActionAtom::new(), 0);
+    assert_eq!(empty.attempts, 0);
+    assert_eq!(empty.bucket_size, 1);
+    assert_eq!(empty.deadliest_bucket(), None);
+}
+
+#[test]
+fn test_death_heatmap() {
+    use slc_oxide::analysis::death_heatmap;
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(120, InputData::Death);
+    replay.add_input(130, InputData::Restart);
+    replay.add_input(250, InputData::Death);
+    replay.add_input(250, InputData::RestartFull);
+    replay.add_input(250, InputData::TPS(480.0));
+    replay.add_input(730, InputData::Death);
+
+    // The first deaths are half a second into their attempts, the last one a
+    // second, counted at 480 tps.
+    assert_eq!(death_heatmap(&replay, 0.25), vec![0, 0, 2, 0, 1]);
+    assert_eq!(death_heatmap(&replay, 2.0), vec![3]);
+    assert!(death_heatmap(&replay, 0.0).is_empty());
+    assert!(death_heatmap(&Replay::<()>::new(240.0, ()), 1.0).is_empty());
+}
+
+#[test]
+fn test_hold_stats() {
+    use slc_oxide::analysis::hold_stats;
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    // Eight one-frame clicks, four frames apart.
+    for i in 0..8 {
+        click(&mut replay, i * 4, 1, false);
+    }
+    click(&mut replay, 100, 10, false);
+    click(&mut replay, 300, 25, false);
+    click(&mut replay, 400, 3, true);
+    replay.add_input(
+        500,
+        InputData::Player(PlayerInput {
+            hold: true,
+            player_2: false,
+            button: 1,
+        }),
+    );
+    replay.add_input(520, InputData::Restart);
+
+    let stats = hold_stats(&replay, false, 8);
+    assert_eq!(stats.presses, 11);
+    assert_eq!(stats.histogram, vec![8, 1, 0, 1]);
+    assert_eq!(stats.max_cps, 9);
+    assert_eq!(stats.max_cps_frame, 0);
+    assert_eq!(stats.longest_frame_perfect_streak, 8);
+
+    let player_2 = hold_stats(&replay, true, 0);
+    assert_eq!(player_2.presses, 1);
+    assert_eq!(player_2.bucket, 1);
+    assert_eq!(player_2.histogram, vec![0, 0, 0, 1]);
+    assert_eq!(player_2.max_cps, 1);
+    assert_eq!(player_2.longest_frame_perfect_streak, 0);
+}
+
+#[test]
+fn test_player_coordination() {
+    use slc_oxide::analysis::heuristics::coordination;
+
+    let build = |presses: &[(u64, bool)]| {
+        let mut events: Vec<_> = presses
+            .iter()
+            .flat_map(|&(frame, p2)| [(frame, true, p2), (frame + 5, false, p2)])
+            .collect();
+        events.sort_by_key(|e| e.0);
+        let mut replay = Replay::<()>::new(240.0, ());
+        for (frame, hold, player_2) in events {
+            let data = PlayerInput {
+                hold,
+                player_2,
+                button: 1,
+            };
+            replay.add_input(frame, InputData::Player(data));
+        }
+        replay
+    };
+
+    let frames: Vec<u64> = (0..12).map(|i| 100 + i * 37).collect();
+    let synced = build(
+        &frames
+            .iter()
+            .flat_map(|&f| [(f, false), (f, true)])
+            .collect::<Vec<_>>(),
+    );
+    let offset = build(
+        &frames
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &f)| [(f, false), (f + 1 + i as u64 % 3, true)])
+            .collect::<Vec<_>>(),
+    );
+
+    let report = analyze(&synced, &HeuristicsOptions::default());
+    let findings: Vec<_> = report.of_kind(FindingKind::SynchronizedPlayers).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].value, 1.0);
+    assert_eq!((findings[0].start_frame, findings[0].end_frame), (100, 507));
+
+    let measured = coordination(&offset, 2);
+    assert_eq!(measured.player_1_presses, 12);
+    assert_eq!(measured.player_2_presses, 12);
+    assert_eq!(measured.offsets, vec![0, 0, 0, 4, 4]);
+    assert_eq!(measured.matched(), 8);
+    assert_eq!(measured.same_frame_ratio(), 0.0);
+    let report = analyze(&offset, &HeuristicsOptions::default());
+    assert_eq!(report.of_kind(FindingKind::SynchronizedPlayers).count(), 0);
+
+    assert_eq!(coordination(&Replay::<()>::new(240.0, ()), 4).matched(), 0);
+}
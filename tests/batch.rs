@@ -0,0 +1,88 @@
+use slc_oxide::batch::{convert_dir, BatchOptions};
+use slc_oxide::diff::diff_files;
+use slc_oxide::replay::Version;
+use std::path::PathBuf;
+
+#[test]
+fn test_batch_convert_dir() {
+    let macro_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("macros");
+    let out_dir = std::env::temp_dir().join("slc_oxide_batch_test");
+    let _ = std::fs::remove_dir_all(&out_dir);
+
+    let options = BatchOptions {
+        threads: 2,
+        ..Default::default()
+    };
+    let report = convert_dir(&macro_dir, &out_dir, Version::V2, &options).unwrap();
+
+    assert!(report.is_success());
+    assert_eq!(report.converted.len(), 3);
+    for converted in &report.converted {
+        let header = std::fs::read(converted).unwrap();
+        assert_eq!(Version::detect(&header), Some(Version::V2));
+
+        let original = macro_dir.join(converted.file_name().unwrap());
+        assert!(diff_files(&original, converted).unwrap().is_identical());
+    }
+
+    let options = BatchOptions {
+        overwrite: false,
+        ..Default::default()
+    };
+    let report = convert_dir(&macro_dir, &out_dir, Version::V2, &options).unwrap();
+    assert_eq!(report.failed.len(), 3);
+
+    // Converted files take the extension of their format.
+    let report = convert_dir(&macro_dir, &out_dir, Version::V3, &options).unwrap();
+    assert!(report.is_success());
+    for converted in &report.converted {
+        assert_eq!(converted.extension().unwrap(), "slc3");
+        let header = std::fs::read(converted).unwrap();
+        assert_eq!(Version::detect(&header), Some(Version::V3));
+    }
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_read_many() {
+    use slc_oxide::batch::{read_many, ReadPool};
+    use slc_oxide::{ReadOptions, Replay};
+
+    let macro_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("macros");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&macro_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "slc"))
+        .collect();
+    paths.sort();
+    paths.insert(1, macro_dir.join("missing.slc"));
+
+    let results = read_many::<Vec<u8>, _>(&paths);
+    assert_eq!(results.len(), paths.len());
+    assert!(results[1].is_err());
+    for (path, result) in paths.iter().zip(&results) {
+        if let Ok(replay) = result {
+            let expected =
+                Replay::<Vec<u8>>::read(&mut std::fs::File::open(path).unwrap()).unwrap();
+            assert_eq!(replay, &expected);
+        }
+    }
+    assert_eq!(
+        results.iter().filter(|r| r.is_ok()).count(),
+        paths.len() - 1
+    );
+
+    let mut pool = ReadPool::new(2).with_read_options(ReadOptions {
+        max_inputs: 1,
+        ..ReadOptions::untrusted()
+    });
+    for _ in 0..2 {
+        let results = pool.read_many::<Vec<u8>, _>(&paths);
+        assert!(results.iter().all(|r| r.is_err()));
+    }
+}
@@ -0,0 +1,128 @@
+use slc_oxide::blob::{Blob, BlobPlanner};
+use slc_oxide::testing::{generate_replay, Profile};
+use slc_oxide::{Input, InputData, Replay};
+use std::io::Cursor;
+
+fn blob(byte_size: u64, start: u64, length: u64) -> Blob {
+    Blob {
+        byte_size,
+        start,
+        length,
+    }
+}
+
+fn input(frame: u64, delta: u64) -> Input {
+    Input::new(frame, delta, InputData::Death)
+}
+
+#[test]
+fn test_merge_rules() {
+    let planner = BlobPlanner::new();
+
+    // Same byte size always merges.
+    assert_eq!(
+        planner.merged_byte_size(&blob(2, 0, 100), &blob(2, 100, 100)),
+        Some(2)
+    );
+    // A small narrow blob joins a wider one.
+    assert_eq!(
+        planner.merged_byte_size(&blob(4, 0, 100), &blob(1, 100, 3)),
+        Some(4)
+    );
+    // A small wide blob widens a narrower one.
+    assert_eq!(
+        planner.merged_byte_size(&blob(1, 0, 100), &blob(2, 100, 3)),
+        Some(2)
+    );
+    // Large blobs stay on their own.
+    assert_eq!(
+        planner.merged_byte_size(&blob(1, 0, 100), &blob(2, 100, 50)),
+        None
+    );
+    assert_eq!(
+        planner.merged_byte_size(&blob(8, 0, 100), &blob(1, 100, 3)),
+        None
+    );
+
+    let eager = BlobPlanner::with_mem_size(1024);
+    assert_eq!(eager.mem_size(), 1024);
+    assert_eq!(
+        eager.merged_byte_size(&blob(1, 0, 100), &blob(2, 100, 50)),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_incremental_plan() {
+    let replay = generate_replay(5, &Profile::spam().with_frames(240 * 60));
+
+    let mut planner = BlobPlanner::new();
+    for (i, input) in replay.inputs.iter().enumerate() {
+        planner.push(input);
+        if i % 97 == 0 {
+            assert_eq!(planner.plan(), Blob::plan(&replay.inputs[..=i]));
+        }
+    }
+    assert_eq!(planner.len(), replay.inputs.len() as u64);
+    assert_eq!(planner.plan(), Blob::plan(&replay.inputs));
+}
+
+#[test]
+fn test_plan_covers_inputs() {
+    // Deltas needing 1, 2 and 4 bytes, in runs of varying lengths.
+    let mut inputs = Vec::new();
+    let mut frame = 0;
+    for i in 0..200u64 {
+        let delta = [1, 1 << 4, 1 << 12, 1 << 20][(i * i % 7 % 4) as usize];
+        frame += delta;
+        inputs.push(input(frame, delta));
+    }
+
+    for mem_size in [0, 24, 64, u64::MAX] {
+        let mut planner = BlobPlanner::with_mem_size(mem_size);
+        planner.extend(&inputs);
+        let blobs = planner.plan();
+
+        let mut next = 0;
+        for b in &blobs {
+            assert_eq!(b.start, next);
+            assert!(b.length > 0);
+            next += b.length;
+        }
+        assert_eq!(next, inputs.len() as u64);
+    }
+
+    let mut planner = BlobPlanner::with_mem_size(u64::MAX);
+    planner.extend(&inputs);
+    assert_eq!(planner.plan(), vec![blob(4, 0, 200)]);
+
+    let mut replay = Replay::new(240.0, ());
+    replay.inputs = inputs;
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer).unwrap();
+    let decoded = Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(decoded.inputs, replay.inputs);
+}
+
+#[test]
+fn test_input_byte_sizes() {
+    // Deltas are stored above 5 bits of button and flags.
+    for bits in 0..59 {
+        for delta in [(1u64 << bits) - 1, 1 << bits] {
+            let expected = match delta << 5 | 0b11000 {
+                0..0x100 => 1,
+                0x100..0x10000 => 2,
+                0x10000..0x100000000 => 4,
+                _ => 8,
+            };
+
+            let mut planner = BlobPlanner::with_mem_size(0);
+            planner.push(&input(delta, delta));
+            assert_eq!(planner.plan()[0].byte_size, expected, "{}", delta);
+        }
+    }
+
+    let mut planner = BlobPlanner::with_mem_size(0);
+    planner.push(&Input::new(0, 0, InputData::TPS(240.0)));
+    assert_eq!(planner.plan(), vec![blob(8, 0, 1)]);
+}
@@ -0,0 +1,262 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use slc_oxide::replay::{V2_HEADER, V3_HEADER};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn player(hold: bool) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button: 1,
+    })
+}
+
+fn replay() -> Replay<Vec<u8>> {
+    let mut replay = Replay::new(240.0, Vec::new());
+    replay.add_input(10, player(true));
+    replay.add_input(20, player(false));
+    replay.add_input(500, player(true));
+    replay.add_input(520, player(false));
+    replay
+}
+
+/// A scratch directory, removed when dropped.
+struct Dir(PathBuf);
+
+impl Dir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("slc_cli_{name}_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_str().unwrap().to_string()
+    }
+
+    fn write(&self, name: &str, replay: &Replay<Vec<u8>>) -> String {
+        let mut bytes = Vec::new();
+        replay.write(&mut bytes).unwrap();
+        self.write_bytes(name, &bytes)
+    }
+
+    fn write_bytes(&self, name: &str, bytes: &[u8]) -> String {
+        let path = self.path(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn slc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_slc"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn load(path: impl AsRef<Path>) -> Replay<Vec<u8>> {
+    Replay::read(&mut fs::File::open(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_usage() {
+    let output = slc(&[]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("usage:"));
+
+    let output = slc(&["info"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("usage:"));
+}
+
+#[test]
+fn test_info() {
+    let dir = Dir::new("info");
+    let input = dir.write("replay.slc", &replay());
+
+    let output = slc(&["info", &input]);
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.contains("format:       V2"));
+    assert!(text.contains("inputs:       4"));
+    assert!(text.contains("last frame:   520"));
+
+    let output = slc(&["info", &dir.path("missing.slc")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("error:"));
+
+    let garbage = dir.write_bytes("garbage.slc", b"not a replay");
+    let output = slc(&["info", &garbage]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown replay format"));
+}
+
+#[test]
+fn test_convert() {
+    let dir = Dir::new("convert");
+    let input = dir.write("replay.slc", &replay());
+
+    // The output format follows the extension, or --format.
+    let output_v3 = dir.path("replay.slc3");
+    assert!(slc(&["convert", &input, &output_v3]).status.success());
+    assert!(fs::read(&output_v3).unwrap().starts_with(&V3_HEADER));
+    assert_eq!(load(&output_v3).inputs, replay().inputs);
+
+    let output_v2 = dir.path("back.out");
+    let output = slc(&["convert", &output_v3, &output_v2, "--format", "v2"]);
+    assert!(output.status.success());
+    assert!(fs::read(&output_v2).unwrap().starts_with(&V2_HEADER));
+
+    let output = slc(&["convert", &input, &output_v2, "--format", "v4"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown format: v4"));
+
+    let output = slc(&["convert", &input, &output_v2, "--format"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("missing value for --format"));
+}
+
+#[test]
+fn test_validate() {
+    let dir = Dir::new("validate");
+    let valid = dir.write("valid.slc", &replay());
+    let output = slc(&["validate", &valid]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "valid\n");
+
+    let mut conflicting = replay();
+    conflicting.add_input(600, player(false));
+    let conflicting = dir.write("conflicting.slc", &conflicting);
+    let output = slc(&["validate", &conflicting]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("OrphanRelease"));
+
+    let garbage = dir.write_bytes("garbage.slc", b"SILL");
+    let output = slc(&["validate", &garbage]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).starts_with("invalid:"));
+}
+
+#[test]
+fn test_repair() {
+    let dir = Dir::new("repair");
+    let mut broken = replay();
+    broken.add_input(600, InputData::TPS(0.0));
+    let input = dir.write("broken.slc", &broken);
+    let output_path = dir.path("fixed.slc");
+
+    let output = slc(&["repair", &input, &output_path]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("1 fixed, 0 stripped"));
+    assert_eq!(load(&output_path).inputs[4].data, InputData::TPS(240.0));
+
+    let output = slc(&["repair", &input, &output_path, "--mode", "strip"]);
+    assert!(output.status.success());
+    assert_eq!(load(&output_path).inputs, replay().inputs);
+
+    let output = slc(&["repair", &input, &output_path, "--mode", "guess"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown repair mode: guess"));
+
+    let output = slc(&["repair", &input, &output_path, "--end-frame", "soon"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_trim() {
+    let dir = Dir::new("trim");
+    let input = dir.write("replay.slc", &replay());
+    let output_path = dir.path("trimmed.slc");
+
+    let output = slc(&["trim", &input, &output_path, "--to-frame", "100"]);
+    assert!(output.status.success());
+    assert!(load(&output_path).inputs.iter().all(|i| i.frame <= 100));
+
+    let output = slc(&["trim", &input, &output_path]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("missing --to-frame"));
+
+    let output = slc(&["trim", &input, &output_path, "--to-frame", "-1"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_diff() {
+    let dir = Dir::new("diff");
+    let left = dir.write("left.slc", &replay());
+    let same = dir.write("same.slc", &replay());
+
+    assert!(slc(&["diff", &left, &same]).status.success());
+
+    let mut changed = replay();
+    changed.add_input(700, player(true));
+    let changed = dir.write("changed.slc", &changed);
+    let output = slc(&["diff", &left, &changed]);
+    assert!(!output.status.success());
+    assert!(!stdout(&output).is_empty());
+
+    let output = slc(&["diff", &left, &dir.path("missing.slc")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("error:"));
+}
+
+#[test]
+fn test_dump() {
+    let dir = Dir::new("dump");
+    let mut bytes = Vec::new();
+    replay().write_v3(&mut bytes).unwrap();
+    let input = dir.write_bytes("replay.slc3", &bytes);
+
+    let output = slc(&["dump", &input]);
+    assert!(output.status.success());
+    assert!(!stdout(&output).is_empty());
+
+    let output = slc(&["dump", &input, "--section", "0"]);
+    assert!(output.status.success());
+
+    let output = slc(&["dump", &input, "--atom", "first"]);
+    assert!(!output.status.success());
+
+    let v2 = dir.write("replay.slc", &replay());
+    let output = slc(&["dump", &v2]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).starts_with("error:"));
+}
+
+#[test]
+fn test_stress() {
+    let dir = Dir::new("stress");
+    let input = dir.write("replay.slc", &replay());
+
+    let output = slc(&["stress", &input]);
+    assert!(output.status.success());
+    assert!(!stdout(&output).is_empty());
+
+    let mut bytes = Vec::new();
+    replay().write_v3(&mut bytes).unwrap();
+    let input = dir.write_bytes("replay.slc3", &bytes);
+    assert!(slc(&["stress", &input]).status.success());
+
+    let garbage = dir.write_bytes("garbage.slc", b"\0\0\0\0");
+    let output = slc(&["stress", &garbage]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown replay format"));
+}
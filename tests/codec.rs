@@ -0,0 +1,120 @@
+use slc_oxide::codec::{self, Number};
+use slc_oxide::Replay;
+
+/// Deterministic values covering every byte position.
+fn values() -> impl Iterator<Item = u64> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let random = std::iter::from_fn(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        Some(state)
+    });
+    [0, 1, u64::MAX, 1 << 63, 0x0102_0304_0506_0708]
+        .into_iter()
+        .chain((0..64).map(|shift| 1 << shift))
+        .chain(random.take(1_000))
+}
+
+/// Little-endian bytes computed with shifts, which don't depend on the host.
+fn reference(value: u64, width: usize) -> Vec<u8> {
+    (0..width).map(|i| (value >> (8 * i)) as u8).collect()
+}
+
+fn encoded<T: Number>(value: T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    codec::write(&mut bytes, value).unwrap();
+    bytes
+}
+
+#[test]
+fn test_fixed_bytes() {
+    assert_eq!(encoded(0x12u8), [0x12]);
+    assert_eq!(encoded(0x1234u16), [0x34, 0x12]);
+    assert_eq!(encoded(0x1234_5678u32), [0x78, 0x56, 0x34, 0x12]);
+    assert_eq!(
+        encoded(0x0102_0304_0506_0708u64),
+        [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+    );
+    assert_eq!(encoded(-2i32), [0xfe, 0xff, 0xff, 0xff]);
+    assert_eq!(encoded(1.0f32), [0x00, 0x00, 0x80, 0x3f]);
+    assert_eq!(encoded(240.0f64), [0, 0, 0, 0, 0, 0, 0x6e, 0x40]);
+
+    let mut be = [0u8; 4];
+    0x1234_5678u32.encode_be(&mut be);
+    assert_eq!(be, [0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(u32::decode_be(&be), 0x1234_5678);
+    assert_eq!(codec::decode_uint_be(&[0x01, 0x02, 0x03]), 0x010203);
+}
+
+#[test]
+fn test_matches_reference() {
+    for value in values() {
+        assert_eq!(encoded(value), reference(value, 8));
+        assert_eq!(encoded(value as u32), reference(value, 4));
+        assert_eq!(encoded(value as u16), reference(value, 2));
+        assert_eq!(encoded(f64::from_bits(value)), reference(value, 8));
+
+        let mut be = [0u8; 8];
+        value.encode_be(&mut be);
+        let mut reversed = reference(value, 8);
+        reversed.reverse();
+        assert_eq!(be.to_vec(), reversed);
+    }
+}
+
+#[test]
+fn test_roundtrip() {
+    for value in values() {
+        let bytes = encoded(value);
+        assert_eq!(codec::read::<u64, _>(&mut bytes.as_slice()).unwrap(), value);
+        assert_eq!(codec::decode::<u64>(&bytes), value);
+        assert_eq!(codec::decode::<i64>(&bytes), value as i64);
+
+        let float = f64::from_bits(value);
+        let read: f64 = codec::read(&mut encoded(float).as_slice()).unwrap();
+        assert_eq!(read.to_bits(), value);
+
+        let mut slot = [0u8; 12];
+        codec::encode(value as u32, &mut slot[4..]);
+        assert_eq!(codec::decode::<u32>(&slot[4..]), value as u32);
+        assert_eq!(slot[..4], [0; 4]);
+        assert_eq!(slot[8..], [0; 4]);
+    }
+}
+
+#[test]
+fn test_partial_width() {
+    for value in values() {
+        for width in 0..=8 {
+            let mask = u64::MAX.checked_shr(64 - 8 * width as u32).unwrap_or(0);
+
+            let mut bytes = Vec::new();
+            codec::write_uint(&mut bytes, value, width).unwrap();
+            assert_eq!(bytes, reference(value, width));
+            assert_eq!(codec::decode_uint(&bytes), value & mask);
+            assert_eq!(
+                codec::read_uint(&mut bytes.as_slice(), width).unwrap(),
+                value & mask
+            );
+
+            let mut be = bytes.clone();
+            be.reverse();
+            assert_eq!(codec::decode_uint_be(&be), value & mask);
+        }
+    }
+}
+
+#[test]
+fn test_short_read() {
+    assert!(codec::read::<u32, _>(&mut [1u8, 2, 3].as_slice()).is_err());
+    assert!(codec::read_uint(&mut [1u8].as_slice(), 2).is_err());
+}
+
+#[test]
+fn test_replay_bytes() {
+    // The tps follows the v2 header as little-endian on every host.
+    let mut bytes = Vec::new();
+    Replay::new(240.0, ()).write(&mut bytes).unwrap();
+    assert_eq!(bytes[4..12], [0, 0, 0, 0, 0, 0, 0x6e, 0x40]);
+}
@@ -0,0 +1,79 @@
+//! Fixtures shared by the v3 integration tests.
+
+// Every test file uses its own subset of the helpers.
+#![allow(dead_code)]
+
+use std::io::Cursor;
+
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::ActionAtom;
+use slc_oxide::v3::{ActionType, Metadata, Replay, WriteOptions};
+
+/// Offset of the first atom: the header, the metadata size and the metadata.
+pub const FIRST_ATOM: usize = 8 + 2 + 64;
+
+/// A 240 tps replay holding the given atoms.
+pub fn replay(atoms: impl IntoIterator<Item = AtomVariant>) -> Replay {
+    let mut replay = Replay::new(Metadata::new(240.0, 0, 1));
+    for atom in atoms {
+        replay.add_atom(atom);
+    }
+    replay
+}
+
+/// An action atom of player 1 jumps, pressed or released on each frame.
+pub fn jumps(frames: &[(u64, bool)]) -> ActionAtom {
+    let mut atom = ActionAtom::new();
+    for &(frame, holding) in frames {
+        atom.add_player_action(frame, ActionType::Jump, holding, false)
+            .unwrap();
+    }
+    atom
+}
+
+pub fn write(replay: &Replay) -> Vec<u8> {
+    write_with(replay, &WriteOptions::default())
+}
+
+pub fn write_with(replay: &Replay, options: &WriteOptions) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    replay.write_with_options(&mut buffer, options).unwrap();
+    buffer
+}
+
+pub fn read(bytes: &[u8]) -> Replay {
+    Replay::read(&mut Cursor::new(bytes)).unwrap()
+}
+
+/// Write the replay and read it back.
+pub fn roundtrip(replay: &Replay) -> Replay {
+    read(&write(replay))
+}
+
+/// The action atom at `index`.
+pub fn atom_at(replay: &Replay, index: usize) -> &ActionAtom {
+    let AtomVariant::Action(atom) = &replay.atoms.atoms[index] else {
+        panic!("expected an action atom at {index}");
+    };
+    atom
+}
+
+/// The action atom at `index`, to edit.
+pub fn atom_at_mut(replay: &mut Replay, index: usize) -> &mut ActionAtom {
+    let AtomVariant::Action(atom) = &mut replay.atoms.atoms[index] else {
+        panic!("expected an action atom at {index}");
+    };
+    atom
+}
+
+/// Change the size of the atom at `offset` by `by` bytes, padding it with
+/// zeros when it grows.
+pub fn resize_atom(bytes: &mut Vec<u8>, offset: usize, by: i64) {
+    let size = u64::from_le_bytes(bytes[offset + 4..offset + 12].try_into().unwrap());
+    let resized = size.checked_add_signed(by).unwrap();
+    bytes[offset + 4..offset + 12].copy_from_slice(&resized.to_le_bytes());
+    let end = offset + 12 + size as usize;
+    if by > 0 {
+        bytes.splice(end..end, std::iter::repeat_n(0, by as usize));
+    }
+}
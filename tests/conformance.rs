@@ -0,0 +1,89 @@
+#![cfg(feature = "conformance")]
+
+use slc_oxide::codec;
+use slc_oxide::conformance::{check_implementation, check_self, Decoded, Stage, VECTORS};
+use slc_oxide::format::{V3_FOOTER, V3_HEADER, V3_METADATA_SIZE};
+
+#[test]
+fn test_self_conformance() {
+    assert_eq!(check_self(), Vec::new());
+}
+
+#[test]
+fn test_vectors_decode() {
+    for vector in VECTORS {
+        let decoded = vector.decoded();
+        assert!(decoded.matches(&decoded), "{}", vector.name);
+        assert_eq!(decoded.write().unwrap(), vector.bytes, "{}", vector.name);
+    }
+}
+
+#[test]
+fn test_broken_implementation() {
+    let failures = check_implementation(
+        |_| Err("not implemented".to_string()),
+        |decoded| {
+            let mut bytes = decoded.write().map_err(|e| e.to_string())?;
+            if matches!(decoded, Decoded::V3(_)) {
+                bytes.push(0);
+            }
+            Ok(bytes)
+        },
+    );
+
+    let reads = failures.iter().filter(|f| f.stage == Stage::Read).count();
+    let writes: Vec<_> = failures
+        .iter()
+        .filter(|f| f.stage == Stage::Write)
+        .collect();
+    assert_eq!(reads, VECTORS.len());
+    assert!(writes.iter().all(|f| f.vector.starts_with("v3")));
+    assert_eq!(writes.len(), 3);
+}
+
+/// FNV-1a, to pin the vectors without depending on the writer.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[test]
+fn test_vectors_pinned() {
+    // Changing a vector breaks other implementations checked against it, so
+    // it takes updating this list on purpose.
+    let pinned = [
+        ("v2_empty", 39, 0xeb76_32ec_34c3_257a),
+        ("v2_inputs", 83, 0x6f6b_1862_4589_2cb1),
+        ("v2_specials", 130, 0x3323_5a89_a006_0aa6),
+        ("v3_inputs", 138, 0x8c55_8c23_c52b_ae62),
+        ("v3_specials", 155, 0x6ce5_a77c_68c0_153d),
+        ("v3_subframe", 112, 0xfb5a_1b5c_dbf5_0631),
+    ];
+    assert_eq!(VECTORS.len(), pinned.len());
+    for (vector, (name, len, hash)) in VECTORS.iter().zip(pinned) {
+        assert_eq!(vector.name, name);
+        assert_eq!(vector.bytes.len(), len, "{name}");
+        assert_eq!(fnv1a(vector.bytes), hash, "{name}");
+    }
+}
+
+#[test]
+fn test_v3_atom_sizes() {
+    // Every atom size field holds the size of its body, so a reader can skip
+    // atoms it doesn't know.
+    let start = V3_HEADER.len() + 2 + V3_METADATA_SIZE;
+    for vector in VECTORS.iter().filter(|v| v.name.starts_with("v3")) {
+        let bytes = vector.bytes;
+        let mut offset = start;
+        let mut atoms = 0;
+        while bytes[offset..] != [V3_FOOTER] {
+            let size: u64 = codec::decode(&bytes[offset + 4..]);
+            assert_ne!(size, 0, "{}", vector.name);
+            offset += 12 + usize::try_from(size).unwrap();
+            assert!(offset < bytes.len(), "{}", vector.name);
+            atoms += 1;
+        }
+        assert!(atoms > 0, "{}", vector.name);
+    }
+}
@@ -0,0 +1,70 @@
+use slc_oxide::context::{DecodeContext, EncodeContext};
+use slc_oxide::testing::{generate_replay, generate_replay_v3, Profile};
+use slc_oxide::v3::WriteOptions;
+use slc_oxide::{ReadOptions, Replay};
+use std::io::Cursor;
+
+#[test]
+fn test_encode_context() {
+    let mut context = EncodeContext::new();
+    let options = WriteOptions::default();
+
+    for seed in 0..8 {
+        let profile = Profile::default().with_frames(240 * (10 + seed * 5));
+        let replay = generate_replay(seed, &profile);
+        let v3 = generate_replay_v3(seed, &profile);
+
+        let mut expected = Vec::new();
+        replay.write(&mut expected).unwrap();
+        assert_eq!(context.encode(&replay).unwrap(), &expected[..]);
+
+        let mut written = Vec::new();
+        context.write(&replay, &mut written).unwrap();
+        assert_eq!(written, expected);
+
+        let mut expected_v3 = Vec::new();
+        v3.write(&mut expected_v3).unwrap();
+        assert_eq!(context.encode_v3(&v3, &options).unwrap(), &expected_v3[..]);
+
+        let mut written_v3 = Vec::new();
+        context.write_v3(&v3, &mut written_v3, &options).unwrap();
+        assert_eq!(written_v3, expected_v3);
+    }
+}
+
+#[test]
+fn test_decode_context() {
+    let mut context = DecodeContext::new();
+    let mut encoder = EncodeContext::new();
+
+    for seed in 0..8 {
+        let replay = generate_replay(seed, &Profile::spam().with_frames(240 * 20));
+        let bytes = encoder.encode(&replay).unwrap().to_vec();
+
+        let decoded: Replay<()> = context.decode(&bytes).unwrap();
+        assert_eq!(decoded, replay);
+        context.recycle(decoded);
+
+        let read: Replay<()> = context.read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, replay);
+        context.recycle(read);
+
+        let mut v3_bytes = Vec::new();
+        replay.write_v3(&mut v3_bytes).unwrap();
+        let from_v3: Replay<()> = context.decode(&v3_bytes).unwrap();
+        let expected = Replay::<()>::read(&mut Cursor::new(&v3_bytes)).unwrap();
+        assert_eq!(from_v3, expected);
+        context.recycle(from_v3);
+    }
+
+    assert!(context.decode::<()>(b"SLC2").is_err());
+    assert!(context.decode::<()>(b"not a replay").is_err());
+
+    let mut limited = DecodeContext::new().with_read_options(ReadOptions {
+        max_inputs: 1,
+        ..ReadOptions::untrusted()
+    });
+    let replay = generate_replay(1, &Profile::default());
+    let bytes = encoder.encode(&replay).unwrap().to_vec();
+    assert!(limited.decode::<()>(&bytes).is_err());
+}
@@ -0,0 +1,387 @@
+use slc_oxide::convert::echo::{EchoAction, EchoReplay, EchoReplayType};
+use slc_oxide::convert::gdr2::{Gdr2Input, Gdr2Replay, GDR2_ATOM_ID};
+use slc_oxide::convert::ConvertError;
+use slc_oxide::{Button, InputData, PlayerInput};
+
+fn echo_action(frame: u32, holding: bool, player_2: bool) -> EchoAction {
+    EchoAction {
+        frame,
+        holding,
+        player_2,
+        x_position: frame as f32 * 2.5,
+    }
+}
+
+#[test]
+fn test_echo_binary() {
+    let echo = EchoReplay {
+        fps: 240.0,
+        starting_frame: 0,
+        replay_type: EchoReplayType::XPosition,
+        actions: vec![
+            echo_action(30, false, false),
+            echo_action(10, true, false),
+            echo_action(10, true, true),
+            echo_action(30, false, true),
+        ],
+    };
+
+    let mut buffer = Vec::new();
+    echo.write_binary(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), 12 + 10 * 4);
+    assert_eq!(EchoReplay::read(&mut buffer.as_slice()).unwrap(), echo);
+
+    let replay = echo.to_replay(());
+    assert_eq!(replay.tps, 240.0);
+    let inputs: Vec<_> = replay
+        .iter()
+        .map(|i| (i.frame, i.delta, i.data.clone()))
+        .collect();
+    let jump = |hold, p2| InputData::Player(PlayerInput::new(Button::Jump, hold, p2));
+    assert_eq!(
+        inputs,
+        vec![
+            (10, 10, jump(true, false)),
+            (10, 0, jump(true, true)),
+            (30, 20, jump(false, false)),
+            (30, 0, jump(false, true)),
+        ]
+    );
+
+    let positions = echo.positions();
+    assert_eq!(positions[1].x, 25.0);
+    assert_eq!(positions[1].data, jump(true, false));
+
+    buffer.truncate(buffer.len() - 3);
+    assert!(matches!(
+        EchoReplay::read_binary(&mut buffer.as_slice()),
+        Err(ConvertError::InvalidField("actions"))
+    ));
+    assert!(matches!(
+        EchoReplay::read(&mut &b"SLC2...."[..]),
+        Err(ConvertError::UnknownFormat)
+    ));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_echo_json() {
+    let old = r#"{
+        "FPS": 60.0,
+        "Starting Frame": 120,
+        "Echo Replay": [
+            {"Frame": 150, "Hold": true, "Player 2": false, "X Position": 300.0},
+            {"Frame": 160, "Hold": false, "Player 2": false, "X Position": 320.0}
+        ]
+    }"#;
+    let new = r#"{
+        "fps": 60.0,
+        "inputs": [
+            {"frame": 150, "holding": true},
+            {"frame": 160, "holding": false, "player_2": false}
+        ]
+    }"#;
+
+    let old = EchoReplay::read(&mut old.as_bytes()).unwrap();
+    let new = EchoReplay::read(&mut new.as_bytes()).unwrap();
+    assert_eq!(old.starting_frame, 120);
+    assert_eq!(old.actions[1].x_position, 320.0);
+    assert_eq!(old.to_replay(()).inputs, new.to_replay(()).inputs);
+    assert_eq!(new.to_replay(()).tps, 60.0);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_urf_dialects() {
+    use slc_oxide::convert::urf::{UrfInput, UrfReplay};
+
+    let plain = r#"{"tps": 480, "inputs": [
+        {"frame": 5, "hold": true, "player": 1, "button": 1},
+        {"frame": 9, "hold": false, "player": 2, "button": 3}
+    ]}"#;
+    let loose = r#"{"fps": 480.0, "actions": [
+        {"f": "9", "down": 0, "p2": true, "key": "Right"},
+        {"f": 5, "pressed": 1, "player": 0}
+    ]}"#;
+
+    let plain = UrfReplay::read(&mut plain.as_bytes()).unwrap();
+    let loose = UrfReplay::read(&mut loose.as_bytes()).unwrap();
+    assert_eq!(plain.tps, 480.0);
+    assert_eq!(
+        plain.inputs[1],
+        UrfInput {
+            frame: 9,
+            hold: false,
+            player_2: true,
+            button: Button::Right,
+        }
+    );
+    assert_eq!(plain.to_replay(()).inputs, loose.to_replay(()).inputs);
+
+    let bare = UrfReplay::read(&mut r#"[{"frame": 1, "holding": true}]"#.as_bytes()).unwrap();
+    assert_eq!(bare.tps, 240.0);
+    assert_eq!(bare.inputs[0].button, Button::Jump);
+
+    let mut buffer = Vec::new();
+    plain.write(&mut buffer).unwrap();
+    let reread = UrfReplay::read(&mut buffer.as_slice()).unwrap();
+    assert_eq!(reread, plain);
+    assert_eq!(UrfReplay::from_replay(&plain.to_replay(())), plain);
+
+    for bad in [
+        r#"[{"hold": true}]"#,
+        r#"[{"frame": 1, "hold": 2}]"#,
+        r#"[{"frame": 1, "hold": true, "player": 3}]"#,
+        r#"[{"frame": 1, "hold": true, "button": "dash"}]"#,
+        r#"{"tps": 0, "inputs": []}"#,
+        r#""inputs""#,
+    ] {
+        assert!(UrfReplay::read(&mut bad.as_bytes()).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn test_frame_lists() {
+    use slc_oxide::convert::framelist::{
+        read, BinaryLayout, Field, FrameListDescriptor, Layout, TextLayout,
+    };
+
+    let jump = |hold, p2| InputData::Player(PlayerInput::new(Button::Jump, hold, p2));
+    let inputs = |replay: slc_oxide::Replay<()>| -> Vec<_> {
+        replay.iter().map(|i| (i.frame, i.data.clone())).collect()
+    };
+
+    let lines = "# presses and releases\n12\n\n20\n31\n";
+    let replay = read(&mut lines.as_bytes(), &FrameListDescriptor::lines(60.0), ()).unwrap();
+    assert_eq!(replay.tps, 60.0);
+    assert_eq!(
+        inputs(replay),
+        vec![
+            (12, jump(true, false)),
+            (20, jump(false, false)),
+            (31, jump(true, false))
+        ]
+    );
+
+    let csv = "frame,player,action,button\n40,2,release,right\n10,1,press,1\n40,1,0,jump\n";
+    let descriptor = FrameListDescriptor::new(
+        Layout::Text(TextLayout {
+            separator: Some(','),
+            skip_lines: 1,
+            hold: Some(2),
+            player: Some(1),
+            button: Some(3),
+            ..Default::default()
+        }),
+        240.0,
+    );
+    let right = InputData::Player(PlayerInput::new(Button::Right, false, true));
+    assert_eq!(
+        inputs(read(&mut csv.as_bytes(), &descriptor, ()).unwrap()),
+        vec![
+            (10, jump(true, false)),
+            (40, right),
+            (40, jump(false, false))
+        ]
+    );
+
+    let mut records = Vec::new();
+    for frame in [7u32, 9, 15, 16] {
+        records.extend_from_slice(&frame.to_le_bytes());
+    }
+    let replay = read(
+        &mut records.as_slice(),
+        &FrameListDescriptor::records(4, 240.0),
+        (),
+    )
+    .unwrap();
+    assert_eq!(replay.inputs.len(), 4);
+    assert_eq!(replay.inputs[3].data, jump(false, false));
+
+    // Big-endian records of a u16 frame and a player flag, after a header.
+    let descriptor = FrameListDescriptor {
+        player_2: 1,
+        ..FrameListDescriptor::new(
+            Layout::Binary(BinaryLayout {
+                header: 2,
+                record_size: 4,
+                big_endian: true,
+                frame: Field::new(0, 2),
+                hold: Some(Field::new(2, 1)),
+                player: Some(Field::new(3, 1)),
+                button: None,
+            }),
+            120.0,
+        )
+    };
+    let bytes = [0xAA, 0xBB, 0x01, 0x00, 1, 1, 0x01, 0x02, 0, 1];
+    assert_eq!(
+        inputs(read(&mut &bytes[..], &descriptor, ()).unwrap()),
+        vec![(256, jump(true, true)), (258, jump(false, true))]
+    );
+    assert!(matches!(
+        read(&mut &bytes[..9], &descriptor, ()),
+        Err(ConvertError::InvalidField("records"))
+    ));
+    assert!(read(
+        &mut "12\nabc\n".as_bytes(),
+        &FrameListDescriptor::lines(60.0),
+        ()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_fps_retime() {
+    use slc_oxide::convert::fps::{convert_frame, retime, FpsOptions, Rounding};
+    use slc_oxide::Replay;
+
+    let nearest = FpsOptions::default();
+    let down = FpsOptions {
+        rounding: Rounding::Down,
+        ..Default::default()
+    };
+    let up = FpsOptions {
+        rounding: Rounding::Up,
+        ..Default::default()
+    };
+    assert_eq!(convert_frame(1, 60.0, &up), 4);
+    assert_eq!(convert_frame(1, 360.0, &nearest), 1);
+    assert_eq!(convert_frame(1, 360.0, &down), 0);
+    assert_eq!(convert_frame(2, 360.0, &up), 2);
+    assert_eq!(convert_frame(3, 360.0, &nearest), 2);
+    assert_eq!(convert_frame(360, 360.0, &up), 240);
+
+    let jump = |hold| InputData::Player(PlayerInput::new(Button::Jump, hold, false));
+    let mut replay = Replay::new(60.0, ());
+    replay.add_input(30, jump(true));
+    replay.add_input(60, InputData::TPS(360.0));
+    replay.add_input(63, jump(false));
+    replay.add_input(64, jump(true));
+
+    retime(
+        &mut replay,
+        &FpsOptions {
+            insert_tps: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(replay.tps, 240.0);
+    let inputs: Vec<_> = replay
+        .iter()
+        .map(|i| (i.frame, i.delta, i.data.clone()))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (0, 0, InputData::TPS(240.0)),
+            (120, 120, jump(true)),
+            (242, 122, jump(false)),
+            (243, 1, jump(true)),
+        ]
+    );
+}
+
+fn gdr2_input(frame: u64, button: u8, down: bool, extension: &[u8]) -> Gdr2Input {
+    Gdr2Input {
+        frame,
+        button,
+        down,
+        player_2: button == 1 && frame % 2 == 1,
+        extension: extension.to_vec(),
+    }
+}
+
+#[test]
+fn test_gdr2_extension_roundtrip() {
+    let gdr = Gdr2Replay {
+        input_tag: "xdbot".to_string(),
+        author: "someone".to_string(),
+        description: "verified".to_string(),
+        duration: 12.5,
+        game_version: 2206,
+        framerate: 240.0,
+        seed: 1234,
+        coins: 3,
+        ldm: true,
+        platformer: false,
+        bot_name: "xdBot".to_string(),
+        bot_version: 2,
+        level_id: 128,
+        level_name: "1st level".to_string(),
+        extension: vec![0xDE, 0xAD, 0xBE, 0xEF],
+        deaths: vec![50, 50, 400],
+        inputs: vec![
+            gdr2_input(10, 1, true, &[1, 2, 3]),
+            gdr2_input(50, 1, false, &[]),
+            gdr2_input(51, 2, true, &[4]),
+            gdr2_input(51, 2, false, &[]),
+            gdr2_input(500, 3, true, &[0; 300]),
+        ],
+    };
+    let mut bytes = Vec::new();
+    gdr.write(&mut bytes).unwrap();
+    assert_eq!(Gdr2Replay::read(&mut bytes.as_slice()).unwrap(), gdr);
+
+    // GDR2 to slc3 and back keeps the header and every extension blob.
+    let replay = Gdr2Replay::read(&mut bytes.as_slice())
+        .unwrap()
+        .to_replay_v3()
+        .unwrap();
+    assert_eq!(replay.metadata.tps, 240.0);
+    let mut slc = Vec::new();
+    replay.write(&mut slc).unwrap();
+    let loaded = slc_oxide::v3::Replay::read(&mut std::io::Cursor::new(&slc)).unwrap();
+    assert_eq!(loaded, replay);
+
+    let back = Gdr2Replay::from_replay_v3(&loaded).unwrap();
+    assert_eq!(back, gdr);
+    let mut written = Vec::new();
+    back.write(&mut written).unwrap();
+    assert_eq!(written, bytes);
+
+    // Without the custom atom, only the inputs and deaths are left.
+    let mut stripped = loaded.clone();
+    stripped
+        .atoms
+        .atoms
+        .retain(|atom| atom.raw_id() != GDR2_ATOM_ID);
+    let bare = Gdr2Replay::from_replay_v3(&stripped).unwrap();
+    assert!(bare.extension.is_empty() && bare.input_tag.is_empty());
+    assert!(bare.inputs.iter().all(|i| i.extension.is_empty()));
+    assert_eq!(bare.deaths, gdr.deaths);
+    assert_eq!(bare.inputs.len(), gdr.inputs.len());
+    assert_eq!(bare.seed, 1234);
+}
+
+#[test]
+fn test_gdr2_invalid() {
+    let gdr = Gdr2Replay {
+        framerate: 240.0,
+        inputs: vec![gdr2_input(10, 1, true, &[1])],
+        ..Default::default()
+    };
+    assert!(matches!(
+        gdr.write(&mut Vec::new()),
+        Err(ConvertError::InvalidField("input tag"))
+    ));
+
+    let gdr = Gdr2Replay {
+        input_tag: "tag".to_string(),
+        ..gdr
+    };
+    let mut bytes = Vec::new();
+    gdr.write(&mut bytes).unwrap();
+    for len in 0..bytes.len() {
+        assert!(Gdr2Replay::read(&mut &bytes[..len]).is_err());
+    }
+    assert!(matches!(
+        Gdr2Replay::read(&mut &b"MYBO"[..]),
+        Err(ConvertError::UnknownFormat)
+    ));
+
+    // A blob length far past the end of the file.
+    let mut huge = b"GDR\x02".to_vec();
+    huge.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    assert!(Gdr2Replay::read(&mut huge.as_slice()).is_err());
+}
@@ -0,0 +1,62 @@
+use slc_oxide::diff::{diff, diff_files, normalize, Divergence};
+use slc_oxide::{InputData, PlayerInput, Replay};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+fn player(hold: bool) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button: 1,
+    })
+}
+
+#[test]
+fn test_diff_reports_divergences() {
+    let mut left = Replay::<()>::new(240.0, ());
+    left.add_input(10, player(true));
+    left.add_input(20, player(false));
+    left.add_input(30, InputData::Death);
+
+    let mut right = Replay::<Vec<u8>>::new(480.0, vec![1, 2, 3]);
+    right.add_input(10, player(true));
+    right.add_input(20, player(true));
+    right.add_input(25, InputData::Skip);
+    right.add_input(40, InputData::Death);
+    normalize(&mut right);
+
+    let report = diff(&left, &right);
+    assert!(!report.is_identical());
+    assert_eq!(
+        report.divergences[0],
+        Divergence::Tps {
+            left: 240.0,
+            right: 480.0
+        }
+    );
+    assert_eq!(report.first_divergence().and_then(|d| d.frame()), Some(20));
+    assert!(matches!(report.divergences[2], Divergence::OnlyLeft(ref i) if i.frame == 30));
+    assert!(matches!(report.divergences[3], Divergence::OnlyRight(ref i) if i.frame == 40));
+    assert_eq!(report.divergences.len(), 4);
+}
+
+#[test]
+fn test_diff_files_across_versions() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("macros")
+        .join("system.slc");
+
+    assert!(diff_files(&path, &path).unwrap().is_identical());
+
+    let replay = Replay::<Vec<u8>>::read(&mut Cursor::new(std::fs::read(&path).unwrap())).unwrap();
+    let mut v2_buffer = Vec::new();
+    replay.write(&mut v2_buffer).unwrap();
+
+    let v2_path = std::env::temp_dir().join("slc_oxide_diff_system_v2.slc");
+    std::fs::write(&v2_path, v2_buffer).unwrap();
+    let report = diff_files(&path, &v2_path).unwrap();
+    std::fs::remove_file(&v2_path).unwrap();
+
+    assert!(report.is_identical(), "{}", report);
+}
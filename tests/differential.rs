@@ -0,0 +1,43 @@
+use slc_oxide::differential::{roundtrip, DifferentialError};
+use slc_oxide::testing::{generate_replay, Profile};
+use slc_oxide::{Button, Input, InputData, PlayerInput};
+
+#[test]
+fn test_codecs_agree() {
+    for profile in [
+        Profile::default(),
+        Profile::spam(),
+        Profile::dual(),
+        Profile::platformer(),
+    ] {
+        let replay = generate_replay(11, &profile.with_frames(240 * 30));
+        roundtrip(replay.tps, &replay.inputs).unwrap();
+    }
+
+    let press = InputData::Player(PlayerInput::new(Button::Jump, true, false));
+    let release = InputData::Player(PlayerInput::new(Button::Jump, false, false));
+    let inputs = [
+        Input::new(0, 0, press.clone()),
+        Input::new(0, 0, release.clone()),
+        Input::new(10, 10, InputData::Skip),
+        Input::new(10, 0, InputData::TPS(480.0)),
+        Input::new(1 << 40, 0, press),
+        Input::new(1 << 40, 0, InputData::Death),
+        Input::new(1 << 40, 0, InputData::RestartFull),
+        Input::new((1 << 40) + 1, 0, release),
+    ];
+    roundtrip(240.0, &inputs).unwrap();
+}
+
+#[test]
+fn test_codec_errors() {
+    let invalid = InputData::Player(PlayerInput {
+        hold: true,
+        player_2: false,
+        button: 6,
+    });
+    assert!(matches!(
+        roundtrip(240.0, &[Input::new(5, 5, invalid)]),
+        Err(DifferentialError::V2(_))
+    ));
+}
@@ -0,0 +1,71 @@
+use slc_oxide::edit::{EditError, EditSession};
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+fn jump(hold: bool) -> InputData {
+    InputData::Player(PlayerInput::new(Button::Jump, hold, false))
+}
+
+fn frames<M: slc_oxide::meta::Meta>(session: &EditSession<M>) -> Vec<u64> {
+    session.replay().iter().map(|i| i.frame).collect()
+}
+
+#[test]
+fn test_edit_session_undo_redo() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, jump(true));
+    replay.add_input(20, jump(false));
+    replay.add_input(50, InputData::Death);
+    let original = replay.clone();
+    let mut session = EditSession::new(replay);
+
+    assert_eq!(session.insert(20, jump(true)), 2);
+    session.shift(2..3, 10).unwrap();
+    assert_eq!(frames(&session), [10, 20, 30, 50]);
+    let removed = session.remove(0..2).unwrap();
+    assert_eq!(removed[1].frame, 20);
+    session
+        .splice(1..2, [(40, InputData::Restart), (45, InputData::Death)])
+        .unwrap();
+    assert_eq!(frames(&session), [30, 40, 45]);
+    assert_eq!(session.replay().inputs[2].delta, 5);
+    assert_eq!(session.undo_depth(), 4);
+
+    while session.undo() {}
+    assert_eq!(session.replay(), &original);
+    assert!(!session.can_undo());
+
+    assert!(session.redo());
+    assert!(session.redo());
+    assert_eq!(frames(&session), [10, 20, 30, 50]);
+    assert_eq!(session.replay().inputs[2].delta, 10);
+
+    session.insert(0, InputData::Restart);
+    assert!(!session.can_redo());
+    assert!(session.undo());
+    assert!(session.undo());
+    assert!(session.undo());
+    assert_eq!(session.into_replay(), original);
+}
+
+#[test]
+fn test_edit_session_rejects_unordered() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, jump(true));
+    replay.add_input(20, jump(false));
+    let mut session = EditSession::new(replay);
+
+    assert_eq!(session.shift(0..1, 11), Err(EditError::Unordered));
+    assert_eq!(session.shift(0..1, -11), Err(EditError::Unordered));
+    assert_eq!(
+        session.splice(0..0, [(5, InputData::Death), (4, InputData::Death)]),
+        Err(EditError::Unordered)
+    );
+    assert!(matches!(
+        session.remove(1..3),
+        Err(EditError::OutOfBounds { len: 2, .. })
+    ));
+    assert!(!session.can_undo());
+
+    session.shift(0..1, 10).unwrap();
+    assert_eq!(frames(&session), [20, 20]);
+}
@@ -0,0 +1,271 @@
+use slc_oxide::export::clicks::{clicks, write_text, ClickKind, ClickOptions};
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+fn press(hold: bool, p2: bool) -> InputData {
+    InputData::Player(PlayerInput::new(Button::Jump, hold, p2))
+}
+
+#[test]
+fn test_click_export() {
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(240, press(true, false));
+    replay.add_input(250, press(true, false));
+    replay.add_input(264, press(false, false));
+    replay.add_input(480, InputData::TPS(480.0));
+    replay.add_input(480, press(true, true));
+    replay.add_input(960, press(false, true));
+    replay.add_input(961, press(false, true));
+    replay.add_input(1000, press(true, false));
+    replay.add_input(1010, InputData::Restart);
+    replay.add_input(1020, press(true, false));
+
+    let clicks = clicks(&replay, &ClickOptions::default());
+    let summary: Vec<_> = clicks
+        .iter()
+        .map(|c| (c.frame, c.player, c.kind, c.soft))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (240, 1, ClickKind::Press, false),
+            (264, 1, ClickKind::Release, false),
+            (480, 2, ClickKind::Press, false),
+            (960, 2, ClickKind::Release, false),
+            (1000, 1, ClickKind::Press, false),
+            (1020, 1, ClickKind::Press, true),
+        ]
+    );
+    assert_eq!(clicks[0].time, 1.0);
+    assert_eq!(clicks[1].time, 1.1);
+    assert_eq!(clicks[3].time, 3.0);
+    assert_eq!(clicks[5].time, 3.125);
+
+    let mut text = Vec::new();
+    write_text(&mut text, &clicks[..2]).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "1.000000 1 1 press hard\n1.100000 1 1 release hard\n"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_click_project() {
+    let click = slc_oxide::export::clicks::Click {
+        time: 0.5,
+        frame: 120,
+        player: 2,
+        button: 1,
+        kind: ClickKind::Release,
+        soft: true,
+    };
+    let mut buffer = Vec::new();
+    slc_oxide::export::clicks::write_project(&mut buffer, 240.0, &[click]).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(value["tps"], 240.0);
+    assert_eq!(value["clicks"][0]["kind"], "release");
+    assert_eq!(value["clicks"][0]["player"], 2);
+    assert_eq!(value["clicks"][0]["soft"], true);
+}
+
+#[test]
+fn test_overlay_keyframes() {
+    use slc_oxide::export::overlay::{keyframes, ButtonState};
+
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(0, press(true, true));
+    replay.add_input(120, press(true, false));
+    replay.add_input(
+        120,
+        InputData::Player(PlayerInput::new(Button::Left, true, false)),
+    );
+    replay.add_input(240, press(false, false));
+    replay.add_input(240, press(true, false));
+    replay.add_input(360, InputData::TPS(120.0));
+    replay.add_input(480, InputData::Restart);
+
+    let keyframes = keyframes(&replay);
+    let jump = ButtonState {
+        jump: true,
+        ..Default::default()
+    };
+    let jump_left = ButtonState { left: true, ..jump };
+    let states: Vec<_> = keyframes
+        .iter()
+        .map(|k| (k.time, k.frame, k.player_1, k.player_2))
+        .collect();
+    assert_eq!(
+        states,
+        vec![
+            (0.0, 0, ButtonState::default(), jump),
+            (0.5, 120, jump_left, jump),
+            (2.5, 480, ButtonState::default(), ButtonState::default()),
+        ]
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_overlay_json() {
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(60, press(true, false));
+    replay.add_input(120, press(false, false));
+
+    let mut buffer = Vec::new();
+    slc_oxide::export::overlay::write_json(&mut buffer, &replay).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(value["duration"], 0.5);
+    assert_eq!(value["keyframes"][1]["time"], 0.25);
+    assert_eq!(value["keyframes"][1]["player_1"]["jump"], true);
+    assert_eq!(value["keyframes"][2]["player_1"]["jump"], false);
+}
+
+#[test]
+fn test_timeline() {
+    use slc_oxide::export::timeline::{timeline, MarkerKind};
+
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(0, press(true, true));
+    replay.add_input(120, press(true, false));
+    replay.add_input(240, press(false, false));
+    replay.add_input(240, InputData::TPS(60.0));
+    replay.add_input(240, InputData::TPS(120.0));
+    replay.add_input(300, InputData::Death);
+    replay.add_input(360, InputData::Restart);
+
+    let timeline = timeline(&replay);
+    assert_eq!(timeline.end, 360);
+    assert_eq!(timeline.duration, 2.0);
+
+    assert_eq!(timeline.lanes.len(), 2);
+    assert!(!timeline.lanes[0].player_2);
+    let bar = timeline.lanes[0].bars[0];
+    assert_eq!(
+        (bar.start, bar.end, bar.start_time, bar.end_time),
+        (120, 240, 0.5, 1.0)
+    );
+    assert!(bar.released);
+    let bar = timeline.lanes[1].bars[0];
+    assert_eq!((bar.start, bar.end, bar.end_time), (0, 360, 2.0));
+    assert!(!bar.released);
+
+    let markers: Vec<_> = timeline
+        .markers
+        .iter()
+        .map(|m| (m.frame, m.time, m.kind))
+        .collect();
+    assert_eq!(
+        markers,
+        vec![
+            (300, 1.5, MarkerKind::Death),
+            (360, 2.0, MarkerKind::Restart)
+        ]
+    );
+
+    let segments: Vec<_> = timeline
+        .tps_segments
+        .iter()
+        .map(|s| (s.start, s.end, s.tps, s.end_time))
+        .collect();
+    assert_eq!(segments, vec![(0, 240, 240.0, 1.0), (240, 360, 120.0, 2.0)]);
+}
+
+#[test]
+fn test_resample() {
+    use slc_oxide::export::resample::{resample, Samples};
+
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(8, press(true, false));
+    replay.add_input(12, press(false, false));
+    replay.add_input(13, press(true, true));
+    replay.add_input(14, press(false, true));
+    replay.add_input(20, InputData::TPS(120.0));
+    replay.add_input(22, press(true, false));
+    replay.add_input(22, press(false, false));
+    replay.add_input(30, InputData::Death);
+
+    // 60 fps: four ticks per sample at 240 tps, two at 120 tps.
+    let samples = resample(&replay, 60.0);
+    let p1 = Samples::bit(1, false);
+    let p2 = Samples::bit(1, true);
+    assert_eq!(p2, 8);
+    assert_eq!(samples.samples, vec![0, 0, p1, p2, 0, 0, p1, 0, 0, 0, 0]);
+    assert!(samples.is_held(3, 1, true));
+    assert!(!samples.is_held(3, 1, false));
+    assert!(!samples.is_held(99, 1, false));
+    assert_eq!(Samples::bit(4, false), 0);
+
+    assert!(resample(&replay, 0.0).samples.is_empty());
+}
+
+#[cfg(feature = "dataset")]
+#[test]
+fn test_dataset_export() {
+    use slc_oxide::export::dataset::{rows, write_npy, ROW_SIZE};
+
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(240, press(true, false));
+    replay.add_input(480, InputData::TPS(480.0));
+    replay.add_input(960, press(false, false));
+    replay.add_input(
+        960,
+        InputData::Player(PlayerInput::new(Button::Left, true, true)),
+    );
+    replay.add_input(1000, InputData::Restart);
+
+    let rows = rows(&replay);
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|r| (r.frame, r.dt, r.buttons, r.player, r.holding))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (240, 1.0, [true, false, false], 1, true),
+            (960, 2.0, [true, false, false], 1, false),
+            (960, 0.0, [false, true, false], 2, true),
+        ]
+    );
+
+    let mut bytes = Vec::new();
+    write_npy(&mut bytes, &rows).unwrap();
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.contains("'shape': (3,)"));
+    assert!(header.ends_with('\n'));
+
+    let data = &bytes[10 + header_len..];
+    assert_eq!(data.len(), 3 * ROW_SIZE);
+    let row = &data[ROW_SIZE..2 * ROW_SIZE];
+    assert_eq!(u64::from_le_bytes(row[0..8].try_into().unwrap()), 960);
+    assert_eq!(f64::from_le_bytes(row[8..16].try_into().unwrap()), 2.0);
+    assert_eq!(&row[16..], &[1, 0, 0, 1, 0]);
+}
+
+#[test]
+fn test_ghost() {
+    use slc_oxide::export::ghost::ghost;
+
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(4, press(true, false));
+    replay.add_input(
+        10,
+        InputData::Player(PlayerInput::new(Button::Right, true, true)),
+    );
+    replay.add_input(12, press(false, false));
+    replay.add_input(
+        21,
+        InputData::Player(PlayerInput::new(Button::Right, false, true)),
+    );
+
+    let track = ghost(&replay, 4);
+    assert_eq!(
+        track.states,
+        vec![0, 0b1, 0b1, 0b100000, 0b100000, 0b100000]
+    );
+    assert_eq!(track.state_at(9), 0b1);
+    assert_eq!(track.state_at(1000), 0);
+    assert!(ghost(&replay, 0).states.is_empty());
+}
@@ -0,0 +1,42 @@
+use slc_oxide::format::{
+    capabilities, Version, BUILTIN_ATOM_IDS, V2_FOOTER, V2_HEADER, V2_MAX_DELTA, V3_FOOTER,
+    V3_HEADER, V3_METADATA_SIZE,
+};
+use slc_oxide::v3::atom::AtomId;
+use slc_oxide::{InputData, Replay};
+use std::io::Cursor;
+
+#[test]
+fn test_format_constants() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(V2_MAX_DELTA, InputData::Death);
+
+    let mut v2 = Vec::new();
+    replay.write(&mut v2).unwrap();
+    assert!(v2.starts_with(&V2_HEADER));
+    assert!(v2.ends_with(&V2_FOOTER));
+    let read = Replay::<()>::read(&mut Cursor::new(&v2)).unwrap();
+    assert_eq!(read.inputs[0].frame, V2_MAX_DELTA);
+
+    let mut v3 = Vec::new();
+    replay.write_v3(&mut v3).unwrap();
+    assert!(v3.starts_with(&V3_HEADER));
+    assert_eq!(v3.last(), Some(&V3_FOOTER));
+    assert_eq!(
+        u16::from_le_bytes([v3[8], v3[9]]) as usize,
+        V3_METADATA_SIZE
+    );
+
+    assert!(BUILTIN_ATOM_IDS.contains(&(AtomId::Session as u32)));
+    assert!(AtomId::try_from(BUILTIN_ATOM_IDS.end() + 1).is_err());
+}
+
+#[test]
+fn test_capabilities() {
+    let caps = capabilities();
+    assert_eq!(caps.versions, &[Version::V2, Version::V3]);
+    assert_eq!(caps.json, cfg!(feature = "json"));
+    assert_eq!(caps.net, cfg!(feature = "net"));
+    assert!(caps.converters.contains(&"echo"));
+    assert_eq!(caps.converters.contains(&"urf"), cfg!(feature = "json"));
+}
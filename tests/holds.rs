@@ -0,0 +1,119 @@
+use slc_oxide::holds::{pair_inputs, HoldIndex};
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+fn player(hold: bool, button: u8, player_2: bool) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2,
+        button,
+    })
+}
+
+#[test]
+fn test_hold_index_queries() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1, false));
+    replay.add_input(15, player(true, 2, true));
+    replay.add_input(20, player(true, 1, false));
+    replay.add_input(30, player(false, 1, false));
+    replay.add_input(40, player(true, 3, false));
+    replay.add_input(45, InputData::Restart);
+    replay.add_input(50, player(false, 2, true));
+    replay.add_input(60, player(true, 1, false));
+    replay.add_input(61, player(false, 1, false));
+    replay.add_input(70, player(true, 1, true));
+
+    let holds = pair_inputs(&replay.inputs);
+    assert_eq!(holds.len(), 5);
+    assert!(holds[0].released);
+    assert_eq!((holds[1].start, holds[1].end), (15, 45));
+    assert!(!holds[1].released);
+    assert_eq!((holds[4].start, holds[4].end), (70, 70));
+
+    let index = HoldIndex::from(&replay);
+    assert_eq!(index.len(), 5);
+    assert!(index.is_held(25, 1, false));
+    assert!(!index.is_held(30, 1, false));
+    assert!(index.is_held(44, 3, false));
+    assert!(!index.is_held(45, 2, true));
+
+    assert_eq!(index.longest().unwrap().start, 15);
+    assert_eq!(index.longest_for(1, false).unwrap().length(), 20);
+    assert_eq!(index.coverage(1, false), 21);
+    assert_eq!(index.coverage_in(25, 65, 1, false), 6);
+
+    for start in 0..80 {
+        for end in start + 1..80 {
+            let expected: Vec<_> = holds
+                .iter()
+                .filter(|h| h.start < end && h.end > start)
+                .collect();
+            assert_eq!(index.overlapping(start, end), expected);
+        }
+    }
+}
+
+#[test]
+fn test_hold_index_last_frame() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(u64::MAX - 2, player(true, 1, false));
+    replay.add_input(u64::MAX, InputData::Death);
+
+    let index = HoldIndex::from(&replay);
+    assert!(index.is_held(u64::MAX - 1, 1, false));
+    assert_eq!(index.at(u64::MAX).count(), 0);
+}
+
+#[test]
+fn test_checkpoint() {
+    use slc_oxide::holds::checkpoint;
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1, false));
+    replay.add_input(12, player(true, 3, true));
+    replay.add_input(15, player(true, 2, false));
+    replay.add_input(18, player(false, 2, false));
+    replay.add_input(30, InputData::TPS(480.0));
+    replay.add_input(40, player(false, 1, false));
+    replay.add_input(50, InputData::Restart);
+    replay.add_input(60, player(true, 1, false));
+
+    let cp = checkpoint(&replay, 20);
+    assert_eq!(
+        cp.held,
+        vec![
+            PlayerInput::new(Button::Jump, true, false),
+            PlayerInput::new(Button::Right, true, true),
+        ]
+    );
+    assert_eq!(cp.replay.tps, 240.0);
+    assert_eq!(cp.replay.inputs[0].frame, 10);
+
+    let primed = cp.into_primed();
+    let inputs: Vec<_> = primed
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.data.clone()))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (0, player(true, 1, false)),
+            (0, player(true, 3, true)),
+            (10, InputData::TPS(480.0)),
+            (20, player(false, 1, false)),
+            (30, InputData::Restart),
+            (40, player(true, 1, false)),
+        ]
+    );
+    assert_eq!(
+        HoldIndex::from(&primed).is_held(15, 1, false),
+        HoldIndex::from(&replay).is_held(35, 1, false)
+    );
+
+    let after_restart = checkpoint(&replay, 55);
+    assert!(after_restart.held.is_empty());
+    assert_eq!(after_restart.replay.tps, 480.0);
+    assert_eq!(checkpoint(&replay, 60).held, vec![]);
+    assert_eq!(checkpoint(&replay, 61).held.len(), 1);
+}
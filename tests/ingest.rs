@@ -0,0 +1,98 @@
+use std::io::Cursor;
+
+use slc_oxide::ingest::{content_hash, ingest, IngestError, IngestOptions, StripReason};
+use slc_oxide::replay::Version;
+use slc_oxide::v3::atom::{AtomId, AtomVariant, NullAtom};
+use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+use slc_oxide::v3::session::SessionAtom;
+use slc_oxide::v3::{ActionType, Metadata, Replay};
+
+#[test]
+fn test_ingest_v3() {
+    let mut actions = ActionAtom::new();
+    actions
+        .add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    actions
+        .add_player_action(20, ActionType::Jump, false, false)
+        .unwrap();
+    actions
+        .add_death_action(30, ActionType::Restart, 7)
+        .unwrap();
+    let session = SessionAtom::from_actions(&actions);
+
+    let mut upload = Replay::new(Metadata::new(240.0, 0, 1));
+    upload.add_atom(AtomVariant::Null(NullAtom { size: 4 }));
+    upload.add_atom(AtomVariant::Action(actions.clone()));
+    upload.add_atom(AtomVariant::Action(ActionAtom::new()));
+    upload.add_atom(AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 3)));
+    upload.add_atom(AtomVariant::Session(session));
+    let mut bytes = Vec::new();
+    upload.write(&mut bytes).unwrap();
+
+    let options = IngestOptions {
+        max_atom_size: 16,
+        ..Default::default()
+    };
+    let ingested = ingest(&bytes, &options).unwrap();
+    let report = &ingested.report;
+    assert_eq!(report.source, Version::V3);
+    assert_eq!(report.uploaded_bytes, bytes.len());
+    assert_eq!(report.actions, 3);
+    let stripped: Vec<_> = report
+        .stripped
+        .iter()
+        .map(|s| (s.index, s.id, s.reason))
+        .collect();
+    assert_eq!(
+        stripped,
+        vec![
+            (0, AtomId::Null, StripReason::Disallowed),
+            (2, AtomId::Action, StripReason::Empty),
+            (4, AtomId::Session, StripReason::TooLarge),
+        ]
+    );
+
+    let ids: Vec<_> = ingested.replay.atoms.iter().map(|a| a.id()).collect();
+    assert_eq!(ids, vec![AtomId::Action, AtomId::Startpos]);
+    assert_eq!(report.encoded_bytes, ingested.bytes.len());
+    assert_eq!(report.hash, content_hash(&ingested.bytes));
+    assert_eq!(
+        Replay::read(&mut Cursor::new(&ingested.bytes)).unwrap(),
+        ingested.replay
+    );
+
+    // Ingesting the cleaned replay again changes nothing.
+    let again = ingest(&ingested.bytes, &options).unwrap();
+    assert!(again.report.stripped.is_empty());
+    assert_eq!(again.report.hash, report.hash);
+}
+
+#[test]
+fn test_ingest_limits() {
+    let mut v2 = slc_oxide::Replay::<()>::new(240.0, ());
+    v2.add_input(5, slc_oxide::InputData::Restart);
+    let mut bytes = Vec::new();
+    v2.write(&mut bytes).unwrap();
+
+    let ingested = ingest(&bytes, &IngestOptions::default()).unwrap();
+    assert_eq!(ingested.report.source, Version::V2);
+    assert_eq!(ingested.report.actions, 1);
+
+    let small = IngestOptions {
+        max_bytes: bytes.len() - 1,
+        ..Default::default()
+    };
+    assert!(matches!(
+        ingest(&bytes, &small),
+        Err(IngestError::TooLarge(_))
+    ));
+    assert!(matches!(
+        ingest(b"not a replay", &IngestOptions::default()),
+        Err(IngestError::UnknownFormat)
+    ));
+    assert!(ingest(&bytes[..bytes.len() - 2], &IngestOptions::default()).is_err());
+
+    assert_eq!(content_hash(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(content_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+}
@@ -0,0 +1,51 @@
+use slc_oxide::invariants::{check, check_v3, InvariantError};
+use slc_oxide::testing::{generate_replay, generate_replay_v3, Profile};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::{InputData, PlayerInput};
+
+#[test]
+fn test_generated_replays_hold() {
+    for profile in [
+        Profile::default(),
+        Profile::spam(),
+        Profile::dual(),
+        Profile::platformer(),
+    ] {
+        let profile = profile.with_frames(240 * 20);
+        assert_eq!(check(&generate_replay(5, &profile)), Ok(()));
+        assert_eq!(check_v3(&generate_replay_v3(5, &profile)), Ok(()));
+    }
+}
+
+#[test]
+fn test_broken_replays() {
+    let mut replay = generate_replay(5, &Profile::default());
+
+    replay.inputs[3].frame += 1_000_000;
+    assert!(matches!(
+        check(&replay),
+        Err(InvariantError::DeltaMismatch { index: 3, .. })
+    ));
+    replay.inputs[3].delta += 1_000_000;
+    assert_eq!(check(&replay), Err(InvariantError::FrameOrder { index: 4 }));
+
+    let mut replay = generate_replay(5, &Profile::default());
+    replay.inputs[2].data = InputData::Player(PlayerInput {
+        hold: true,
+        player_2: false,
+        button: 7,
+    });
+    assert_eq!(
+        check(&replay),
+        Err(InvariantError::InvalidButton { index: 2 })
+    );
+
+    let mut v3 = generate_replay_v3(5, &Profile::default());
+    if let Some(AtomVariant::Action(atom)) = v3.atoms.atoms.first_mut() {
+        atom.actions[1].frame += 1;
+    }
+    assert!(matches!(
+        check_v3(&v3),
+        Err(InvariantError::DeltaMismatch { index: 1, .. })
+    ));
+}
@@ -0,0 +1,120 @@
+use slc_oxide::layout::{
+    delta_sizes, delta_sizes_v3, snapshot, snapshot_v3, Layout, SectionLayout, SizeDistribution,
+};
+use slc_oxide::v3::section::SpecialType;
+use slc_oxide::v3::WriteOptions;
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+fn replay() -> Replay<()> {
+    let mut replay = Replay::new(240.0, ());
+    for i in 0..16 {
+        let input = PlayerInput::new(Button::Jump, i % 2 == 0, false);
+        replay.add_input(100 + i * 4, InputData::Player(input));
+    }
+    replay.add_input(5_000, InputData::TPS(480.0));
+    replay.add_input(5_000, InputData::Death);
+    replay.add_input(1_000_000, InputData::Restart);
+    replay
+}
+
+#[test]
+fn test_v2_layout() {
+    let layout = snapshot(&replay());
+    assert_eq!(
+        layout.to_string(),
+        "blob 2B 0..1\nblob 1B 1..16\nblob 8B 16..19\n"
+    );
+    let Layout::V2(blobs) = layout else {
+        panic!("expected a v2 layout");
+    };
+    assert_eq!(blobs.iter().map(|b| b.length).sum::<u64>(), 19);
+}
+
+#[test]
+fn test_v3_layout() {
+    let v3 = replay().to_v3();
+    let layout = snapshot_v3(&v3, &WriteOptions::default());
+    assert_eq!(
+        layout.to_string(),
+        "atom Action
+  input 2B x1
+  repeat 1B x2 *2
+  input 1B x4
+  input 1B x4
+  input 1B x2
+  input 1B x1
+  special TPS 2B
+  special Death 1B
+  special Restart 4B
+"
+    );
+
+    let Layout::V3(atoms) = &layout else {
+        panic!("expected a v3 layout");
+    };
+    assert_eq!(
+        atoms[0].sections.last(),
+        Some(&SectionLayout::Special {
+            kind: SpecialType::Restart,
+            delta_size: 4
+        })
+    );
+
+    // Layouts are deterministic, and follow the write options.
+    assert_eq!(layout, snapshot_v3(&v3, &WriteOptions::default()));
+    let mut same_frame = replay();
+    same_frame.add_input(
+        1_000_010,
+        InputData::Player(PlayerInput::new(Button::Jump, true, false)),
+    );
+    same_frame.add_input(
+        1_000_010,
+        InputData::Player(PlayerInput::new(Button::Jump, false, false)),
+    );
+    let v3 = same_frame.to_v3();
+    let no_swift = WriteOptions {
+        swift: false,
+        ..WriteOptions::default()
+    };
+    assert_ne!(
+        snapshot_v3(&v3, &WriteOptions::default()),
+        snapshot_v3(&v3, &no_swift)
+    );
+}
+
+#[test]
+fn test_delta_sizes() {
+    let replay = replay();
+    let sizes = delta_sizes(&replay);
+    assert_eq!(sizes.len(), replay.inputs.len());
+    assert!(sizes.iter().enumerate().all(|(i, s)| s.index == i));
+
+    // The tps change needs 8 bytes and widens the death after it.
+    assert_eq!(sizes[16].required, 8);
+    assert!(sizes[17].widened());
+    assert!(!sizes[0].widened());
+
+    let distribution = SizeDistribution::new(&sizes);
+    assert_eq!(distribution.count(1), 15);
+    assert_eq!(distribution.count(2), 1);
+    assert_eq!(distribution.count(8), 3);
+    assert_eq!(distribution.wide(), 3);
+    assert_eq!(distribution.total(), 19);
+    assert_eq!(distribution.to_string(), "1B 15\n2B 1\n4B 0\n8B 3\n");
+}
+
+#[test]
+fn test_delta_sizes_v3() {
+    let v3 = replay().to_v3();
+    let sizes = delta_sizes_v3(&v3);
+    assert!(sizes.iter().all(|s| !s.widened()));
+
+    // Sizes match the sections the encoder writes.
+    let distribution = SizeDistribution::new(&sizes);
+    assert_eq!(distribution.count(4), 1);
+    assert_eq!(
+        sizes.last().map(|s| (s.frame, s.bytes)),
+        Some((1_000_000, 4))
+    );
+    assert_eq!(distribution.total(), sizes.len() as u64);
+}
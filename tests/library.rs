@@ -0,0 +1,68 @@
+use slc_oxide::library::{Library, SortKey};
+use slc_oxide::replay::Version;
+use slc_oxide::Replay;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[test]
+fn test_library_scan_matches_full_decode() {
+    let macro_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("macros");
+
+    let mut library = Library::scan(&macro_dir).unwrap();
+    assert_eq!(library.len(), 3);
+    assert!(library.errors.is_empty());
+
+    for entry in &library.entries {
+        let mut reader = BufReader::new(File::open(&entry.path).unwrap());
+        let replay = Replay::<Vec<u8>>::read(&mut reader).unwrap();
+
+        assert_eq!(entry.tps, replay.tps);
+        assert_eq!(entry.input_count, replay.inputs.len() as u64);
+        assert_eq!(
+            entry.meta_size,
+            (entry.version == Version::V2).then_some(replay.meta.len() as u64)
+        );
+    }
+
+    assert_eq!(library.search_level("nullscapes").count(), 1);
+    assert_eq!(
+        library.with_tps(240.0).count(),
+        library.filter(|e| e.tps == 240.0).count()
+    );
+
+    library.sort_by(SortKey::Length, false);
+    assert!(library
+        .entries
+        .windows(2)
+        .all(|w| w[0].input_count >= w[1].input_count));
+}
+
+#[test]
+fn test_library_entry_counts_actions_after_other_atoms() {
+    use slc_oxide::library::LibraryEntry;
+    use slc_oxide::v3::atom::AtomVariant;
+    use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+    use slc_oxide::v3::{ActionType, Metadata};
+
+    let mut replay = slc_oxide::v3::Replay::new(Metadata::new(240.0, 7, 1));
+    replay.add_atom(AtomVariant::Startpos(StartposAtom::new(10.0, 20.0, 300)));
+    let mut atom = ActionAtom::new();
+    for frame in [100, 150, 200] {
+        atom.add_player_action(frame, ActionType::Jump, frame != 150, false)
+            .unwrap();
+    }
+    replay.add_atom(AtomVariant::Action(atom));
+
+    let path = std::env::temp_dir().join(format!("slc_library_test_{}.slc3", std::process::id()));
+    replay.write(&mut File::create(&path).unwrap()).unwrap();
+    let entry = LibraryEntry::read(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let entry = entry.unwrap();
+    assert_eq!(entry.version, Version::V3);
+    assert_eq!(entry.seed, Some(7));
+    assert_eq!(entry.input_count, 3);
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "net")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+
+use slc_oxide::net::{Client, NetError};
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+/// Answer one request per response, returning the bodies received.
+fn serve(
+    responses: Vec<(&'static str, Vec<u8>)>,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}/api/", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (content_type, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut payload = vec![0; length];
+            reader.read_exact(&mut payload).unwrap();
+            request.push_str(&String::from_utf8_lossy(&payload));
+            requests.push(request);
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content_type,
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+        requests
+    });
+    (base, handle)
+}
+
+#[test]
+fn test_share_roundtrip() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, InputData::Skip);
+    replay.add_input(
+        20,
+        InputData::Player(PlayerInput::new(Button::Jump, true, false)),
+    );
+    replay.add_input(
+        30,
+        InputData::Player(PlayerInput::new(Button::Jump, false, false)),
+    );
+
+    let mut canonical = replay.clone();
+    slc_oxide::diff::normalize(&mut canonical);
+    let mut bytes = Vec::new();
+    canonical.write(&mut bytes).unwrap();
+
+    let (base, server) = serve(vec![
+        ("application/json", br#"{"id": "abc123"}"#.to_vec()),
+        ("application/octet-stream", bytes.clone()),
+        ("application/octet-stream", bytes.clone()),
+    ]);
+    let client = Client::new(&base);
+    let id = client
+        .upload(&replay, &serde_json::json!({ "level": 128 }))
+        .unwrap();
+    assert_eq!(id, "abc123");
+
+    let shared = client.download::<()>(&id).unwrap();
+    assert_eq!(shared.inputs, canonical.inputs);
+    assert!(matches!(
+        client.with_max_download(8).download::<()>(&id),
+        Err(NetError::TooLarge(8))
+    ));
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("POST /api/macros "));
+    assert!(requests[0].contains("multipart/form-data; boundary="));
+    assert!(requests[0].contains("name=\"metadata\""));
+    assert!(requests[0].contains(r#"{"level":128}"#));
+    assert!(requests[0].contains(&String::from_utf8_lossy(&bytes).into_owned()));
+    assert!(requests[1].starts_with("GET /api/macros/abc123 "));
+}
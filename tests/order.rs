@@ -0,0 +1,84 @@
+use slc_oxide::diff::normalize_with;
+use slc_oxide::order::SameFrameOrder;
+use slc_oxide::v3::{Action, ActionType};
+use slc_oxide::{Button, InputData, PlayerInput, Replay};
+
+fn press(button: Button, player_2: bool) -> InputData {
+    InputData::Player(PlayerInput::new(button, true, player_2))
+}
+
+fn sample() -> Replay<()> {
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(10, press(Button::Right, true));
+    replay.add_input(10, press(Button::Jump, false));
+    replay.add_input(10, press(Button::Jump, true));
+    replay.add_input(10, InputData::Restart);
+    replay.add_input(10, press(Button::Left, true));
+    replay.add_input(10, press(Button::Left, false));
+    replay.add_input(20, press(Button::Right, false));
+    replay
+}
+
+fn order(replay: &Replay<()>) -> Vec<InputData> {
+    replay.iter().map(|i| i.data.clone()).collect()
+}
+
+#[test]
+fn test_same_frame_order_inputs() {
+    let mut replay = sample();
+    normalize_with(&mut replay, SameFrameOrder::Insertion);
+    assert_eq!(replay, sample());
+
+    let mut replay = sample();
+    normalize_with(&mut replay, SameFrameOrder::PlayerFirst);
+    assert_eq!(
+        order(&replay),
+        [
+            press(Button::Jump, false),
+            press(Button::Right, true),
+            press(Button::Jump, true),
+            InputData::Restart,
+            press(Button::Left, false),
+            press(Button::Left, true),
+            press(Button::Right, false),
+        ]
+    );
+
+    let mut replay = sample();
+    normalize_with(&mut replay, SameFrameOrder::ButtonFirst);
+    assert_eq!(
+        order(&replay)[..3],
+        [
+            press(Button::Jump, false),
+            press(Button::Jump, true),
+            press(Button::Right, true),
+        ]
+    );
+    assert_eq!(replay.inputs[6].delta, 10);
+}
+
+#[test]
+fn test_same_frame_order_actions() {
+    let mut actions = vec![
+        Action::player(5, 0, ActionType::Right, true, false),
+        Action::player(0, 5, ActionType::Left, true, true),
+        Action::player(5, 0, ActionType::Jump, true, true),
+        Action::player(5, 0, ActionType::Jump, true, false),
+    ];
+    actions[3].subframe = 100;
+
+    SameFrameOrder::ButtonFirst.sort_actions(&mut actions);
+    let sorted: Vec<_> = actions
+        .iter()
+        .map(|a| (a.frame, a.action_type, a.player2))
+        .collect();
+    assert_eq!(
+        sorted,
+        [
+            (5, ActionType::Jump, true),
+            (5, ActionType::Left, true),
+            (5, ActionType::Right, false),
+            (5, ActionType::Jump, false),
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+use slc_oxide::pack::{Pack, PackError};
+use slc_oxide::replay::Version;
+use slc_oxide::v3::{ActionType, Metadata};
+use slc_oxide::{InputData, PlayerInput, Replay};
+use std::io::Cursor;
+
+#[test]
+fn test_pack_roundtrip() {
+    let mut v2 = Replay::<Vec<u8>>::new(240.0, vec![1, 2, 3]);
+    v2.add_input(
+        10,
+        InputData::Player(PlayerInput {
+            hold: true,
+            player_2: false,
+            button: 1,
+        }),
+    );
+
+    let mut v3 = slc_oxide::v3::Replay::new(Metadata::new(360.0, 42, 1));
+    let mut atom = slc_oxide::v3::builtin::ActionAtom::new();
+    atom.add_player_action(20, ActionType::Left, true, true)
+        .unwrap();
+    v3.add_atom(slc_oxide::v3::atom::AtomVariant::Action(atom));
+
+    let mut pack = Pack::new();
+    pack.add_replay("segment_0.slc", &v2).unwrap();
+    pack.add_v3("segment_1.slc", &v3).unwrap();
+    assert!(matches!(
+        pack.add_replay("segment_0.slc", &v2),
+        Err(PackError::DuplicateMember(_))
+    ));
+
+    let mut buffer = Vec::new();
+    pack.write(&mut buffer).unwrap();
+
+    let loaded = Pack::read(&mut Cursor::new(&buffer)).unwrap();
+    assert_eq!(loaded, pack);
+
+    let versions: Vec<_> = loaded.iter().map(|m| m.version()).collect();
+    assert_eq!(versions, vec![Some(Version::V2), Some(Version::V3)]);
+
+    let member = loaded.get("segment_0.slc").unwrap();
+    let replay = member.replay::<Vec<u8>>().unwrap();
+    assert_eq!(replay.meta, vec![1, 2, 3]);
+    assert_eq!(replay.inputs, v2.inputs);
+
+    let member = Pack::read_member(&mut Cursor::new(&buffer), "segment_1.slc").unwrap();
+    assert_eq!(member.replay_v3().unwrap().metadata.seed, 42);
+    assert!(matches!(
+        Pack::read_member(&mut Cursor::new(&buffer), "missing"),
+        Err(PackError::MissingMember(_))
+    ));
+
+    let dir = std::env::temp_dir().join(format!("slc_pack_test_{}", std::process::id()));
+    loaded.extract_to(&dir).unwrap();
+    assert_eq!(
+        std::fs::read(dir.join("segment_1.slc")).unwrap(),
+        loaded.get("segment_1.slc").unwrap().data
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pack_corrupt_toc() {
+    let mut pack = Pack::new();
+    pack.add("a.slc", vec![1, 2, 3, 4]).unwrap();
+    pack.add("b.slc", vec![5, 6]).unwrap();
+    let mut buffer = Vec::new();
+    pack.write(&mut buffer).unwrap();
+
+    // The second entry's offset and length follow its 5 byte name.
+    let entry = 8 + 8 + (2 + 5 + 16) + 2 + 5;
+    for (offset, length) in [(u64::MAX, 2), (4, u64::MAX), (4, 3), (5, 2)] {
+        let mut corrupt = buffer.clone();
+        corrupt[entry..entry + 8].copy_from_slice(&offset.to_le_bytes());
+        corrupt[entry + 8..entry + 16].copy_from_slice(&length.to_le_bytes());
+
+        assert!(matches!(
+            Pack::read(&mut Cursor::new(&corrupt)),
+            Err(PackError::MemberOutOfBounds(name)) if name == "b.slc"
+        ));
+        assert!(matches!(
+            Pack::read_member(&mut Cursor::new(&corrupt), "b.slc"),
+            Err(PackError::MemberOutOfBounds(_))
+        ));
+        let member = Pack::read_member(&mut Cursor::new(&corrupt), "a.slc").unwrap();
+        assert_eq!(member.data, vec![1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::Cursor;
+
+use slc_oxide::patch::{patch_metadata, patch_metadata_in, read_metadata, FileMeta};
+use slc_oxide::{InputData, Replay, ReplayError};
+
+fn replay() -> Replay<Vec<u8>> {
+    let mut replay = Replay::new(240.0, b"name".to_vec());
+    replay.add_input(10, InputData::Restart);
+    replay.add_input(500, InputData::Death);
+    replay
+}
+
+#[test]
+fn test_patch_v2_meta() {
+    let mut bytes = Vec::new();
+    replay().write(&mut bytes).unwrap();
+    let mut file = Cursor::new(bytes);
+
+    let patched = patch_metadata_in(&mut file, |meta| {
+        meta.set_tps(480.0);
+        if let FileMeta::V2 { meta, .. } = meta {
+            meta.copy_from_slice(b"rnmd");
+        }
+    })
+    .unwrap();
+    assert_eq!(patched.tps(), 480.0);
+
+    let loaded = Replay::<Vec<u8>>::read(&mut Cursor::new(file.get_ref())).unwrap();
+    assert_eq!(loaded.tps, 480.0);
+    assert_eq!(loaded.meta, b"rnmd");
+    assert_eq!(loaded.inputs, replay().inputs);
+
+    // A meta of another size doesn't fit, and leaves the file alone.
+    let before = file.get_ref().clone();
+    let result = patch_metadata_in(&mut file, |meta| {
+        if let FileMeta::V2 { meta, .. } = meta {
+            meta.push(0);
+        }
+    });
+    assert!(matches!(result, Err(ReplayError::MetaSizeMismatchError)));
+    assert_eq!(file.get_ref(), &before);
+}
+
+#[test]
+fn test_patch_v3_file() {
+    let path = std::env::temp_dir().join(format!("slc_patch_test_{}.slc3", std::process::id()));
+    let mut bytes = Vec::new();
+    replay().write_v3(&mut bytes).unwrap();
+    std::fs::write(&path, &bytes).unwrap();
+
+    patch_metadata(&path, |meta| {
+        if let FileMeta::V3(metadata) = meta {
+            metadata.seed = 1234;
+        }
+    })
+    .unwrap();
+
+    let patched = std::fs::read(&path).unwrap();
+    assert_eq!(patched.len(), bytes.len());
+    let FileMeta::V3(metadata) = read_metadata(&mut Cursor::new(&patched)).unwrap() else {
+        panic!("expected v3 metadata");
+    };
+    assert_eq!(metadata.seed, 1234);
+    let loaded = slc_oxide::v3::Replay::read(&mut Cursor::new(&patched)).unwrap();
+    assert_eq!(loaded.atoms, replay().to_v3().atoms);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,21 @@
+use slc_oxide::prelude::*;
+use std::io::Cursor;
+
+#[test]
+fn test_prelude_covers_both_versions() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(
+        10,
+        InputData::Player(PlayerInput::new(Button::Jump, true, false)),
+    );
+
+    let mut buffer = Vec::new();
+    replay.write_v3(&mut buffer).unwrap();
+
+    let v3 = ReplayV3::read(&mut Cursor::new(buffer)).unwrap();
+    let AtomVariant::Action(atom) = &v3.atoms.atoms[0] else {
+        panic!("Expected ActionAtom");
+    };
+    assert_eq!(atom.actions[0].action_type, ActionType::Jump);
+    assert_eq!(v3.metadata, Metadata::new(240.0, 0, 1));
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "profile")]
+
+use std::io::Cursor;
+
+use slc_oxide::order::SameFrameOrder;
+use slc_oxide::profile::{OutputFormat, Profile, ProfileError, TransformSpec};
+use slc_oxide::transform::HumanizeOptions;
+use slc_oxide::v3::{AnalogPrecision, WriteOptions};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn jump(hold: bool) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button: 1,
+    })
+}
+
+fn sample() -> Replay<()> {
+    let mut replay = Replay::new(240.0, ());
+    replay.add_input(5, jump(true));
+    replay.add_input(5, jump(false));
+    replay.add_input(10, InputData::TPS(240.0));
+    replay.add_input(20, InputData::Skip);
+    replay.add_input(5000, InputData::Death);
+    replay
+}
+
+#[test]
+fn test_profile_formats_agree() {
+    let toml = r#"
+        format = "slc3"
+
+        [write]
+        swift = false
+
+        [limits]
+        max_tps = 1000.0
+
+        [[transforms]]
+        type = "dedup_tps"
+
+        [[transforms]]
+        type = "humanize"
+        max_jitter = 0
+
+        [[transforms]]
+        type = "trim"
+        to_frame = 1000
+    "#;
+    let json = r#"{
+        "format": "v3",
+        "write": { "swift": false },
+        "limits": { "max_tps": 1000.0 },
+        "transforms": [
+            { "type": "dedup_tps" },
+            { "type": "humanize", "max_jitter": 0 },
+            { "type": "trim", "to_frame": 1000 }
+        ]
+    }"#;
+
+    let profile = Profile::from_toml(toml).unwrap();
+    assert_eq!(profile, Profile::from_json(json).unwrap());
+    assert_eq!(profile.format, Some(OutputFormat::V3));
+    assert_eq!(
+        profile.write,
+        WriteOptions {
+            swift: false,
+            analog_precision: AnalogPrecision::Float,
+            gc: None,
+        }
+    );
+    assert_eq!(profile.limits.max_tps, 1000.0);
+    assert_eq!(profile.limits.max_frame, u64::MAX);
+    assert_eq!(
+        profile.transforms[1],
+        TransformSpec::Humanize(HumanizeOptions {
+            max_jitter: 0,
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_profile_convert() {
+    let profile = Profile::from_toml(
+        r#"
+        format = "v3"
+        write = { swift = false }
+        transforms = [
+            { type = "normalize" },
+            { type = "dedup_tps" },
+            { type = "trim", to_frame = 1000 },
+        ]
+        "#,
+    )
+    .unwrap();
+
+    let mut input = Vec::new();
+    sample().write(&mut input).unwrap();
+    let mut output = Vec::new();
+    let report = profile
+        .convert::<(), _, _>(&mut Cursor::new(input), &mut output)
+        .unwrap();
+    assert_eq!((report.inputs_before, report.inputs_after), (5, 2));
+
+    let mut expected = sample();
+    profile.apply(&mut expected).unwrap();
+    let mut v3 = Vec::new();
+    expected
+        .to_v3()
+        .write_with_options(&mut v3, &profile.write)
+        .unwrap();
+    assert_eq!(output, v3);
+
+    let read = Replay::<()>::read(&mut Cursor::new(output)).unwrap();
+    assert_eq!(read.inputs, expected.inputs);
+}
+
+#[test]
+fn test_profile_limits() {
+    let profile = Profile::from_json(r#"{ "limits": { "max_frame": 1000 } }"#).unwrap();
+    let mut replay = sample();
+    assert!(matches!(
+        profile.apply(&mut replay),
+        Err(ProfileError::LimitExceeded("frame"))
+    ));
+
+    let profile = Profile::from_json(
+        r#"{ "limits": { "max_frame": 1000 }, "transforms": [{ "type": "rebase", "start_frame": 4500 }] }"#,
+    )
+    .unwrap();
+    let mut replay = sample();
+    profile.apply(&mut replay).unwrap();
+    assert_eq!(replay.inputs[0].frame, 500);
+
+    let profile =
+        Profile::from_toml("transforms = [{ type = \"normalize\", order = \"button_first\" }]")
+            .unwrap();
+    assert_eq!(
+        profile.transforms,
+        [TransformSpec::Normalize {
+            order: SameFrameOrder::ButtonFirst
+        }]
+    );
+
+    assert!(matches!(
+        Profile::from_json(r#"{ "transforms": [{ "type": "explode" }] }"#),
+        Err(ProfileError::Json(_))
+    ));
+}
+
+#[test]
+fn test_profile_dry_run() {
+    let profile = Profile::from_json(
+        r#"{ "format": "v3", "transforms": [{ "type": "trim", "to_frame": 1000 }] }"#,
+    )
+    .unwrap();
+    let replay = sample();
+    let preview = profile.dry_run(&replay).unwrap();
+    assert_eq!(replay.inputs.len(), 5);
+    assert_eq!(preview.format, OutputFormat::V3);
+    assert_eq!(preview.transforms.report.removed(), 1);
+    assert_eq!(preview.transforms.changes.divergences.len(), 1);
+
+    let mut output = Vec::new();
+    let mut converted = replay.clone();
+    profile.apply(&mut converted).unwrap();
+    profile.write(&converted, &mut output).unwrap();
+    assert_eq!(preview.bytes, output.len() as u64);
+    assert_eq!(converted.encoded_size_v3().unwrap(), preview.bytes);
+
+    let mut v2 = Vec::new();
+    replay.write(&mut v2).unwrap();
+    assert_eq!(replay.encoded_size().unwrap(), v2.len() as u64);
+}
@@ -0,0 +1,86 @@
+use slc_oxide::repair::{
+    find_special_issues, repair_specials, RepairMode, RepairOptions, SpecialIssueKind,
+};
+use slc_oxide::{Button, Input, InputData, PlayerInput, Replay};
+
+/// A replay as written by a buggy bot, with frames and deltas set by hand.
+fn broken_replay() -> Replay<()> {
+    let mut replay = Replay::<()>::new(240.0, ());
+    let jump = |hold| InputData::Player(PlayerInput::new(Button::Jump, hold, false));
+    for (frame, data) in [
+        (10, InputData::TPS(0.0)),
+        (20, jump(true)),
+        (30, jump(false)),
+        (25, InputData::Restart),
+        (40, jump(true)),
+        (900, InputData::Death),
+    ] {
+        replay.inputs.push(Input {
+            delta: 0,
+            frame,
+            data,
+        });
+    }
+    replay
+}
+
+#[test]
+fn test_find_special_issues() {
+    let replay = broken_replay();
+    let issues = find_special_issues(&replay, Some(500));
+    let found: Vec<_> = issues.iter().map(|i| (i.kind, i.index)).collect();
+    assert_eq!(
+        found,
+        [
+            (SpecialIssueKind::InvalidTps, 0),
+            (SpecialIssueKind::RestartBackwards, 3),
+            (SpecialIssueKind::DeathAfterEnd, 5),
+        ]
+    );
+
+    // Without an end, trailing deaths are fine.
+    assert_eq!(find_special_issues(&replay, None).len(), 2);
+
+    let mut clean = Replay::<()>::new(240.0, ());
+    clean.add_input(10, InputData::Restart);
+    clean.add_input(20, InputData::Death);
+    assert!(find_special_issues(&clean, None).is_empty());
+}
+
+#[test]
+fn test_repair_specials_fix() {
+    let mut replay = broken_replay();
+    let options = RepairOptions::default().with_end_frame(500);
+    let report = repair_specials(&mut replay, &options);
+    assert_eq!((report.fixed, report.stripped), (3, 0));
+
+    assert_eq!(replay.inputs.len(), 6);
+    assert_eq!(replay.inputs[0].data, InputData::TPS(240.0));
+    assert_eq!(replay.inputs[3].data, InputData::Restart);
+    assert_eq!(replay.inputs[3].frame, 30);
+    assert_eq!(replay.inputs[5].frame, 500);
+    assert!(replay.inputs.windows(2).all(|w| w[0].frame <= w[1].frame));
+    assert_eq!(replay.inputs[5].delta, 460);
+
+    // Repaired replays have nothing left to repair.
+    assert!(repair_specials(&mut replay, &options).is_clean());
+}
+
+#[test]
+fn test_repair_specials_strip() {
+    let mut replay = broken_replay();
+    let options = RepairOptions::default()
+        .with_mode(RepairMode::Strip)
+        .with_end_frame(500);
+    let report = repair_specials(&mut replay, &options);
+    assert_eq!((report.fixed, report.stripped), (0, 3));
+    assert_eq!(replay.inputs.len(), 3);
+    assert!(replay
+        .inputs
+        .iter()
+        .all(|i| matches!(i.data, InputData::Player(_))));
+    assert_eq!(
+        report.to_string().lines().last(),
+        Some("0 fixed, 3 stripped")
+    );
+}
@@ -0,0 +1,456 @@
+use slc_oxide::replay::ReplayError;
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::{InputData, PlayerInput, Replay};
+use std::io::Cursor;
+
+fn player(hold: bool, button: u8) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button,
+    })
+}
+
+#[test]
+fn test_skip_management() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1));
+    replay.add_input(12, player(false, 1));
+    replay.add_input(70000, player(true, 1));
+    replay.add_input(70002, player(false, 1));
+
+    replay.insert_skip(12);
+    replay.insert_skip(40000);
+    assert_eq!(replay.inputs[2].data, InputData::Skip);
+    assert_eq!(replay.inputs[2].delta, 0);
+    assert_eq!(replay.inputs[3].frame, 40000);
+    assert_eq!(replay.inputs[4].delta, 30000);
+    assert_eq!(replay.inputs[4].delta(), 30000);
+
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer).unwrap();
+    let loaded = Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(loaded.inputs, replay.inputs);
+
+    // Skips are kept as skip actions in v3.
+    let mut v3_buffer = Vec::new();
+    replay.write_v3(&mut v3_buffer).unwrap();
+    let v3 = slc_oxide::v3::Replay::read(&mut Cursor::new(&v3_buffer)).unwrap();
+    let AtomVariant::Action(atom) = &v3.atoms.atoms[0] else {
+        panic!("Expected ActionAtom");
+    };
+    assert_eq!(atom.actions.len(), 6);
+    assert_eq!(atom.actions[2].action_type, slc_oxide::v3::ActionType::Skip);
+    let from_v3 = Replay::<()>::read(&mut Cursor::new(&v3_buffer)).unwrap();
+    assert_eq!(from_v3.inputs, replay.inputs);
+
+    assert_eq!(replay.strip_skips(), 2);
+    assert_eq!(replay.inputs.len(), 4);
+    assert_eq!(replay.inputs[2].delta, 69988);
+}
+
+#[test]
+fn test_append_inserts_tps() {
+    let mut first = Replay::<()>::new(240.0, ());
+    first.add_input(10, player(true, 1));
+    first.add_input(20, player(false, 1));
+
+    let mut second = Replay::<()>::new(360.0, ());
+    second.add_input(0, InputData::TPS(360.0));
+    second.add_input(5, player(true, 1));
+    second.add_input(8, player(false, 1));
+
+    first.append(&second, 100).unwrap();
+    let data: Vec<_> = first
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.data.clone()))
+        .collect();
+    assert_eq!(
+        data,
+        vec![
+            (10, player(true, 1)),
+            (20, player(false, 1)),
+            (100, InputData::TPS(360.0)),
+            (105, player(true, 1)),
+            (108, player(false, 1)),
+        ]
+    );
+    assert_eq!(first.tps_at(50), 240.0);
+    assert_eq!(first.tps_at(200), 360.0);
+
+    // Appending a replay at the same tps adds nothing.
+    let mut third = Replay::<()>::new(360.0, ());
+    third.add_input(1, InputData::TPS(360.0));
+    third.add_input(2, player(true, 2));
+    first.append(&third, 200).unwrap();
+    assert_eq!(first.inputs.len(), 6);
+    assert_eq!(first.inputs[5].frame, 202);
+    assert_eq!(first.inputs[5].delta, 94);
+}
+
+#[test]
+fn test_append_overlap() {
+    let mut first = Replay::<()>::new(240.0, ());
+    first.add_input(10, player(true, 1));
+    first.add_input(50, player(false, 1));
+
+    let mut second = Replay::<()>::new(240.0, ());
+    second.add_input(5, player(true, 2));
+    second.add_input(30, player(false, 2));
+
+    // Shifted by 20, the first input would land on frame 25, before frame 50.
+    let before = first.clone();
+    assert!(matches!(
+        first.append(&second, 20),
+        Err(ReplayError::AppendOverlap(25))
+    ));
+    assert_eq!(first, before);
+
+    assert!(matches!(
+        first.append(&second, u64::MAX - 10),
+        Err(ReplayError::LimitExceeded("frame"))
+    ));
+    assert_eq!(first, before);
+
+    // Landing exactly on the last frame is fine.
+    first.append(&second, 45).unwrap();
+    let frames: Vec<_> = first.inputs.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![10, 50, 50, 75]);
+}
+
+#[test]
+fn test_best_attempt() {
+    let mut replay = Replay::<Vec<u8>>::new(240.0, vec![7]);
+    replay.add_input(10, player(true, 1));
+    replay.add_input(50, InputData::Death);
+    replay.add_input(60, InputData::Restart);
+    replay.add_input(60, InputData::TPS(480.0));
+    replay.add_input(70, player(true, 1));
+    replay.add_input(75, player(false, 1));
+    replay.add_input(300, InputData::Death);
+    replay.add_input(310, InputData::RestartFull);
+    replay.add_input(320, player(true, 1));
+
+    let attempts = replay.attempts();
+    assert_eq!(attempts.len(), 3);
+    assert_eq!(attempts[1], (60, 3..7));
+
+    let best = replay.best_attempt().unwrap();
+    assert_eq!(best.tps, 480.0);
+    assert_eq!(best.meta, vec![7]);
+    let frames: Vec<_> = best.inputs.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![0, 10, 15, 240]);
+    assert_eq!(best.inputs[3].data, InputData::Death);
+
+    assert!(Replay::<()>::new(240.0, ()).best_attempt().is_none());
+}
+
+#[test]
+fn test_clone_and_eq() {
+    let mut replay = Replay::<Vec<u8>>::new(240.0, vec![1, 2]);
+    replay.add_input(10, player(true, 1));
+
+    let mut copy = replay.clone();
+    assert_eq!(copy, replay);
+
+    copy.add_input(20, player(false, 1));
+    assert_ne!(copy, replay);
+    assert!(format!("{:?}", replay).contains("tps: 240.0"));
+}
+
+#[test]
+fn test_semantic_equality() {
+    let mut replay = Replay::<Vec<u8>>::new(240.0, vec![1]);
+    replay.add_input(10, player(true, 1));
+    replay.add_input(10, player(false, 1));
+    replay.add_input(70000, InputData::Death);
+
+    let mut skipped = replay.clone();
+    skipped.insert_skip(30000);
+    assert!(replay.semantically_eq(&skipped));
+
+    // A v3 roundtrip loses the meta and turns the click into a swift pair.
+    let mut buffer = Vec::new();
+    replay.write_v3(&mut buffer).unwrap();
+    let v3 = Replay::<()>::read(&mut Cursor::new(&buffer)).unwrap();
+    assert!(replay.semantically_eq(&v3));
+
+    let v3_replay = slc_oxide::v3::Replay::read(&mut Cursor::new(&buffer)).unwrap();
+    let mut v3_buffer = Vec::new();
+    v3_replay
+        .write_with_options(
+            &mut v3_buffer,
+            &slc_oxide::v3::WriteOptions {
+                swift: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_ne!(buffer, v3_buffer);
+    let reread = slc_oxide::v3::Replay::read(&mut Cursor::new(&v3_buffer)).unwrap();
+    assert!(v3_replay.semantically_eq(&reread));
+
+    let mut moved = replay.clone();
+    moved.inputs[2].frame += 1;
+    assert!(!replay.semantically_eq(&moved));
+}
+
+#[test]
+fn test_iteration() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1));
+    replay.add_input(20, player(false, 1));
+
+    for input in &mut replay {
+        input.frame *= 2;
+    }
+    replay.recalculate_deltas();
+
+    let frames: Vec<_> = replay.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![20, 40]);
+    assert_eq!((&replay).into_iter().count(), 2);
+    assert_eq!(replay.into_iter().last().unwrap().delta, 20);
+}
+
+#[test]
+fn test_typed_buttons() {
+    use slc_oxide::input::InputError;
+    use slc_oxide::Button;
+
+    let input = PlayerInput::new(Button::Left, true, false);
+    assert_eq!(input.button, 2);
+    assert_eq!(input.button_type().unwrap(), Button::Left);
+    assert_eq!(Button::try_from(3).unwrap(), Button::Right);
+    assert!(matches!(
+        PlayerInput::try_new(4, true, false),
+        Err(InputError::InvalidButton)
+    ));
+
+    // Raw inputs with invalid buttons are rejected when writing.
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 5));
+    assert!(replay.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_duration() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    assert_eq!(replay.duration_seconds(), Some(0.0));
+
+    replay.add_input(240, player(true, 1));
+    replay.add_input(480, InputData::TPS(480.0));
+    replay.add_input(960, player(false, 1));
+    replay.add_input(960, InputData::TPS(60.0));
+
+    assert_eq!(replay.last_frame(), 960);
+    assert_eq!(replay.duration_frames(), 960);
+    // 480 frames at 240 tps, then 480 frames at 480 tps.
+    assert_eq!(replay.duration_seconds(), Some(3.0));
+
+    let mut buffer = Vec::new();
+    replay.write_v3(&mut buffer).unwrap();
+    let v3 = slc_oxide::v3::Replay::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(v3.last_frame(), 960);
+    assert_eq!(v3.duration_seconds(), Some(3.0));
+}
+
+#[test]
+fn test_duration_invalid_tps() {
+    for tps in [0.0, -240.0, f64::NAN, f64::INFINITY, 1e-320] {
+        let mut replay = Replay::<()>::new(tps, ());
+        assert_eq!(replay.duration_seconds(), Some(0.0));
+        replay.add_input(240, player(true, 1));
+        assert_eq!(replay.duration_seconds(), None);
+    }
+
+    // A change to an invalid tps only matters once frames pass at it.
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(240, InputData::TPS(0.0));
+    assert_eq!(replay.duration_seconds(), Some(1.0));
+    replay.add_input(480, player(true, 1));
+    assert_eq!(replay.duration_seconds(), None);
+}
+
+#[test]
+fn test_const_constructors() {
+    use slc_oxide::v3::{Action, ActionType, Metadata};
+    use slc_oxide::{Button, Input};
+
+    const JUMP: Input = Input::new(
+        100,
+        100,
+        InputData::Player(PlayerInput::new(Button::Jump, true, false)),
+    );
+    const ACTION: Action = Action::player(0, 100, ActionType::Jump, true, false);
+    const META: Metadata = Metadata::new(240.0, 0, 1);
+
+    assert_eq!(JUMP.frame, 100);
+    assert_eq!(JUMP.delta(), 100);
+    assert_eq!(ACTION.frame, 100);
+    assert_eq!(META.tps, 240.0);
+}
+
+#[test]
+fn test_bounded_read() {
+    use slc_oxide::{ReadOptions, ReplayError};
+
+    let mut replay = Replay::<Vec<u8>>::new(240.0, vec![7; 32]);
+    for frame in 0..100 {
+        replay.add_input(frame, player(frame % 2 == 0, 1));
+    }
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer).unwrap();
+
+    let read =
+        Replay::<Vec<u8>>::read_with_options(&mut Cursor::new(&buffer), &ReadOptions::untrusted())
+            .unwrap();
+    assert_eq!(read, replay);
+
+    let options = ReadOptions {
+        max_inputs: 50,
+        ..ReadOptions::default()
+    };
+    assert!(matches!(
+        Replay::<Vec<u8>>::read_with_options(&mut Cursor::new(&buffer), &options),
+        Err(ReplayError::LimitExceeded(_))
+    ));
+    let options = ReadOptions {
+        max_meta_size: 16,
+        ..ReadOptions::default()
+    };
+    assert!(matches!(
+        Replay::<Vec<u8>>::read_with_options(&mut Cursor::new(&buffer), &options),
+        Err(ReplayError::LimitExceeded(_))
+    ));
+
+    // A meta size far past the end of the file fails without allocating it.
+    let mut corrupt = buffer.clone();
+    corrupt[12..20].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+    assert!(Replay::<Vec<u8>>::read(&mut Cursor::new(&corrupt)).is_err());
+
+    // So does an absurd input count, and a blob with an invalid byte size.
+    let mut corrupt = buffer.clone();
+    corrupt[52..60].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Replay::<Vec<u8>>::read(&mut Cursor::new(&corrupt)).is_err());
+    let mut corrupt = buffer.clone();
+    corrupt[68..76].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Replay::<Vec<u8>>::read(&mut Cursor::new(&corrupt)).is_err());
+}
+
+#[test]
+fn test_mixed_input_widths() {
+    // Wide inputs followed by narrow ones, so stale bytes would show up in the frames.
+    let mut replay = Replay::new(240.0, ());
+    let mut frame = 0;
+    for i in 0..64u64 {
+        frame += if i % 16 < 8 { 1 << 40 } else { 1 };
+        replay.add_input(frame, player(i % 2 == 0, 1));
+        if i % 16 == 7 {
+            replay.add_input(frame, InputData::TPS(480.0 + i as f64));
+        }
+    }
+
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer).unwrap();
+    let decoded = Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(decoded.inputs, replay.inputs);
+}
+
+#[test]
+fn test_rebase() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1));
+    replay.add_input(20, InputData::TPS(480.0));
+    replay.add_input(30, player(false, 1));
+    replay.add_input(100, player(true, 1));
+    replay.add_input(100, InputData::TPS(360.0));
+    replay.add_input(150, InputData::Death);
+
+    let mut rebased = replay.clone();
+    assert_eq!(rebased.rebase(100), 3);
+    assert_eq!(rebased.tps, 480.0);
+    let inputs: Vec<_> = rebased
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.delta, i.data.clone()))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (0, 0, player(true, 1)),
+            (0, 0, InputData::TPS(360.0)),
+            (50, 50, InputData::Death),
+        ]
+    );
+
+    let mut unchanged = replay.clone();
+    assert_eq!(unchanged.rebase(0), 0);
+    assert_eq!(unchanged, replay);
+
+    let mut emptied = replay.clone();
+    assert_eq!(emptied.rebase(1000), 6);
+    assert!(emptied.inputs.is_empty());
+    assert_eq!(emptied.tps, 360.0);
+}
+
+#[test]
+fn test_limits() {
+    use slc_oxide::replay::Limits;
+    use slc_oxide::{ReadOptions, ReplayError};
+
+    let limits = Limits {
+        max_frame: 1000,
+        max_tps: 1000.0,
+        max_inputs: 3,
+    };
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay
+        .add_input_within(10, player(true, 1), &limits)
+        .unwrap();
+    assert!(matches!(
+        replay.add_input_within(1 << 60, player(false, 1), &limits),
+        Err(ReplayError::LimitExceeded("frame"))
+    ));
+    assert!(matches!(
+        replay.add_input_within(20, InputData::TPS(f64::NAN), &limits),
+        Err(ReplayError::LimitExceeded("tps"))
+    ));
+    replay
+        .add_input_within(20, InputData::TPS(480.0), &limits)
+        .unwrap();
+    replay
+        .add_input_within(30, player(false, 1), &limits)
+        .unwrap();
+    assert!(matches!(
+        replay.add_input_within(40, InputData::Death, &limits),
+        Err(ReplayError::LimitExceeded("inputs"))
+    ));
+    assert_eq!(replay.inputs.len(), 3);
+
+    replay.add_input(1 << 40, InputData::Death);
+    replay.add_input(1 << 40, InputData::TPS(1e9));
+    let mut v2 = Vec::new();
+    replay.write(&mut v2).unwrap();
+    let mut v3 = Vec::new();
+    replay.write_v3(&mut v3).unwrap();
+
+    for bytes in [&v2, &v3] {
+        assert!(Replay::<()>::read(&mut Cursor::new(bytes)).is_ok());
+        assert!(Replay::<()>::read_with_options(
+            &mut Cursor::new(bytes),
+            &ReadOptions::untrusted()
+        )
+        .is_err());
+        let frames = ReadOptions::default().with_limits(Limits {
+            max_frame: 1000,
+            ..Limits::default()
+        });
+        assert!(Replay::<()>::read_with_options(&mut Cursor::new(bytes), &frames).is_err());
+        let tps = ReadOptions::default().with_limits(Limits {
+            max_tps: 1e6,
+            ..Limits::default()
+        });
+        assert!(Replay::<()>::read_with_options(&mut Cursor::new(bytes), &tps).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use slc_oxide::splice::{splice_many, Manifest, ManifestError, SpliceConflict, SpliceConflictKind};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn player(hold: bool, button: u8) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button,
+    })
+}
+
+#[test]
+fn test_splice_segments() {
+    let mut intro = Replay::<()>::new(240.0, ());
+    intro.add_input(10, player(true, 1));
+    intro.add_input(20, player(false, 1));
+    intro.add_input(90, player(true, 2));
+    intro.add_input(150, player(false, 2));
+
+    let mut middle = Replay::<()>::new(240.0, ());
+    middle.add_input(5, player(false, 1));
+    middle.add_input(10, player(true, 1));
+    middle.add_input(30, player(false, 1));
+
+    let mut ending = Replay::<()>::new(480.0, ());
+    ending.add_input(4, player(true, 3));
+    ending.add_input(8, player(false, 3));
+
+    let splice = splice_many(&[(ending, 200), (intro, 0), (middle, 100)]).unwrap();
+
+    let data: Vec<_> = splice
+        .replay
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.data.clone()))
+        .collect();
+    assert_eq!(
+        data,
+        vec![
+            (10, player(true, 1)),
+            (20, player(false, 1)),
+            (90, player(true, 2)),
+            (100, player(false, 2)),
+            (110, player(true, 1)),
+            (130, player(false, 1)),
+            (200, InputData::TPS(480.0)),
+            (204, player(true, 3)),
+            (208, player(false, 3)),
+        ]
+    );
+
+    assert_eq!(
+        splice.conflicts,
+        vec![
+            SpliceConflict {
+                kind: SpliceConflictKind::Overlap { trimmed: 1 },
+                segment: 1,
+                frame: 100,
+            },
+            SpliceConflict {
+                kind: SpliceConflictKind::HeldAtSeam {
+                    button: 2,
+                    player_2: false
+                },
+                segment: 2,
+                frame: 100,
+            },
+            SpliceConflict {
+                kind: SpliceConflictKind::OrphanRelease {
+                    button: 1,
+                    player_2: false
+                },
+                segment: 2,
+                frame: 105,
+            },
+        ]
+    );
+
+    assert!(splice_many::<()>(&[]).is_none());
+}
+
+#[test]
+fn test_splice_manifest() {
+    let dir = std::env::temp_dir().join(format!("slc_manifest_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut first = Replay::<()>::new(240.0, ());
+    first.add_input(10, player(true, 1));
+    first.add_input(20, player(false, 1));
+    let mut second = Replay::<()>::new(240.0, ());
+    second.add_input(5, player(true, 1));
+    second.add_input(6, player(false, 1));
+    first
+        .write(&mut std::fs::File::create(dir.join("first.slc")).unwrap())
+        .unwrap();
+    second
+        .write(&mut std::fs::File::create(dir.join("second part.slc")).unwrap())
+        .unwrap();
+
+    let mut manifest = Manifest::new();
+    manifest.push("second part.slc", 100);
+    manifest.push("first.slc", 0);
+    let mut text = Vec::new();
+    manifest.write(&mut text).unwrap();
+    assert_eq!(text, b"100 second part.slc\n0 first.slc\n");
+
+    let with_comments = format!("# segments\n\n{}", String::from_utf8(text).unwrap());
+    assert_eq!(
+        Manifest::read(&mut with_comments.as_bytes()).unwrap(),
+        manifest
+    );
+    std::fs::write(dir.join("run.txt"), with_comments).unwrap();
+
+    let loaded = Manifest::load(dir.join("run.txt")).unwrap();
+    assert_eq!(loaded.entries[0].path, dir.join("second part.slc"));
+    let splice = loaded.assemble::<()>().unwrap();
+    assert!(splice.conflicts.is_empty());
+    let frames: Vec<_> = splice.replay.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![10, 20, 105, 106]);
+
+    manifest.push("missing.slc", 200);
+    assert!(matches!(
+        manifest.assemble::<()>(),
+        Err(ManifestError::Segment { .. })
+    ));
+    assert!(matches!(
+        Manifest::read(&mut "0 a.slc\nten b.slc\n".as_bytes()),
+        Err(ManifestError::InvalidLine(2))
+    ));
+    assert!(matches!(
+        Manifest::new().assemble::<()>(),
+        Err(ManifestError::Empty)
+    ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
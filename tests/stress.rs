@@ -0,0 +1,29 @@
+use slc_oxide::stress::{stress, stress_v3};
+use slc_oxide::testing::{generate_replay, generate_replay_v3, Profile};
+
+#[test]
+fn test_stress_report() {
+    let replay = generate_replay(3, &Profile::spam().with_frames(240 * 120));
+    let report = stress(&replay).unwrap();
+
+    assert_eq!(report.inputs, replay.inputs.len());
+    assert!(report.write_calls > 0 && report.read_calls > 0);
+    assert!(report.peak_bytes >= report.encoded_bytes);
+    assert!(report
+        .buffers
+        .iter()
+        .any(|&(name, bytes)| name == "replay" && bytes >= replay.inputs.len() * 24));
+    assert!(report
+        .to_string()
+        .starts_with(&format!("{} inputs", replay.inputs.len())));
+
+    let v3 = generate_replay_v3(3, &Profile::spam().with_frames(240 * 120));
+    let report_v3 = stress_v3(&v3).unwrap();
+    assert_eq!(report_v3.inputs, report.inputs);
+    // v3 is run-length encoded, and smaller than v2 on regular clicking.
+    assert!(report_v3.encoded_bytes < report.encoded_bytes);
+    assert!(report_v3
+        .buffers
+        .iter()
+        .any(|&(name, bytes)| name == "action copy" && bytes > 0));
+}
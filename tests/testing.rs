@@ -0,0 +1,108 @@
+use slc_oxide::testing::{generate_replay, generate_replay_v3, Profile};
+use slc_oxide::{InputData, Replay};
+use std::io::Cursor;
+
+#[test]
+fn test_generation_is_deterministic() {
+    let profile = Profile::default();
+    assert_eq!(generate_replay(7, &profile), generate_replay(7, &profile));
+    assert_ne!(generate_replay(7, &profile), generate_replay(8, &profile));
+}
+
+#[test]
+fn test_profiles() {
+    for profile in [
+        Profile::default(),
+        Profile::spam(),
+        Profile::dual(),
+        Profile::platformer(),
+    ] {
+        let replay = generate_replay(1, &profile.clone().with_frames(240 * 30));
+        let clicks = replay
+            .iter()
+            .filter(|i| matches!(&i.data, InputData::Player(p) if p.hold))
+            .count() as f64;
+
+        // Roughly the requested amount of clicks over 30 seconds.
+        let expected = profile.cps * 30.0;
+        assert!(clicks > expected * 0.5 && clicks < expected * 1.5);
+        assert!(replay.inputs.windows(2).all(|w| w[0].frame <= w[1].frame));
+    }
+
+    let dual = generate_replay(1, &Profile::dual());
+    assert!(dual
+        .iter()
+        .any(|i| matches!(&i.data, InputData::Player(p) if p.player_2)));
+}
+
+#[test]
+fn test_generated_roundtrip() {
+    let replay = generate_replay(42, &Profile::default());
+    assert!(replay.iter().any(|i| i.data == InputData::Restart));
+
+    let mut buffer = Vec::new();
+    replay.write(&mut buffer).unwrap();
+    let read = Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(read, replay);
+
+    let v3 = generate_replay_v3(42, &Profile::default());
+    assert_eq!(v3.metadata.seed, 42);
+    let mut buffer = Vec::new();
+    v3.write(&mut buffer).unwrap();
+    let read = Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert!(read.semantically_eq(&replay));
+}
+
+#[test]
+fn test_bench_workloads() {
+    let workload = slc_oxide::bench::Workload::new("test", 3, &Profile::default());
+    assert!(!workload.is_empty());
+    assert_eq!(workload.action_atom().actions.len(), workload.len());
+
+    let read = Replay::<()>::read(&mut Cursor::new(&workload.v3_bytes)).unwrap();
+    assert!(read.semantically_eq(&workload.replay));
+}
+
+#[test]
+fn test_self_check() {
+    assert_eq!(slc_oxide::testing::self_check(), Ok(()));
+}
+
+#[test]
+fn test_faulty_reader() {
+    use slc_oxide::testing::{Fault, FaultyReader};
+    use std::io::{Read, Seek, SeekFrom};
+
+    let data: Vec<u8> = (0..32).collect();
+
+    let mut reader = FaultyReader::new(Cursor::new(data.clone())).truncate_at(10);
+    assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 9);
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, [9]);
+
+    let mut reader = FaultyReader::new(Cursor::new(data.clone()))
+        .short_reads(3)
+        .flip_bit(4, 7);
+    let mut buf = [0u8; 8];
+    assert_eq!(reader.read(&mut buf).unwrap(), 3);
+    reader.read_exact(&mut buf[3..]).unwrap();
+    assert_eq!(buf, [0, 1, 2, 3, 0x84, 5, 6, 7]);
+
+    let mut reader = FaultyReader::new(Cursor::new(data)).with_fault(Fault::Error { at: 6 });
+    assert!(reader.read_exact(&mut buf).is_err());
+}
+
+#[test]
+fn test_decoders_are_robust() {
+    use slc_oxide::testing::check_robustness;
+
+    let replay = generate_replay(9, &Profile::dual().with_frames(240 * 5));
+    let mut v2 = Vec::new();
+    replay.write(&mut v2).unwrap();
+    let mut v3 = Vec::new();
+    replay.write_v3(&mut v3).unwrap();
+
+    assert_eq!(check_robustness(&v2), Ok(()));
+    assert_eq!(check_robustness(&v3), Ok(()));
+}
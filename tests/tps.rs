@@ -0,0 +1,88 @@
+use slc_oxide::tps::TpsChange;
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::ActionAtom;
+use slc_oxide::v3::{ActionType, Metadata};
+use slc_oxide::{InputData, Replay, TpsTimeline};
+
+#[test]
+fn test_tps_timeline_time_math() {
+    let mut timeline = TpsTimeline::new(240.0);
+    timeline.push(480, 480.0);
+    timeline.push(240, 120.0);
+
+    assert_eq!(timeline.tps_at(0), 240.0);
+    assert_eq!(timeline.tps_at(240), 120.0);
+    assert_eq!(timeline.tps_at(1000), 480.0);
+
+    assert_eq!(timeline.seconds(240), 1.0);
+    assert_eq!(timeline.seconds(480), 3.0);
+    assert_eq!(timeline.seconds(960), 4.0);
+
+    for frame in [0, 1, 239, 240, 479, 480, 481, 960] {
+        assert_eq!(timeline.frame_at(timeline.seconds(frame)), frame);
+    }
+}
+
+#[test]
+fn test_tps_timeline_same_frame() {
+    let mut timeline = TpsTimeline::new(240.0);
+    timeline.push(100, 60.0);
+    timeline.push(100, 120.0);
+
+    assert_eq!(timeline.tps_at(100), 120.0);
+    assert_eq!(timeline.changes()[0].tps, 60.0);
+
+    let sorted = TpsTimeline::from_changes(
+        240.0,
+        [
+            TpsChange {
+                frame: 100,
+                tps: 60.0,
+            },
+            TpsChange {
+                frame: 50,
+                tps: 480.0,
+            },
+            TpsChange {
+                frame: 100,
+                tps: 120.0,
+            },
+        ],
+    );
+    assert_eq!(sorted.changes()[0].frame, 50);
+    assert_eq!(sorted.tps_at(100), 120.0);
+}
+
+#[test]
+fn test_tps_timeline_from_replays() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(0, InputData::Death);
+    replay.add_input(240, InputData::TPS(480.0));
+    let timeline = TpsTimeline::from(&replay);
+    assert_eq!(timeline.initial(), 240.0);
+    assert_eq!(timeline.seconds(720), 2.0);
+
+    let mut v3 = slc_oxide::v3::Replay::new(Metadata::new(240.0, 0, 1));
+    let mut atom = ActionAtom::new();
+    atom.add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_tps_action(240, 480.0).unwrap();
+    v3.add_atom(AtomVariant::Action(atom));
+    assert_eq!(TpsTimeline::from(&v3), timeline);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_tps_timeline_serde() {
+    let mut timeline = TpsTimeline::new(240.0);
+    timeline.push(240, 480.0);
+
+    let json = serde_json::to_value(&timeline).unwrap();
+    assert_eq!(json["initial"], 240.0);
+    assert_eq!(json["changes"][0]["frame"], 240);
+    assert!(json.get("seconds").is_none());
+
+    let read: TpsTimeline = serde_json::from_value(json).unwrap();
+    assert_eq!(read, timeline);
+    assert_eq!(read.seconds(720), 2.0);
+}
@@ -0,0 +1,349 @@
+use slc_oxide::transform::{humanize, HumanizeOptions};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn player(hold: bool, button: u8) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button,
+    })
+}
+
+#[test]
+fn test_humanize_preserves_structure() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    for i in 0..50u64 {
+        let base = i * 20;
+        replay.add_input(base + 5, player(true, 1));
+        replay.add_input(base + 7, player(false, 1));
+        if i % 10 == 9 {
+            replay.add_input(base + 8, InputData::Death);
+        }
+    }
+
+    let original = replay.inputs.clone();
+    let options = HumanizeOptions {
+        max_jitter: 3,
+        min_hold: 1,
+        seed: 42,
+    };
+    humanize(&mut replay, &options);
+
+    assert_eq!(replay.inputs.len(), original.len());
+    assert_ne!(replay.inputs, original);
+
+    let mut previous_frame = 0;
+    let mut press_frame = 0;
+    for (input, before) in replay.inputs.iter().zip(&original) {
+        assert!(input.frame >= previous_frame);
+        assert_eq!(input.delta, input.frame - previous_frame);
+        assert_eq!(input.data, before.data);
+        assert!(input.frame.abs_diff(before.frame) <= options.max_jitter);
+
+        match &input.data {
+            InputData::Death => assert_eq!(input.frame, before.frame),
+            InputData::Player(p) if p.hold => press_frame = input.frame,
+            InputData::Player(_) => assert!(input.frame > press_frame),
+            _ => {}
+        }
+        previous_frame = input.frame;
+    }
+
+    let mut again = Replay::<()>::new(240.0, ());
+    again.inputs = original;
+    humanize(&mut again, &options);
+    assert_eq!(again.inputs, replay.inputs);
+}
+
+#[test]
+fn test_humanize_large_options() {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(5, player(true, 1));
+    replay.add_input(7, player(false, 1));
+    replay.add_input(u64::MAX - 1, player(true, 2));
+    replay.add_input(u64::MAX, InputData::Death);
+
+    for (max_jitter, min_hold) in [(u64::MAX, u64::MAX), (u64::MAX / 2 + 1, 1), (3, u64::MAX)] {
+        let mut humanized = replay.clone();
+        humanize(
+            &mut humanized,
+            &HumanizeOptions {
+                max_jitter,
+                min_hold,
+                seed: 7,
+            },
+        );
+
+        assert!(humanized
+            .inputs
+            .windows(2)
+            .all(|w| w[0].frame <= w[1].frame));
+        assert_eq!(humanized.inputs.last().unwrap().frame, u64::MAX);
+    }
+}
+
+#[test]
+fn test_startpos_rebase_roundtrip() {
+    use slc_oxide::transform::{apply_startpos, rebase_startpos, startpos::startpos};
+    use slc_oxide::v3::atom::AtomVariant;
+    use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+    use slc_oxide::v3::{ActionType, Metadata};
+    use std::io::Cursor;
+
+    let mut full = slc_oxide::v3::Replay::new(Metadata::new(240.0, 0, 1));
+    let mut atom = ActionAtom::new();
+    for frame in [100, 500, 1200, 1300] {
+        atom.add_player_action(frame, ActionType::Jump, frame % 200 == 0, false)
+            .unwrap();
+    }
+    full.add_atom(AtomVariant::Action(atom));
+
+    let sp = StartposAtom::new(1500.0, 105.0, 1000);
+    apply_startpos(&mut full, sp);
+
+    let mut buffer = Vec::new();
+    full.write(&mut buffer).unwrap();
+    let mut loaded = slc_oxide::v3::Replay::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(startpos(&loaded), Some(sp));
+
+    let AtomVariant::Action(atom) = &loaded.atoms.atoms[1] else {
+        panic!("Expected ActionAtom");
+    };
+    let frames: Vec<_> = atom.actions.iter().map(|a| a.frame).collect();
+    assert_eq!(frames, vec![200, 300]);
+
+    assert_eq!(rebase_startpos(&mut loaded), Some(sp));
+    assert_eq!(startpos(&loaded), None);
+    let AtomVariant::Action(atom) = &loaded.atoms.atoms[0] else {
+        panic!("Expected ActionAtom");
+    };
+    let frames: Vec<_> = atom.actions.iter().map(|a| (a.frame, a.delta())).collect();
+    assert_eq!(frames, vec![(1200, 1200), (1300, 100)]);
+}
+
+#[test]
+fn test_mirror_ranges_keeps_holds_paired() {
+    use slc_oxide::transform::mirror_ranges;
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 2));
+    replay.add_input(20, player(false, 2));
+    replay.add_input(95, player(true, 3));
+    replay.add_input(105, player(false, 3));
+    replay.add_input(150, player(true, 2));
+    replay.add_input(150, player(true, 1));
+    replay.add_input(210, player(false, 2));
+    replay.add_input(210, player(false, 1));
+
+    mirror_ranges(&mut replay, &[100..200, 300..400]);
+
+    let buttons: Vec<_> = replay
+        .inputs
+        .iter()
+        .map(|i| match &i.data {
+            InputData::Player(p) => p.button,
+            _ => 0,
+        })
+        .collect();
+    // The hold pressed before the range stays unswapped, the one pressed
+    // inside is swapped along with its release outside of it.
+    assert_eq!(buttons, vec![2, 2, 3, 3, 3, 1, 3, 1]);
+}
+
+#[test]
+fn test_respawn_compensation() {
+    use slc_oxide::transform::{compensate_respawn, RespawnOptions};
+
+    let build = || {
+        let mut replay = Replay::<()>::new(240.0, ());
+        replay.add_input(10, player(true, 1));
+        replay.add_input(20, InputData::Death);
+        replay.add_input(30, player(false, 1));
+        replay.add_input(40, InputData::Restart);
+        replay.add_input(45, player(true, 1));
+        replay
+    };
+
+    let mut longer = build();
+    compensate_respawn(
+        &mut longer,
+        &RespawnOptions {
+            delay: 5,
+            ..Default::default()
+        },
+    );
+    let frames: Vec<_> = longer.inputs.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![10, 20, 35, 45, 55]);
+    assert_eq!(longer.inputs[4].delta, 10);
+
+    let mut replay = build();
+    compensate_respawn(
+        &mut replay,
+        &RespawnOptions {
+            delay: -8,
+            deaths: false,
+            restarts: true,
+        },
+    );
+    let frames: Vec<_> = replay.inputs.iter().map(|i| i.frame).collect();
+    assert_eq!(frames, vec![10, 20, 30, 40, 40]);
+}
+
+#[test]
+fn test_seed_regeneration() {
+    use slc_oxide::transform::{apply_seed_sequence, regenerate_seeds};
+    use slc_oxide::v3::atom::AtomVariant;
+    use slc_oxide::v3::builtin::ActionAtom;
+    use slc_oxide::v3::{ActionType, Metadata};
+
+    let mut atom = ActionAtom::new();
+    atom.add_player_action(5, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_death_action(10, ActionType::Death, 1).unwrap();
+    atom.add_death_action(11, ActionType::Restart, 2).unwrap();
+    atom.add_tps_action(20, 480.0).unwrap();
+    atom.add_death_action(30, ActionType::RestartFull, 3)
+        .unwrap();
+
+    let mut replay = slc_oxide::v3::Replay::new(Metadata::new(240.0, 7, 1));
+    replay.add_atom(AtomVariant::Action(atom));
+
+    let seeds = |replay: &slc_oxide::v3::Replay| -> Vec<u64> {
+        let AtomVariant::Action(atom) = &replay.atoms.atoms[0] else {
+            panic!("Expected ActionAtom");
+        };
+        atom.actions.iter().map(|a| a.seed).collect()
+    };
+
+    let mut state = 100;
+    assert_eq!(
+        regenerate_seeds(&mut replay, || {
+            state += 1;
+            state
+        }),
+        3
+    );
+    assert_eq!(seeds(&replay), vec![0, 101, 102, 0, 103]);
+
+    assert_eq!(apply_seed_sequence(&mut replay, [9, 8]), 2);
+    assert_eq!(seeds(&replay), vec![0, 9, 8, 0, 103]);
+    assert_eq!(replay.metadata.seed, 7);
+}
+
+#[test]
+fn test_reverse() {
+    use slc_oxide::transform::reverse;
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1));
+    replay.add_input(20, player(false, 1));
+    replay.add_input(20, player(true, 1));
+    replay.add_input(25, InputData::TPS(480.0));
+    replay.add_input(30, player(false, 1));
+    replay.add_input(30, InputData::Death);
+    replay.add_input(40, player(true, 2));
+    replay.add_input(40, player(false, 2));
+    replay.add_input(100, InputData::Restart);
+
+    reverse(&mut replay);
+    assert_eq!(replay.tps, 480.0);
+    let inputs: Vec<_> = replay
+        .inputs
+        .iter()
+        .map(|i| (i.frame, i.data.clone()))
+        .collect();
+    assert_eq!(
+        inputs,
+        vec![
+            (60, player(true, 2)),
+            (60, player(false, 2)),
+            (70, player(true, 1)),
+            (75, InputData::TPS(240.0)),
+            (80, player(false, 1)),
+            (80, player(true, 1)),
+            (90, player(false, 1)),
+        ]
+    );
+}
+
+#[test]
+fn test_transform_pipeline() {
+    use slc_oxide::transform::pipeline::{DedupTps, Mirror, Normalize, Trim};
+    use slc_oxide::transform::{Pipeline, Report, Transform, TransformError};
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(5, player(true, 2));
+    replay.add_input(10, InputData::TPS(240.0));
+    replay.add_input(20, InputData::Skip);
+    replay.add_input(30, player(false, 2));
+    replay.add_input(500, InputData::Death);
+
+    let pipeline = Pipeline::new()
+        .then(Normalize::default())
+        .then(DedupTps)
+        .then(Mirror {
+            ranges: vec![0..10, 400..600],
+        })
+        .then(Trim { to_frame: 100 });
+    assert_eq!(
+        pipeline.names(),
+        ["normalize", "dedup_tps", "mirror", "trim"]
+    );
+
+    let report = pipeline.apply(&mut replay).unwrap();
+    assert_eq!((report.inputs_before, report.inputs_after), (5, 2));
+    assert_eq!(report.removed(), 3);
+    let removed: Vec<_> = report.steps.iter().map(Report::removed).collect();
+    assert_eq!(removed, [1, 1, 0, 1]);
+    assert_eq!(replay.inputs[0].data, player(true, 3));
+    assert_eq!(replay.inputs[1].data, player(false, 3));
+
+    let failing = Pipeline::new()
+        .then(Trim { to_frame: 10 })
+        .then(|_: &mut Replay<()>| Err(TransformError::InvalidOptions("nope".into())));
+    let error = failing.apply(&mut replay).unwrap_err();
+    assert!(matches!(
+        error,
+        TransformError::Step {
+            index: 1,
+            name: "custom",
+            ..
+        }
+    ));
+    assert_eq!(replay.inputs.len(), 1);
+}
+
+#[test]
+fn test_transform_dry_run() {
+    use slc_oxide::diff::Divergence;
+    use slc_oxide::transform::pipeline::{StripSkips, Trim};
+    use slc_oxide::transform::{Pipeline, Transform};
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(5, player(true, 1));
+    replay.add_input(20, InputData::Skip);
+    replay.add_input(30, player(false, 1));
+    replay.add_input(500, InputData::Death);
+    let original = replay.clone();
+
+    let pipeline = Pipeline::new()
+        .then(StripSkips)
+        .then(Trim { to_frame: 100 });
+    let preview = pipeline.dry_run(&replay).unwrap();
+    assert_eq!(replay, original);
+    assert_eq!(preview.report.removed(), 2);
+
+    let removed: Vec<_> = preview
+        .changes
+        .divergences
+        .iter()
+        .map(|d| match d {
+            Divergence::OnlyLeft(input) => input.frame,
+            other => panic!("unexpected divergence {other:?}"),
+        })
+        .collect();
+    assert_eq!(removed, [20, 500]);
+
+    let mut applied = replay.clone();
+    assert_eq!(pipeline.apply(&mut applied).unwrap(), preview.report);
+}
@@ -0,0 +1,40 @@
+use std::io::Cursor;
+
+use slc_oxide::layout::{snapshot, Layout};
+use slc_oxide::v2::inspect;
+use slc_oxide::{Button, InputData, PlayerInput, Replay, ReplayError};
+
+fn replay() -> Replay<Vec<u8>> {
+    let mut replay = Replay::new(240.0, b"meta".to_vec());
+    for i in 0..8 {
+        let input = PlayerInput::new(Button::Jump, i % 2 == 0, false);
+        replay.add_input(100 + i * 4, InputData::Player(input));
+    }
+    replay.add_input(5_000, InputData::TPS(480.0));
+    replay
+}
+
+#[test]
+fn test_v2_inspect() {
+    let replay = replay();
+    let mut bytes = Vec::new();
+    replay.write(&mut bytes).unwrap();
+
+    let mut reader = Cursor::new(&bytes);
+    let layout = inspect(&mut reader).unwrap();
+    assert_eq!(layout.tps, 240.0);
+    assert_eq!(layout.meta, b"meta");
+    assert_eq!(layout.input_count, 9);
+    assert_eq!(layout.inputs_offset, reader.position());
+
+    // The blob table matches the one the replay is written with.
+    let Layout::V2(blobs) = snapshot(&replay) else {
+        panic!("expected a v2 layout");
+    };
+    assert_eq!(layout.blobs, blobs);
+
+    assert!(matches!(
+        inspect(&mut Cursor::new(&bytes[1..])),
+        Err(ReplayError::HeaderMismatchError)
+    ));
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use common::{atom_at, atom_at_mut, replay, roundtrip, write, write_with};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::ActionAtom;
+use slc_oxide::v3::{Action, ActionType, AnalogPrecision, AttemptKind, WriteOptions};
+use std::io::Cursor;
+
+#[test]
+fn test_v3_analog_actions() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    action_atom.add_analog_action(12, 0.5, false).unwrap();
+    action_atom.add_analog_action(400, -0.25, true).unwrap();
+    action_atom.add_analog_action(401, 3.0, false).unwrap();
+    action_atom
+        .add_player_action(500, ActionType::Jump, false, false)
+        .unwrap();
+    let replay = replay([AtomVariant::Action(action_atom)]);
+
+    for (precision, tolerance) in [
+        (AnalogPrecision::Float, 0.0),
+        (AnalogPrecision::I16, 1e-4),
+        (AnalogPrecision::I8, 1e-2),
+    ] {
+        let options = WriteOptions {
+            analog_precision: precision,
+            ..Default::default()
+        };
+        let loaded = common::read(&write_with(&replay, &options));
+        let atom = atom_at(&loaded, 0);
+
+        assert_eq!(atom.actions.len(), 5);
+        let analog: Vec<_> = atom
+            .actions
+            .iter()
+            .filter(|a| a.action_type == ActionType::Analog)
+            .collect();
+        assert_eq!(analog.len(), 3);
+        assert_eq!(analog[1].frame, 400);
+        assert!(analog[1].player2);
+        for (action, expected) in analog.iter().zip([0.5f32, -0.25, 1.0]) {
+            assert!((action.axis - expected).abs() <= tolerance);
+        }
+        assert_eq!(atom.actions[4].frame, 500);
+    }
+}
+
+#[test]
+fn test_v3_subframe_actions() {
+    let mut replay = replay([AtomVariant::Action(common::jumps(&[
+        (10, true),
+        (12, false),
+    ]))]);
+    let integer_buffer = write(&replay);
+
+    let atom = atom_at_mut(&mut replay, 0);
+    atom.add_subframe_player_action(12, 0x4000, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_subframe_player_action(12, 0xC000, ActionType::Jump, false, false)
+        .unwrap();
+    atom.add_player_action(20, ActionType::Left, true, true)
+        .unwrap();
+
+    let buffer = write(&replay);
+    let loaded = common::read(&buffer);
+    let loaded_atom = atom_at(&loaded, 0);
+    assert_eq!(loaded_atom.actions.len(), 5);
+    let subframes: Vec<_> = loaded_atom.actions.iter().map(|a| a.subframe).collect();
+    assert_eq!(subframes, vec![0, 0, 0x4000, 0xC000, 0]);
+    assert_eq!(loaded_atom.actions[3].precise_frame(), 12.75);
+    assert!(!loaded_atom.actions[3].holding);
+    assert_eq!(loaded_atom.actions[4].frame, 20);
+    assert!(loaded_atom.actions[4].player2);
+    assert_eq!(write(&loaded), buffer);
+
+    // Integer-frame actions keep their original encoding.
+    atom_at_mut(&mut replay, 0).actions.truncate(2);
+    assert_eq!(write(&replay), integer_buffer);
+}
+
+#[test]
+fn test_v3_player_tps_actions() {
+    let mut action_atom = ActionAtom::new();
+    action_atom.add_tps_action(0, 240.0).unwrap();
+    action_atom.add_player_tps_action(50, 480.0, true).unwrap();
+    action_atom
+        .add_player_action(60, ActionType::Jump, true, true)
+        .unwrap();
+    action_atom.add_player_tps_action(70, 120.0, false).unwrap();
+
+    let buffer = write(&replay([AtomVariant::Action(action_atom)]));
+    let loaded = common::read(&buffer);
+    let atom = atom_at(&loaded, 0);
+    let scoped: Vec<_> = atom
+        .actions
+        .iter()
+        .filter(|a| a.action_type == ActionType::PlayerTPS)
+        .map(|a| (a.frame, a.tps, a.player2))
+        .collect();
+    assert_eq!(scoped, vec![(50, 480.0, true), (70, 120.0, false)]);
+    assert_eq!(atom.actions[0].action_type, ActionType::TPS);
+
+    // Converting to v2 keeps the global tps change only.
+    let v2 = slc_oxide::Replay::<()>::read(&mut Cursor::new(buffer)).unwrap();
+    assert_eq!(v2.inputs.len(), 2);
+}
+
+#[test]
+fn test_v3_attempt_kinds() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_death_action(100, ActionType::Death, 1)
+        .unwrap();
+    action_atom
+        .add_death_action(101, ActionType::Restart, 2)
+        .unwrap();
+    action_atom
+        .actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Practice);
+    action_atom
+        .add_death_action(300, ActionType::RestartFull, 3)
+        .unwrap();
+    action_atom
+        .actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Startpos);
+
+    let loaded = roundtrip(&replay([AtomVariant::Action(action_atom)]));
+    let atom = atom_at(&loaded, 0);
+    let kinds: Vec<_> = atom.actions.iter().map(|a| a.attempt_kind()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            AttemptKind::Normal,
+            AttemptKind::Practice,
+            AttemptKind::Startpos
+        ]
+    );
+    assert!(atom.actions[1].is_practice());
+    assert_eq!(atom.actions[2].seed, 3);
+}
+
+#[test]
+fn test_v3_player_input_conversions() {
+    use slc_oxide::v3::section::{Button, SectionInput};
+    use slc_oxide::PlayerInput;
+
+    let action = Action::player(0, 12, ActionType::Right, true, true);
+    let input = PlayerInput::try_from(&action).unwrap();
+    assert_eq!(input.button, 3);
+    assert!(input.hold && input.player_2);
+
+    let tps = Action::tps_change(0, 12, 480.0);
+    assert!(PlayerInput::try_from(&tps).is_err());
+
+    let section_input = SectionInput::from_action(&action);
+    assert_eq!(section_input.button, Button::Right);
+    assert_eq!(PlayerInput::from(&section_input), input);
+    assert_eq!(
+        Button::from(slc_oxide::Button::Left) as u8,
+        slc_oxide::Button::Left as u8
+    );
+}
+
+#[test]
+fn test_v3_display() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    action_atom
+        .add_death_action(40, ActionType::Death, 99)
+        .unwrap();
+    action_atom.add_tps_action(50, 480.0).unwrap();
+
+    assert_eq!(
+        action_atom.actions[0].to_string(),
+        "frame: 10, action: jump, hold: true, p2: false"
+    );
+    assert_eq!(
+        action_atom.actions[1].to_string(),
+        "frame: 40, action: death, seed: 99"
+    );
+    assert_eq!(ActionType::RestartFull.to_string(), "full restart");
+    assert_eq!(action_atom.to_string(), "3 actions, frames 10..=50");
+
+    let replay = replay([AtomVariant::Action(action_atom)]);
+    assert_eq!(
+        replay.atoms.to_string(),
+        "1 atoms\n  atom 0: action, 3 actions, frames 10..=50\n"
+    );
+}
+
+#[test]
+fn test_v3_minimum_sizes() {
+    use slc_oxide::v3::action::minimum_sizes;
+
+    fn expected(delta: u64, offset: u32) -> u8 {
+        match delta {
+            d if d < 1 << offset => 0,
+            d if d < 1 << (offset + 8) => 1,
+            d if d < 1 << (offset + 24) => 2,
+            _ => 3,
+        }
+    }
+
+    let mut actions = Vec::new();
+    for bits in 0..64 {
+        for delta in [(1u64 << bits) - 1, 1 << bits, (1 << bits) + 1, u64::MAX] {
+            let player = Action::player(0, delta, ActionType::Jump, true, false);
+            let death = Action::death(0, delta, ActionType::Death, 0);
+            assert_eq!(player.minimum_size(), expected(delta, 4), "{}", delta);
+            assert_eq!(death.minimum_size(), expected(delta, 8), "{}", delta);
+            actions.push(player);
+            actions.push(death);
+        }
+    }
+
+    let sizes: Vec<u8> = actions.iter().map(|a| a.minimum_size()).collect();
+    assert_eq!(minimum_sizes(&actions), sizes);
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::{jumps, replay, write_with};
+use slc_oxide::replay::Limits;
+use slc_oxide::v3::atom::{AtomError, AtomId, AtomVariant, CustomAtom, NullAtom};
+use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+use slc_oxide::v3::changelog::{ChangeEntry, ChangeLogAtom};
+use slc_oxide::v3::{ActionType, GcOptions, NullAtomPolicy, WriteOptions};
+use std::io::Cursor;
+
+#[test]
+fn test_v3_atom_size_headers() {
+    let mut action_atom = ActionAtom::new();
+    for i in 0..100 {
+        action_atom
+            .add_player_action(i * 3, ActionType::Jump, i % 2 == 0, false)
+            .unwrap();
+    }
+    action_atom.add_tps_action(400, 480.0).unwrap();
+
+    let options = WriteOptions::default();
+    for atom in [
+        AtomVariant::Action(action_atom),
+        AtomVariant::Action(ActionAtom::new()),
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 3)),
+        AtomVariant::Null(NullAtom { size: 5 }),
+        AtomVariant::Custom(CustomAtom::new(0x100, vec![1, 2, 3])),
+    ] {
+        let mut buffer = Vec::new();
+        atom.write_with_options(&mut buffer, &options).unwrap();
+
+        let size = u64::from_le_bytes(buffer[4..12].try_into().unwrap());
+        assert_eq!(size, buffer.len() as u64 - 12, "{}", atom);
+        assert_eq!(atom.encoded_size(&options).unwrap(), size, "{}", atom);
+
+        let read = AtomVariant::read(&mut Cursor::new(&buffer)).unwrap();
+        assert_eq!(read.size() as u64, size);
+    }
+}
+
+#[test]
+fn test_v3_atom_limits() {
+    let mut atom = ActionAtom::new().with_limits(Limits::sane());
+    atom.add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    assert!(matches!(
+        atom.add_player_action(1 << 60, ActionType::Jump, false, false),
+        Err(AtomError::LimitExceeded("frame"))
+    ));
+    assert!(matches!(
+        atom.add_tps_action(20, 0.0),
+        Err(AtomError::LimitExceeded("tps"))
+    ));
+    assert!(matches!(
+        atom.add_player_tps_action(20, 1e9, true),
+        Err(AtomError::LimitExceeded("tps"))
+    ));
+    atom.add_tps_action(20, 480.0).unwrap();
+    assert_eq!(atom.actions.len(), 2);
+
+    let mut unlimited = ActionAtom::new();
+    unlimited.add_tps_action(20, 0.0).unwrap();
+}
+
+#[test]
+fn test_v3_atom_gc() {
+    let log = |operations: &[&str]| {
+        let mut log = ChangeLogAtom::new();
+        for op in operations {
+            log.push(ChangeEntry::new("alice", *op).with_timestamp(100));
+        }
+        AtomVariant::ChangeLog(log)
+    };
+
+    let replay = replay([
+        AtomVariant::Null(NullAtom { size: 4 }),
+        AtomVariant::Action(jumps(&[(10, true)])),
+        AtomVariant::Action(ActionAtom::new()),
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 0)),
+        log(&["record", "trim"]),
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 0)),
+        log(&["trim", "humanize"]),
+        AtomVariant::Null(NullAtom { size: 8 }),
+    ]);
+
+    // Collecting on write leaves the replay alone.
+    let options = WriteOptions {
+        gc: Some(GcOptions::default()),
+        ..Default::default()
+    };
+    let buffer = write_with(&replay, &options);
+    assert_eq!(replay.atoms.atoms.len(), 8);
+    let loaded = common::read(&buffer);
+    assert_eq!(loaded.atoms.atoms.len(), 5);
+    assert!(buffer.len() < replay.encoded_size(&WriteOptions::default()).unwrap() as usize);
+
+    let mut atoms = replay.atoms.clone();
+    let report = atoms.gc(&GcOptions {
+        null_atoms: NullAtomPolicy::DropTrailing,
+        ..Default::default()
+    });
+    assert_eq!(
+        (report.empty_actions, report.merged, report.nulls),
+        (1, 2, 1)
+    );
+    assert_eq!(report.removed(), 4);
+    assert_eq!(atoms.atoms[..], loaded.atoms.atoms[..4]);
+
+    let AtomVariant::ChangeLog(log) = &atoms.atoms[3] else {
+        panic!("expected the change log");
+    };
+    let operations: Vec<_> = log.entries.iter().map(|e| e.operation.as_str()).collect();
+    assert_eq!(operations, ["record", "trim", "humanize"]);
+
+    // Nothing is left to collect, and dropping every null atom removes the padding.
+    assert_eq!(atoms.gc(&GcOptions::default()).removed(), 0);
+    let report = atoms.gc(&GcOptions {
+        null_atoms: NullAtomPolicy::Drop,
+        ..Default::default()
+    });
+    assert_eq!(report.nulls, 1);
+    assert!(matches!(atoms.atoms[0], AtomVariant::Action(_)));
+}
+
+#[test]
+fn test_v3_custom_atom() {
+    let custom = || AtomVariant::Custom(CustomAtom::new(0xC0FFEE, vec![9, 8, 7, 6]));
+    let replay = replay([
+        custom(),
+        AtomVariant::Action(jumps(&[(10, true), (20, false)])),
+        custom(),
+        AtomVariant::Custom(CustomAtom::new(6, Vec::new())),
+    ]);
+    assert_eq!(replay.atoms.atoms[0].id(), AtomId::Custom);
+    assert_eq!(replay.atoms.atoms[0].raw_id(), 0xC0FFEE);
+    assert_eq!(AtomId::from_raw(3), AtomId::Startpos);
+    assert_eq!(AtomId::from_raw(6), AtomId::Custom);
+
+    let loaded = common::read(&common::write(&replay));
+    assert_eq!(loaded, replay);
+
+    // Custom atoms are neither merged nor dropped, even when identical or empty.
+    let mut atoms = loaded.atoms.clone();
+    assert_eq!(
+        atoms
+            .gc(&GcOptions {
+                null_atoms: NullAtomPolicy::Drop,
+                ..Default::default()
+            })
+            .removed(),
+        0
+    );
+    assert_eq!(atoms, replay.atoms);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{jumps, replay, roundtrip, write, FIRST_ATOM};
+use slc_oxide::transform::Report;
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::changelog::{ChangeEntry, ChangeLogAtom};
+use slc_oxide::v3::Replay;
+use std::io::Cursor;
+
+#[test]
+fn test_v3_change_log() {
+    let mut replay = replay([AtomVariant::Action(jumps(&[(10, true)]))]);
+    assert!(replay.change_log().is_none());
+
+    replay.log_change(ChangeEntry::new("alice", "record").with_timestamp(100));
+    replay.log_change(
+        ChangeEntry::from_report("bob", &Report::new("trim", 12, 10)).with_timestamp(200),
+    );
+    replay.log_change(ChangeEntry::new("alice", "humanize").with_timestamp(300));
+    assert_eq!(replay.atoms.atoms.len(), 2);
+
+    let mut loaded = roundtrip(&replay);
+    assert_eq!(loaded, replay);
+
+    let log = loaded.change_log().unwrap();
+    assert_eq!(log.entries[1].operation, "trim");
+    assert_eq!(log.entries[1].details, "12 inputs before, 10 after");
+    assert_eq!(log.by_author("alice").count(), 2);
+
+    let log = loaded.change_log_mut().unwrap();
+    assert_eq!(log.prune_before(150), 1);
+    assert_eq!(log.keep_last(1), 1);
+    assert_eq!(log.entries[0].operation, "humanize");
+}
+
+#[test]
+fn test_v3_change_log_size() {
+    let mut log = ChangeLogAtom::new();
+    log.push(ChangeEntry::new("alice", "record").with_timestamp(100));
+    let replay = replay([
+        AtomVariant::ChangeLog(log),
+        AtomVariant::Action(jumps(&[(10, true)])),
+    ]);
+    let buffer = write(&replay);
+
+    let mut padded = buffer.clone();
+    common::resize_atom(&mut padded, FIRST_ATOM, 7);
+    assert_eq!(common::read(&padded), replay);
+
+    let mut short = buffer.clone();
+    common::resize_atom(&mut short, FIRST_ATOM, -2);
+    assert!(Replay::read(&mut Cursor::new(&short)).is_err());
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{atom_at, atom_at_mut, roundtrip};
+use slc_oxide::testing::{generate_replay_v3, Profile};
+use slc_oxide::v3::{Action, ActionColumns, ActionType, AttemptKind};
+
+#[test]
+fn test_v3_action_columns() {
+    // Add a swift click and read back so its flags are set.
+    let mut generated = generate_replay_v3(3, &Profile::dual().with_frames(240 * 20));
+    let atom = atom_at_mut(&mut generated, 0);
+    let frame = atom.actions.last().unwrap().frame + 10;
+    atom.add_player_action(frame, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_player_action(frame, ActionType::Jump, false, false)
+        .unwrap();
+    let replay = roundtrip(&generated);
+
+    let mut actions = atom_at(&replay, 0).actions.clone();
+    let last = actions.last().unwrap().frame;
+    let mut death = Action::death(last, 5, ActionType::Death, 1234);
+    death.set_attempt_kind(AttemptKind::Practice);
+    actions.push(death);
+    actions.push(Action::analog(last + 5, 1, -0.5, true));
+    let mut subframe = Action::player(last + 6, 0, ActionType::Jump, true, false);
+    subframe.subframe = 300;
+    actions.push(subframe);
+
+    let columns = ActionColumns::from(&actions[..]);
+    assert_eq!(columns.len(), actions.len());
+    assert_eq!(columns.extras.len(), 3);
+
+    let rebuilt = columns.to_actions();
+    assert_eq!(rebuilt, actions);
+    for (a, b) in rebuilt.iter().zip(&actions) {
+        assert_eq!(a.delta(), b.delta());
+        assert_eq!(a.swift(), b.swift());
+        assert_eq!(a.minimum_size(), b.minimum_size());
+    }
+    assert!(actions.iter().any(|a| a.swift()));
+    assert_eq!(
+        columns.get(actions.len() - 3),
+        Some(actions[actions.len() - 3].clone())
+    );
+    assert_eq!(columns.get(actions.len()), None);
+
+    let sizes: Vec<u8> = actions.iter().map(|a| a.minimum_size()).collect();
+    assert_eq!(columns.minimum_sizes(), sizes);
+    assert_eq!(actions.into_iter().collect::<ActionColumns>(), columns);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{replay, write, FIRST_ATOM};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+use slc_oxide::v3::dump::{dump, DumpError, DumpTarget};
+use slc_oxide::v3::ActionType;
+
+#[test]
+fn test_v3_dump() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_player_action(100, ActionType::Jump, true, false)
+        .unwrap();
+    action_atom
+        .add_player_action(102, ActionType::Jump, false, true)
+        .unwrap();
+    action_atom
+        .add_death_action(300, ActionType::Death, 1337)
+        .unwrap();
+    let buffer = write(&replay([AtomVariant::Action(action_atom)]));
+
+    let mut out = Vec::new();
+    dump(&buffer, DumpTarget::Atom(0), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("3 actions"));
+    assert!(text.contains("delta 100, Jump, hold"));
+    assert!(text.contains("delta 2, Jump, release, player 2"));
+    assert!(text.contains("type 2 = death"));
+    assert!(text.contains("seed 1337"));
+
+    let mut out = Vec::new();
+    dump(
+        &buffer,
+        DumpTarget::Section {
+            atom: 0,
+            section: 2,
+        },
+        &mut out,
+    )
+    .unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("  section 2"));
+    assert!(text.contains("death"));
+    assert_eq!(text.lines().count(), 3);
+
+    assert!(matches!(
+        dump(&buffer, DumpTarget::Atom(1), &mut Vec::new()),
+        Err(DumpError::AtomNotFound(1))
+    ));
+}
+
+#[test]
+fn test_v3_dump_malformed() {
+    let buffer = write(&replay([
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 3)),
+        AtomVariant::Action(common::jumps(&[(100, true)])),
+    ]));
+    let second = FIRST_ATOM + 12 + 16;
+
+    // An atom size past the end of the file.
+    let mut huge_size = buffer.clone();
+    huge_size[FIRST_ATOM + 4..FIRST_ATOM + 12].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        dump(&huge_size, DumpTarget::Atom(1), &mut Vec::new()),
+        Err(DumpError::UnexpectedEof(_))
+    ));
+
+    // An action count far above what the file holds.
+    let mut huge_count = buffer.clone();
+    huge_count[second + 12..second + 20].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(dump(&huge_count, DumpTarget::Atom(1), &mut Vec::new()).is_err());
+
+    // A repeat section decoding to more actions than the atom declares.
+    let mut repeat = buffer.clone();
+    let header = u16::from_le_bytes([repeat[second + 20], repeat[second + 21]]);
+    let header = (header & 0x0fff) | (1 << 14) | (31 << 3);
+    repeat[second + 20..second + 22].copy_from_slice(&header.to_le_bytes());
+    assert!(dump(&repeat, DumpTarget::Atom(1), &mut Vec::new()).is_err());
+
+    // A few dozen bytes claiming 2^40 actions, one input repeated 2^31 times.
+    // The dump walks section headers, so this doesn't decode a single repeat.
+    for (count, complete) in [(1u64 << 40, false), (1 << 31, true)] {
+        let mut bomb = buffer[..FIRST_ATOM].to_vec();
+        bomb.extend_from_slice(&1u32.to_le_bytes());
+        bomb.extend_from_slice(&11u64.to_le_bytes());
+        bomb.extend_from_slice(&count.to_le_bytes());
+        bomb.extend_from_slice(&((1u16 << 14) | (31 << 3)).to_le_bytes());
+        bomb.push(0x15);
+        bomb.push(0xCC);
+
+        let mut out = Vec::new();
+        let result = dump(&bomb, DumpTarget::Atom(0), &mut out);
+        assert_eq!(result.is_ok(), complete);
+        if complete {
+            let text = String::from_utf8(out).unwrap();
+            assert!(text.contains("repeats exp 31 = 2147483648 times"));
+        }
+    }
+
+    for len in 0..buffer.len() {
+        for target in [
+            DumpTarget::Atom(1),
+            DumpTarget::Section {
+                atom: 1,
+                section: 0,
+            },
+        ] {
+            let _ = dump(&buffer[..len], target, &mut Vec::new());
+        }
+    }
+}
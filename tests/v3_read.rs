@@ -0,0 +1,162 @@
+mod common;
+
+use common::{atom_at, atom_at_mut, jumps, replay, write, FIRST_ATOM};
+use slc_oxide::testing::{generate_replay_v3, Profile};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::StartposAtom;
+use slc_oxide::v3::replay::{Footer, ReplayError};
+use slc_oxide::v3::section::{Section, SectionError};
+use slc_oxide::v3::sink::{FnSink, SpillFile};
+use slc_oxide::v3::{ActionType, Replay};
+use slc_oxide::ReadOptions;
+use std::io::Cursor;
+
+#[test]
+fn test_v3_bounded_read() {
+    let frames: Vec<_> = (0..64).map(|i| (i * 2, i % 2 == 0)).collect();
+    let buffer = write(&replay([
+        AtomVariant::Action(jumps(&frames)),
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 3)),
+    ]));
+
+    let options = ReadOptions {
+        max_inputs: 32,
+        ..ReadOptions::default()
+    };
+    assert!(Replay::read_with_options(&mut Cursor::new(&buffer), &options).is_err());
+    let options = ReadOptions {
+        max_atom_size: 8,
+        ..ReadOptions::default()
+    };
+    assert!(Replay::read_with_options(&mut Cursor::new(&buffer), &options).is_err());
+    assert!(
+        Replay::read_with_options(&mut Cursor::new(&buffer), &ReadOptions::untrusted()).is_ok()
+    );
+
+    // A null atom claiming to be huge fails on the missing data.
+    let mut corrupt = buffer[..FIRST_ATOM].to_vec();
+    corrupt.extend_from_slice(&0u32.to_le_bytes());
+    corrupt.extend_from_slice(&(u64::MAX / 2).to_le_bytes());
+    corrupt.push(0xCC);
+    assert!(Replay::read(&mut Cursor::new(&corrupt)).is_err());
+
+    // A repeat section of 2^15 inputs repeated 2^31 times is rejected up front.
+    let header: u16 = (1 << 14) | (15 << 8) | (31 << 3);
+    let mut section = header.to_le_bytes().to_vec();
+    section.extend_from_slice(&[0x15; 1 << 15]);
+    let mut actions = Vec::new();
+    assert!(matches!(
+        Section::read_with_limit(&mut Cursor::new(&section), &mut actions, 1 << 20),
+        Err(SectionError::TooManyActions)
+    ));
+    assert!(actions.is_empty());
+
+    // A whole file of a few dozen bytes: one action atom claiming 2^40
+    // actions, holding a single input repeated 2^31 times. The default
+    // options don't bound the action count, untrusted reads do.
+    assert_eq!(ReadOptions::default().max_inputs, u64::MAX);
+    let mut bomb = buffer[..FIRST_ATOM].to_vec();
+    bomb.extend_from_slice(&1u32.to_le_bytes());
+    bomb.extend_from_slice(&11u64.to_le_bytes());
+    for count in [1u64 << 40, 1 << 24] {
+        let mut file = bomb.clone();
+        file.extend_from_slice(&count.to_le_bytes());
+        file.extend_from_slice(&((1u16 << 14) | (31 << 3)).to_le_bytes());
+        file.push(0x15);
+        file.push(0xCC);
+        assert!(file.len() < 100);
+        let options = ReadOptions::untrusted();
+        assert!(Replay::read_with_options(&mut Cursor::new(&file), &options).is_err());
+        assert!(
+            slc_oxide::Replay::<()>::read_with_options(&mut Cursor::new(&file), &options).is_err()
+        );
+    }
+}
+
+#[test]
+fn test_v3_read_into_sink() {
+    let mut generated = generate_replay_v3(7, &Profile::dual().with_frames(240 * 600));
+    let atom = atom_at_mut(&mut generated, 0);
+    let frame = atom.actions.last().unwrap().frame + 10;
+    atom.add_player_action(frame, ActionType::Jump, true, false)
+        .unwrap();
+    atom.add_player_action(frame, ActionType::Jump, false, false)
+        .unwrap();
+    let buffer = write(&generated);
+    let replay = common::read(&buffer);
+    let expected = atom_at(&replay, 0);
+    assert!(expected.actions.len() > 1024);
+
+    let mut count = 0;
+    let streamed = Replay::read_into(
+        &mut Cursor::new(&buffer),
+        &ReadOptions::default(),
+        &mut FnSink(|_| {
+            count += 1;
+            Ok(())
+        }),
+    )
+    .unwrap();
+    assert_eq!(count, expected.actions.len());
+    assert_eq!(streamed.metadata, replay.metadata);
+    assert!(atom_at(&streamed, 0).actions.is_empty());
+
+    let path = std::env::temp_dir().join(format!("slc_spill_test_{}", std::process::id()));
+    let mut spill = SpillFile::create(&path).unwrap();
+    Replay::read_into(
+        &mut Cursor::new(&buffer),
+        &ReadOptions::default(),
+        &mut spill,
+    )
+    .unwrap();
+    assert_eq!(spill.len(), expected.actions.len() as u64);
+
+    let spilled: Vec<_> = spill.iter().unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(&spilled, &expected.actions);
+    for (a, b) in spilled.iter().zip(&expected.actions) {
+        assert_eq!(a.delta(), b.delta());
+        assert_eq!(a.swift(), b.swift());
+    }
+    assert!(spilled.iter().any(|a| a.swift()));
+    drop(spill);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut in_memory = SpillFile::new(Cursor::new(Vec::new()));
+    let limited = ReadOptions {
+        max_inputs: 10,
+        ..Default::default()
+    };
+    assert!(Replay::read_into(&mut Cursor::new(&buffer), &limited, &mut in_memory).is_err());
+}
+
+#[test]
+fn test_v3_trailing_data() {
+    let replay = replay([AtomVariant::Action(jumps(&[(10, true), (20, false)]))]);
+    let mut buffer = write(&replay);
+    let footer = buffer.len() as u64 - 1;
+
+    let clean = Replay::locate_footer(&mut Cursor::new(&buffer), &ReadOptions::default()).unwrap();
+    assert_eq!(
+        clean,
+        Footer {
+            position: footer,
+            trailing: 0
+        }
+    );
+
+    buffer.extend_from_slice(&[0x01, 0x00, 0xCC, 0x7F, 0x00]);
+    let located =
+        Replay::locate_footer(&mut Cursor::new(&buffer), &ReadOptions::default()).unwrap();
+    assert_eq!(located.position, footer);
+    assert_eq!(located.trailing, 5);
+    assert_eq!(common::read(&buffer), replay);
+
+    let strict = Replay::read_with_options(&mut Cursor::new(&buffer), &ReadOptions::untrusted());
+    assert!(matches!(strict, Err(ReplayError::TrailingData(5))));
+
+    buffer.truncate(footer as usize);
+    assert!(matches!(
+        Replay::read(&mut Cursor::new(&buffer)),
+        Err(ReplayError::InvalidFooter)
+    ));
+}
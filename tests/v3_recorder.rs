@@ -0,0 +1,52 @@
+mod common;
+
+use common::{replay, write};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::{ActionType, Recorder};
+use std::io::Cursor;
+
+#[test]
+fn test_v3_recorder_pause() {
+    let mut recorder = Recorder::new();
+    recorder
+        .add_player_action(100, ActionType::Jump, true, false)
+        .unwrap();
+    recorder.pause(150);
+    assert!(recorder.is_paused());
+    recorder
+        .add_player_action(5000, ActionType::Jump, false, false)
+        .unwrap();
+    recorder.resume(10150).unwrap();
+    recorder.resume(10200).unwrap();
+    recorder
+        .add_player_action(10170, ActionType::Jump, true, false)
+        .unwrap();
+    recorder
+        .add_death_action(10200, ActionType::Death, 7)
+        .unwrap();
+    assert_eq!(recorder.paused_frames(), 10000);
+
+    let atom = recorder.into_atom();
+    let frames: Vec<_> = atom
+        .actions
+        .iter()
+        .map(|a| (a.frame, a.action_type, a.paused_frames()))
+        .collect();
+    assert_eq!(
+        frames,
+        vec![
+            (100, ActionType::Jump, None),
+            (150, ActionType::Jump, None),
+            (150, ActionType::Pause, Some(10000)),
+            (170, ActionType::Jump, None),
+            (200, ActionType::Death, None),
+        ]
+    );
+
+    let replay = replay([AtomVariant::Action(atom)]);
+    let buffer = write(&replay);
+    assert_eq!(common::read(&buffer), replay);
+
+    let v2 = slc_oxide::Replay::<()>::read(&mut Cursor::new(&buffer)).unwrap();
+    assert_eq!(v2.inputs.len(), 4);
+}
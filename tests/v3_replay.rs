@@ -0,0 +1,186 @@
+mod common;
+
+use common::{atom_at, atom_at_mut, jumps, replay, roundtrip, write};
+use slc_oxide::v3::atom::{AtomVariant, NullAtom};
+use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+use slc_oxide::v3::changelog::ChangeEntry;
+use slc_oxide::v3::replay::SeedSpan;
+use slc_oxide::v3::session::SessionAtom;
+use slc_oxide::v3::{ActionType, Metadata, WriteOptions};
+
+#[test]
+fn test_v3_clone_and_eq() {
+    let mut action_atom = jumps(&[(10, true), (10, false)]);
+    action_atom.add_tps_action(50, 480.0).unwrap();
+    let mut replay = replay([AtomVariant::Action(action_atom)]);
+    replay.metadata = Metadata::new(240.0, 3, 1);
+    let loaded = roundtrip(&replay);
+
+    // Swift flags set while reading don't affect equality.
+    assert_eq!(loaded, replay);
+
+    let mut edited = loaded.clone();
+    atom_at_mut(&mut edited, 0).actions[2].tps = 360.0;
+    assert_ne!(edited, loaded);
+    assert!(format!("{:?}", edited).contains("360.0"));
+}
+
+#[test]
+fn test_v3_iteration() {
+    let mut action_atom = jumps(&[(10, true), (20, false)]);
+    for action in action_atom.iter_mut() {
+        action.player2 = true;
+    }
+    assert!((&action_atom).into_iter().all(|a| a.player2));
+
+    let replay = replay([AtomVariant::Action(action_atom)]);
+    let count: usize = replay
+        .atoms
+        .iter()
+        .filter_map(|atom| match atom {
+            AtomVariant::Action(a) => Some(a.iter().count()),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(count, 2);
+
+    let atoms: Vec<AtomVariant> = replay.atoms.into_iter().collect();
+    let AtomVariant::Action(atom) = atoms.into_iter().next().unwrap() else {
+        panic!("Expected ActionAtom");
+    };
+    assert_eq!(atom.into_iter().map(|a| a.frame).sum::<u64>(), 30);
+}
+
+#[test]
+fn test_v3_seed_timeline() {
+    let mut replay = replay([]);
+    replay.metadata = Metadata::new(240.0, 100, 1);
+    assert_eq!(
+        replay.seeds(),
+        vec![SeedSpan {
+            start: 0,
+            end: None,
+            seed: 100
+        }]
+    );
+
+    let mut atom = jumps(&[(5, true)]);
+    atom.add_death_action(50, ActionType::Death, 1).unwrap();
+    atom.add_death_action(60, ActionType::Restart, 2).unwrap();
+    atom.add_death_action(90, ActionType::Death, 3).unwrap();
+    atom.add_death_action(90, ActionType::Restart, 2).unwrap();
+    atom.add_death_action(120, ActionType::RestartFull, 2)
+        .unwrap();
+    replay.add_atom(AtomVariant::Action(atom));
+    let mut other = ActionAtom::new();
+    other
+        .add_death_action(200, ActionType::Restart, 100)
+        .unwrap();
+    replay.add_atom(AtomVariant::Action(other));
+
+    let spans: Vec<_> = replay
+        .seeds()
+        .iter()
+        .map(|s| (s.start, s.end, s.seed))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (0, Some(50), 100),
+            (50, Some(60), 1),
+            (60, Some(200), 2),
+            (200, None, 100),
+        ]
+    );
+    assert_eq!(replay.seed_at(0), 100);
+    assert_eq!(replay.seed_at(49), 100);
+    assert_eq!(replay.seed_at(50), 1);
+    assert_eq!(replay.seed_at(90), 2);
+    assert_eq!(replay.seed_at(u64::MAX), 100);
+    assert!(replay.seeds()[2].contains(199));
+    assert!(!replay.seeds()[2].contains(200));
+}
+
+#[test]
+fn test_v3_write_filtered() {
+    let mut replay = replay([
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 0)),
+        AtomVariant::Action(jumps(&[(10, true)])),
+    ]);
+    replay.log_change(ChangeEntry::new("alice", "record").with_timestamp(100));
+
+    let options = WriteOptions::default();
+    let mut buffer = Vec::new();
+    replay
+        .write_filtered(&mut buffer, &options, |atom| {
+            matches!(atom, AtomVariant::Action(_))
+        })
+        .unwrap();
+    let actions_only = common::read(&buffer);
+    assert_eq!(actions_only.metadata, replay.metadata);
+    assert_eq!(actions_only.atoms.atoms[..], replay.atoms.atoms[1..2]);
+
+    // Accepting every atom writes the same bytes as a plain write.
+    let mut all = Vec::new();
+    replay.write_filtered(&mut all, &options, |_| true).unwrap();
+    assert_eq!(all, write(&replay));
+}
+
+#[test]
+fn test_v3_extract_range() {
+    let mut action_atom = jumps(&[(10, true)]);
+    action_atom.add_tps_action(50, 480.0).unwrap();
+    action_atom
+        .add_death_action(100, ActionType::Death, 7)
+        .unwrap();
+    action_atom
+        .add_death_action(120, ActionType::Restart, 9)
+        .unwrap();
+    action_atom
+        .add_player_action(150, ActionType::Jump, true, false)
+        .unwrap();
+    action_atom
+        .add_player_action(152, ActionType::Jump, false, false)
+        .unwrap();
+    action_atom
+        .add_death_action(300, ActionType::Death, 11)
+        .unwrap();
+
+    let mut replay = replay([
+        AtomVariant::Null(NullAtom { size: 4 }),
+        AtomVariant::Session(SessionAtom::from_actions(&action_atom)),
+        AtomVariant::Action(action_atom),
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 1_000)),
+    ]);
+    replay.metadata = Metadata::new(240.0, 3, 1);
+    replay.log_change(ChangeEntry::new("alice", "record").with_timestamp(100));
+
+    let clip = replay.extract_range(110, 200);
+    assert_eq!(clip.metadata.tps, 480.0);
+    assert_eq!(clip.metadata.seed, 7);
+    assert_eq!(clip.atoms.atoms.len(), 4);
+
+    let AtomVariant::Session(session) = &clip.atoms.atoms[0] else {
+        panic!("expected the session");
+    };
+    let spans: Vec<_> = session
+        .attempts
+        .iter()
+        .map(|a| (a.start_frame, a.end_frame, a.death_frame))
+        .collect();
+    assert_eq!(spans, [(0, 10, None), (10, 90, None)]);
+
+    let actions = atom_at(&clip, 1);
+    let frames: Vec<_> = actions.actions.iter().map(|a| a.frame).collect();
+    assert_eq!(frames, [10, 40, 42]);
+    assert_eq!(actions.actions[1].delta(), 30);
+    assert_eq!(
+        clip.atoms.atoms[2],
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 1_110))
+    );
+    assert_eq!(clip.change_log(), replay.change_log());
+
+    // The clip is a replay of its own.
+    assert_eq!(roundtrip(&clip), clip);
+    assert_eq!(replay.extract_range(0, u64::MAX).metadata, replay.metadata);
+}
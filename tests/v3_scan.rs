@@ -0,0 +1,64 @@
+mod common;
+
+use common::{replay, write};
+use slc_oxide::v3::atom::{AtomId, AtomVariant};
+use slc_oxide::v3::builtin::{ActionAtom, StartposAtom};
+use slc_oxide::v3::{scan, Action, ActionType, Metadata, Visitor};
+use std::io::Cursor;
+
+#[derive(Default)]
+struct Stats {
+    tps: f64,
+    atoms: Vec<AtomId>,
+    actions: usize,
+    presses: usize,
+    last_frame: u64,
+    others: usize,
+}
+
+impl Visitor for Stats {
+    fn metadata(&mut self, metadata: &Metadata) {
+        self.tps = metadata.tps;
+    }
+    fn atom_start(&mut self, id: AtomId, _size: u64) {
+        self.atoms.push(id);
+    }
+    fn action(&mut self, action: &Action) {
+        self.actions += 1;
+        self.presses += action.holding as usize;
+        self.last_frame = action.frame;
+    }
+    fn atom(&mut self, _atom: &AtomVariant) {
+        self.others += 1;
+    }
+}
+
+#[test]
+fn test_v3_scan() {
+    let mut atom = ActionAtom::new();
+    for i in 0..100 {
+        atom.add_player_action(i * 10, ActionType::Jump, true, false)
+            .unwrap();
+        atom.add_player_action(i * 10 + 4, ActionType::Jump, false, false)
+            .unwrap();
+    }
+    let mut replay = replay([
+        AtomVariant::Startpos(StartposAtom::new(1.0, 2.0, 0)),
+        AtomVariant::Action(atom),
+    ]);
+    replay.metadata = Metadata::new(480.0, 1, 1);
+    let mut buffer = write(&replay);
+
+    let mut stats = Stats::default();
+    scan(&mut Cursor::new(&buffer), &mut stats).unwrap();
+    assert_eq!(stats.tps, 480.0);
+    assert_eq!(stats.atoms, vec![AtomId::Startpos, AtomId::Action]);
+    assert_eq!(stats.actions, 200);
+    assert_eq!(stats.presses, 100);
+    assert_eq!(stats.last_frame, 994);
+    assert_eq!(stats.others, 1);
+
+    buffer.pop();
+    buffer.push(0);
+    assert!(scan(&mut Cursor::new(&buffer), &mut stats).is_err());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{atom_at, replay, write, write_with};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::ActionAtom;
+use slc_oxide::v3::{ActionType, WriteOptions};
+
+#[test]
+fn test_v3_swift_pairs_and_opt_out() {
+    let mut action_atom = ActionAtom::new();
+    for (frame, button, holding, player2) in [
+        (10, ActionType::Jump, true, false),
+        (10, ActionType::Jump, false, false),
+        (20, ActionType::Left, true, false),
+        (25, ActionType::Left, false, false),
+        (30, ActionType::Jump, true, true),
+        (30, ActionType::Jump, false, true),
+    ] {
+        action_atom
+            .add_player_action(frame, button, holding, player2)
+            .unwrap();
+    }
+    assert_eq!(action_atom.swift_pairs(), vec![0, 4]);
+
+    let replay = replay([AtomVariant::Action(action_atom)]);
+    let swift_buffer = write(&replay);
+    let plain_buffer = write_with(
+        &replay,
+        &WriteOptions {
+            swift: false,
+            ..Default::default()
+        },
+    );
+    assert!(plain_buffer.len() > swift_buffer.len());
+
+    let loaded_replay = common::read(&plain_buffer);
+    let original = atom_at(&replay, 0);
+    let loaded = atom_at(&loaded_replay, 0);
+    assert_eq!(original.actions.len(), loaded.actions.len());
+    assert!(loaded.actions.iter().all(|a| !a.swift()));
+    for (action1, action2) in original.actions.iter().zip(&loaded.actions) {
+        assert_eq!(action1.frame, action2.frame);
+        assert_eq!(action1.action_type, action2.action_type);
+        assert_eq!(action1.holding, action2.holding);
+        assert_eq!(action1.player2, action2.player2);
+    }
+    assert_eq!(loaded.swift_pairs(), vec![0, 4]);
+}
+
+#[test]
+fn test_v3_sections_match_encoding() {
+    let mut action_atom = ActionAtom::new();
+    let mut frame = 0;
+    for i in 0..5000u64 {
+        // Stretches of identical clicks, broken up by irregular ones.
+        frame += if i % 300 < 200 { 4 } else { 1 + i % 7 };
+        let button = if i % 3 == 0 {
+            ActionType::Left
+        } else {
+            ActionType::Jump
+        };
+        action_atom
+            .add_player_action(frame, button, i % 2 == 0, false)
+            .unwrap();
+    }
+    action_atom
+        .add_death_action(frame + 1, ActionType::Death, 0)
+        .unwrap();
+
+    let options = WriteOptions::default();
+    let mut encoded = Vec::new();
+    action_atom
+        .write_with_options(&mut encoded, &options)
+        .unwrap();
+
+    let mut from_sections = (action_atom.actions.len() as u64).to_le_bytes().to_vec();
+    for section in action_atom.sections(&options).unwrap() {
+        section.write(&mut from_sections).unwrap();
+    }
+    assert_eq!(encoded, from_sections);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::{jumps, replay, roundtrip, write, FIRST_ATOM};
+use slc_oxide::v3::atom::AtomVariant;
+use slc_oxide::v3::builtin::ActionAtom;
+use slc_oxide::v3::session::{LevelLength, PercentMap, SessionAtom};
+use slc_oxide::v3::{ActionType, AttemptKind, Replay};
+use std::io::Cursor;
+
+#[test]
+fn test_v3_session_atom() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_player_action(10, ActionType::Jump, true, false)
+        .unwrap();
+    action_atom
+        .add_death_action(100, ActionType::Death, 0)
+        .unwrap();
+    action_atom
+        .add_death_action(120, ActionType::Restart, 0)
+        .unwrap();
+    action_atom
+        .actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Practice);
+    action_atom
+        .add_player_action(130, ActionType::Jump, false, false)
+        .unwrap();
+    action_atom
+        .add_player_action(400, ActionType::Jump, true, false)
+        .unwrap();
+
+    let mut session = SessionAtom::from_actions(&action_atom);
+    assert_eq!(session.attempts.len(), 2);
+    assert_eq!(session.attempts[0].death_frame, Some(100));
+    assert_eq!(session.attempts[0].end_frame, 120);
+    assert_eq!(session.attempts[1].kind, AttemptKind::Practice);
+    assert_eq!(session.attempts[1].end_frame, 401);
+    assert_eq!(session.attempts[1].actions(&action_atom).len(), 3);
+    session.attempts[0].percent = 12.5;
+    session.attempts[1].percent = 40.0;
+
+    let loaded = roundtrip(&replay([
+        AtomVariant::Action(action_atom),
+        AtomVariant::Session(session.clone()),
+    ]));
+    let AtomVariant::Session(loaded_session) = &loaded.atoms.atoms[1] else {
+        panic!("Expected SessionAtom");
+    };
+    assert_eq!(loaded_session, &session);
+    assert_eq!(loaded_session.attempt_at(150).unwrap().name, "Attempt 2");
+    assert_eq!(loaded_session.best().unwrap().percent, 40.0);
+}
+
+#[test]
+fn test_v3_session_percent() {
+    let mut action_atom = ActionAtom::new();
+    action_atom
+        .add_death_action(100, ActionType::Death, 0)
+        .unwrap();
+    action_atom
+        .add_death_action(120, ActionType::Restart, 0)
+        .unwrap();
+    action_atom
+        .add_death_action(420, ActionType::Death, 0)
+        .unwrap();
+    action_atom
+        .add_death_action(430, ActionType::Restart, 0)
+        .unwrap();
+    action_atom
+        .actions
+        .last_mut()
+        .unwrap()
+        .set_attempt_kind(AttemptKind::Practice);
+    action_atom
+        .add_player_action(1430, ActionType::Jump, true, false)
+        .unwrap();
+
+    let mut session = SessionAtom::from_actions(&action_atom);
+    session.annotate_percent(&LevelLength(400));
+    let percents: Vec<_> = session.attempts.iter().map(|a| a.percent).collect();
+    assert_eq!(percents, vec![25.0, 75.0, 100.0]);
+    assert_eq!(session.best_progress(), vec![25.0, 75.0, 75.0]);
+
+    session.annotate_percent(&|frames: u64| frames as f32 / 10.0);
+    assert_eq!(session.attempts[1].percent, 30.0);
+    assert_eq!(LevelLength(0).percent_at(5), 100.0);
+}
+
+#[test]
+fn test_v3_session_atom_size() {
+    let actions = jumps(&[(10, true), (20, false)]);
+    let replay = replay([
+        AtomVariant::Session(SessionAtom::from_actions(&actions)),
+        AtomVariant::Action(actions),
+    ]);
+    let buffer = write(&replay);
+
+    // Bytes after the last attempt are skipped, so the next atom still reads.
+    let mut padded = buffer.clone();
+    common::resize_atom(&mut padded, FIRST_ATOM, 5);
+    assert_eq!(common::read(&padded), replay);
+
+    // Attempts can't run past the atom.
+    let mut short = buffer.clone();
+    common::resize_atom(&mut short, FIRST_ATOM, -1);
+    assert!(Replay::read(&mut Cursor::new(&short)).is_err());
+}
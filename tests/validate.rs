@@ -0,0 +1,68 @@
+use slc_oxide::validate::{find_conflicts, resolve_conflicts, ConflictKind, Resolution};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+fn player(hold: bool, button: u8) -> InputData {
+    InputData::Player(PlayerInput {
+        hold,
+        player_2: false,
+        button,
+    })
+}
+
+fn conflicting_replay() -> Replay<()> {
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, player(true, 1));
+    replay.add_input(10, player(false, 1));
+    replay.add_input(20, player(true, 2));
+    replay.add_input(25, player(true, 2));
+    replay.add_input(30, player(false, 2));
+    replay.add_input(40, player(false, 3));
+    replay.add_input(50, player(true, 1));
+    replay.add_input(55, InputData::Restart);
+    replay.add_input(60, player(true, 1));
+    replay.add_input(61, player(false, 1));
+    replay
+}
+
+#[test]
+fn test_conflict_detection() {
+    let replay = conflicting_replay();
+    let kinds: Vec<_> = find_conflicts(&replay.inputs)
+        .iter()
+        .map(|c| (c.kind, c.index, c.frame))
+        .collect();
+
+    assert_eq!(
+        kinds,
+        vec![
+            (ConflictKind::SameFramePressRelease, 1, 10),
+            (ConflictKind::DuplicatePress, 3, 25),
+            (ConflictKind::OrphanRelease, 5, 40),
+        ]
+    );
+}
+
+#[test]
+fn test_conflict_resolution() {
+    let mut replay = conflicting_replay();
+    let original = replay.inputs.clone();
+    assert_eq!(
+        resolve_conflicts(&mut replay, Resolution::ReportOnly).len(),
+        3
+    );
+    assert_eq!(replay.inputs, original);
+
+    let mut dropped = conflicting_replay();
+    resolve_conflicts(&mut dropped, Resolution::Drop);
+    assert_eq!(dropped.inputs.len(), original.len() - 4);
+    assert!(find_conflicts(&dropped.inputs).is_empty());
+
+    let mut repaired = conflicting_replay();
+    resolve_conflicts(&mut repaired, Resolution::Repair);
+    assert_eq!(repaired.inputs.len(), original.len());
+    assert!(find_conflicts(&repaired.inputs).is_empty());
+    assert_eq!(repaired.inputs[1].frame, 11);
+    assert_eq!(repaired.inputs[1].delta, 1);
+    assert_eq!(repaired.inputs[3].data, player(false, 2));
+    assert_eq!(repaired.inputs[3].frame, 25);
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "notify")]
+
+use std::{fs, sync::mpsc, time::Duration};
+
+use slc_oxide::watch::watch;
+use slc_oxide::{InputData, Replay};
+
+#[test]
+fn test_watch_reloads() {
+    let dir = std::env::temp_dir().join(format!("slc_watch_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("live.slc");
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    replay.add_input(10, InputData::Restart);
+    let mut bytes = Vec::new();
+    replay.write(&mut bytes).unwrap();
+    fs::write(&path, &bytes).unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let watcher = watch::<(), _>(&path, move |replay| {
+        if let Ok(replay) = replay {
+            let _ = sender.send(replay);
+        }
+    })
+    .unwrap();
+    assert_eq!(watcher.path(), path);
+
+    replay.add_input(20, InputData::Death);
+    let mut bytes = Vec::new();
+    replay.write(&mut bytes).unwrap();
+    fs::write(&path, &bytes).unwrap();
+
+    let reloaded = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(reloaded.inputs, replay.inputs);
+
+    drop(watcher);
+    fs::remove_dir_all(&dir).unwrap();
+}
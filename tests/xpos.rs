@@ -0,0 +1,47 @@
+use slc_oxide::xpos::{to_positions, to_replay, PositionInput, PositionMap};
+use slc_oxide::{InputData, PlayerInput, Replay};
+
+#[test]
+fn test_position_roundtrip() {
+    // The player stands still for 10 frames, then moves 2.5 units per frame.
+    let positions = (0..200u64)
+        .map(|f| f.saturating_sub(10) as f64 * 2.5)
+        .collect();
+    let map = PositionMap::new(positions);
+
+    let mut replay = Replay::<()>::new(240.0, ());
+    for (frame, hold) in [(20, true), (25, false), (100, true), (180, false)] {
+        replay.add_input(
+            frame,
+            InputData::Player(PlayerInput {
+                hold,
+                player_2: false,
+                button: 1,
+            }),
+        );
+    }
+
+    let keyed = to_positions(&replay.inputs, &map).unwrap();
+    assert_eq!(keyed[0].x, 25.0);
+    assert_eq!(keyed[3].x, 425.0);
+
+    let restored = to_replay(&keyed, &map, 240.0, ()).unwrap();
+    assert_eq!(restored.inputs, replay.inputs);
+
+    // Positions between frames land on the frame that reaches them.
+    let late = [PositionInput {
+        x: 26.0,
+        data: InputData::Death,
+    }];
+    assert_eq!(
+        to_replay(&late, &map, 240.0, ()).unwrap().inputs[0].frame,
+        21
+    );
+
+    let unreachable = [PositionInput {
+        x: 1000.0,
+        data: InputData::Death,
+    }];
+    assert_eq!(to_replay(&unreachable, &map, 240.0, ()).err(), Some(0));
+    assert_eq!(map.frame_at(0.0), Some(0));
+}